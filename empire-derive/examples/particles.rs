@@ -0,0 +1,89 @@
+#[macro_use]
+extern crate empire_derive;
+extern crate empire;
+
+use empire::{CBool, Destination, Equivalence, Source, Tag, Universe};
+use std::mem;
+
+#[derive(Equivalence, Clone, Copy, Debug, Default, PartialEq)]
+#[repr(C)]
+struct Vector {
+    components: [f64; 3],
+}
+
+// Padding after `id` and `alive`, a nested struct and an array of arrays.
+#[derive(Equivalence, Clone, Copy, Debug, Default, PartialEq)]
+#[repr(C)]
+struct Particle {
+    id: u8,
+    position: Vector,
+    charges: [[i16; 2]; 3],
+    alive: CBool,
+    velocity: Vector,
+}
+
+#[derive(Equivalence, Clone, Copy, Debug, Default, PartialEq)]
+#[repr(C)]
+struct Pair(u32, u64);
+
+fn particle(rank: usize, i: usize) -> Particle {
+    let x = (rank * 100 + i) as f64;
+    Particle {
+        id: i as u8,
+        position: Vector {
+            components: [x, x + 0.25, x + 0.5],
+        },
+        charges: [[i as i16, -1], [2, -(rank as i16)], [3, 4]],
+        alive: (i != 3).into(),
+        velocity: Vector {
+            components: [-x, 0.0, 1.0],
+        },
+    }
+}
+
+fn main() {
+    let universe = Universe::from_env().unwrap();
+    let world = universe.read().unwrap().comm_world();
+    let (rank, size) = (world.rank(), world.size());
+
+    // The datatype covers the fields and skips the padding, and has the extent of the struct.
+    let datatype = Particle::equivalent_datatype();
+    assert_eq!(datatype.size(), 1 + 2 * 24 + 12 + 1);
+    assert_eq!(datatype.extent(), mem::size_of::<Particle>() as isize);
+    assert_eq!(Pair::equivalent_datatype().size(), 12);
+
+    let partner = rank ^ 1;
+    if partner < size {
+        let sent: Vec<_> = (0..5).map(|i| particle(rank, i)).collect();
+        let mut received = vec![Particle::default(); 5];
+        world
+            .sendrecv(
+                &sent[..],
+                Destination::Rank(partner),
+                0,
+                &mut received[..],
+                Source::Rank(partner),
+                Tag::Value(0),
+            )
+            .unwrap();
+        for (i, particle) in received.iter().enumerate() {
+            assert_eq!(*particle, self::particle(partner, i));
+        }
+
+        let pairs = [Pair(rank as u32, !0), Pair(7, rank as u64)];
+        let mut received = [Pair::default(); 2];
+        world
+            .sendrecv(
+                &pairs[..],
+                Destination::Rank(partner),
+                1,
+                &mut received[..],
+                Source::Rank(partner),
+                Tag::Value(1),
+            )
+            .unwrap();
+        assert_eq!(received, [Pair(partner as u32, !0), Pair(7, partner as u64)]);
+    }
+
+    println!("Rank {}: particles passed", rank);
+}
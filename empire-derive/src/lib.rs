@@ -0,0 +1,136 @@
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+#[macro_use]
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as Tokens};
+use syn::{Attribute, Data, DeriveInput, Fields, Index, Meta, NestedMeta, Type};
+
+// #[derive(Equivalence)] describes a #[repr(C)] struct with the equivalent of
+// MPI_Type_create_struct: one block per field at the field's offset, resized to the size of the
+// struct so that slices of it are laid out like arrays. Each field's type must implement
+// Equivalence, which is how nested structs get their own derived datatypes. Fixed size arrays,
+// nested to any depth, become a single block of their element type. The datatype is committed the
+// first time it's asked for and cloned from then on.
+#[proc_macro_derive(Equivalence)]
+pub fn derive_equivalence(input: TokenStream) -> TokenStream {
+    let input: DeriveInput =
+        syn::parse(input).expect("#[derive(Equivalence)] could not parse its input.");
+
+    match equivalence(&input) {
+        Ok(tokens) => tokens.into(),
+        Err((span, message)) => quote_spanned!(span=> compile_error!(#message);).into(),
+    }
+}
+
+fn equivalence(input: &DeriveInput) -> Result<Tokens, (Span, &'static str)> {
+    let fields = match input.data {
+        Data::Struct(ref data) => &data.fields,
+        _ => {
+            return Err((
+                input.ident.span(),
+                "#[derive(Equivalence)] only supports structs",
+            ))
+        }
+    };
+    // Without #[repr(C)] the compiler is free to reorder fields and to change the layout between
+    // builds, so two processes might not agree on it.
+    if !is_repr_c(&input.attrs) {
+        return Err((
+            input.ident.span(),
+            "#[derive(Equivalence)] requires the struct to be #[repr(C)]",
+        ));
+    }
+
+    let members: Vec<Tokens> = match *fields {
+        Fields::Named(ref fields) => fields
+            .named
+            .iter()
+            .map(|field| {
+                let ident = &field.ident;
+                quote!(#ident)
+            })
+            .collect(),
+        Fields::Unnamed(ref fields) => (0..fields.unnamed.len())
+            .map(|i| {
+                let index = Index::from(i);
+                quote!(#index)
+            })
+            .collect(),
+        Fields::Unit => Vec::new(),
+    };
+    let (blocklengths, elements): (Vec<_>, Vec<_>) =
+        fields.iter().map(|field| block(&field.ty)).unzip();
+
+    // Generic structs need their fields' element types to be Equivalence, and to be 'static so
+    // that each instantiation can cache its own datatype.
+    let name = &input.ident;
+    let mut generics = input.generics.clone();
+    {
+        let (_, ty_generics, _) = input.generics.split_for_impl();
+        let where_clause = generics.make_where_clause();
+        where_clause
+            .predicates
+            .push(parse_quote!(#name #ty_generics: 'static));
+        for element in &elements {
+            where_clause
+                .predicates
+                .push(parse_quote!(#element: ::empire::Equivalence));
+        }
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        unsafe impl #impl_generics ::empire::Equivalence for #name #ty_generics #where_clause {
+            fn equivalent_datatype() -> ::empire::Datatype {
+                ::empire::datatype::cached_datatype::<Self, _>(|| {
+                    let blocklengths: &[usize] = &[#(#blocklengths),*];
+                    let displacements: &[isize] =
+                        &[#(::std::mem::offset_of!(Self, #members) as isize),*];
+                    let types: &[::empire::Datatype] =
+                        &[#(<#elements as ::empire::Equivalence>::equivalent_datatype()),*];
+
+                    let mut datatype = ::empire::Datatype::resized(
+                        0,
+                        ::std::mem::size_of::<Self>() as isize,
+                        &::empire::Datatype::structure(blocklengths, displacements, types),
+                    );
+                    datatype.commit();
+                    datatype
+                })
+            }
+        }
+    })
+}
+
+fn is_repr_c(attrs: &[Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter_map(|attr| attr.interpret_meta())
+        .any(|meta| match meta {
+            Meta::List(ref list) if list.ident == "repr" => {
+                list.nested.iter().any(|nested| match *nested {
+                    NestedMeta::Meta(Meta::Word(ref word)) => word == "C",
+                    _ => false,
+                })
+            }
+            _ => false,
+        })
+}
+
+// The number of elements in a field and their type. Arrays of arrays are contiguous, so they
+// flatten into one block of their innermost element type.
+fn block(ty: &Type) -> (Tokens, &Type) {
+    match *ty {
+        Type::Array(ref array) => {
+            let (inner, element) = block(&array.elem);
+            let len = &array.len;
+            (quote!((#len) * #inner), element)
+        }
+        Type::Paren(ref paren) => block(&paren.elem),
+        Type::Group(ref group) => block(&group.elem),
+        _ => (quote!(1), ty),
+    }
+}
@@ -0,0 +1,7 @@
+extern crate trybuild;
+
+#[test]
+fn structs_without_repr_c_are_rejected() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/missing_repr_c.rs");
+}
@@ -0,0 +1,129 @@
+#[macro_use]
+extern crate empire_derive;
+extern crate empire;
+
+use empire::datatype::Constructor;
+use empire::{Buffer, BufferMut, Equivalence};
+use std::mem;
+
+#[derive(Equivalence, Clone, Copy, Debug, Default, PartialEq)]
+#[repr(C)]
+struct Inner {
+    flag: u8,
+    value: u32,
+}
+
+// Padding after `id` and between the nested structs, and an array of arrays.
+#[derive(Equivalence, Clone, Copy, Debug, Default, PartialEq)]
+#[repr(C)]
+struct Outer {
+    id: u16,
+    inners: [Inner; 2],
+    grid: [[i16; 3]; 2],
+    last: f64,
+}
+
+#[derive(Equivalence, Clone, Copy, Debug, Default, PartialEq)]
+#[repr(C)]
+struct Tagged<T> {
+    tag: u8,
+    value: T,
+}
+
+fn outer() -> Outer {
+    Outer {
+        id: 0x0102,
+        inners: [
+            Inner {
+                flag: 3,
+                value: 0x0405_0607,
+            },
+            Inner {
+                flag: 8,
+                value: 0x090a_0b0c,
+            },
+        ],
+        grid: [[1, -2, 3], [-4, 5, -6]],
+        last: 0.5,
+    }
+}
+
+fn bytes<T: Copy>(value: T) -> Vec<u8> {
+    let ptr = &value as *const T as *const u8;
+    unsafe { std::slice::from_raw_parts(ptr, mem::size_of::<T>()).to_vec() }
+}
+
+#[test]
+fn nested_structs_and_arrays_become_one_block_per_field() {
+    let datatype = Outer::equivalent_datatype();
+    assert_eq!(datatype.size(), 2 + 2 * 5 + 12 + 8);
+    assert_eq!(datatype.lb(), 0);
+    assert_eq!(datatype.extent(), mem::size_of::<Outer>() as isize);
+
+    let old = match *datatype.constructor() {
+        Constructor::Resized { lb: 0, ref old, .. } => old,
+        ref constructor => panic!("expected a resized struct, not {:?}", constructor),
+    };
+    match *old.constructor() {
+        Constructor::Struct {
+            ref blocklengths,
+            ref displacements,
+            ref types,
+        } => {
+            assert_eq!(*blocklengths, [1, 2, 6, 1]);
+            assert_eq!(
+                *displacements,
+                [
+                    mem::offset_of!(Outer, id) as isize,
+                    mem::offset_of!(Outer, inners) as isize,
+                    mem::offset_of!(Outer, grid) as isize,
+                    mem::offset_of!(Outer, last) as isize,
+                ]
+            );
+            assert!(types[1].is_same(&Inner::equivalent_datatype()));
+            assert_eq!(types[2].size(), 2);
+        }
+        ref constructor => panic!("expected a struct, not {:?}", constructor),
+    }
+}
+
+#[test]
+fn packing_skips_the_padding() {
+    let sent = [outer(), Outer::default(), outer()];
+    let mut packed = Vec::new();
+    Buffer::from(&sent[..]).pack_into(&mut packed);
+
+    let inner = |inner: Inner| {
+        let mut data = bytes(inner.flag);
+        data.extend(bytes(inner.value));
+        data
+    };
+    let mut expected = Vec::new();
+    for outer in &sent {
+        expected.extend(bytes(outer.id));
+        expected.extend(inner(outer.inners[0]));
+        expected.extend(inner(outer.inners[1]));
+        expected.extend(bytes(outer.grid));
+        expected.extend(bytes(outer.last));
+    }
+    assert_eq!(packed, expected);
+
+    let mut received = [Outer::default(); 3];
+    BufferMut::from(&mut received[..]).unpack(&packed);
+    assert_eq!(received, sent);
+}
+
+#[test]
+fn datatypes_are_committed_once_per_type() {
+    let datatype = Outer::equivalent_datatype();
+    assert!(datatype.is_committed());
+    assert!(datatype.is_same(&Outer::equivalent_datatype()));
+
+    assert_eq!(Tagged::<u16>::equivalent_datatype().size(), 3);
+    assert_eq!(Tagged::<f64>::equivalent_datatype().size(), 9);
+    assert_eq!(Tagged::<Inner>::equivalent_datatype().size(), 6);
+    assert_eq!(
+        Tagged::<Inner>::equivalent_datatype().extent(),
+        mem::size_of::<Tagged<Inner>>() as isize
+    );
+}
@@ -0,0 +1,11 @@
+#[macro_use]
+extern crate empire_derive;
+extern crate empire;
+
+#[derive(Equivalence)]
+struct Point {
+    x: f64,
+    y: f64,
+}
+
+fn main() {}
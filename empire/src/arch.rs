@@ -0,0 +1,791 @@
+use super::{dataloop::Dataloop, datatype::Primitive};
+
+use std::slice;
+
+// How a machine lays out the primitives whose representation isn't the same everywhere. Integers
+// are two's complement and floating point numbers IEEE on every machine we run on, and char,
+// short, int, long long, float and double have the same sizes, so what's left is the byte order,
+// the sizes of long, MPI_Aint and wchar_t, and the format of long double.
+//
+// Every connection's handshake carries the sender's Arch. Messages are sent in the sender's
+// layout, and a receiver whose layout differs converts them as it unpacks them, which is what lets
+// wasm32 and 64-bit native ranks share a job. external32, the data representation the standard
+// defines for packed data, is one more layout: big-endian, with a 4 byte long, an 8 byte MPI_Aint,
+// a 2 byte wchar_t and a quad long double.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Arch {
+    pub big_endian: bool,
+    pub long_size: usize,
+    pub aint_size: usize,
+    pub wchar_size: usize,
+    pub long_double: LongDouble,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LongDouble {
+    // long double is the same as double.
+    Double = 1,
+    // x87 80-bit extended precision, padded to 16 bytes: a 64 bit significand with an explicit
+    // integer bit, then the sign and the same 15 bit exponent a quad has.
+    Extended = 2,
+    // IEEE binary128.
+    Quad = 3,
+}
+
+impl LongDouble {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(LongDouble::Double),
+            2 => Some(LongDouble::Extended),
+            3 => Some(LongDouble::Quad),
+            _ => None,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match *self {
+            LongDouble::Double => 8,
+            LongDouble::Extended | LongDouble::Quad => 16,
+        }
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", not(windows)))]
+const NATIVE_LONG_DOUBLE: LongDouble = LongDouble::Extended;
+#[cfg(not(all(target_arch = "x86_64", not(windows))))]
+const NATIVE_LONG_DOUBLE: LongDouble = LongDouble::Double;
+
+impl Arch {
+    pub fn native() -> Self {
+        Arch {
+            big_endian: cfg!(target_endian = "big"),
+            long_size: Primitive::Long.size(),
+            aint_size: Primitive::Aint.size(),
+            wchar_size: Primitive::WChar.size(),
+            long_double: NATIVE_LONG_DOUBLE,
+        }
+    }
+
+    pub fn external32() -> Self {
+        Arch {
+            big_endian: true,
+            long_size: 4,
+            aint_size: 8,
+            wchar_size: 2,
+            long_double: LongDouble::Quad,
+        }
+    }
+
+    pub fn is_native(&self) -> bool {
+        *self == Arch::native()
+    }
+
+    // Packs the layout into a word, a byte each for the byte order and the sizes of long and
+    // MPI_Aint, then the size of wchar_t and the long double format in the last.
+    pub fn to_bits(self) -> u32 {
+        (self.big_endian as u32) << 24
+            | (self.long_size as u32) << 16
+            | (self.aint_size as u32) << 8
+            | (self.wchar_size as u32) << 4
+            | self.long_double as u32
+    }
+
+    // The layout packed by `to_bits`, if the word holds one. The sizes are those the handshake
+    // accepts.
+    pub fn from_bits(bits: u32) -> Option<Self> {
+        let size = |size: u32, sizes: &[u32]| {
+            if sizes.contains(&size) {
+                Some(size as usize)
+            } else {
+                None
+            }
+        };
+
+        Some(Arch {
+            big_endian: match bits >> 24 {
+                0 => false,
+                1 => true,
+                _ => return None,
+            },
+            long_size: size(bits >> 16 & 0xff, &[4, 8])?,
+            aint_size: size(bits >> 8 & 0xff, &[4, 8])?,
+            wchar_size: size(bits >> 4 & 0xf, &[2, 4])?,
+            long_double: LongDouble::from_u8((bits & 0xf) as u8)?,
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Encoding {
+    Signed,
+    Unsigned,
+    LongDouble,
+}
+
+// How the values of a primitive are encoded, and how many of them are in one primitive: complex
+// numbers are a pair of floating point values.
+fn encoding(primitive: Primitive) -> (Encoding, usize) {
+    match primitive {
+        Primitive::SignedChar
+        | Primitive::Short
+        | Primitive::Int
+        | Primitive::Long
+        | Primitive::LongLong
+        | Primitive::Int8
+        | Primitive::Int16
+        | Primitive::Int32
+        | Primitive::Int64
+        | Primitive::Aint
+        | Primitive::Offset
+        | Primitive::Count => (Encoding::Signed, 1),
+        Primitive::LongDouble => (Encoding::LongDouble, 1),
+        Primitive::CFloatComplex
+        | Primitive::CDoubleComplex
+        | Primitive::CxxFloatComplex
+        | Primitive::CxxDoubleComplex => (Encoding::Unsigned, 2),
+        Primitive::CLongDoubleComplex | Primitive::CxxLongDoubleComplex => {
+            (Encoding::LongDouble, 2)
+        }
+        // Floating point values have the same size in every layout, so they only need their bytes
+        // reversed, like unsigned integers.
+        _ => (Encoding::Unsigned, 1),
+    }
+}
+
+// Reads an integer of any size up to 8 bytes, sign extending it if it is signed. Integers whose
+// size differs between layouts are sign or zero extended, or truncated to the low bytes.
+fn read(bytes: &[u8], big_endian: bool, signed: bool) -> u64 {
+    let value = if big_endian {
+        bytes.iter().fold(0, |value, &byte| value << 8 | u64::from(byte))
+    } else {
+        bytes.iter().rev().fold(0, |value, &byte| value << 8 | u64::from(byte))
+    };
+
+    if signed && bytes.len() < 8 {
+        let shift = 64 - 8 * bytes.len();
+        ((value << shift) as i64 >> shift) as u64
+    } else {
+        value
+    }
+}
+
+fn write(value: u64, big_endian: bool, bytes: &mut [u8]) {
+    let len = bytes.len();
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        let shift = if big_endian { len - 1 - i } else { i };
+        *byte = (value >> (8 * shift)) as u8;
+    }
+}
+
+fn read_u128(bytes: &[u8], big_endian: bool) -> u128 {
+    let (high, low) = if big_endian {
+        (&bytes[..8], &bytes[8..])
+    } else {
+        (&bytes[8..], &bytes[..8])
+    };
+    u128::from(read(high, big_endian, false)) << 64 | u128::from(read(low, big_endian, false))
+}
+
+fn write_u128(value: u128, big_endian: bool, bytes: &mut [u8]) {
+    let (high, low) = bytes.split_at_mut(8);
+    let (high, low) = if big_endian { (high, low) } else { (low, high) };
+    write((value >> 64) as u64, big_endian, high);
+    write(value as u64, big_endian, low);
+}
+
+// Long doubles convert through a quad, which can hold any of the formats. Converting an extended
+// long double to a quad is exact; converting back drops the low 49 bits of the quad's fraction.
+fn extended_to_quad(bytes: &[u8], big_endian: bool) -> u128 {
+    let significand = read(&bytes[..8], big_endian, false);
+    let sign_exponent = read(&bytes[8..10], big_endian, false);
+    u128::from(sign_exponent) << 112 | u128::from(significand & !(1 << 63)) << 49
+}
+
+fn quad_to_extended(quad: u128, big_endian: bool, bytes: &mut [u8]) {
+    let sign_exponent = (quad >> 112) as u64;
+    let integer_bit = if sign_exponent & 0x7fff == 0 { 0 } else { 1 << 63 };
+    let significand = (quad >> 49) as u64 & !(1 << 63) | integer_bit;
+    write(significand, big_endian, &mut bytes[..8]);
+    write(sign_exponent, big_endian, &mut bytes[8..10]);
+    for byte in &mut bytes[10..] {
+        *byte = 0;
+    }
+}
+
+// A double widens to a quad exactly and narrows towards zero.
+fn double_to_quad(bits: u64) -> u128 {
+    let sign = u128::from(bits >> 63) << 127;
+    let exponent = (bits >> 52 & 0x7ff) as i64;
+    let fraction = bits & ((1 << 52) - 1);
+    match (exponent, fraction) {
+        (0, 0) => sign,
+        // Subnormal doubles are normal quads.
+        (0, fraction) => {
+            let top = 63 - i64::from(fraction.leading_zeros());
+            let fraction = u128::from(fraction & !(1 << top)) << (112 - top);
+            sign | ((top - 1074 + 16383) as u128) << 112 | fraction
+        }
+        (0x7ff, fraction) => sign | 0x7fff << 112 | u128::from(fraction) << 60,
+        (exponent, fraction) => {
+            sign | ((exponent - 1023 + 16383) as u128) << 112 | u128::from(fraction) << 60
+        }
+    }
+}
+
+fn quad_to_double(quad: u128) -> u64 {
+    let sign = (quad >> 127) as u64;
+    let exponent = (quad >> 112 & 0x7fff) as i64;
+    let fraction = (quad >> 60) as u64 & ((1 << 52) - 1);
+    let bits = if exponent == 0x7fff {
+        // Keep NaNs that only had low fraction bits set from turning into infinities.
+        let nan = quad & ((1 << 112) - 1) != 0;
+        0x7ff << 52 | if nan { fraction | 1 } else { 0 }
+    } else {
+        let exponent = exponent - 16383 + 1023;
+        if exponent >= 0x7ff {
+            0x7ff << 52
+        } else if exponent > 0 {
+            (exponent as u64) << 52 | fraction
+        } else if exponent > -52 {
+            (1 << 52 | fraction) >> (1 - exponent)
+        } else {
+            0
+        }
+    };
+    sign << 63 | bits
+}
+
+fn long_double_to_quad(arch: &Arch, bytes: &[u8]) -> u128 {
+    match arch.long_double {
+        LongDouble::Double => double_to_quad(read(bytes, arch.big_endian, false)),
+        LongDouble::Extended => extended_to_quad(bytes, arch.big_endian),
+        LongDouble::Quad => read_u128(bytes, arch.big_endian),
+    }
+}
+
+fn quad_to_long_double(quad: u128, arch: &Arch, bytes: &mut [u8]) {
+    match arch.long_double {
+        LongDouble::Double => write(quad_to_double(quad), arch.big_endian, bytes),
+        LongDouble::Extended => quad_to_extended(quad, arch.big_endian, bytes),
+        LongDouble::Quad => write_u128(quad, arch.big_endian, bytes),
+    }
+}
+
+// Converts one value from the layout `from` into the layout `to`.
+fn convert(encoding: Encoding, from: &Arch, input: &[u8], to: &Arch, output: &mut [u8]) {
+    match encoding {
+        Encoding::Signed | Encoding::Unsigned => {
+            let signed = match encoding {
+                Encoding::Signed => true,
+                _ => false,
+            };
+            write(read(input, from.big_endian, signed), to.big_endian, output);
+        }
+        // Going through a quad would disturb the payloads of NaNs, so a format both sides share
+        // only has its bytes reordered.
+        Encoding::LongDouble if from.long_double == to.long_double => {
+            output.copy_from_slice(input);
+            if from.big_endian != to.big_endian {
+                output.reverse();
+            }
+        }
+        Encoding::LongDouble => {
+            quad_to_long_double(long_double_to_quad(from, input), to, output);
+        }
+    }
+}
+
+// Appends `count` elements at `base`, converted from the native layout into `to`, to `out`. The
+// caller promises that every byte the dataloop describes is readable.
+pub(crate) unsafe fn pack(
+    dataloop: &Dataloop,
+    base: *const u8,
+    count: usize,
+    extent: isize,
+    to: &Arch,
+    out: &mut Vec<u8>,
+) {
+    let native = Arch::native();
+    out.reserve((dataloop.size_in(to) * count as u64) as usize);
+    for (offset, primitive, run) in dataloop.runs(count, extent) {
+        let (encoding, values) = encoding(primitive);
+        let native_size = primitive.size() / values;
+        let size = primitive.size_in(to) / values;
+
+        let input = slice::from_raw_parts(base.wrapping_offset(offset), run * primitive.size());
+        let start = out.len();
+        out.resize(start + run * primitive.size_in(to), 0);
+        for (input, output) in input.chunks(native_size).zip(out[start..].chunks_mut(size)) {
+            convert(encoding, &native, input, to, output);
+        }
+    }
+}
+
+// Converts `data`, which is bytes `position..` of the data in the layout `from`, into the elements
+// at `base`, stopping early if it runs out. `position` falls between two primitives. Returns the
+// number of bytes of `data` converted, which stops short of a primitive cut off at the end, and the
+// number of bytes they take up in the native layout. The caller promises that every byte the
+// dataloop describes is writable.
+pub(crate) unsafe fn unpack_segment(
+    dataloop: &Dataloop,
+    base: *mut u8,
+    count: usize,
+    extent: isize,
+    from: &Arch,
+    position: u64,
+    data: &[u8],
+) -> (usize, u64) {
+    let native = Arch::native();
+    let element_size = dataloop.size_in(from).max(1);
+    let mut skip = (position % element_size) as usize;
+    let first = (position / element_size) as usize;
+
+    let mut data = data;
+    let mut converted = 0;
+    let mut unpacked = 0;
+    for (offset, primitive, run) in dataloop.runs_from(first, count, extent) {
+        let (encoding, values) = encoding(primitive);
+        let size = primitive.size_in(from);
+
+        // Step over the primitives of the first element that come before `position`.
+        let skipped = (skip / size).min(run);
+        skip -= skipped * size;
+        let (offset, run) = (offset + (skipped * primitive.size()) as isize, run - skipped);
+        if run == 0 {
+            continue;
+        }
+
+        if data.len() < size {
+            break;
+        }
+
+        let run = run.min(data.len() / size);
+        let output =
+            slice::from_raw_parts_mut(base.wrapping_offset(offset), run * primitive.size());
+        for (output, input) in output
+            .chunks_mut(primitive.size() / values)
+            .zip(data.chunks(size / values))
+        {
+            convert(encoding, from, input, &native, output);
+        }
+
+        data = &data[run * size..];
+        converted += run * size;
+        unpacked += (run * primitive.size()) as u64;
+    }
+    (converted, unpacked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::datatype::Datatype;
+
+    // A layout that differs from this machine's in everything but the long double format.
+    fn other() -> Arch {
+        let native = Arch::native();
+        Arch {
+            big_endian: !native.big_endian,
+            long_size: if native.long_size == 8 { 4 } else { 8 },
+            aint_size: if native.aint_size == 8 { 4 } else { 8 },
+            wchar_size: if native.wchar_size == 4 { 2 } else { 4 },
+            ..native
+        }
+    }
+
+    fn little(long_size: usize) -> Arch {
+        Arch {
+            big_endian: false,
+            long_size,
+            ..Arch::native()
+        }
+    }
+
+    fn big(long_size: usize) -> Arch {
+        Arch {
+            big_endian: true,
+            ..little(long_size)
+        }
+    }
+
+    fn converted(encoding: Encoding, from: &Arch, input: &[u8], to: &Arch, len: usize) -> Vec<u8> {
+        let mut output = vec![0xaa; len];
+        convert(encoding, from, input, to, &mut output);
+        output
+    }
+
+    #[test]
+    fn bits_round_trip() {
+        for &arch in &[Arch::native(), Arch::external32(), other()] {
+            assert_eq!(Arch::from_bits(arch.to_bits()), Some(arch));
+        }
+        for &long_double in &[LongDouble::Double, LongDouble::Extended, LongDouble::Quad] {
+            let arch = Arch {
+                long_double,
+                ..Arch::external32()
+            };
+            assert_eq!(Arch::from_bits(arch.to_bits()), Some(arch));
+        }
+    }
+
+    #[test]
+    fn bits_reject_unknown_layouts() {
+        let bits = Arch::external32().to_bits();
+        assert_eq!(Arch::from_bits(0), None);
+        assert_eq!(Arch::from_bits(bits | 2 << 24), None);
+        assert_eq!(Arch::from_bits(bits & !(0xff << 16) | 2 << 16), None);
+        assert_eq!(Arch::from_bits(bits & !(0xff << 8) | 16 << 8), None);
+        assert_eq!(Arch::from_bits(bits & !(0xf << 4) | 8 << 4), None);
+        assert_eq!(Arch::from_bits(bits & !0xf), None);
+    }
+
+    #[test]
+    fn integers_swap_their_bytes() {
+        let input = [1, 2, 3, 4];
+        for &encoding in &[Encoding::Signed, Encoding::Unsigned] {
+            assert_eq!(
+                converted(encoding, &little(8), &input, &big(8), 4),
+                vec![4, 3, 2, 1]
+            );
+            assert_eq!(converted(encoding, &big(8), &input, &big(8), 4), input.to_vec());
+        }
+    }
+
+    #[test]
+    fn narrower_integers_keep_their_low_bytes() {
+        let value = 0x1234_5678_9abc_def0u64.to_le_bytes();
+        assert_eq!(
+            converted(Encoding::Signed, &little(8), &value, &big(4), 4),
+            vec![0x9a, 0xbc, 0xde, 0xf0]
+        );
+
+        let minus_two = (-2i64).to_le_bytes();
+        assert_eq!(
+            converted(Encoding::Signed, &little(8), &minus_two, &little(4), 4),
+            (-2i32).to_le_bytes().to_vec()
+        );
+    }
+
+    #[test]
+    fn wider_integers_extend_by_signedness() {
+        let minus_two = (-2i32).to_be_bytes();
+        assert_eq!(
+            converted(Encoding::Signed, &big(4), &minus_two, &little(8), 8),
+            (-2i64).to_le_bytes().to_vec()
+        );
+        assert_eq!(
+            converted(Encoding::Unsigned, &big(4), &minus_two, &little(8), 8),
+            0xffff_fffeu64.to_le_bytes().to_vec()
+        );
+        assert_eq!(
+            converted(Encoding::Signed, &big(4), &[0x7f, 0xff, 0xff, 0xff], &big(8), 8),
+            0x7fff_ffffu64.to_be_bytes().to_vec()
+        );
+    }
+
+    // Packs `values` as `primitive` into `to` and unpacks them back into the native layout.
+    fn round_trip(primitive: Primitive, values: &[i64], to: &Arch) -> Vec<i64> {
+        let mut datatype = Datatype::primitive(primitive);
+        datatype.commit();
+        let dataloop = datatype.dataloop().unwrap();
+        let size = primitive.size();
+
+        let mut native = Vec::new();
+        for &value in values {
+            if size == 8 {
+                native.extend_from_slice(&value.to_ne_bytes());
+            } else {
+                native.extend_from_slice(&(value as i32).to_ne_bytes());
+            }
+        }
+
+        let mut packed = Vec::new();
+        unsafe {
+            pack(dataloop, native.as_ptr(), values.len(), size as isize, to, &mut packed);
+        }
+        assert_eq!(packed.len(), values.len() * primitive.size_in(to));
+
+        let mut unpacked = vec![0u8; native.len()];
+        let len = unsafe {
+            unpack_segment(
+                dataloop,
+                unpacked.as_mut_ptr(),
+                values.len(),
+                size as isize,
+                to,
+                0,
+                &packed,
+            )
+            .1
+        };
+        assert_eq!(len, native.len() as u64);
+
+        unpacked
+            .chunks(size)
+            .map(|bytes| {
+                if size == 8 {
+                    i64::from_ne_bytes([
+                        bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6],
+                        bytes[7],
+                    ])
+                } else {
+                    i64::from(i32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn longs_and_aints_round_trip_through_another_layout() {
+        // Small enough to fit in either size, and some negative to need sign extension.
+        let values = [0, 1, -1, -2_000_000_000, 2_000_000_000];
+        for &primitive in &[Primitive::Long, Primitive::Aint] {
+            assert_eq!(round_trip(primitive, &values, &other()), values.to_vec());
+            assert_eq!(
+                round_trip(primitive, &values, &Arch::external32()),
+                values.to_vec()
+            );
+        }
+    }
+
+    #[test]
+    fn longs_too_large_for_another_layout_are_truncated() {
+        if Primitive::Long.size() == 8 {
+            let values = [1 << 40 | 5, -(1 << 40) - 1];
+            assert_eq!(round_trip(Primitive::Long, &values, &little(4)), vec![5, -1]);
+        }
+        if Primitive::Aint.size() == 8 {
+            let narrow = Arch {
+                aint_size: 4,
+                ..Arch::native()
+            };
+            let values = [0x1_8000_0000, 0x1_0000_0007];
+            assert_eq!(
+                round_trip(Primitive::Aint, &values, &narrow),
+                vec![-0x8000_0000, 7]
+            );
+        }
+    }
+
+    #[test]
+    fn unpacking_stops_at_a_partial_primitive() {
+        let mut datatype = Datatype::primitive(Primitive::Int);
+        datatype.commit();
+        let mut unpacked = [0i32; 3];
+        let data = [0, 0, 0, 1, 0, 0, 0, 2, 0, 0];
+        let len = unsafe {
+            unpack_segment(
+                datatype.dataloop().unwrap(),
+                unpacked.as_mut_ptr() as *mut u8,
+                3,
+                4,
+                &big(8),
+                0,
+                &data,
+            )
+            .1
+        };
+        assert_eq!(len, 8);
+        assert_eq!(unpacked, [1, 2, 0]);
+    }
+
+    #[test]
+    fn segments_convert_from_any_primitive_boundary() {
+        #[repr(C)]
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        struct Element {
+            int: i32,
+            double: f64,
+        }
+
+        let mut datatype = Datatype::structure(
+            &[1, 1],
+            &[0, 8],
+            &[
+                Datatype::primitive(Primitive::Int),
+                Datatype::primitive(Primitive::Double),
+            ],
+        );
+        datatype.commit();
+        let dataloop = datatype.dataloop().unwrap();
+        let elements = [
+            Element { int: 1, double: 0.5 },
+            Element { int: 2, double: 1.5 },
+            Element { int: 3, double: -2.5 },
+        ];
+        let mut data = Vec::new();
+        unsafe { pack(dataloop, elements.as_ptr() as *const u8, 3, 16, &other(), &mut data) };
+        assert_eq!(data.len(), 36);
+
+        let mut unpacked = [Element { int: 0, double: 0.0 }; 3];
+        let base = unpacked.as_mut_ptr() as *mut u8;
+        // Each segment but the last stops partway through a double.
+        let segments = [(0, 10, (4, 4)), (4, 30, (24, 24)), (28, 36, (8, 8))];
+        for &(start, end, converted) in &segments {
+            let segment = &data[start..end];
+            let result =
+                unsafe { unpack_segment(dataloop, base, 3, 16, &other(), start as u64, segment) };
+            assert_eq!(result, converted);
+        }
+        assert_eq!(unpacked, elements);
+    }
+
+    #[test]
+    fn doubles_round_trip_through_quads() {
+        let values = [
+            0.0,
+            -0.0,
+            1.0,
+            -1.5,
+            0.1,
+            f64::MAX,
+            f64::MIN_POSITIVE,
+            f64::from_bits(1),
+            f64::from_bits((1 << 52) - 1),
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+        ];
+        for &value in &values {
+            assert_eq!(quad_to_double(double_to_quad(value.to_bits())), value.to_bits());
+        }
+
+        assert_eq!(double_to_quad(1f64.to_bits()), 0x3fff << 112);
+        assert_eq!(double_to_quad((-2f64).to_bits()), 1 << 127 | 0x4000 << 112);
+    }
+
+    #[test]
+    fn subnormal_doubles_widen_to_normal_quads() {
+        // 2^-1074, the smallest subnormal double.
+        assert_eq!(double_to_quad(1), ((16383 - 1074) as u128) << 112);
+        // 2^-1023, the largest power of two below the normal range.
+        assert_eq!(double_to_quad(1 << 51), ((16383 - 1023) as u128) << 112);
+        // 1.5 * 2^-1073.
+        assert_eq!(
+            double_to_quad(3),
+            ((16383 - 1073) as u128) << 112 | 1 << 111
+        );
+    }
+
+    #[test]
+    fn nans_stay_nans() {
+        let quiet = 0x7ff8_0000_0000_0001u64;
+        assert_eq!(quad_to_double(double_to_quad(quiet)), quiet);
+
+        // A quad NaN whose payload is all in bits a double doesn't have.
+        let nan = quad_to_double(0x7fff << 112 | 1);
+        assert!(f64::from_bits(nan).is_nan());
+        let negative = quad_to_double(1 << 127 | 0x7fff << 112 | 1 << 20);
+        assert!(f64::from_bits(negative).is_nan());
+        assert_eq!(negative >> 63, 1);
+    }
+
+    #[test]
+    fn quads_outside_the_double_range_overflow_or_underflow() {
+        // 2^1024 is too large for a double.
+        assert_eq!(quad_to_double((16383 + 1024) << 112), f64::INFINITY.to_bits());
+        assert_eq!(
+            quad_to_double(1 << 127 | (16383 + 5000) << 112),
+            f64::NEG_INFINITY.to_bits()
+        );
+        // 2^-1075 is half the smallest subnormal, and rounds towards zero.
+        assert_eq!(quad_to_double((16383 - 1075) << 112), 0);
+        assert_eq!(quad_to_double(1 << 127 | 1 << 112), 1 << 63);
+        // 2^-1030 is a subnormal double.
+        assert_eq!(quad_to_double((16383 - 1030) << 112), 1 << 44);
+        // Narrowing drops the low bits of the fraction.
+        assert_eq!(quad_to_double(0x3fff << 112 | 1), 1f64.to_bits());
+    }
+
+    #[test]
+    fn extended_round_trips_through_quads() {
+        // 1.0: the integer bit set and an exponent of 0x3fff, then six bytes of padding.
+        let mut one = vec![0u8; 16];
+        one[7] = 0x80;
+        one[8] = 0xff;
+        one[9] = 0x3f;
+        assert_eq!(extended_to_quad(&one, false), 0x3fff << 112);
+
+        let mut output = vec![0xaa; 16];
+        quad_to_extended(0x3fff << 112, false, &mut output);
+        assert_eq!(output, one);
+
+        // -3.0 big-endian, with a fraction bit below the integer bit.
+        let mut minus_three = vec![0u8; 16];
+        minus_three[0] = 0xc0;
+        minus_three[8] = 0xc0;
+        minus_three[9] = 0x00;
+        let quad = extended_to_quad(&minus_three, true);
+        assert_eq!(quad, 1 << 127 | 0x4000 << 112 | 1 << 111);
+        quad_to_extended(quad, true, &mut output);
+        assert_eq!(output, minus_three);
+
+        // Denormals have no integer bit.
+        let mut output = vec![0xaa; 16];
+        quad_to_extended(1 << 60, false, &mut output);
+        assert_eq!(output[7] & 0x80, 0);
+        assert_eq!(extended_to_quad(&output, false), 1 << 60);
+    }
+
+    #[test]
+    fn long_doubles_convert_between_formats() {
+        let double = Arch {
+            long_double: LongDouble::Double,
+            ..little(8)
+        };
+        let extended = Arch {
+            long_double: LongDouble::Extended,
+            ..little(8)
+        };
+        let quad = Arch {
+            long_double: LongDouble::Quad,
+            ..big(8)
+        };
+
+        let input = (-1.5f64).to_bits().to_le_bytes();
+        let as_extended = converted(Encoding::LongDouble, &double, &input, &extended, 16);
+        let as_quad = converted(Encoding::LongDouble, &extended, &as_extended, &quad, 16);
+        assert_eq!(
+            as_quad,
+            (1u128 << 127 | 0x3fff << 112 | 1 << 111).to_be_bytes().to_vec()
+        );
+        assert_eq!(
+            converted(Encoding::LongDouble, &quad, &as_quad, &double, 8),
+            input.to_vec()
+        );
+
+        // A format both sides share is only byte swapped, payload and all.
+        let nan = (0x7fffu128 << 112 | 0x1234).to_le_bytes();
+        let swapped = converted(
+            Encoding::LongDouble,
+            &Arch {
+                long_double: LongDouble::Quad,
+                ..little(8)
+            },
+            &nan,
+            &quad,
+            16,
+        );
+        assert_eq!(swapped, (0x7fffu128 << 112 | 0x1234).to_be_bytes().to_vec());
+    }
+
+    #[test]
+    fn lengths_convert_between_layouts() {
+        let mut long = Datatype::primitive(Primitive::Long);
+        long.commit();
+        let other = other();
+        let (native_size, other_size) = (Primitive::Long.size() as u64, other.long_size as u64);
+
+        assert_eq!(long.len_from(&Arch::native(), 3 * native_size), 3 * native_size);
+        assert_eq!(long.len_from(&other, 3 * other_size), 3 * native_size);
+        // A trailing partial primitive isn't counted, as a receive wouldn't unpack it.
+        assert_eq!(long.len_from(&other, 3 * other_size + 1), 3 * native_size);
+
+        let mut pair = Datatype::structure(
+            &[1, 1],
+            &[0, 8],
+            &[Datatype::primitive(Primitive::Int), long.clone()],
+        );
+        pair.commit();
+        assert_eq!(pair.len_from(&other, 4 + other_size + 4), 4 + native_size + 4);
+    }
+}
@@ -0,0 +1,105 @@
+use super::{datatype::{Buffer, Equivalence}, error::{self, Error}, request::Request};
+
+use std::{slice, ops::Range};
+
+// The bookkeeping charged against the attached buffer for every buffered send, in addition to the
+// message itself.
+pub const BSEND_OVERHEAD: usize = 32;
+
+// The memory attached for buffered-mode sends. A buffered send packs its message into a free region
+// of the memory and is sent from there, so it no longer needs the user's buffer once it has
+// started. The region is free again once the send has been delivered.
+pub struct BsendBuffer {
+    memory: *mut u8,
+    capacity: usize,
+    // Sends still in flight, with the region of memory each one holds, in the order of the regions.
+    pending: Vec<(Range<usize>, Request<'static>)>,
+}
+
+// The memory is only ever touched by the sends it is lent to.
+unsafe impl Send for BsendBuffer {}
+unsafe impl Sync for BsendBuffer {}
+
+impl BsendBuffer {
+    pub(crate) fn new(memory: &'static mut [u8]) -> Self {
+        Self {
+            memory: memory.as_mut_ptr(),
+            capacity: memory.len(),
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> u64 {
+        self.capacity as u64
+    }
+
+    // Packs `buf` into a free region, if there is one large enough, and calls `start` to send the
+    // packed message from there.
+    pub(crate) fn send<F: FnOnce(&Buffer<'static>) -> Request<'static>>(
+        &mut self,
+        buf: &Buffer,
+        start: F,
+    ) -> error::Result<()> {
+        self.retire_completed();
+
+        let length = buf.len() as usize;
+        let needed = length + BSEND_OVERHEAD;
+        let (index, offset) = match self.find_room(needed) {
+            Some(room) => room,
+            None => {
+                let used: usize = self.pending.iter().map(|(region, _)| region.len()).sum();
+                return Err(Error::InsufficientBuffer {
+                    needed: needed as u64,
+                    available: (self.capacity - used) as u64,
+                });
+            }
+        };
+
+        let packed = unsafe {
+            let region = slice::from_raw_parts_mut(self.memory.add(offset), length);
+            buf.pack_to(region);
+            Buffer::new(region.as_ptr(), length, u8::equivalent_datatype())
+        };
+        let request = start(&packed);
+        self.pending.insert(index, (offset..offset + needed, request));
+        Ok(())
+    }
+
+    // The first gap between the regions in use that can hold `needed` bytes, as the index its
+    // region goes at in `pending` and its offset.
+    fn find_room(&self, needed: usize) -> Option<(usize, usize)> {
+        let mut offset = 0;
+        for (index, (region, _)) in self.pending.iter().enumerate() {
+            if region.start - offset >= needed {
+                return Some((index, offset));
+            }
+            offset = region.end;
+        }
+
+        if self.capacity - offset >= needed {
+            Some((self.pending.len(), offset))
+        } else {
+            None
+        }
+    }
+
+    fn retire_completed(&mut self) {
+        self.pending = self.pending
+            .drain(..)
+            .filter_map(|(region, request)| match request.test() {
+                Ok(_) => None,
+                Err(request) => Some((region, request)),
+            })
+            .collect();
+    }
+
+    // Waits for every buffered send to be delivered and hands back the memory. The universe must
+    // not be locked meanwhile, since delivering the sends may need it.
+    pub fn detach(self) -> &'static mut [u8] {
+        for (_, request) in self.pending {
+            // Buffered sends have no one to report failures to.
+            let _ = request.wait();
+        }
+        unsafe { slice::from_raw_parts_mut(self.memory, self.capacity) }
+    }
+}
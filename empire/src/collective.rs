@@ -0,0 +1,1192 @@
+use super::{Comm, Op, datatype::{Buffer, BufferMut}, error::{self, Error},
+            request::{Request, Start}, schedule::{Region, Schedule, Step}};
+
+use std::{mem, cmp::min, sync::atomic::Ordering};
+
+// The largest power of two no greater than `n`, which must not be zero.
+fn power_of_two_below(n: usize) -> usize {
+    let mut power = 1;
+    while power * 2 <= n {
+        power *= 2;
+    }
+    power
+}
+
+// The rank a relative rank other than the root's receives from in a binomial tree, which differs
+// from it in its lowest set bit.
+fn binomial_parent(relative: usize) -> usize {
+    relative & (relative - 1)
+}
+
+// The number of ranks in the binomial subtree of `relative`, which is itself and those that differ
+// from it only in the bits below its lowest set bit. The root's subtree holds every rank.
+fn subtree_size(relative: usize, size: usize) -> usize {
+    if relative == 0 {
+        size
+    } else {
+        min(relative & relative.wrapping_neg(), size - relative)
+    }
+}
+
+fn regions<'a, B: Into<Region<'a>>>(bufs: Vec<B>) -> Vec<Region<'a>> {
+    bufs.into_iter().map(Into::into).collect()
+}
+
+// Each collective comes in three forms, all of which lay the algorithm out as a schedule. The
+// nonblocking one returns the request running the schedule and the blocking one waits for that
+// request. The persistent `_init` one keeps the schedule in an inactive request and runs it again
+// from the start every time the request is started.
+impl Comm {
+    fn assert_intracomm(&self) {
+        assert!(
+            !self.is_intercomm(),
+            "Collectives on intercommunicators are not supported."
+        );
+    }
+
+    // Every rank calls the collectives of a communicator in the same order, so numbering them
+    // gives each one a tag that its messages share on every rank and no other collective uses.
+    fn collective_tag(&self) -> i32 {
+        (self.collectives.fetch_add(1, Ordering::Relaxed) & 0x7fff_ffff) as i32
+    }
+
+    pub(crate) fn schedule<'a>(&self) -> Schedule<'a> {
+        Schedule::new(self, self.collective_tag())
+    }
+
+    // Every start of a persistent collective reuses the schedule, tag and all: the starts of one
+    // request are never in flight at the same time, so their messages can't be confused. The
+    // connections the schedule sends over are opened now rather than on the first start.
+    pub(crate) fn persistent<'a>(&self, schedule: Schedule<'a>) -> Request<'a> {
+        schedule.connect();
+        Request::persistent(
+            self.engine().clone(),
+            Start::Schedule(Some(Box::new(schedule))),
+        )
+    }
+
+    fn relative_rank(&self, root: usize) -> usize {
+        (self.rank() + self.size() - root) % self.size()
+    }
+
+    pub fn barrier(&self) -> error::Result<()> {
+        self.ibarrier().wait().map(|_| ())
+    }
+
+    pub fn ibarrier(&self) -> Request<'static> {
+        Request::schedule(self.barrier_schedule())
+    }
+
+    pub fn barrier_init(&self) -> Request<'static> {
+        self.persistent(self.barrier_schedule())
+    }
+
+    fn barrier_schedule(&self) -> Schedule<'static> {
+        if self.is_intercomm() {
+            return self.intercomm_barrier_schedule();
+        }
+
+        let mut schedule = self.schedule();
+        self.barrier_steps(&mut schedule, &[]);
+        schedule
+    }
+
+    // A dissemination barrier: in round k every rank signals the rank 2^k after it and waits for
+    // the rank 2^k before it, so after log2(size) rounds each has heard from all of the others.
+    // Starts after the steps in `after`, and returns the steps after which this rank has heard.
+    pub(crate) fn barrier_steps(
+        &self,
+        schedule: &mut Schedule<'static>,
+        after: &[Step],
+    ) -> Vec<Step> {
+        let (rank, size) = (self.rank(), self.size());
+        let empty: &mut [u8] = &mut [];
+        let nothing = Region::from(BufferMut::from(empty));
+
+        let mut heard = after.to_vec();
+        let mut distance = 1;
+        while distance < size {
+            schedule.send(&nothing, self.address((rank + distance) % size), &heard);
+            heard = vec![schedule.recv(&nothing, (rank + size - distance) % size, &heard)];
+            distance <<= 1;
+        }
+        heard
+    }
+
+    pub fn bcast<'b, B: Into<BufferMut<'b>>>(&self, buf: B, root: usize) -> error::Result<()> {
+        self.ibcast(buf, root).wait().map(|_| ())
+    }
+
+    pub fn ibcast<'a, B: Into<BufferMut<'a>>>(&self, buf: B, root: usize) -> Request<'a> {
+        Request::schedule(self.bcast_schedule(Region::from(buf.into()), root))
+    }
+
+    pub fn bcast_init<'a, B: Into<BufferMut<'a>>>(&self, buf: B, root: usize) -> Request<'a> {
+        self.persistent(self.bcast_schedule(Region::from(buf.into()), root))
+    }
+
+    fn bcast_schedule<'a>(&self, buf: Region<'a>, root: usize) -> Schedule<'a> {
+        self.assert_intracomm();
+        assert!(root < self.size());
+        let mut schedule = self.schedule();
+        self.bcast_steps(&mut schedule, &buf, root, &[]);
+        schedule
+    }
+
+    // A binomial tree rooted at `root`: ranks are numbered relative to the root, each receives
+    // from the rank that differs from it in its lowest set bit and then sends on to the ranks that
+    // differ from it in each lower bit. Every step comes after the steps in `after`.
+    pub(crate) fn bcast_steps<'a>(
+        &self,
+        schedule: &mut Schedule<'a>,
+        buf: &Region<'a>,
+        root: usize,
+        after: &[Step],
+    ) {
+        let size = self.size();
+        let relative = self.relative_rank(root);
+
+        let mut received = after.to_vec();
+        let mut mask = 1;
+        while mask < size {
+            if relative & mask != 0 {
+                let parent = (relative - mask + root) % size;
+                received = vec![schedule.recv(buf, parent, after)];
+                break;
+            }
+            mask <<= 1;
+        }
+
+        mask >>= 1;
+        while mask > 0 {
+            if relative + mask < size {
+                let child = (relative + mask + root) % size;
+                schedule.send(buf, self.address(child), &received);
+            }
+            mask >>= 1;
+        }
+    }
+
+    pub fn reduce<'b, 'c, S: Into<Buffer<'b>>, R: Into<BufferMut<'c>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: Option<R>,
+        op: &Op,
+        root: usize,
+    ) -> error::Result<()> {
+        let sendbuf = sendbuf.map(Into::<Buffer>::into);
+        let recvbuf = recvbuf.map(Into::<BufferMut>::into);
+        self.ireduce(sendbuf, recvbuf, op, root)?.wait().map(|_| ())
+    }
+
+    pub fn ireduce<'a, S: Into<Buffer<'a>>, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: Option<R>,
+        op: &Op,
+        root: usize,
+    ) -> error::Result<Request<'a>> {
+        let sendbuf = sendbuf.map(|sendbuf| Region::from(sendbuf.into()));
+        let recvbuf = recvbuf.map(|recvbuf| Region::from(recvbuf.into()));
+        Ok(Request::schedule(
+            self.reduce_schedule(sendbuf, recvbuf, op, root)?,
+        ))
+    }
+
+    pub fn reduce_init<'a, S: Into<Buffer<'a>>, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: Option<R>,
+        op: &Op,
+        root: usize,
+    ) -> error::Result<Request<'a>> {
+        let sendbuf = sendbuf.map(|sendbuf| Region::from(sendbuf.into()));
+        let recvbuf = recvbuf.map(|recvbuf| Region::from(recvbuf.into()));
+        Ok(self.persistent(self.reduce_schedule(sendbuf, recvbuf, op, root)?))
+    }
+
+    // Only the root needs `recvbuf`; without `sendbuf`, the root's contribution is taken from it
+    // instead.
+    fn reduce_schedule<'a>(
+        &self,
+        sendbuf: Option<Region<'a>>,
+        recvbuf: Option<Region<'a>>,
+        op: &Op,
+        root: usize,
+    ) -> error::Result<Schedule<'a>> {
+        self.assert_intracomm();
+        assert!(root < self.size());
+        let input = match sendbuf {
+            Some(sendbuf) => sendbuf,
+            None => recvbuf
+                .clone()
+                .expect("Only the root rank can reduce in place."),
+        };
+        if !op.is_defined_for(input.datatype()) {
+            return Err(Error::UndefinedOp);
+        }
+
+        let mut schedule = self.schedule();
+        self.reduce_steps(&mut schedule, &input, recvbuf.as_ref(), op, root);
+        Ok(schedule)
+    }
+
+    // A binomial tree like bcast's run backwards. Each rank combines what it holds with what the
+    // rank above it in relative order sent, so every partial result covers consecutive relative
+    // ranks in order. Relative order is only rank order with rank 0 at the top, so for operations
+    // that aren't commutative the tree is rooted there and the result sent on to the root, which
+    // leaves it in `output`. Returns the steps the root's result is ready after.
+    pub(crate) fn reduce_steps<'a>(
+        &self,
+        schedule: &mut Schedule<'a>,
+        input: &Region<'a>,
+        output: Option<&Region<'a>>,
+        op: &Op,
+        root: usize,
+    ) -> Vec<Step> {
+        let (rank, size) = (self.rank(), self.size());
+        let top = if op.is_commutative() { root } else { 0 };
+        let relative = self.relative_rank(top);
+
+        let mut acc = schedule.scratch(input.count(), input.datatype());
+        let mut incoming = schedule.scratch(input.count(), input.datatype());
+        let mut last = vec![schedule.copy(input, &acc, &[])];
+
+        let mut mask = 1;
+        while mask < size {
+            if relative & mask != 0 {
+                let parent = (relative - mask + top) % size;
+                last = vec![schedule.send(&acc, self.address(parent), &last)];
+                break;
+            }
+
+            if relative + mask < size {
+                let child = (relative + mask + top) % size;
+                let received = schedule.recv(&incoming, child, &last);
+                last = vec![schedule.reduce(&acc, &incoming, op, &[received])];
+                mem::swap(&mut acc, &mut incoming);
+            }
+            mask <<= 1;
+        }
+
+        if rank == top && rank != root {
+            vec![schedule.send(&acc, self.address(root), &last)]
+        } else if rank != root {
+            last
+        } else {
+            if top != root {
+                last = vec![schedule.recv(&acc, top, &last)];
+            }
+            let output = output.expect("The root rank must supply a receive buffer.");
+            vec![schedule.copy(&acc, output, &last)]
+        }
+    }
+
+    pub fn allreduce<'b, 'c, S: Into<Buffer<'b>>, R: Into<BufferMut<'c>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: R,
+        op: &Op,
+    ) -> error::Result<()> {
+        let sendbuf = sendbuf.map(Into::<Buffer>::into);
+        let recvbuf: BufferMut = recvbuf.into();
+        self.iallreduce(sendbuf, recvbuf, op)?.wait().map(|_| ())
+    }
+
+    pub fn iallreduce<'a, S: Into<Buffer<'a>>, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: R,
+        op: &Op,
+    ) -> error::Result<Request<'a>> {
+        let sendbuf = sendbuf.map(|sendbuf| Region::from(sendbuf.into()));
+        let recvbuf = Region::from(recvbuf.into());
+        Ok(Request::schedule(
+            self.allreduce_schedule(sendbuf, recvbuf, op)?,
+        ))
+    }
+
+    pub fn allreduce_init<'a, S: Into<Buffer<'a>>, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: R,
+        op: &Op,
+    ) -> error::Result<Request<'a>> {
+        let sendbuf = sendbuf.map(|sendbuf| Region::from(sendbuf.into()));
+        let recvbuf = Region::from(recvbuf.into());
+        Ok(self.persistent(self.allreduce_schedule(sendbuf, recvbuf, op)?))
+    }
+
+    // Recursive doubling: in round k every rank exchanges its partial result with the rank that
+    // differs from it in bit k and combines the two, so after log2(size) rounds every rank holds
+    // the whole result. With a size that isn't a power of two, the first ranks pair off and fold
+    // their data into one of each pair beforehand, and pass the result back afterwards. Without
+    // `sendbuf`, each rank's contribution is taken from `recvbuf`.
+    fn allreduce_schedule<'a>(
+        &self,
+        sendbuf: Option<Region<'a>>,
+        recvbuf: Region<'a>,
+        op: &Op,
+    ) -> error::Result<Schedule<'a>> {
+        if self.is_intercomm() {
+            return self.intercomm_allreduce_schedule(sendbuf, recvbuf, op);
+        }
+
+        let input = sendbuf.unwrap_or_else(|| recvbuf.clone());
+        if !op.is_defined_for(input.datatype()) {
+            return Err(Error::UndefinedOp);
+        }
+
+        let mut schedule = self.schedule();
+        let (rank, size) = (self.rank(), self.size());
+        let mut acc = schedule.scratch(input.count(), input.datatype());
+        let mut incoming = schedule.scratch(input.count(), input.datatype());
+        let mut last = vec![schedule.copy(&input, &acc, &[])];
+
+        let pof2 = power_of_two_below(size);
+        let rem = size - pof2;
+
+        // Of the first 2 * rem ranks, the even ones hand their data to the odd ones and sit out.
+        let folded = if rank < 2 * rem {
+            if rank % 2 == 0 {
+                last = vec![schedule.send(&acc, self.address(rank + 1), &last)];
+                None
+            } else {
+                let received = schedule.recv(&incoming, rank - 1, &last);
+                last = vec![schedule.reduce(&incoming, &acc, op, &[received])];
+                Some(rank / 2)
+            }
+        } else {
+            Some(rank - rem)
+        };
+
+        if let Some(folded) = folded {
+            let mut mask = 1;
+            while mask < pof2 {
+                let partner = folded ^ mask;
+                let partner = if partner < rem {
+                    partner * 2 + 1
+                } else {
+                    partner + rem
+                };
+
+                let exchanged = [
+                    schedule.send(&acc, self.address(partner), &last),
+                    schedule.recv(&incoming, partner, &last),
+                ];
+                if partner < rank {
+                    last = vec![schedule.reduce(&incoming, &acc, op, &exchanged)];
+                } else {
+                    last = vec![schedule.reduce(&acc, &incoming, op, &exchanged)];
+                    mem::swap(&mut acc, &mut incoming);
+                }
+                mask <<= 1;
+            }
+        }
+
+        if rank < 2 * rem {
+            last = if rank % 2 == 0 {
+                vec![schedule.recv(&acc, rank + 1, &last)]
+            } else {
+                vec![schedule.send(&acc, self.address(rank - 1), &last)]
+            };
+        }
+
+        schedule.copy(&acc, &recvbuf, &last);
+        Ok(schedule)
+    }
+
+    pub fn gather<'b, 'c, S: Into<Buffer<'b>>, R: Into<BufferMut<'c>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: Option<R>,
+        root: usize,
+    ) -> error::Result<()> {
+        let sendbuf = sendbuf.map(Into::<Buffer>::into);
+        let recvbuf = recvbuf.map(Into::<BufferMut>::into);
+        self.igather(sendbuf, recvbuf, root).wait().map(|_| ())
+    }
+
+    pub fn igather<'a, S: Into<Buffer<'a>>, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: Option<R>,
+        root: usize,
+    ) -> Request<'a> {
+        let sendbuf = sendbuf.map(|sendbuf| Region::from(sendbuf.into()));
+        let recvbuf = recvbuf.map(|recvbuf| Region::from(recvbuf.into()));
+        Request::schedule(self.gather_schedule(sendbuf, recvbuf, root))
+    }
+
+    pub fn gather_init<'a, S: Into<Buffer<'a>>, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: Option<R>,
+        root: usize,
+    ) -> Request<'a> {
+        let sendbuf = sendbuf.map(|sendbuf| Region::from(sendbuf.into()));
+        let recvbuf = recvbuf.map(|recvbuf| Region::from(recvbuf.into()));
+        self.persistent(self.gather_schedule(sendbuf, recvbuf, root))
+    }
+
+    fn gather_schedule<'a>(
+        &self,
+        sendbuf: Option<Region<'a>>,
+        recvbuf: Option<Region<'a>>,
+        root: usize,
+    ) -> Schedule<'a> {
+        self.assert_intracomm();
+        assert!(root < self.size());
+        let mut schedule = self.schedule();
+        self.gather_steps(&mut schedule, sendbuf, recvbuf, root);
+        schedule
+    }
+
+    // Gathers `sendbuf` from every rank into the root's `recvbuf`, which is split into a block for
+    // each rank in order. A binomial tree like reduce's: each rank collects the blocks of its
+    // subtree, which are consecutive in relative order, and passes them up as one message. Without
+    // `sendbuf`, the root's block is left as it is. Returns the steps after which the root's
+    // blocks are all in place.
+    pub(crate) fn gather_steps<'a>(
+        &self,
+        schedule: &mut Schedule<'a>,
+        sendbuf: Option<Region<'a>>,
+        recvbuf: Option<Region<'a>>,
+        root: usize,
+    ) -> Vec<Step> {
+        let size = self.size();
+        let relative = self.relative_rank(root);
+
+        if relative == 0 {
+            let recvbuf = recvbuf.expect("The root rank must supply a receive buffer.");
+            let (count, datatype) = (recvbuf.count() / size, recvbuf.datatype().clone());
+            let blocks: Vec<Region> = (0..size)
+                .map(|rank| recvbuf.block(rank * count, count))
+                .collect();
+
+            let mut filled = Vec::new();
+            if let Some(sendbuf) = sendbuf {
+                filled.push(schedule.copy(&sendbuf, &blocks[root], &[]));
+            }
+
+            let mut child = 1;
+            while child < size {
+                let n = subtree_size(child, size);
+                let subtree = schedule.scratch(n * count, &datatype);
+                let received = schedule.recv(&subtree, (child + root) % size, &[]);
+                for k in 0..n {
+                    let block = &blocks[(child + k + root) % size];
+                    let part = subtree.block(k * count, count);
+                    filled.push(schedule.copy(&part, block, &[received]));
+                }
+                child <<= 1;
+            }
+            filled
+        } else {
+            let sendbuf = sendbuf.expect("Only the root rank can gather in place.");
+            let count = sendbuf.count();
+            let n = subtree_size(relative, size);
+            let subtree = schedule.scratch(n * count, sendbuf.datatype());
+            let mut filled = vec![schedule.copy(&sendbuf, &subtree.block(0, count), &[])];
+
+            let mut mask = 1;
+            while mask < n {
+                let child = (relative + mask + root) % size;
+                let m = subtree_size(relative + mask, size);
+                let blocks = subtree.block(mask * count, m * count);
+                filled.push(schedule.recv(&blocks, child, &[]));
+                mask <<= 1;
+            }
+
+            let parent = (binomial_parent(relative) + root) % size;
+            vec![schedule.send(&subtree, self.address(parent), &filled)]
+        }
+    }
+
+    pub fn gatherv<'b, 'c, S: Into<Buffer<'b>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbufs: Option<Vec<BufferMut<'c>>>,
+        root: usize,
+    ) -> error::Result<()> {
+        let sendbuf = sendbuf.map(Into::<Buffer>::into);
+        self.igatherv(sendbuf, recvbufs, root).wait().map(|_| ())
+    }
+
+    pub fn igatherv<'a, S: Into<Buffer<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbufs: Option<Vec<BufferMut<'a>>>,
+        root: usize,
+    ) -> Request<'a> {
+        let sendbuf = sendbuf.map(|sendbuf| Region::from(sendbuf.into()));
+        Request::schedule(self.gatherv_schedule(sendbuf, recvbufs.map(regions), root))
+    }
+
+    pub fn gatherv_init<'a, S: Into<Buffer<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbufs: Option<Vec<BufferMut<'a>>>,
+        root: usize,
+    ) -> Request<'a> {
+        let sendbuf = sendbuf.map(|sendbuf| Region::from(sendbuf.into()));
+        self.persistent(self.gatherv_schedule(sendbuf, recvbufs.map(regions), root))
+    }
+
+    // Like gather, with a block of any length at any place for each rank. The root receives
+    // from every rank directly.
+    fn gatherv_schedule<'a>(
+        &self,
+        sendbuf: Option<Region<'a>>,
+        recvbufs: Option<Vec<Region<'a>>>,
+        root: usize,
+    ) -> Schedule<'a> {
+        self.assert_intracomm();
+        assert!(root < self.size());
+        let mut schedule = self.schedule();
+
+        if self.rank() == root {
+            let blocks = recvbufs.expect("The root rank must supply receive buffers.");
+            assert!(blocks.len() == self.size());
+            if let Some(sendbuf) = sendbuf {
+                schedule.copy(&sendbuf, &blocks[root], &[]);
+            }
+
+            for (rank, block) in blocks.iter().enumerate() {
+                if rank != root {
+                    schedule.recv(block, rank, &[]);
+                }
+            }
+        } else {
+            let sendbuf = sendbuf.expect("Only the root rank can gather in place.");
+            schedule.send(&sendbuf, self.address(root), &[]);
+        }
+        schedule
+    }
+
+    pub fn scatter<'b, 'c, S: Into<Buffer<'b>>, R: Into<BufferMut<'c>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: Option<R>,
+        root: usize,
+    ) -> error::Result<()> {
+        let sendbuf = sendbuf.map(Into::<Buffer>::into);
+        let recvbuf = recvbuf.map(Into::<BufferMut>::into);
+        self.iscatter(sendbuf, recvbuf, root).wait().map(|_| ())
+    }
+
+    pub fn iscatter<'a, S: Into<Buffer<'a>>, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: Option<R>,
+        root: usize,
+    ) -> Request<'a> {
+        let sendbuf = sendbuf.map(Into::<Buffer>::into);
+        let recvbuf = recvbuf.map(|recvbuf| Region::from(recvbuf.into()));
+        Request::schedule(self.scatter_schedule(sendbuf, recvbuf, root))
+    }
+
+    pub fn scatter_init<'a, S: Into<Buffer<'a>>, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: Option<R>,
+        root: usize,
+    ) -> Request<'a> {
+        let sendbuf = sendbuf.map(Into::<Buffer>::into);
+        let recvbuf = recvbuf.map(|recvbuf| Region::from(recvbuf.into()));
+        self.persistent(self.scatter_schedule(sendbuf, recvbuf, root))
+    }
+
+    // The inverse of gather: the root's `sendbuf` is split into a block for each rank, and each
+    // rank receives its block into `recvbuf`. Each rank receives the blocks of its whole subtree
+    // and passes on those of its children's. Without `recvbuf`, the root keeps its block where it
+    // is.
+    fn scatter_schedule<'a>(
+        &self,
+        sendbuf: Option<Buffer<'a>>,
+        recvbuf: Option<Region<'a>>,
+        root: usize,
+    ) -> Schedule<'a> {
+        self.assert_intracomm();
+        assert!(root < self.size());
+        let mut schedule = self.schedule();
+        let size = self.size();
+        let relative = self.relative_rank(root);
+
+        if relative == 0 {
+            let blocks = regions(
+                sendbuf
+                    .expect("The root rank must supply a send buffer.")
+                    .split(size),
+            );
+            let (count, datatype) = (blocks[0].count(), blocks[0].datatype().clone());
+
+            let mut child = 1;
+            while child < size {
+                let n = subtree_size(child, size);
+                let subtree = schedule.scratch(n * count, &datatype);
+                let filled: Vec<Step> = (0..n)
+                    .map(|k| {
+                        let block = &blocks[(child + k + root) % size];
+                        schedule.copy(block, &subtree.block(k * count, count), &[])
+                    })
+                    .collect();
+                schedule.send(&subtree, self.address((child + root) % size), &filled);
+                child <<= 1;
+            }
+
+            if let Some(recvbuf) = recvbuf {
+                schedule.copy(&blocks[root], &recvbuf, &[]);
+            }
+        } else {
+            let recvbuf = recvbuf.expect("Only the root rank can scatter in place.");
+            let count = recvbuf.count();
+            let n = subtree_size(relative, size);
+            let subtree = schedule.scratch(n * count, recvbuf.datatype());
+
+            let parent = (binomial_parent(relative) + root) % size;
+            let received = [schedule.recv(&subtree, parent, &[])];
+
+            let mut mask = 1;
+            while mask < n {
+                let child = (relative + mask + root) % size;
+                let m = subtree_size(relative + mask, size);
+                let blocks = subtree.block(mask * count, m * count);
+                schedule.send(&blocks, self.address(child), &received);
+                mask <<= 1;
+            }
+
+            schedule.copy(&subtree.block(0, count), &recvbuf, &received);
+        }
+        schedule
+    }
+
+    pub fn scatterv<'b, 'c, R: Into<BufferMut<'c>>>(
+        &self,
+        sendbufs: Option<Vec<Buffer<'b>>>,
+        recvbuf: Option<R>,
+        root: usize,
+    ) -> error::Result<()> {
+        let recvbuf = recvbuf.map(Into::<BufferMut>::into);
+        self.iscatterv(sendbufs, recvbuf, root).wait().map(|_| ())
+    }
+
+    pub fn iscatterv<'a, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbufs: Option<Vec<Buffer<'a>>>,
+        recvbuf: Option<R>,
+        root: usize,
+    ) -> Request<'a> {
+        let recvbuf = recvbuf.map(|recvbuf| Region::from(recvbuf.into()));
+        Request::schedule(self.scatterv_schedule(sendbufs.map(regions), recvbuf, root))
+    }
+
+    pub fn scatterv_init<'a, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbufs: Option<Vec<Buffer<'a>>>,
+        recvbuf: Option<R>,
+        root: usize,
+    ) -> Request<'a> {
+        let recvbuf = recvbuf.map(|recvbuf| Region::from(recvbuf.into()));
+        self.persistent(self.scatterv_schedule(sendbufs.map(regions), recvbuf, root))
+    }
+
+    // Like scatter, with a block of any length at any place for each rank.
+    fn scatterv_schedule<'a>(
+        &self,
+        sendbufs: Option<Vec<Region<'a>>>,
+        recvbuf: Option<Region<'a>>,
+        root: usize,
+    ) -> Schedule<'a> {
+        self.assert_intracomm();
+        assert!(root < self.size());
+        let mut schedule = self.schedule();
+        self.scatterv_steps(&mut schedule, sendbufs, recvbuf, root, &[]);
+        schedule
+    }
+
+    // The root sends to every rank directly, once the steps in `after` are done.
+    fn scatterv_steps<'a>(
+        &self,
+        schedule: &mut Schedule<'a>,
+        sendbufs: Option<Vec<Region<'a>>>,
+        recvbuf: Option<Region<'a>>,
+        root: usize,
+        after: &[Step],
+    ) {
+        if self.rank() == root {
+            let blocks = sendbufs.expect("The root rank must supply send buffers.");
+            assert!(blocks.len() == self.size());
+
+            for (rank, block) in blocks.iter().enumerate() {
+                if rank != root {
+                    schedule.send(block, self.address(rank), after);
+                }
+            }
+            if let Some(recvbuf) = recvbuf {
+                schedule.copy(&blocks[root], &recvbuf, after);
+            }
+        } else {
+            let recvbuf = recvbuf.expect("Only the root rank can scatter in place.");
+            schedule.recv(&recvbuf, root, after);
+        }
+    }
+
+    pub fn allgather<'b, 'c, S: Into<Buffer<'b>>, R: Into<BufferMut<'c>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: R,
+    ) -> error::Result<()> {
+        let sendbuf = sendbuf.map(Into::<Buffer>::into);
+        let recvbuf: BufferMut = recvbuf.into();
+        self.iallgather(sendbuf, recvbuf).wait().map(|_| ())
+    }
+
+    // Gathers `sendbuf` from every rank into the blocks of every rank's `recvbuf`, which is split
+    // into one for each rank, or for each rank of the remote group of an intercommunicator.
+    // Without `sendbuf`, each rank's block is already in place.
+    pub fn iallgather<'a, S: Into<Buffer<'a>>, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: R,
+    ) -> Request<'a> {
+        let size = self.remote_size();
+        self.iallgatherv(sendbuf, recvbuf.into().split(size))
+    }
+
+    pub fn allgather_init<'a, S: Into<Buffer<'a>>, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: R,
+    ) -> Request<'a> {
+        let size = self.remote_size();
+        self.allgatherv_init(sendbuf, recvbuf.into().split(size))
+    }
+
+    pub fn allgatherv<'b, 'c, S: Into<Buffer<'b>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbufs: Vec<BufferMut<'c>>,
+    ) -> error::Result<()> {
+        let sendbuf = sendbuf.map(Into::<Buffer>::into);
+        self.iallgatherv(sendbuf, recvbufs).wait().map(|_| ())
+    }
+
+    pub fn iallgatherv<'a, S: Into<Buffer<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbufs: Vec<BufferMut<'a>>,
+    ) -> Request<'a> {
+        let sendbuf = sendbuf.map(|sendbuf| Region::from(sendbuf.into()));
+        Request::schedule(self.allgatherv_schedule(sendbuf, regions(recvbufs)))
+    }
+
+    pub fn allgatherv_init<'a, S: Into<Buffer<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbufs: Vec<BufferMut<'a>>,
+    ) -> Request<'a> {
+        let sendbuf = sendbuf.map(|sendbuf| Region::from(sendbuf.into()));
+        self.persistent(self.allgatherv_schedule(sendbuf, regions(recvbufs)))
+    }
+
+    // A ring: in each of size - 1 steps every rank passes the block it received last on to the
+    // next rank, starting with its own, so each block travels all the way round.
+    fn allgatherv_schedule<'a>(
+        &self,
+        sendbuf: Option<Region<'a>>,
+        blocks: Vec<Region<'a>>,
+    ) -> Schedule<'a> {
+        if self.is_intercomm() {
+            return self.intercomm_allgatherv_schedule(sendbuf, blocks);
+        }
+
+        let mut schedule = self.schedule();
+        let (rank, size) = (self.rank(), self.size());
+        assert!(blocks.len() == size);
+
+        let mut last = Vec::new();
+        if let Some(sendbuf) = sendbuf {
+            last.push(schedule.copy(&sendbuf, &blocks[rank], &[]));
+        }
+
+        let (next, prev) = ((rank + 1) % size, (rank + size - 1) % size);
+        for step in 0..size - 1 {
+            let sent = (rank + size - step) % size;
+            let received = (sent + size - 1) % size;
+            last = vec![
+                schedule.send(&blocks[sent], self.address(next), &last),
+                schedule.recv(&blocks[received], prev, &last),
+            ];
+        }
+        schedule
+    }
+
+    pub fn alltoall<'b, 'c, S: Into<Buffer<'b>>, R: Into<BufferMut<'c>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: R,
+    ) -> error::Result<()> {
+        let sendbuf = sendbuf.map(Into::<Buffer>::into);
+        let recvbuf: BufferMut = recvbuf.into();
+        self.ialltoall(sendbuf, recvbuf).wait().map(|_| ())
+    }
+
+    // Sends block i of every rank's `sendbuf` to rank i, which receives it into block j of its
+    // `recvbuf`, where j is the sender. On an intercommunicator, the ranks are those of the remote
+    // group. Without `sendbuf`, the blocks are sent from `recvbuf` and replaced.
+    pub fn ialltoall<'a, S: Into<Buffer<'a>>, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: R,
+    ) -> Request<'a> {
+        let size = self.remote_size();
+        self.ialltoallv(
+            sendbuf.map(|sendbuf| sendbuf.into().split(size)),
+            recvbuf.into().split(size),
+        )
+    }
+
+    pub fn alltoall_init<'a, S: Into<Buffer<'a>>, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: R,
+    ) -> Request<'a> {
+        let size = self.remote_size();
+        self.alltoallv_init(
+            sendbuf.map(|sendbuf| sendbuf.into().split(size)),
+            recvbuf.into().split(size),
+        )
+    }
+
+    pub fn alltoallv<'b, 'c>(
+        &self,
+        sendbufs: Option<Vec<Buffer<'b>>>,
+        recvbufs: Vec<BufferMut<'c>>,
+    ) -> error::Result<()> {
+        self.ialltoallv(sendbufs, recvbufs).wait().map(|_| ())
+    }
+
+    pub fn ialltoallv<'a>(
+        &self,
+        sendbufs: Option<Vec<Buffer<'a>>>,
+        recvbufs: Vec<BufferMut<'a>>,
+    ) -> Request<'a> {
+        Request::schedule(self.alltoallv_schedule(sendbufs.map(regions), regions(recvbufs)))
+    }
+
+    pub fn alltoallv_init<'a>(
+        &self,
+        sendbufs: Option<Vec<Buffer<'a>>>,
+        recvbufs: Vec<BufferMut<'a>>,
+    ) -> Request<'a> {
+        self.persistent(self.alltoallv_schedule(sendbufs.map(regions), regions(recvbufs)))
+    }
+
+    // Like alltoall, with a block of any length, place and datatype for each rank. Every exchange
+    // starts at once, in the order of a pairwise exchange: the rank k after this one is sent to,
+    // and the rank k before it received from, for each k in turn.
+    fn alltoallv_schedule<'a>(
+        &self,
+        sendbufs: Option<Vec<Region<'a>>>,
+        recvbufs: Vec<Region<'a>>,
+    ) -> Schedule<'a> {
+        if self.is_intercomm() {
+            return self.intercomm_alltoallv_schedule(sendbufs, recvbufs);
+        }
+
+        let mut schedule = self.schedule();
+        let (rank, size) = (self.rank(), self.size());
+        assert!(recvbufs.len() == size);
+
+        // In place, every block is copied out before any is received into.
+        let (sendbufs, copied) = match sendbufs {
+            Some(sendbufs) => (sendbufs, Vec::new()),
+            None => {
+                let copies: Vec<Region> = recvbufs
+                    .iter()
+                    .map(|block| schedule.scratch(block.count(), block.datatype()))
+                    .collect();
+                let copied = recvbufs
+                    .iter()
+                    .zip(&copies)
+                    .map(|(block, copy)| schedule.copy(block, copy, &[]))
+                    .collect();
+                (copies, copied)
+            }
+        };
+        assert!(sendbufs.len() == size);
+
+        schedule.copy(&sendbufs[rank], &recvbufs[rank], &copied);
+        for step in 1..size {
+            let (dest, source) = ((rank + step) % size, (rank + size - step) % size);
+            schedule.send(&sendbufs[dest], self.address(dest), &copied);
+            schedule.recv(&recvbufs[source], source, &copied);
+        }
+        schedule
+    }
+
+    pub fn reduce_scatter<'b, 'c, S: Into<Buffer<'b>>, R: Into<BufferMut<'c>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: R,
+        counts: &[usize],
+        op: &Op,
+    ) -> error::Result<()> {
+        let sendbuf = sendbuf.map(Into::<Buffer>::into);
+        let recvbuf: BufferMut = recvbuf.into();
+        self.ireduce_scatter(sendbuf, recvbuf, counts, op)?
+            .wait()
+            .map(|_| ())
+    }
+
+    pub fn ireduce_scatter<'a, S: Into<Buffer<'a>>, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: R,
+        counts: &[usize],
+        op: &Op,
+    ) -> error::Result<Request<'a>> {
+        let sendbuf = sendbuf.map(|sendbuf| Region::from(sendbuf.into()));
+        let recvbuf = Region::from(recvbuf.into());
+        Ok(Request::schedule(
+            self.reduce_scatter_schedule(sendbuf, recvbuf, counts, op)?,
+        ))
+    }
+
+    pub fn reduce_scatter_init<'a, S: Into<Buffer<'a>>, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: R,
+        counts: &[usize],
+        op: &Op,
+    ) -> error::Result<Request<'a>> {
+        let sendbuf = sendbuf.map(|sendbuf| Region::from(sendbuf.into()));
+        let recvbuf = Region::from(recvbuf.into());
+        Ok(self.persistent(self.reduce_scatter_schedule(sendbuf, recvbuf, counts, op)?))
+    }
+
+    // Reduces the elements of every rank's `sendbuf` and splits the result into consecutive
+    // blocks, `counts[i]` elements long for rank i, which receives its block into `recvbuf`. A
+    // reduce to rank 0 and a scatterv from it, so the contributions are combined in rank order.
+    // Rank 0 only receives in the reduce and only sends in the scatterv, so the two share a tag.
+    // Without `sendbuf`, each rank's contribution is taken from `recvbuf`, which then holds all of
+    // the elements, and its block is left at the start.
+    fn reduce_scatter_schedule<'a>(
+        &self,
+        sendbuf: Option<Region<'a>>,
+        recvbuf: Region<'a>,
+        counts: &[usize],
+        op: &Op,
+    ) -> error::Result<Schedule<'a>> {
+        self.assert_intracomm();
+        assert!(counts.len() == self.size());
+        let input = sendbuf.unwrap_or_else(|| recvbuf.clone());
+        assert!(input.count() == counts.iter().sum::<usize>());
+        if !op.is_defined_for(input.datatype()) {
+            return Err(Error::UndefinedOp);
+        }
+
+        let mut schedule = self.schedule();
+        if self.rank() == 0 {
+            let total = schedule.scratch(input.count(), input.datatype());
+            let reduced = self.reduce_steps(&mut schedule, &input, Some(&total), op, 0);
+
+            let mut start = 0;
+            let blocks = counts
+                .iter()
+                .map(|&count| {
+                    start += count;
+                    total.block(start - count, count)
+                })
+                .collect();
+            self.scatterv_steps(&mut schedule, Some(blocks), Some(recvbuf), 0, &reduced);
+        } else {
+            let reduced = self.reduce_steps(&mut schedule, &input, None, op, 0);
+            self.scatterv_steps(&mut schedule, None, Some(recvbuf), 0, &reduced);
+        }
+        Ok(schedule)
+    }
+
+    pub fn reduce_scatter_block<'b, 'c, S: Into<Buffer<'b>>, R: Into<BufferMut<'c>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: R,
+        op: &Op,
+    ) -> error::Result<()> {
+        let sendbuf = sendbuf.map(Into::<Buffer>::into);
+        let recvbuf: BufferMut = recvbuf.into();
+        self.ireduce_scatter_block(sendbuf, recvbuf, op)?
+            .wait()
+            .map(|_| ())
+    }
+
+    // Like reduce_scatter, with a block of the same length for every rank. Without `sendbuf`,
+    // `recvbuf` holds a block for each rank.
+    pub fn ireduce_scatter_block<'a, S: Into<Buffer<'a>>, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: R,
+        op: &Op,
+    ) -> error::Result<Request<'a>> {
+        let recvbuf: BufferMut = recvbuf.into();
+        let counts = self.block_counts(sendbuf.is_some(), &recvbuf);
+        self.ireduce_scatter(sendbuf, recvbuf, &counts, op)
+    }
+
+    pub fn reduce_scatter_block_init<'a, S: Into<Buffer<'a>>, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: R,
+        op: &Op,
+    ) -> error::Result<Request<'a>> {
+        let recvbuf: BufferMut = recvbuf.into();
+        let counts = self.block_counts(sendbuf.is_some(), &recvbuf);
+        self.reduce_scatter_init(sendbuf, recvbuf, &counts, op)
+    }
+
+    // The counts of a reduce_scatter with equal blocks held in `recvbuf`.
+    fn block_counts(&self, has_sendbuf: bool, recvbuf: &BufferMut) -> Vec<usize> {
+        let size = self.size();
+        let count = if has_sendbuf {
+            recvbuf.count()
+        } else {
+            recvbuf.count() / size
+        };
+        vec![count; size]
+    }
+
+    pub fn scan<'b, 'c, S: Into<Buffer<'b>>, R: Into<BufferMut<'c>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: R,
+        op: &Op,
+    ) -> error::Result<()> {
+        let sendbuf = sendbuf.map(Into::<Buffer>::into);
+        let recvbuf: BufferMut = recvbuf.into();
+        self.iscan(sendbuf, recvbuf, op)?.wait().map(|_| ())
+    }
+
+    // Leaves the reduction of the contributions of ranks 0 to i in rank i's `recvbuf`. Without
+    // `sendbuf`, each rank's contribution is taken from `recvbuf`.
+    pub fn iscan<'a, S: Into<Buffer<'a>>, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: R,
+        op: &Op,
+    ) -> error::Result<Request<'a>> {
+        let sendbuf = sendbuf.map(|sendbuf| Region::from(sendbuf.into()));
+        let recvbuf = Region::from(recvbuf.into());
+        Ok(Request::schedule(
+            self.prefix_reduce(sendbuf, recvbuf, op, true)?,
+        ))
+    }
+
+    pub fn scan_init<'a, S: Into<Buffer<'a>>, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: R,
+        op: &Op,
+    ) -> error::Result<Request<'a>> {
+        let sendbuf = sendbuf.map(|sendbuf| Region::from(sendbuf.into()));
+        let recvbuf = Region::from(recvbuf.into());
+        Ok(self.persistent(self.prefix_reduce(sendbuf, recvbuf, op, true)?))
+    }
+
+    pub fn exscan<'b, 'c, S: Into<Buffer<'b>>, R: Into<BufferMut<'c>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: R,
+        op: &Op,
+    ) -> error::Result<()> {
+        let sendbuf = sendbuf.map(Into::<Buffer>::into);
+        let recvbuf: BufferMut = recvbuf.into();
+        self.iexscan(sendbuf, recvbuf, op)?.wait().map(|_| ())
+    }
+
+    // Like scan, without each rank's own contribution. Rank 0's `recvbuf` is left as it is.
+    pub fn iexscan<'a, S: Into<Buffer<'a>>, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: R,
+        op: &Op,
+    ) -> error::Result<Request<'a>> {
+        let sendbuf = sendbuf.map(|sendbuf| Region::from(sendbuf.into()));
+        let recvbuf = Region::from(recvbuf.into());
+        Ok(Request::schedule(
+            self.prefix_reduce(sendbuf, recvbuf, op, false)?,
+        ))
+    }
+
+    pub fn exscan_init<'a, S: Into<Buffer<'a>>, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: R,
+        op: &Op,
+    ) -> error::Result<Request<'a>> {
+        let sendbuf = sendbuf.map(|sendbuf| Region::from(sendbuf.into()));
+        let recvbuf = Region::from(recvbuf.into());
+        Ok(self.persistent(self.prefix_reduce(sendbuf, recvbuf, op, false)?))
+    }
+
+    // Recursive doubling: in round k every rank exchanges the reduction over its group of 2^k
+    // ranks with the rank that differs from it in bit k, so both end up with the reduction over
+    // the two groups. What comes from a lower group is also combined into the rank's own result.
+    fn prefix_reduce<'a>(
+        &self,
+        sendbuf: Option<Region<'a>>,
+        recvbuf: Region<'a>,
+        op: &Op,
+        inclusive: bool,
+    ) -> error::Result<Schedule<'a>> {
+        self.assert_intracomm();
+        let input = sendbuf.unwrap_or_else(|| recvbuf.clone());
+        if !op.is_defined_for(input.datatype()) {
+            return Err(Error::UndefinedOp);
+        }
+
+        let mut schedule = self.schedule();
+        let (rank, size) = (self.rank(), self.size());
+        let mut group = schedule.scratch(input.count(), input.datatype());
+        let mut incoming = schedule.scratch(input.count(), input.datatype());
+        let mut last = vec![schedule.copy(&input, &group, &[])];
+        let mut result = None;
+        if inclusive {
+            let scratch = schedule.scratch(input.count(), input.datatype());
+            last.push(schedule.copy(&input, &scratch, &[]));
+            result = Some(scratch);
+        }
+
+        let mut mask = 1;
+        while mask < size {
+            let partner = rank ^ mask;
+            if partner < size {
+                let exchanged = [
+                    schedule.send(&group, self.address(partner), &last),
+                    schedule.recv(&incoming, partner, &last),
+                ];
+                if partner < rank {
+                    let combined = match result {
+                        Some(ref result) => schedule.reduce(&incoming, result, op, &exchanged),
+                        None => {
+                            let scratch = schedule.scratch(input.count(), input.datatype());
+                            let copied = schedule.copy(&incoming, &scratch, &exchanged);
+                            result = Some(scratch);
+                            copied
+                        }
+                    };
+                    last = vec![combined, schedule.reduce(&incoming, &group, op, &exchanged)];
+                } else {
+                    last = vec![schedule.reduce(&group, &incoming, op, &exchanged)];
+                    mem::swap(&mut group, &mut incoming);
+                }
+            }
+            mask <<= 1;
+        }
+
+        if let Some(result) = result {
+            schedule.copy(&result, &recvbuf, &last);
+        }
+        Ok(schedule)
+    }
+}
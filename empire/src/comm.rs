@@ -1,11 +1,22 @@
-use super::{Universe, error::{self, Error}, port::Port};
+use super::{Universe, engine::{self, Engine}, error::Error, port::Port, universe::new_job_id};
 
-use std::{io, ffi::OsString, process::Command, sync::{Arc, RwLock, Weak}};
+use std::{io, mem, ffi::OsString, process::{Command, ExitStatus},
+          sync::{Arc, RwLock, Weak, atomic::{AtomicU64, AtomicUsize, Ordering}}};
 
-use futures::future;
+use futures::{future, sync::oneshot};
 
 use tokio::prelude::*;
-use tokio_process::CommandExt;
+use tokio_process::{Child, CommandExt};
+
+// Context ids of the predefined communicators. Every process in a job agrees on these without
+// having to communicate.
+pub(crate) const WORLD_CONTEXT_ID: u32 = 0;
+pub(crate) const SELF_CONTEXT_ID: u32 = 1;
+pub(crate) const PARENT_CONTEXT_ID: u32 = 2;
+
+// Messages between the ranks of one group of an intercommunicator go in a context of their own, so
+// they never match messages from the other group. It is the intercommunicator's with this bit set.
+const LOCAL_GROUP_CONTEXT_BIT: u32 = 1 << 30;
 
 pub struct SpawnCommandInfo {
     command: OsString,
@@ -31,62 +42,202 @@ impl SpawnCommandInfo {
     }
 }
 
+// `results` holds whether each rank was launched, and for ranks that exited before every rank had
+// called MPI_Init, whether they exited successfully.
 pub struct SpawnMultipleResult {
     pub comm: Comm,
     pub results: Vec<super::Result<()>>,
 }
 
+fn exit_result(status: ExitStatus) -> super::Result<()> {
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Error::FailExitCode(status.code().unwrap_or(-1)))
+    }
+}
+
+// The ranks of a job being spawned, until either every one of them has called MPI_Init or none of
+// them is running any more. Yields the ports the ranks announced if they all did, the ranks that
+// are still running and the outcome of each rank so far.
+struct Launch {
+    children: Vec<Option<Child>>,
+    results: Vec<super::Result<()>>,
+    ports: oneshot::Receiver<Vec<String>>,
+}
+
+impl Future for Launch {
+    type Item = (
+        Option<Vec<String>>,
+        Vec<Option<Child>>,
+        Vec<super::Result<()>>,
+    );
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Error> {
+        for (child, result) in self.children.iter_mut().zip(&mut self.results) {
+            let exit = match *child {
+                Some(ref mut child) => child.poll(),
+                None => continue,
+            };
+            *result = match exit {
+                Ok(Async::Ready(status)) => exit_result(status),
+                Ok(Async::NotReady) => continue,
+                Err(err) => Err(err.into()),
+            };
+            *child = None;
+        }
+
+        // The ports are handed over before any rank hears about the others, so a rank can't have
+        // exited after MPI_Init without them being ready.
+        let ports = match self.ports.poll() {
+            Ok(Async::Ready(ports)) => Some(ports),
+            Ok(Async::NotReady) if self.children.iter().any(Option::is_some) => {
+                return Ok(Async::NotReady)
+            }
+            _ => None,
+        };
+
+        Ok(Async::Ready((
+            ports,
+            mem::replace(&mut self.children, Vec::new()),
+            mem::replace(&mut self.results, Vec::new()),
+        )))
+    }
+}
+
 pub struct Comm {
     universe: Weak<RwLock<Universe>>,
+    engine: Arc<Engine>,
 
     // properties
     name: Option<String>,
+    context_id: u32,
     rank: usize,
     size: usize,
     is_intercomm: bool,
+    // Messages larger than this many bytes are sent by rendezvous.
+    eager_limit: AtomicU64,
+    // The number of collectives started on the communicator, which tags their messages.
+    pub(crate) collectives: AtomicUsize,
 
     // tracking state
     ports: Vec<Option<Port>>,
+    // The port name of every rank that messages can be sent to, if known: the ranks of the
+    // communicator's group, or of the remote group of an intercommunicator.
+    addresses: Vec<Option<String>>,
+    // The local group of an intercommunicator as a communicator of its own, which its collectives
+    // use for the messages within the group.
+    local_group: Option<Box<Comm>>,
+    // The ranks spawned along with an intercommunicator that were still running when spawning
+    // returned, by rank.
+    children: Vec<Option<Child>>,
     child_commands: Vec<Command>,
 }
 
 impl Comm {
+    // `addresses` holds the port name of every rank in the communicator, so its length is the size
+    // of the communicator. `port` is the listening port of this rank.
     pub(crate) fn intracomm(
         universe: Weak<RwLock<Universe>>,
+        engine: Arc<Engine>,
+        context_id: u32,
+        rank: usize,
+        port: Port,
+        addresses: Vec<String>,
+    ) -> Self {
+        assert!(addresses[rank] == port.name());
+
+        let mut comm = Comm::group(universe, engine, context_id, rank, addresses);
+        comm.ports[rank] = Some(port);
+        comm
+    }
+
+    // The ranks of `addresses` as an intracommunicator, without a port of its own. Messages to
+    // this rank arrive at whichever of its ports the others know it by.
+    fn group(
+        universe: Weak<RwLock<Universe>>,
+        engine: Arc<Engine>,
+        context_id: u32,
         rank: usize,
-        size: usize,
-    ) -> error::Result<Self> {
+        addresses: Vec<String>,
+    ) -> Self {
+        let size = addresses.len();
         assert!(rank < size);
 
         let mut ports = Vec::new();
         for _ in 0..size {
             ports.push(None);
         }
-        ports[rank] = Some(Port::new()?);
 
-        Ok(Self {
+        let eager_limit = AtomicU64::new(engine.eager_limit());
+
+        Self {
             universe,
+            engine,
             name: None,
+            context_id,
             rank,
             size,
             is_intercomm: false,
+            eager_limit,
+            collectives: AtomicUsize::new(0),
             ports,
+            addresses: addresses.into_iter().map(Some).collect(),
+            local_group: None,
+            children: Vec::new(),
             child_commands: Vec::new(),
-        })
+        }
     }
 
-    pub(crate) fn intercomm(universe: Weak<RwLock<Universe>>, rank: usize) -> error::Result<Self> {
-        assert!(rank < 2);
+    // `local_addresses` holds the port name of every rank in this rank's group, and
+    // `remote_addresses` that of every rank in the other group. `port` is the listening port of
+    // this rank, if the intercommunicator has one of its own.
+    pub(crate) fn intercomm(
+        universe: Weak<RwLock<Universe>>,
+        engine: Arc<Engine>,
+        context_id: u32,
+        rank: usize,
+        port: Option<Port>,
+        local_addresses: Vec<String>,
+        remote_addresses: Vec<String>,
+    ) -> Self {
+        let local_group = Comm::group(
+            universe.clone(),
+            engine.clone(),
+            context_id | LOCAL_GROUP_CONTEXT_BIT,
+            rank,
+            local_addresses,
+        );
+        let size = local_group.size();
+        if let Some(ref port) = port {
+            assert!(local_group.address(rank) == port.name());
+        }
 
-        Ok(Self {
+        let mut ports = Vec::new();
+        for _ in 0..size {
+            ports.push(None);
+        }
+        ports[rank] = port;
+
+        let eager_limit = AtomicU64::new(engine.eager_limit());
+
+        Self {
             universe,
+            engine,
             name: None,
+            context_id,
             rank,
-            size: 2,
+            size,
             is_intercomm: true,
-            ports: vec![Some(Port::new()?), None],
+            eager_limit,
+            collectives: AtomicUsize::new(0),
+            ports,
+            addresses: remote_addresses.into_iter().map(Some).collect(),
+            local_group: Some(Box::new(local_group)),
+            children: Vec::new(),
             child_commands: Vec::new(),
-        })
+        }
     }
 
     pub fn name(&self) -> Option<&str> {
@@ -105,14 +256,51 @@ impl Comm {
         self.size
     }
 
+    // The number of ranks that messages can be sent to: the size of the remote group of an
+    // intercommunicator, or of the communicator itself.
+    pub fn remote_size(&self) -> usize {
+        self.addresses.len()
+    }
+
     pub fn is_intercomm(&self) -> bool {
         self.is_intercomm
     }
 
+    pub fn eager_limit(&self) -> u64 {
+        self.eager_limit.load(Ordering::Relaxed)
+    }
+
+    // Only affects messages sent from this rank. A limit above a chunk is lowered to a chunk.
+    pub fn set_eager_limit(&self, eager_limit: u64) {
+        let eager_limit = eager_limit.min(engine::MAX_EAGER_LIMIT);
+        self.eager_limit.store(eager_limit, Ordering::Relaxed);
+    }
+
     pub fn port(&self, idx: usize) -> &Option<Port> {
         &self.ports[idx]
     }
 
+    pub(crate) fn context_id(&self) -> u32 {
+        self.context_id
+    }
+
+    pub(crate) fn engine(&self) -> &Arc<Engine> {
+        &self.engine
+    }
+
+    pub(crate) fn address(&self, rank: usize) -> &str {
+        self.addresses[rank]
+            .as_ref()
+            .expect("The port of the requested rank is not known.")
+    }
+
+    // The local group of an intercommunicator.
+    pub(crate) fn local_group(&self) -> &Comm {
+        self.local_group
+            .as_ref()
+            .expect("Only intercommunicators have a local group.")
+    }
+
     pub fn attach_children(&mut self, commands: Vec<Command>) {
         self.child_commands = commands;
     }
@@ -123,6 +311,10 @@ impl Comm {
             .expect("The MPI Universe has been destroyed - this Comm object was leaked")
     }
 
+    pub(crate) fn weak_universe(&self) -> Weak<RwLock<Universe>> {
+        self.universe.clone()
+    }
+
     pub fn spawn_multiple_async<'a, I: IntoIterator<Item = SpawnCommandInfo>>(
         &'a self,
         commands: Option<I>,
@@ -132,16 +324,36 @@ impl Comm {
         assert!(root == self.rank());
         assert!(self.size() == 1);
 
+        let commands = commands.expect("The root rank must supply commands to run.");
+        self.spawn_job(commands, true)
+    }
+
+    // Launches the ranks of `commands` as a job of their own. The ranks of a spawned job have this
+    // rank's group as their parent, and those of the initial job mpiexec launches have none.
+    fn spawn_job<'a, I: IntoIterator<Item = SpawnCommandInfo>>(
+        &'a self,
+        commands: I,
+        spawned: bool,
+    ) -> impl Future<Item = SpawnMultipleResult, Error = super::Error> + 'a {
         let commands: Vec<_> = commands
-            .expect("The root rank must supply commands to run.")
             .into_iter()
             .map(|command| Arc::new(command))
             .collect();
 
-        future::result(Comm::intercomm(self.universe.clone(), 0)).and_then(move |intercomm| {
+        let universe = self.universe.clone();
+        let engine = self.engine.clone();
+        let port = Port::new(engine.clone());
+
+        future::result(port).and_then(move |port| {
             let world_size: usize = commands.iter().map(|command| command.max_procs).sum();
 
-            let children: Vec<_> = commands
+            // The children announce themselves to the intercomm's port. Once all of them have, the
+            // engine sends each of them the port of every other rank, and hands the ports over
+            // here.
+            let job_id = new_job_id();
+            let ports = engine.expect_job(job_id, world_size);
+
+            let (children, results) = commands
                 .iter()
                 .flat_map(|spawn_command| {
                     (0..spawn_command.max_procs)
@@ -151,51 +363,70 @@ impl Comm {
                 })
                 .enumerate()
                 .map(|(world_rank, spawn_command)| {
-                    future::result(
-                        Command::new(&spawn_command.command)
-                            .env("EMPIRE_COMM_WORLD_RANK", format!("{}", world_rank))
-                            .env("EMPIRE_COMM_WORLD_SIZE", format!("{}", world_size))
-                            .env(
-                                "EMPIRE_COMM_WORLD_PARENT_PORT",
-                                format!("{}", intercomm.port(0).as_ref().unwrap().name()),
-                            )
-                            .args(&spawn_command.args)
-                            .spawn_async(),
-                    ).flatten()
-                        .map_err(move |err| {
-                            if err.kind() == io::ErrorKind::NotFound {
+                    let child = Command::new(&spawn_command.command)
+                        .env("EMPIRE_JOB_ID", format!("{}", job_id))
+                        .env("EMPIRE_COMM_WORLD_RANK", format!("{}", world_rank))
+                        .env("EMPIRE_COMM_WORLD_SIZE", format!("{}", world_size))
+                        .env("EMPIRE_COMM_WORLD_PARENT_PORT", port.name())
+                        .env("EMPIRE_SPAWNED", if spawned { "1" } else { "0" })
+                        .args(&spawn_command.args)
+                        .spawn_async();
+
+                    // We want to track the result of each rank separately.
+                    match child {
+                        Ok(child) => (Some(child), Ok(())),
+                        Err(err) => {
+                            let err = if err.kind() == io::ErrorKind::NotFound {
                                 Error::CommandNotFound(spawn_command.command.clone())
                             } else {
                                 err.into()
-                            }
-                        })
-                        .then(|child_result| {
-                            // This is a Result<Result<_>>. This is intentional: we want to track
-                            // the result of each rank separately.
-                            future::done(Ok(child_result))
-                        })
-                })
-                .collect();
-
-            // Wait for all children to either exit or call MPI_Init (unimplemented)
-            future::join_all(children).map(|children_results| SpawnMultipleResult {
-                comm: intercomm,
-                results: children_results
-                    .into_iter()
-                    .map(|child_result| -> super::Result<()> {
-                        let status = child_result?;
-
-                        if status.success() {
-                            Ok(())
-                        } else {
-                            Err(Error::FailExitCode(status.code().unwrap_or(-1)))
+                            };
+                            (None, Err(err))
                         }
-                    })
-                    .collect(),
+                    }
+                })
+                .unzip();
+
+            // Wait for all children to either exit or call MPI_Init. If they never all call it,
+            // the intercomm has no one on the other side.
+            let launch = Launch {
+                children,
+                results,
+                ports,
+            };
+            launch.map(move |(ports, children, results)| {
+                let address = port.name().to_owned();
+                let mut intercomm = Comm::intercomm(
+                    universe,
+                    engine,
+                    PARENT_CONTEXT_ID,
+                    0,
+                    Some(port),
+                    vec![address],
+                    ports.unwrap_or_default(),
+                );
+                intercomm.children = children;
+
+                SpawnMultipleResult {
+                    comm: intercomm,
+                    results,
+                }
             })
         })
     }
 
+    // Waits for every rank spawned along with this intercomm to exit. Ranks whose outcome was
+    // already in the results of the spawn are Ok here.
+    pub fn wait_children(&mut self) -> Vec<super::Result<()>> {
+        mem::replace(&mut self.children, Vec::new())
+            .into_iter()
+            .map(|child| match child {
+                Some(child) => child.wait().map_err(Into::into).and_then(exit_result),
+                None => Ok(()),
+            })
+            .collect()
+    }
+
     pub fn spawn_multiple<I: IntoIterator<Item = SpawnCommandInfo>>(
         &self,
         commands: Option<I>,
@@ -220,4 +451,28 @@ impl Comm {
     ) -> super::Result<SpawnMultipleResult> {
         self.spawn_multiple_root_async(commands, root).wait()
     }
+
+    // Launches the initial job of an MPI program, as mpiexec does.
+    pub fn launch_async<'a, I: IntoIterator<Item = SpawnCommandInfo> + 'a>(
+        &'a self,
+        commands: I,
+    ) -> impl Future<Item = SpawnMultipleResult, Error = super::Error> + 'a {
+        self.spawn_job(commands, false)
+    }
+
+    pub fn launch<I: IntoIterator<Item = SpawnCommandInfo>>(
+        &self,
+        commands: I,
+    ) -> super::Result<SpawnMultipleResult> {
+        self.launch_async(commands).wait()
+    }
+}
+
+impl Drop for Comm {
+    // Spawned ranks outlive the intercomm they were spawned with, rather than being killed with it.
+    fn drop(&mut self) {
+        for child in self.children.drain(..).flatten() {
+            child.forget();
+        }
+    }
 }
@@ -0,0 +1,469 @@
+use super::{arch::Arch, datatype::Primitive};
+
+use std::ptr;
+
+// Where the data of one element of a datatype lies, as nested loops over contiguous runs of
+// primitives. Datatypes build their dataloop out of those of the datatypes they are made from, and
+// committing one compiles it: loops that add nothing are removed and adjacent runs are merged, so
+// a vector of a million doubles is one loop, and a contiguous one is a single run.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Dataloop {
+    // `count` primitives laid end to end.
+    Run { primitive: Primitive, count: usize },
+    // `count` copies of `body`, `stride` bytes apart.
+    Repeat {
+        count: usize,
+        stride: isize,
+        body: Box<Dataloop>,
+    },
+    // Each part at its own displacement, in order.
+    Sequence(Vec<(isize, Dataloop)>),
+}
+
+impl Dataloop {
+    pub fn empty() -> Self {
+        Dataloop::Sequence(Vec::new())
+    }
+
+    pub fn run(primitive: Primitive, count: usize) -> Self {
+        Dataloop::Run { primitive, count }
+    }
+
+    pub fn repeat(count: usize, stride: isize, body: Dataloop) -> Self {
+        Dataloop::Repeat {
+            count,
+            stride,
+            body: Box::new(body),
+        }
+    }
+
+    // The number of bytes of data.
+    pub fn size(&self) -> u64 {
+        match *self {
+            Dataloop::Run { primitive, count } => primitive.size() as u64 * count as u64,
+            Dataloop::Repeat {
+                count, ref body, ..
+            } => count as u64 * body.size(),
+            Dataloop::Sequence(ref parts) => parts.iter().map(|&(_, ref part)| part.size()).sum(),
+        }
+    }
+
+    // The number of primitives.
+    pub fn primitives(&self) -> u64 {
+        match *self {
+            Dataloop::Run { count, .. } => count as u64,
+            Dataloop::Repeat {
+                count, ref body, ..
+            } => count as u64 * body.primitives(),
+            Dataloop::Sequence(ref parts) => {
+                parts.iter().map(|&(_, ref part)| part.primitives()).sum()
+            }
+        }
+    }
+
+    // The number of bytes of data in a machine's layout.
+    pub(crate) fn size_in(&self, arch: &Arch) -> u64 {
+        match *self {
+            Dataloop::Run { primitive, count } => primitive.size_in(arch) as u64 * count as u64,
+            Dataloop::Repeat {
+                count, ref body, ..
+            } => count as u64 * body.size_in(arch),
+            Dataloop::Sequence(ref parts) => parts
+                .iter()
+                .map(|&(_, ref part)| part.size_in(arch))
+                .sum(),
+        }
+    }
+
+    // True if the data is one block of bytes starting at offset zero, in the order it is packed.
+    pub fn is_dense(&self) -> bool {
+        match *self {
+            Dataloop::Run { .. } => true,
+            Dataloop::Repeat {
+                count,
+                stride,
+                ref body,
+            } => count <= 1 || (body.is_dense() && stride == body.size() as isize),
+            Dataloop::Sequence(ref parts) => {
+                let mut offset = 0;
+                for &(displacement, ref part) in parts {
+                    if displacement != offset as isize || !part.is_dense() {
+                        return false;
+                    }
+                    offset += part.size();
+                }
+                true
+            }
+        }
+    }
+
+    pub fn compile(&self) -> Self {
+        match *self {
+            Dataloop::Run { .. } => self.clone(),
+            Dataloop::Repeat {
+                count,
+                stride,
+                ref body,
+            } => {
+                let body = body.compile();
+                if count == 0 || body.size() == 0 {
+                    return Dataloop::empty();
+                }
+                if count == 1 {
+                    return body;
+                }
+
+                match body {
+                    Dataloop::Run {
+                        primitive,
+                        count: run,
+                    } if stride == (primitive.size() * run) as isize =>
+                    {
+                        Dataloop::run(primitive, run * count)
+                    }
+                    // A repeated part that isn't at the start of its element is the same as the
+                    // repeat of the part, displaced.
+                    Dataloop::Sequence(mut parts) => if parts.len() == 1 {
+                        let (displacement, part) = parts.pop().unwrap();
+                        Dataloop::Sequence(vec![
+                            (displacement, Dataloop::repeat(count, stride, part)),
+                        ])
+                    } else {
+                        Dataloop::repeat(count, stride, Dataloop::Sequence(parts))
+                    },
+                    body => Dataloop::repeat(count, stride, body),
+                }
+            }
+            Dataloop::Sequence(ref parts) => {
+                let mut compiled: Vec<(isize, Dataloop)> = Vec::with_capacity(parts.len());
+                for &(displacement, ref part) in parts {
+                    match part.compile() {
+                        Dataloop::Sequence(inner) => for (inner_displacement, part) in inner {
+                            push_part(&mut compiled, displacement + inner_displacement, part);
+                        },
+                        part => push_part(&mut compiled, displacement, part),
+                    }
+                }
+
+                if compiled.len() == 1 && compiled[0].0 == 0 {
+                    compiled.pop().unwrap().1
+                } else {
+                    Dataloop::Sequence(compiled)
+                }
+            }
+        }
+    }
+
+    // The runs of `count` elements laid `extent` bytes apart, in the order they are packed.
+    pub fn runs(&self, count: usize, extent: isize) -> Runs {
+        self.runs_from(0, count, extent)
+    }
+
+    // Like `runs`, but starting at element `first`.
+    pub fn runs_from(&self, first: usize, count: usize, extent: isize) -> Runs {
+        Runs {
+            root: self,
+            elements: count,
+            extent,
+            element: first.min(count),
+            stack: Vec::new(),
+        }
+    }
+}
+
+// Appends a compiled part to a sequence, merging it into the previous part if both are runs of the
+// same primitive that meet.
+fn push_part(parts: &mut Vec<(isize, Dataloop)>, displacement: isize, part: Dataloop) {
+    if part.size() == 0 {
+        return;
+    }
+
+    if let Some(&mut (
+        previous_displacement,
+        Dataloop::Run {
+            primitive,
+            ref mut count,
+        },
+    )) = parts.last_mut()
+    {
+        if let Dataloop::Run {
+            primitive: next,
+            count: more,
+        } = part
+        {
+            let end = previous_displacement + (primitive.size() * *count) as isize;
+            if next == primitive && displacement == end {
+                *count += more;
+                return;
+            }
+        }
+    }
+
+    parts.push((displacement, part));
+}
+
+// A position within a dataloop: the loop, where it starts, and which of its iterations or parts
+// comes next.
+struct Level<'a> {
+    dataloop: &'a Dataloop,
+    offset: isize,
+    next: usize,
+}
+
+// Iterates over the runs of a dataloop as the byte offset of the run, its primitive and the number
+// of primitives in it. Walking the loops doesn't allocate beyond one level per nested loop.
+pub struct Runs<'a> {
+    root: &'a Dataloop,
+    elements: usize,
+    extent: isize,
+    element: usize,
+    stack: Vec<Level<'a>>,
+}
+
+impl<'a> Iterator for Runs<'a> {
+    type Item = (isize, Primitive, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let child = match self.stack.last_mut() {
+                None => {
+                    if self.element == self.elements {
+                        return None;
+                    }
+                    let offset = self.element as isize * self.extent;
+                    self.element += 1;
+                    Level {
+                        dataloop: self.root,
+                        offset,
+                        next: 0,
+                    }
+                }
+                Some(level) => {
+                    let dataloop: &'a Dataloop = level.dataloop;
+                    match *dataloop {
+                        Dataloop::Run { primitive, count } => {
+                            let offset = level.offset;
+                            self.stack.pop();
+                            return Some((offset, primitive, count));
+                        }
+                        Dataloop::Repeat {
+                            count,
+                            stride,
+                            ref body,
+                        } => {
+                            if level.next == count {
+                                self.stack.pop();
+                                continue;
+                            }
+                            let offset = level.offset + level.next as isize * stride;
+                            level.next += 1;
+                            Level {
+                                dataloop: body,
+                                offset,
+                                next: 0,
+                            }
+                        }
+                        Dataloop::Sequence(ref parts) => {
+                            if level.next == parts.len() {
+                                self.stack.pop();
+                                continue;
+                            }
+                            let (displacement, ref part) = parts[level.next];
+                            level.next += 1;
+                            Level {
+                                dataloop: part,
+                                offset: level.offset + displacement,
+                                next: 0,
+                            }
+                        }
+                    }
+                }
+            };
+            self.stack.push(child);
+        }
+    }
+}
+
+// Iterates over runs as the offset and length of each block of bytes, merging runs that meet even
+// if their primitives differ, so densely packed structs are copied a block at a time.
+struct Blocks<'a> {
+    runs: Runs<'a>,
+    pending: Option<(isize, usize)>,
+}
+
+impl<'a> Iterator for Blocks<'a> {
+    type Item = (isize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (offset, primitive, count) in self.runs.by_ref() {
+            let len = primitive.size() * count;
+            match self.pending {
+                Some((start, pending_len)) if start + pending_len as isize == offset => {
+                    self.pending = Some((start, pending_len + len));
+                }
+                pending => {
+                    self.pending = Some((offset, len));
+                    if pending.is_some() {
+                        return pending;
+                    }
+                }
+            }
+        }
+        self.pending.take()
+    }
+}
+
+// The blocks that hold bytes `position..` of the data of `count` elements, the first of them cut
+// short at the front.
+fn blocks_from<'a>(
+    dataloop: &'a Dataloop,
+    count: usize,
+    extent: isize,
+    position: u64,
+) -> impl Iterator<Item = (isize, usize)> + 'a {
+    let size = dataloop.size().max(1);
+    let mut skip = (position % size) as usize;
+    let blocks = Blocks {
+        runs: dataloop.runs_from((position / size) as usize, count, extent),
+        pending: None,
+    };
+    blocks.filter_map(move |(offset, len)| {
+        if skip >= len {
+            skip -= len;
+            None
+        } else {
+            let block = (offset + skip as isize, len - skip);
+            skip = 0;
+            Some(block)
+        }
+    })
+}
+
+// Appends the data of `count` elements at `base` to `out`. The caller promises that every byte
+// the dataloop describes is readable.
+pub(crate) unsafe fn pack(
+    dataloop: &Dataloop,
+    base: *const u8,
+    count: usize,
+    extent: isize,
+    out: &mut Vec<u8>,
+) {
+    let len = (dataloop.size() * count as u64) as usize;
+    pack_segment(dataloop, base, count, extent, 0, len, out);
+}
+
+// Appends bytes `position..position + len` of the data of `count` elements at `base` to `out`,
+// so that large data can be packed a piece at a time. The caller promises as for `pack`.
+pub(crate) unsafe fn pack_segment(
+    dataloop: &Dataloop,
+    base: *const u8,
+    count: usize,
+    extent: isize,
+    position: u64,
+    len: usize,
+    out: &mut Vec<u8>,
+) {
+    out.reserve(len);
+    let start = out.len();
+    let packed = pack_raw(dataloop, base, count, extent, position, len, out.as_mut_ptr().add(start));
+    out.set_len(start + packed);
+}
+
+// Copies the data of `count` elements at `base` into `out`, which has room for all of it. The
+// caller promises as for `pack`.
+pub(crate) unsafe fn pack_to(
+    dataloop: &Dataloop,
+    base: *const u8,
+    count: usize,
+    extent: isize,
+    out: &mut [u8],
+) {
+    pack_raw(dataloop, base, count, extent, 0, out.len(), out.as_mut_ptr());
+}
+
+// Copies up to `len` bytes of the data from `position` on to `out`. Returns how many it copied.
+unsafe fn pack_raw(
+    dataloop: &Dataloop,
+    base: *const u8,
+    count: usize,
+    extent: isize,
+    position: u64,
+    len: usize,
+    out: *mut u8,
+) -> usize {
+    let mut packed = 0;
+    for (offset, block) in blocks_from(dataloop, count, extent, position) {
+        if packed == len {
+            break;
+        }
+        let block = block.min(len - packed);
+        ptr::copy_nonoverlapping(base.wrapping_offset(offset), out.add(packed), block);
+        packed += block;
+    }
+    packed
+}
+
+// Scatters `data` into the elements at `base` as bytes `position..` of their data, stopping early
+// if it runs out. The caller promises that every byte the dataloop describes is writable.
+pub(crate) unsafe fn unpack_segment(
+    dataloop: &Dataloop,
+    base: *mut u8,
+    count: usize,
+    extent: isize,
+    position: u64,
+    data: &[u8],
+) {
+    let mut data = data;
+    for (offset, len) in blocks_from(dataloop, count, extent, position) {
+        if data.is_empty() {
+            break;
+        }
+        let len = len.min(data.len());
+        ptr::copy_nonoverlapping(data.as_ptr(), base.wrapping_offset(offset), len);
+        data = &data[len..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::datatype::Datatype;
+
+    #[test]
+    fn segments_pack_and_unpack_like_the_whole() {
+        // An int and a double with a gap between them, so the data isn't contiguous.
+        let mut datatype = Datatype::structure(
+            &[1, 1],
+            &[0, 8],
+            &[
+                Datatype::primitive(Primitive::Int),
+                Datatype::primitive(Primitive::Double),
+            ],
+        );
+        datatype.commit();
+        let dataloop = datatype.dataloop().unwrap();
+        let extent = datatype.extent();
+        let memory: Vec<u8> = (0..5 * extent as u8).collect();
+
+        let mut whole = Vec::new();
+        unsafe { pack(dataloop, memory.as_ptr(), 5, extent, &mut whole) };
+
+        let mut pieces = Vec::new();
+        for position in (0..whole.len()).step_by(7) {
+            unsafe {
+                pack_segment(dataloop, memory.as_ptr(), 5, extent, position as u64, 7, &mut pieces)
+            };
+        }
+        assert_eq!(pieces, whole);
+
+        let mut unpacked = vec![0; memory.len()];
+        for (index, piece) in whole.chunks(7).enumerate() {
+            let position = (index * 7) as u64;
+            unsafe { unpack_segment(dataloop, unpacked.as_mut_ptr(), 5, extent, position, piece) };
+        }
+        for (byte, (&unpacked, &original)) in unpacked.iter().zip(&memory).enumerate() {
+            let gap = byte as isize % extent >= 4 && (byte as isize) % extent < 8;
+            assert_eq!(unpacked, if gap { 0 } else { original });
+        }
+    }
+}
@@ -0,0 +1,1310 @@
+use super::{arch::{self, Arch}, dataloop::{self, Dataloop}};
+
+use std::{fmt, mem, slice, any::TypeId, cmp::{max, min}, collections::BTreeMap,
+          marker::PhantomData, os::raw::{c_char, c_int, c_long, c_longlong, c_short},
+          sync::{Arc, Mutex}};
+
+// The basic types that every datatype is ultimately made of. A datatype's type signature is the
+// sequence of primitives in one of its elements, and two datatypes can only communicate with each
+// other if their signatures agree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Primitive {
+    Char,
+    SignedChar,
+    UnsignedChar,
+    Short,
+    UnsignedShort,
+    Int,
+    Unsigned,
+    Long,
+    UnsignedLong,
+    LongLong,
+    UnsignedLongLong,
+    Float,
+    Double,
+    LongDouble,
+    WChar,
+    CBool,
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+    Uint8,
+    Uint16,
+    Uint32,
+    Uint64,
+    Aint,
+    Offset,
+    Count,
+    CFloatComplex,
+    CDoubleComplex,
+    CLongDoubleComplex,
+    CxxBool,
+    CxxFloatComplex,
+    CxxDoubleComplex,
+    CxxLongDoubleComplex,
+    Byte,
+    Packed,
+}
+
+// long double is 80-bit extended precision padded to 16 bytes on x86-64 outside of Windows, and
+// plain double elsewhere that matters to us.
+#[cfg(all(target_arch = "x86_64", not(windows)))]
+const LONG_DOUBLE_SIZE: usize = 16;
+#[cfg(not(all(target_arch = "x86_64", not(windows))))]
+const LONG_DOUBLE_SIZE: usize = 8;
+
+#[cfg(windows)]
+const WCHAR_SIZE: usize = 2;
+#[cfg(not(windows))]
+const WCHAR_SIZE: usize = 4;
+
+impl Primitive {
+    pub fn size(&self) -> usize {
+        match *self {
+            Primitive::Char | Primitive::SignedChar | Primitive::UnsignedChar => {
+                mem::size_of::<c_char>()
+            }
+            Primitive::Short | Primitive::UnsignedShort => mem::size_of::<c_short>(),
+            Primitive::Int | Primitive::Unsigned => mem::size_of::<c_int>(),
+            Primitive::Long | Primitive::UnsignedLong => mem::size_of::<c_long>(),
+            Primitive::LongLong | Primitive::UnsignedLongLong => mem::size_of::<c_longlong>(),
+            Primitive::Float => 4,
+            Primitive::Double => 8,
+            Primitive::LongDouble => LONG_DOUBLE_SIZE,
+            Primitive::WChar => WCHAR_SIZE,
+            Primitive::CBool | Primitive::CxxBool => 1,
+            Primitive::Int8 | Primitive::Uint8 | Primitive::Byte | Primitive::Packed => 1,
+            Primitive::Int16 | Primitive::Uint16 => 2,
+            Primitive::Int32 | Primitive::Uint32 => 4,
+            Primitive::Int64 | Primitive::Uint64 => 8,
+            Primitive::Aint => mem::size_of::<isize>(),
+            Primitive::Offset | Primitive::Count => 8,
+            Primitive::CFloatComplex | Primitive::CxxFloatComplex => 8,
+            Primitive::CDoubleComplex | Primitive::CxxDoubleComplex => 16,
+            Primitive::CLongDoubleComplex | Primitive::CxxLongDoubleComplex => {
+                2 * LONG_DOUBLE_SIZE
+            }
+        }
+    }
+
+    // The size in a machine's layout, which only differs from the native size for the few
+    // primitives whose size isn't the same everywhere.
+    pub(crate) fn size_in(&self, arch: &Arch) -> usize {
+        match *self {
+            Primitive::Long | Primitive::UnsignedLong => arch.long_size,
+            Primitive::Aint => arch.aint_size,
+            Primitive::WChar => arch.wchar_size,
+            Primitive::LongDouble => arch.long_double.size(),
+            Primitive::CLongDoubleComplex | Primitive::CxxLongDoubleComplex => {
+                2 * arch.long_double.size()
+            }
+            _ => self.size(),
+        }
+    }
+
+    // The size in the external32 representation, which is the same on every machine.
+    pub fn external_size(&self) -> usize {
+        self.size_in(&Arch::external32())
+    }
+
+    // The alignment the C compiler gives the type, which decides the padding in pair types and the
+    // extent of structs built from them.
+    pub fn alignment(&self) -> usize {
+        match *self {
+            Primitive::CFloatComplex | Primitive::CxxFloatComplex => 4,
+            Primitive::CDoubleComplex | Primitive::CxxDoubleComplex => 8,
+            Primitive::CLongDoubleComplex | Primitive::CxxLongDoubleComplex => LONG_DOUBLE_SIZE,
+            _ => self.size(),
+        }
+    }
+}
+
+// The predefined datatypes that describe a value followed by an int, as used by MPI_MINLOC and
+// MPI_MAXLOC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Pair {
+    FloatInt,
+    DoubleInt,
+    LongInt,
+    TwoInt,
+    ShortInt,
+    LongDoubleInt,
+}
+
+impl Pair {
+    pub fn value(&self) -> Primitive {
+        match *self {
+            Pair::FloatInt => Primitive::Float,
+            Pair::DoubleInt => Primitive::Double,
+            Pair::LongInt => Primitive::Long,
+            Pair::TwoInt => Primitive::Int,
+            Pair::ShortInt => Primitive::Short,
+            Pair::LongDoubleInt => Primitive::LongDouble,
+        }
+    }
+}
+
+// The order in which the dimensions of a multidimensional array are laid out: C arrays vary the
+// last index fastest, Fortran arrays the first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Order {
+    C,
+    Fortran,
+}
+
+// How one dimension of a distributed array is split across a dimension of the process grid. Block
+// and cyclic distributions take the size of their blocks, or None for the default: an even share
+// for a block distribution, and one element for a cyclic one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Distribution {
+    Block(Option<usize>),
+    Cyclic(Option<usize>),
+    // Every process has the whole dimension; its process grid dimension must be 1.
+    Undistributed,
+}
+
+impl Distribution {
+    // The ranges of indices owned by the process at `coord` of `psize`, as starts and lengths.
+    fn owned(&self, gsize: usize, psize: usize, coord: usize) -> Vec<(usize, usize)> {
+        match *self {
+            Distribution::Block(darg) => {
+                let block = darg.unwrap_or((gsize + psize - 1) / psize);
+                let start = min(coord * block, gsize);
+                vec![(start, min(start + block, gsize) - start)]
+            }
+            Distribution::Cyclic(darg) => {
+                let block = darg.unwrap_or(1);
+                (0..)
+                    .map(|round| (round * psize + coord) * block)
+                    .take_while(|&start| start < gsize)
+                    .map(|start| (start, min(block, gsize - start)))
+                    .collect()
+            }
+            Distribution::Undistributed => vec![(0, gsize)],
+        }
+    }
+}
+
+// How a datatype was made, with the arguments it was made from.
+#[derive(Clone, Debug)]
+pub enum Constructor {
+    Primitive(Primitive),
+    Pair(Pair),
+    Contiguous {
+        count: usize,
+        old: Datatype,
+    },
+    // The stride of a vector is in elements of `old`, and that of an hvector in bytes.
+    Vector {
+        count: usize,
+        blocklength: usize,
+        stride: isize,
+        old: Datatype,
+    },
+    Hvector {
+        count: usize,
+        blocklength: usize,
+        stride: isize,
+        old: Datatype,
+    },
+    // Likewise, indexed displacements are in elements and hindexed ones in bytes.
+    Indexed {
+        blocklengths: Vec<usize>,
+        displacements: Vec<isize>,
+        old: Datatype,
+    },
+    Hindexed {
+        blocklengths: Vec<usize>,
+        displacements: Vec<isize>,
+        old: Datatype,
+    },
+    IndexedBlock {
+        blocklength: usize,
+        displacements: Vec<isize>,
+        old: Datatype,
+    },
+    Struct {
+        blocklengths: Vec<usize>,
+        displacements: Vec<isize>,
+        types: Vec<Datatype>,
+    },
+    Subarray {
+        sizes: Vec<usize>,
+        subsizes: Vec<usize>,
+        starts: Vec<usize>,
+        order: Order,
+        old: Datatype,
+    },
+    Darray {
+        size: usize,
+        rank: usize,
+        gsizes: Vec<usize>,
+        distributions: Vec<Distribution>,
+        psizes: Vec<usize>,
+        order: Order,
+        old: Datatype,
+    },
+    Resized {
+        lb: isize,
+        extent: isize,
+        old: Datatype,
+    },
+    Dup(Datatype),
+}
+
+fn round_up(value: usize, alignment: usize) -> usize {
+    (value + alignment - 1) / alignment * alignment
+}
+
+// The lower and upper bounds of `count` copies of something bounded by `lb` and `ub`, laid `stride`
+// bytes apart.
+fn spread(lb: isize, ub: isize, count: usize, stride: isize) -> (isize, isize) {
+    let last = (count as isize - 1) * stride;
+    (lb + min(0, last), ub + max(0, last))
+}
+
+// Everything about a datatype but how it was made.
+#[derive(Clone)]
+struct Layout {
+    size: u64,
+    lb: isize,
+    ub: isize,
+    true_lb: isize,
+    true_ub: isize,
+    alignment: usize,
+    dataloop: Dataloop,
+}
+
+impl Layout {
+    fn primitive(primitive: Primitive) -> Self {
+        let size = primitive.size();
+        Layout {
+            size: size as u64,
+            lb: 0,
+            ub: size as isize,
+            true_lb: 0,
+            true_ub: size as isize,
+            alignment: primitive.alignment(),
+            dataloop: Dataloop::run(primitive, 1),
+        }
+    }
+
+    // A layout with no data and an extent of zero.
+    fn empty(alignment: usize) -> Self {
+        Layout {
+            size: 0,
+            lb: 0,
+            ub: 0,
+            true_lb: 0,
+            true_ub: 0,
+            alignment,
+            dataloop: Dataloop::empty(),
+        }
+    }
+
+    fn extent(&self) -> isize {
+        self.ub - self.lb
+    }
+
+    // `count` blocks of `blocklength` consecutive elements, the starts of the blocks `stride` bytes
+    // apart.
+    fn repeat(&self, count: usize, blocklength: usize, stride: isize) -> Self {
+        if count == 0 || blocklength == 0 {
+            return Layout::empty(self.alignment);
+        }
+
+        let extent = self.extent();
+        let (lb, ub) = spread(self.lb, self.ub, blocklength, extent);
+        let (true_lb, true_ub) = spread(self.true_lb, self.true_ub, blocklength, extent);
+        let (lb, ub) = spread(lb, ub, count, stride);
+        let (true_lb, true_ub) = spread(true_lb, true_ub, count, stride);
+
+        Layout {
+            size: count as u64 * blocklength as u64 * self.size,
+            lb,
+            ub,
+            true_lb,
+            true_ub,
+            alignment: self.alignment,
+            dataloop: Dataloop::repeat(
+                count,
+                stride,
+                Dataloop::repeat(blocklength, extent, self.dataloop.clone()),
+            ),
+        }
+    }
+
+    // Blocks of consecutive elements, each given as its displacement in bytes, its length and the
+    // layout of its elements. Empty blocks don't count towards the bounds.
+    fn blocks(blocks: &[(isize, usize, &Layout)]) -> Self {
+        let alignment = blocks
+            .iter()
+            .map(|&(_, _, layout)| layout.alignment)
+            .max()
+            .unwrap_or(1);
+
+        let mut parts = Vec::with_capacity(blocks.len());
+        let mut bounds: Option<(isize, isize, isize, isize)> = None;
+        let mut size = 0;
+        for &(displacement, blocklength, layout) in blocks {
+            let block = layout.repeat(1, blocklength, 0);
+            if blocklength == 0 {
+                continue;
+            }
+
+            bounds = Some(match bounds {
+                None => (
+                    displacement + block.lb,
+                    displacement + block.ub,
+                    displacement + block.true_lb,
+                    displacement + block.true_ub,
+                ),
+                Some((lb, ub, true_lb, true_ub)) => (
+                    min(lb, displacement + block.lb),
+                    max(ub, displacement + block.ub),
+                    min(true_lb, displacement + block.true_lb),
+                    max(true_ub, displacement + block.true_ub),
+                ),
+            });
+            size += block.size;
+            parts.push((displacement, block.dataloop));
+        }
+
+        match bounds {
+            Some((lb, ub, true_lb, true_ub)) => Layout {
+                size,
+                lb,
+                ub,
+                true_lb,
+                true_ub,
+                alignment,
+                dataloop: Dataloop::Sequence(parts),
+            },
+            None => Layout::empty(alignment),
+        }
+    }
+
+    fn resized(&self, lb: isize, extent: isize) -> Self {
+        Layout {
+            lb,
+            ub: lb + extent,
+            ..self.clone()
+        }
+    }
+
+    // An array whose dimensions are given in `order` as their size and the ranges of indices that
+    // are part of the datatype. The extent is that of the whole array.
+    fn grid(dims: &[(usize, Vec<(usize, usize)>)], order: Order, old: &Layout) -> Self {
+        let fastest_first: Vec<_> = match order {
+            Order::C => dims.iter().rev().collect(),
+            Order::Fortran => dims.iter().collect(),
+        };
+
+        // Each dimension is built from rows of the dimensions that vary faster, resized to the
+        // whole row so that consecutive rows are laid out one after the other.
+        let mut layout = old.clone();
+        let mut stride = old.extent();
+        for &&(size, ref ranges) in &fastest_first {
+            let blocks: Vec<_> = ranges
+                .iter()
+                .map(|&(start, len)| (start as isize * stride, len, &layout))
+                .collect();
+            let dim = Layout::blocks(&blocks);
+            stride *= size as isize;
+            layout = dim.resized(0, stride);
+        }
+        layout
+    }
+}
+
+struct Type {
+    constructor: Constructor,
+    layout: Layout,
+}
+
+// Describes the layout of the elements in a buffer. Datatypes are immutable and cheap to clone.
+// Only committed datatypes can describe buffers for communication; predefined ones are committed
+// from the start.
+#[derive(Clone)]
+pub struct Datatype {
+    ty: Arc<Type>,
+    // The compiled dataloop, once the datatype has been committed.
+    compiled: Option<Arc<Dataloop>>,
+}
+
+impl fmt::Debug for Datatype {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Datatype").field(&self.ty.constructor).finish()
+    }
+}
+
+impl Datatype {
+    fn new(constructor: Constructor, layout: Layout) -> Self {
+        Datatype {
+            ty: Arc::new(Type {
+                constructor,
+                layout,
+            }),
+            compiled: None,
+        }
+    }
+
+    fn predefined(constructor: Constructor, layout: Layout) -> Self {
+        let mut datatype = Datatype::new(constructor, layout);
+        datatype.commit();
+        datatype
+    }
+
+    pub fn primitive(primitive: Primitive) -> Self {
+        Datatype::predefined(Constructor::Primitive(primitive), Layout::primitive(primitive))
+    }
+
+    // Laid out like a C struct of the value and an int, so the extent includes any trailing
+    // padding.
+    pub fn pair(pair: Pair) -> Self {
+        let value = Layout::primitive(pair.value());
+        let int = Layout::primitive(Primitive::Int);
+        let int_offset = round_up(value.size as usize, int.alignment) as isize;
+
+        let mut layout = Layout::blocks(&[(0, 1, &value), (int_offset, 1, &int)]);
+        layout.ub = round_up(layout.ub as usize, layout.alignment) as isize;
+        Datatype::predefined(Constructor::Pair(pair), layout)
+    }
+
+    pub fn contiguous(count: usize, old: &Datatype) -> Self {
+        Datatype::new(
+            Constructor::Contiguous {
+                count,
+                old: old.clone(),
+            },
+            old.layout().repeat(1, count, 0),
+        )
+    }
+
+    pub fn vector(count: usize, blocklength: usize, stride: isize, old: &Datatype) -> Self {
+        Datatype::new(
+            Constructor::Vector {
+                count,
+                blocklength,
+                stride,
+                old: old.clone(),
+            },
+            old.layout()
+                .repeat(count, blocklength, stride * old.extent()),
+        )
+    }
+
+    pub fn hvector(count: usize, blocklength: usize, stride: isize, old: &Datatype) -> Self {
+        Datatype::new(
+            Constructor::Hvector {
+                count,
+                blocklength,
+                stride,
+                old: old.clone(),
+            },
+            old.layout().repeat(count, blocklength, stride),
+        )
+    }
+
+    pub fn indexed(blocklengths: &[usize], displacements: &[isize], old: &Datatype) -> Self {
+        assert_eq!(blocklengths.len(), displacements.len());
+
+        let layout = old.layout();
+        let blocks: Vec<_> = blocklengths
+            .iter()
+            .zip(displacements)
+            .map(|(&blocklength, &displacement)| {
+                (displacement * old.extent(), blocklength, layout)
+            })
+            .collect();
+
+        Datatype::new(
+            Constructor::Indexed {
+                blocklengths: blocklengths.to_vec(),
+                displacements: displacements.to_vec(),
+                old: old.clone(),
+            },
+            Layout::blocks(&blocks),
+        )
+    }
+
+    pub fn hindexed(blocklengths: &[usize], displacements: &[isize], old: &Datatype) -> Self {
+        assert_eq!(blocklengths.len(), displacements.len());
+
+        let layout = old.layout();
+        let blocks: Vec<_> = blocklengths
+            .iter()
+            .zip(displacements)
+            .map(|(&blocklength, &displacement)| (displacement, blocklength, layout))
+            .collect();
+
+        Datatype::new(
+            Constructor::Hindexed {
+                blocklengths: blocklengths.to_vec(),
+                displacements: displacements.to_vec(),
+                old: old.clone(),
+            },
+            Layout::blocks(&blocks),
+        )
+    }
+
+    pub fn indexed_block(blocklength: usize, displacements: &[isize], old: &Datatype) -> Self {
+        let layout = old.layout();
+        let blocks: Vec<_> = displacements
+            .iter()
+            .map(|&displacement| (displacement * old.extent(), blocklength, layout))
+            .collect();
+
+        Datatype::new(
+            Constructor::IndexedBlock {
+                blocklength,
+                displacements: displacements.to_vec(),
+                old: old.clone(),
+            },
+            Layout::blocks(&blocks),
+        )
+    }
+
+    // The extent is rounded up to the strictest alignment of the members, the way a C compiler
+    // pads a struct so that arrays of it keep every member aligned.
+    pub fn structure(blocklengths: &[usize], displacements: &[isize], types: &[Datatype]) -> Self {
+        assert_eq!(blocklengths.len(), displacements.len());
+        assert_eq!(blocklengths.len(), types.len());
+
+        let blocks: Vec<_> = blocklengths
+            .iter()
+            .zip(displacements)
+            .zip(types)
+            .map(|((&blocklength, &displacement), datatype)| {
+                (displacement, blocklength, datatype.layout())
+            })
+            .collect();
+
+        let mut layout = Layout::blocks(&blocks);
+        let extent = layout.extent();
+        if extent > 0 {
+            layout.ub = layout.lb + round_up(extent as usize, layout.alignment) as isize;
+        }
+
+        Datatype::new(
+            Constructor::Struct {
+                blocklengths: blocklengths.to_vec(),
+                displacements: displacements.to_vec(),
+                types: types.to_vec(),
+            },
+            layout,
+        )
+    }
+
+    // The block of an array with dimensions `sizes` that starts at `starts` and spans `subsizes`.
+    // The extent is that of the whole array, so consecutive elements are consecutive arrays.
+    pub fn subarray(
+        sizes: &[usize],
+        subsizes: &[usize],
+        starts: &[usize],
+        order: Order,
+        old: &Datatype,
+    ) -> Self {
+        assert!(!sizes.is_empty());
+        assert_eq!(sizes.len(), subsizes.len());
+        assert_eq!(sizes.len(), starts.len());
+
+        let dims: Vec<_> = sizes
+            .iter()
+            .zip(subsizes)
+            .zip(starts)
+            .map(|((&size, &subsize), &start)| {
+                assert!(start + subsize <= size, "The subarray lies outside of the array.");
+                (size, vec![(start, subsize)])
+            })
+            .collect();
+
+        Datatype::new(
+            Constructor::Subarray {
+                sizes: sizes.to_vec(),
+                subsizes: subsizes.to_vec(),
+                starts: starts.to_vec(),
+                order,
+                old: old.clone(),
+            },
+            Layout::grid(&dims, order, old.layout()),
+        )
+    }
+
+    // The part of an array with dimensions `gsizes` that process `rank` of `size` owns when the
+    // array is distributed over a process grid of `psizes`. Ranks are placed in the grid in row
+    // major order whatever the order of the array. The extent is that of the whole array.
+    pub fn darray(
+        size: usize,
+        rank: usize,
+        gsizes: &[usize],
+        distributions: &[Distribution],
+        psizes: &[usize],
+        order: Order,
+        old: &Datatype,
+    ) -> Self {
+        assert!(!gsizes.is_empty());
+        assert_eq!(gsizes.len(), distributions.len());
+        assert_eq!(gsizes.len(), psizes.len());
+        assert_eq!(
+            psizes.iter().product::<usize>(),
+            size,
+            "The process grid must hold every rank."
+        );
+        assert!(rank < size);
+
+        let mut coords = vec![0; psizes.len()];
+        let mut remainder = rank;
+        for (coord, &psize) in coords.iter_mut().zip(psizes).rev() {
+            *coord = remainder % psize;
+            remainder /= psize;
+        }
+
+        let dims: Vec<_> = gsizes
+            .iter()
+            .zip(distributions)
+            .zip(psizes.iter().zip(&coords))
+            .map(|((&gsize, distribution), (&psize, &coord))| {
+                match *distribution {
+                    Distribution::Block(Some(darg)) => assert!(
+                        darg * psize >= gsize,
+                        "The blocks of a block distribution must cover the dimension."
+                    ),
+                    Distribution::Undistributed => assert_eq!(psize, 1),
+                    _ => {}
+                }
+                (gsize, distribution.owned(gsize, psize, coord))
+            })
+            .collect();
+
+        Datatype::new(
+            Constructor::Darray {
+                size,
+                rank,
+                gsizes: gsizes.to_vec(),
+                distributions: distributions.to_vec(),
+                psizes: psizes.to_vec(),
+                order,
+                old: old.clone(),
+            },
+            Layout::grid(&dims, order, old.layout()),
+        )
+    }
+
+    // The same data with new bounds, which changes where consecutive elements start.
+    pub fn resized(lb: isize, extent: isize, old: &Datatype) -> Self {
+        Datatype::new(
+            Constructor::Resized {
+                lb,
+                extent,
+                old: old.clone(),
+            },
+            old.layout().resized(lb, extent),
+        )
+    }
+
+    // A new datatype with the same layout, committed if `old` is.
+    pub fn dup(old: &Datatype) -> Self {
+        Datatype {
+            ty: Arc::new(Type {
+                constructor: Constructor::Dup(old.clone()),
+                layout: old.layout().clone(),
+            }),
+            compiled: old.compiled.clone(),
+        }
+    }
+
+    // Compiles the dataloop so the datatype can describe buffers. Committing a datatype again does
+    // nothing.
+    pub fn commit(&mut self) {
+        if self.compiled.is_none() {
+            self.compiled = Some(Arc::new(self.ty.layout.dataloop.compile()));
+        }
+    }
+
+    pub fn is_committed(&self) -> bool {
+        self.compiled.is_some()
+    }
+
+    // The compiled dataloop of a committed datatype.
+    pub fn dataloop(&self) -> Option<&Dataloop> {
+        self.compiled.as_ref().map(|compiled| &**compiled)
+    }
+
+    fn layout(&self) -> &Layout {
+        &self.ty.layout
+    }
+
+    pub fn constructor(&self) -> &Constructor {
+        &self.ty.constructor
+    }
+
+    // True if both were made by the same constructor call, rather than merely laid out alike.
+    pub fn is_same(&self, other: &Datatype) -> bool {
+        Arc::ptr_eq(&self.ty, &other.ty)
+    }
+
+    // Predefined datatypes are named; derived ones are not.
+    pub fn is_named(&self) -> bool {
+        match self.ty.constructor {
+            Constructor::Primitive(_) | Constructor::Pair(_) => true,
+            _ => false,
+        }
+    }
+
+    // The number of bytes of data in one element.
+    pub fn size(&self) -> u64 {
+        self.layout().size
+    }
+
+    // The number of bytes one element takes in a machine's layout.
+    pub(crate) fn size_in(&self, arch: &Arch) -> u64 {
+        self.dataloop().unwrap_or(&self.layout().dataloop).size_in(arch)
+    }
+
+    // The number of bytes one element takes in the external32 representation.
+    pub fn external_size(&self) -> u64 {
+        self.size_in(&Arch::external32())
+    }
+
+    // The number of whole elements in `bytes` bytes of data, if it is a whole number of them.
+    pub fn count_in(&self, bytes: u64) -> Option<u64> {
+        match self.size() {
+            0 if bytes == 0 => Some(0),
+            0 => None,
+            size if bytes % size == 0 => Some(bytes / size),
+            _ => None,
+        }
+    }
+
+    // The number of bytes that `bytes` bytes of data in a machine's layout take up in the native
+    // layout. Like a receive, it counts only the primitives the data holds whole.
+    pub(crate) fn len_from(&self, arch: &Arch, bytes: u64) -> u64 {
+        let size = self.size_in(arch);
+        if size == 0 {
+            return 0;
+        }
+
+        let dataloop = self.dataloop().unwrap_or(&self.layout().dataloop);
+        let mut len = bytes / size * self.size();
+        let mut rest = bytes % size;
+        for (_, primitive, count) in dataloop.runs(1, self.extent()) {
+            let whole = min(rest / primitive.size_in(arch) as u64, count as u64);
+            len += whole * primitive.size() as u64;
+            rest -= whole * primitive.size_in(arch) as u64;
+            if whole < count as u64 {
+                break;
+            }
+        }
+        len
+    }
+
+    // The number of primitives, the basic elements of the standard, in `bytes` bytes of data.
+    // Data that ends partway through an element still counts the primitives it holds, but none if
+    // it ends partway through a primitive.
+    pub fn basic_elements_in(&self, bytes: u64) -> Option<u64> {
+        let dataloop = self.dataloop().unwrap_or(&self.layout().dataloop);
+        if self.size() == 0 {
+            return if bytes == 0 { Some(0) } else { None };
+        }
+
+        let mut elements = bytes / self.size() * dataloop.primitives();
+        let mut rest = bytes % self.size();
+        for (_, primitive, count) in dataloop.runs(1, self.extent()) {
+            if rest == 0 {
+                break;
+            }
+            let whole = min(rest / primitive.size() as u64, count as u64);
+            elements += whole;
+            rest -= whole * primitive.size() as u64;
+            if whole < count as u64 && rest > 0 {
+                return None;
+            }
+        }
+        Some(elements)
+    }
+
+    // The number of bytes in the first `elements` primitives of a buffer of the datatype.
+    pub fn basic_elements_len(&self, elements: u64) -> u64 {
+        let dataloop = self.dataloop().unwrap_or(&self.layout().dataloop);
+        let primitives = dataloop.primitives();
+        if primitives == 0 {
+            return 0;
+        }
+
+        let mut len = elements / primitives * self.size();
+        let mut rest = elements % primitives;
+        for (_, primitive, count) in dataloop.runs(1, self.extent()) {
+            if rest == 0 {
+                break;
+            }
+            let taken = min(rest, count as u64);
+            len += taken * primitive.size() as u64;
+            rest -= taken;
+        }
+        len
+    }
+
+    pub fn lb(&self) -> isize {
+        self.layout().lb
+    }
+
+    pub fn ub(&self) -> isize {
+        self.layout().ub
+    }
+
+    // The distance between the starts of consecutive elements.
+    pub fn extent(&self) -> isize {
+        self.layout().extent()
+    }
+
+    // The bounds of the bytes that actually hold data, ignoring padding.
+    pub fn true_lb(&self) -> isize {
+        self.layout().true_lb
+    }
+
+    pub fn true_extent(&self) -> isize {
+        self.layout().true_ub - self.layout().true_lb
+    }
+
+    pub fn alignment(&self) -> usize {
+        self.layout().alignment
+    }
+
+    // True if `count` elements occupy `count * size` consecutive bytes starting at the buffer,
+    // which lets them be sent straight from the buffer.
+    pub fn is_contiguous(&self) -> bool {
+        let layout = self.layout();
+        layout.lb == 0
+            && layout.extent() == layout.size as isize
+            && self.dataloop().unwrap_or(&layout.dataloop).is_dense()
+    }
+}
+
+// Memory holding `count` elements of a committed datatype at `base`, which is what messages are
+// sent from. Slices of Equivalence types convert into one.
+pub struct Buffer<'a> {
+    base: *const u8,
+    count: usize,
+    datatype: Datatype,
+    marker: PhantomData<&'a [u8]>,
+}
+
+// Buffers are shared and sent between threads the way the slices they stand for are.
+unsafe impl<'a> Send for Buffer<'a> {}
+unsafe impl<'a> Sync for Buffer<'a> {}
+
+impl<'a> Buffer<'a> {
+    // The caller promises that every byte of data the datatype places in the elements can be read
+    // for as long as 'a.
+    pub unsafe fn new(base: *const u8, count: usize, datatype: Datatype) -> Self {
+        assert!(datatype.is_committed(), "Only committed datatypes can describe buffers.");
+        Buffer {
+            base,
+            count,
+            datatype,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn datatype(&self) -> &Datatype {
+        &self.datatype
+    }
+
+    // Where the elements lie. The datatype says which bytes around it hold their data.
+    pub fn as_ptr(&self) -> *const u8 {
+        self.base
+    }
+
+    // The number of bytes of data, which is the length of the message sent from the buffer.
+    pub fn len(&self) -> u64 {
+        self.count as u64 * self.datatype.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // The number of bytes of data in the external32 representation.
+    pub fn external_len(&self) -> u64 {
+        self.count as u64 * self.datatype.external_size()
+    }
+
+    // Splits the elements into `parts` consecutive buffers of equal length.
+    pub fn split(self, parts: usize) -> Vec<Buffer<'a>> {
+        assert!(self.count % parts == 0, "The buffer does not split evenly.");
+        let (count, stride) = (self.count / parts, self.datatype.extent());
+
+        (0..parts)
+            .map(|part| Buffer {
+                base: self.base.wrapping_offset((part * count) as isize * stride),
+                count,
+                datatype: self.datatype.clone(),
+                marker: PhantomData,
+            })
+            .collect()
+    }
+
+    // The `count` elements starting at element `first`.
+    pub(crate) fn elements(&self, first: usize, count: usize) -> Buffer<'a> {
+        assert!(first + count <= self.count, "The elements are outside of the buffer.");
+        Buffer {
+            base: self.base.wrapping_offset(first as isize * self.datatype.extent()),
+            count,
+            datatype: self.datatype.clone(),
+            marker: PhantomData,
+        }
+    }
+
+    // The same elements, for the engine to pack from once a receiver asks for them. The caller
+    // promises that the alias is gone before 'a ends.
+    pub(crate) unsafe fn alias(&self) -> Buffer<'static> {
+        Buffer::new(self.base, self.count, self.datatype.clone())
+    }
+
+    // Appends the data to `out` straight from the elements.
+    pub fn pack_into(&self, out: &mut Vec<u8>) {
+        if self.is_empty() {
+            return;
+        }
+
+        unsafe {
+            if self.datatype.is_contiguous() {
+                out.extend_from_slice(slice::from_raw_parts(self.base, self.len() as usize));
+            } else {
+                dataloop::pack(
+                    self.datatype.dataloop().unwrap(),
+                    self.base,
+                    self.count,
+                    self.datatype.extent(),
+                    out,
+                );
+            }
+        }
+    }
+
+    // Appends bytes `position..position + len` of the data to `out`, so that a large buffer can be
+    // sent a piece at a time. Stops at the end of the data.
+    pub(crate) fn pack_segment_into(&self, position: u64, len: usize, out: &mut Vec<u8>) {
+        let len = min(len as u64, self.len().saturating_sub(position)) as usize;
+        if len == 0 {
+            return;
+        }
+
+        unsafe {
+            if self.datatype.is_contiguous() {
+                let data = slice::from_raw_parts(self.base.add(position as usize), len);
+                out.extend_from_slice(data);
+            } else {
+                dataloop::pack_segment(
+                    self.datatype.dataloop().unwrap(),
+                    self.base,
+                    self.count,
+                    self.datatype.extent(),
+                    position,
+                    len,
+                    out,
+                );
+            }
+        }
+    }
+
+    // Copies the data into `out`, which must be exactly as long as the data.
+    pub(crate) fn pack_to(&self, out: &mut [u8]) {
+        assert_eq!(out.len() as u64, self.len(), "The data does not fit the output.");
+        if out.is_empty() {
+            return;
+        }
+
+        unsafe {
+            if self.datatype.is_contiguous() {
+                out.copy_from_slice(slice::from_raw_parts(self.base, out.len()));
+            } else {
+                dataloop::pack_to(
+                    self.datatype.dataloop().unwrap(),
+                    self.base,
+                    self.count,
+                    self.datatype.extent(),
+                    out,
+                );
+            }
+        }
+    }
+
+    // Appends the data to `out` in the external32 representation.
+    pub fn pack_external(&self, out: &mut Vec<u8>) {
+        unsafe {
+            arch::pack(
+                self.datatype.dataloop().unwrap(),
+                self.base,
+                self.count,
+                self.datatype.extent(),
+                &Arch::external32(),
+                out,
+            );
+        }
+    }
+}
+
+impl<'a, T: Equivalence> From<&'a [T]> for Buffer<'a> {
+    fn from(slice: &'a [T]) -> Self {
+        unsafe { Buffer::new(slice.as_ptr() as *const u8, slice.len(), T::equivalent_datatype()) }
+    }
+}
+
+// Memory holding `count` elements of a committed datatype at `base`, which is what messages are
+// received into.
+pub struct BufferMut<'a> {
+    base: *mut u8,
+    count: usize,
+    datatype: Datatype,
+    marker: PhantomData<&'a mut [u8]>,
+}
+
+unsafe impl<'a> Send for BufferMut<'a> {}
+unsafe impl<'a> Sync for BufferMut<'a> {}
+
+impl<'a> BufferMut<'a> {
+    // The caller promises that every byte of data the datatype places in the elements can be
+    // written, and nothing else reads or writes them, for as long as 'a.
+    pub unsafe fn new(base: *mut u8, count: usize, datatype: Datatype) -> Self {
+        assert!(datatype.is_committed(), "Only committed datatypes can describe buffers.");
+        BufferMut {
+            base,
+            count,
+            datatype,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn datatype(&self) -> &Datatype {
+        &self.datatype
+    }
+
+    // Like Buffer::as_ptr, for writing the elements.
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.base
+    }
+
+    // The number of bytes of data the buffer has room for.
+    pub fn len(&self) -> u64 {
+        self.count as u64 * self.datatype.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn external_len(&self) -> u64 {
+        self.count as u64 * self.datatype.external_size()
+    }
+
+    // The number of bytes of data the buffer has room for in a machine's layout.
+    pub(crate) fn len_in(&self, arch: &Arch) -> u64 {
+        self.count as u64 * self.datatype.size_in(arch)
+    }
+
+    pub fn split(self, parts: usize) -> Vec<BufferMut<'a>> {
+        assert!(self.count % parts == 0, "The buffer does not split evenly.");
+        let (count, stride) = (self.count / parts, self.datatype.extent());
+
+        (0..parts)
+            .map(|part| BufferMut {
+                base: self.base.wrapping_offset((part * count) as isize * stride),
+                count,
+                datatype: self.datatype.clone(),
+                marker: PhantomData,
+            })
+            .collect()
+    }
+
+    // Like Buffer::elements, borrowing the elements to write to them.
+    pub(crate) fn elements_mut(&mut self, first: usize, count: usize) -> BufferMut {
+        assert!(first + count <= self.count, "The elements are outside of the buffer.");
+        BufferMut {
+            base: self.base.wrapping_offset(first as isize * self.datatype.extent()),
+            count,
+            datatype: self.datatype.clone(),
+            marker: PhantomData,
+        }
+    }
+
+    // The same elements, for the engine to unpack a payload into as it arrives. The caller promises
+    // that the alias is gone before 'a ends, and that nothing else touches the elements meanwhile.
+    pub(crate) unsafe fn alias(&mut self) -> BufferMut<'static> {
+        BufferMut::new(self.base, self.count, self.datatype.clone())
+    }
+
+    // The same memory, to send from.
+    pub fn as_buffer(&self) -> Buffer {
+        unsafe { Buffer::new(self.base, self.count, self.datatype.clone()) }
+    }
+
+    // Scatters `data` into the elements in order. Data beyond the buffer's length is ignored.
+    pub fn unpack(&mut self, data: &[u8]) {
+        self.unpack_segment(0, data);
+    }
+
+    // Scatters `data` into the elements as bytes `position..` of their data, so that a large
+    // message can be received a piece at a time. Data beyond the buffer's length is ignored.
+    pub(crate) fn unpack_segment(&mut self, position: u64, data: &[u8]) {
+        let len = min(data.len() as u64, self.len().saturating_sub(position)) as usize;
+        if len == 0 {
+            return;
+        }
+
+        unsafe {
+            if self.datatype.is_contiguous() {
+                slice::from_raw_parts_mut(self.base.add(position as usize), len)
+                    .copy_from_slice(&data[..len]);
+            } else {
+                dataloop::unpack_segment(
+                    self.datatype.dataloop().unwrap(),
+                    self.base,
+                    self.count,
+                    self.datatype.extent(),
+                    position,
+                    &data[..len],
+                );
+            }
+        }
+    }
+
+    // Converts external32 `data` into the elements in order, ignoring data beyond the buffer.
+    pub fn unpack_external(&mut self, data: &[u8]) {
+        self.unpack_from(&Arch::external32(), data);
+    }
+
+    // Converts `data`, which is in the layout `arch`, into the elements in order, ignoring data
+    // beyond the buffer. Returns the number of bytes the data takes up in the native layout.
+    pub(crate) fn unpack_from(&mut self, arch: &Arch, data: &[u8]) -> u64 {
+        self.unpack_segment_from(arch, 0, data).1
+    }
+
+    // Like `unpack_from`, but `data` is bytes `position..` of the data in the layout `arch`, and
+    // `position` falls between two primitives. Also returns the number of bytes of `data` that were
+    // used, which stops short of a primitive cut off at the end.
+    pub(crate) fn unpack_segment_from(
+        &mut self,
+        arch: &Arch,
+        position: u64,
+        data: &[u8],
+    ) -> (usize, u64) {
+        unsafe {
+            arch::unpack_segment(
+                self.datatype.dataloop().unwrap(),
+                self.base,
+                self.count,
+                self.datatype.extent(),
+                arch,
+                position,
+                data,
+            )
+        }
+    }
+}
+
+impl<'a, T: Equivalence> From<&'a mut [T]> for BufferMut<'a> {
+    fn from(slice: &'a mut [T]) -> Self {
+        unsafe {
+            BufferMut::new(
+                slice.as_mut_ptr() as *mut u8,
+                slice.len(),
+                T::equivalent_datatype(),
+            )
+        }
+    }
+}
+
+// Rust types with an MPI datatype describing their layout. The implementation promises that the
+// datatype is committed, covers exactly the bytes of the type that hold data and has the extent of
+// the type, and that any bytes a receive writes through it leave a valid value.
+pub unsafe trait Equivalence {
+    fn equivalent_datatype() -> Datatype;
+}
+
+// Builds the datatype of `T` the first time it's asked for and hands out clones of it from then on.
+// #[derive(Equivalence)] goes through here so that slices of a struct don't commit a datatype each
+// time they're sent. The datatype is built without holding the lock, since building a struct's
+// datatype asks for those of its fields.
+#[doc(hidden)]
+pub fn cached_datatype<T: 'static, F: FnOnce() -> Datatype>(build: F) -> Datatype {
+    static CACHE: Mutex<BTreeMap<TypeId, Datatype>> = Mutex::new(BTreeMap::new());
+
+    if let Some(datatype) = CACHE.lock().unwrap().get(&TypeId::of::<T>()) {
+        return datatype.clone();
+    }
+    let datatype = build();
+    CACHE
+        .lock()
+        .unwrap()
+        .entry(TypeId::of::<T>())
+        .or_insert(datatype)
+        .clone()
+}
+
+macro_rules! equivalent_primitives {
+    ($($type:ty => $primitive:ident),* $(,)*) => {
+        $(
+            unsafe impl Equivalence for $type {
+                fn equivalent_datatype() -> Datatype {
+                    Datatype::primitive(Primitive::$primitive)
+                }
+            }
+        )*
+    };
+}
+
+equivalent_primitives! {
+    i8 => Int8,
+    i16 => Int16,
+    i32 => Int32,
+    i64 => Int64,
+    u8 => Uint8,
+    u16 => Uint16,
+    u32 => Uint32,
+    u64 => Uint64,
+    f32 => Float,
+    f64 => Double,
+    CBool => CBool,
+}
+
+// C's bool, a byte holding 0 or 1. Rust's bool has the same layout but no other byte is a valid
+// bool, and a receive can't promise the message holds only 0s and 1s, so bools travel as this.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct CBool(pub u8);
+
+impl From<bool> for CBool {
+    fn from(value: bool) -> Self {
+        CBool(value as u8)
+    }
+}
+
+// Any byte other than 0 is true, as it is in C.
+impl From<CBool> for bool {
+    fn from(value: CBool) -> Self {
+        value.0 != 0
+    }
+}
+
+#[cfg(target_pointer_width = "32")]
+equivalent_primitives! {
+    isize => Int32,
+    usize => Uint32,
+}
+
+#[cfg(target_pointer_width = "64")]
+equivalent_primitives! {
+    isize => Int64,
+    usize => Uint64,
+}
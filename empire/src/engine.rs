@@ -0,0 +1,961 @@
+use super::{error, arch::Arch, datatype::{Buffer, BufferMut},
+            protocol::{self, Envelope, Frame, FrameKind, Handshake}, transport::{Done, Transport}};
+
+use std::{cmp::min, mem, collections::{HashMap, HashSet, VecDeque},
+          sync::{Arc, Condvar, Mutex, MutexGuard}};
+
+use futures::sync::oneshot;
+
+// Messages up to this many bytes are sent eagerly unless the job or communicator says otherwise.
+pub(crate) const DEFAULT_EAGER_LIMIT: u64 = 64 * 1024;
+
+// An eager message goes out as a single frame, so larger eager limits are lowered to a chunk.
+pub(crate) const MAX_EAGER_LIMIT: u64 = protocol::CHUNK_LEN as u64;
+
+// The payload of a message, as far as it has got.
+pub(crate) enum Payload {
+    // Sent along with the message.
+    Eager(Vec<u8>),
+    // Still held by the sender, which sends it once a receive matches the message.
+    Rendezvous,
+    // A rendezvous payload that has been unpacked into the buffer of the receive as it arrived,
+    // taking up this many bytes in this rank's layout.
+    Unpacked(u64),
+}
+
+// A message that arrived from a peer but has not yet been consumed.
+pub(crate) struct Message {
+    pub envelope: Envelope,
+    // The length of the payload in bytes, known even before a rendezvous payload arrives.
+    pub length: u64,
+    pub payload: Payload,
+    // The sender is waiting to hear that the message was matched.
+    pub sync: bool,
+    // Where the sender accepts replies about this message.
+    pub reply_port: String,
+    // How the sender laid out the payload.
+    pub arch: Arch,
+}
+
+impl Message {
+    // Whether the sender still holds the payload.
+    pub fn is_rendezvous(&self) -> bool {
+        matches!(self.payload, Payload::Rendezvous)
+    }
+}
+
+// Where the payload of a rendezvous send is packed from once the receiver asks for it.
+pub(crate) enum Outgoing {
+    // The buffer of the send, which its request keeps borrowed until the send completes.
+    Buffer(Buffer<'static>),
+    // The data already packed, for a send whose buffer may change before the receiver asks.
+    Packed(Vec<u8>),
+}
+
+impl Outgoing {
+    pub fn len(&self) -> u64 {
+        match *self {
+            Outgoing::Buffer(ref buf) => buf.len(),
+            Outgoing::Packed(ref data) => data.len() as u64,
+        }
+    }
+
+    // Appends bytes `position..position + len` of the payload to `out`.
+    pub fn pack_segment_into(&self, position: u64, len: usize, out: &mut Vec<u8>) {
+        match *self {
+            Outgoing::Buffer(ref buf) => buf.pack_segment_into(position, len, out),
+            Outgoing::Packed(ref data) => {
+                let start = min(position, data.len() as u64) as usize;
+                let end = min(start + len, data.len());
+                out.extend_from_slice(&data[start..end]);
+            }
+        }
+    }
+}
+
+// A send waiting on the receiver: a rendezvous send for the receiver to ask for its payload, or a
+// synchronous eager send for the receiver to match it.
+struct PendingSend {
+    address: String,
+    envelope: Envelope,
+    // The payload of a rendezvous send until the receiver asks for it. None for a synchronous
+    // eager send, whose payload went out with the message.
+    payload: Option<Outgoing>,
+    // The payload has been handed to the transport to write out. The request of a send whose
+    // payload is still its buffer can't let go of the buffer until the transport is done.
+    streaming: bool,
+    // A CancelSend has gone out and the reply is still to come.
+    cancelling: bool,
+}
+
+// A rendezvous message matched to a receive, whose payload is unpacked into the buffer of the
+// receive a chunk at a time as it arrives.
+struct Incoming {
+    message: Message,
+    // None once the request of the receive is gone, so the payload is thrown away, and for a
+    // message matched by mprobe until it is received.
+    buf: Option<BufferMut<'static>>,
+    // The receiver has been asked for the payload. A message matched by mprobe is only asked for
+    // once there is a buffer to receive it into.
+    cleared: bool,
+    // Bytes of the payload that have arrived so far, in the sender's layout.
+    arrived: u64,
+    // Bytes that the payload has taken up in the buffer so far, in this rank's layout.
+    unpacked: u64,
+    // The start of a primitive cut off at the end of the last chunk from a sender with another
+    // layout, which can only be converted once the rest of it arrives.
+    partial: Vec<u8>,
+}
+
+impl Incoming {
+    fn new(message: Message, buf: Option<BufferMut<'static>>, cleared: bool) -> Self {
+        Self {
+            message,
+            buf,
+            cleared,
+            arrived: 0,
+            unpacked: 0,
+            partial: Vec::new(),
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.arrived == self.message.length
+    }
+
+    // Unpacks the next chunk of the payload into the buffer, if there still is one.
+    fn unpack(&mut self, data: &[u8]) {
+        let position = self.arrived;
+        self.arrived += data.len() as u64;
+
+        let arch = self.message.arch;
+        let buf = match self.buf {
+            Some(ref mut buf) => buf,
+            None => return,
+        };
+
+        if arch.is_native() {
+            buf.unpack_segment(position, data);
+            self.unpacked = min(self.arrived, buf.len());
+            return;
+        }
+
+        let mut joined = mem::take(&mut self.partial);
+        let position = position - joined.len() as u64;
+        let data = if joined.is_empty() {
+            data
+        } else {
+            joined.extend_from_slice(data);
+            &joined
+        };
+
+        let (converted, unpacked) = buf.unpack_segment_from(&arch, position, data);
+        self.unpacked += unpacked;
+        if position + (converted as u64) < buf.len_in(&arch) {
+            self.partial = data[converted..].to_vec();
+        }
+    }
+
+    fn into_message(self) -> Message {
+        let mut message = self.message;
+        message.payload = Payload::Unpacked(self.unpacked);
+        message
+    }
+}
+
+// Tracks the ranks of a spawned job as they announce their ports.
+struct JobWireup {
+    ports: Vec<Option<String>>,
+    // Where the ports go once every rank has announced itself.
+    complete: oneshot::Sender<Vec<String>>,
+}
+
+// The envelope a posted receive is willing to accept. None is a wildcard.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct MatchSpec {
+    pub source: Option<u32>,
+    pub tag: Option<i32>,
+}
+
+impl MatchSpec {
+    pub fn matches(&self, envelope: &Envelope) -> bool {
+        self.source.map_or(true, |source| source == envelope.source)
+            && self.tag.map_or(true, |tag| tag == envelope.tag)
+    }
+}
+
+struct PostedRecv {
+    id: u64,
+    spec: MatchSpec,
+    // Where a rendezvous payload goes. None once the request of the receive is gone.
+    buf: Option<BufferMut<'static>>,
+}
+
+// Each communicator context has two queues. Receives that found no message wait in `posted`, and
+// messages that found no receive wait in `unexpected`. At most one of the two can hold entries
+// that match each other, and both are searched oldest first, which is what keeps messages between
+// a pair of ranks from overtaking each other.
+#[derive(Default)]
+struct Context {
+    posted: VecDeque<PostedRecv>,
+    unexpected: VecDeque<Message>,
+}
+
+// Identifies one round of a partitioned operation. Both sides number their partitioned operations
+// on each (context, peer, tag) in the order they are initialized, which MPI requires to agree, and
+// count the starts of each operation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct PartitionKey {
+    pub context_id: u32,
+    // The rank of the sender.
+    pub source: u32,
+    pub tag: i32,
+    pub channel: u32,
+    pub round: u32,
+}
+
+impl PartitionKey {
+    fn of(envelope: &Envelope) -> Self {
+        PartitionKey {
+            context_id: envelope.context_id,
+            source: envelope.source,
+            tag: envelope.tag,
+            channel: (envelope.id >> 32) as u32,
+            round: envelope.id as u32,
+        }
+    }
+
+    pub fn envelope(&self) -> Envelope {
+        Envelope {
+            context_id: self.context_id,
+            source: self.source,
+            tag: self.tag,
+            id: (self.channel as u64) << 32 | self.round as u64,
+        }
+    }
+}
+
+// Finishes a receive that no request is waiting on with the message it matched.
+pub(crate) type Completion = Box<dyn FnOnce(Message) + Send>;
+
+#[derive(Default)]
+struct State {
+    contexts: HashMap<u32, Context>,
+    // Messages matched to a posted receive, keyed by the id of the receive.
+    matched: HashMap<u64, Message>,
+    // Rendezvous messages matched to a receive whose payload hasn't arrived yet.
+    awaiting_payload: HashMap<u64, Incoming>,
+    next_recv_id: u64,
+    // Receives whose request is gone but which still take the message they match, so that no later
+    // receive can. The message is handed to the receive's completion once its payload is in.
+    orphaned_recvs: HashMap<u64, Completion>,
+    // Sends waiting on the receiver, keyed by the id of the send.
+    pending_sends: HashMap<u64, PendingSend>,
+    // Sends that were waiting on the receiver and have since completed, and whether they completed
+    // by being cancelled.
+    completed_sends: HashMap<u64, bool>,
+    // Sends whose request was dropped before they completed, so nobody will ask about them.
+    discarded_sends: HashSet<u64>,
+    next_send_id: u64,
+    // Partitions that have arrived but haven't been copied out by their receive, as byte offsets,
+    // data and the layout of the rank that sent them.
+    partitions: HashMap<PartitionKey, Vec<(u64, Vec<u8>, Arch)>>,
+    // The number of partitioned operations initialized so far on each (context, peer, tag), for
+    // sends and receives separately.
+    partitioned_channels: HashMap<(u32, u32, i32, bool), u32>,
+    // Bumped whenever something happens that could complete a request.
+    progress_count: u64,
+    jobs: HashMap<u64, JobWireup>,
+    directory: Option<Vec<String>>,
+}
+
+impl State {
+    fn context(&mut self, context_id: u32) -> &mut Context {
+        self.contexts
+            .entry(context_id)
+            .or_insert_with(Context::default)
+    }
+
+    // Receive `id` has its message, payload and all.
+    fn complete_recv(&mut self, id: u64, message: Message) {
+        match self.orphaned_recvs.remove(&id) {
+            Some(complete) => complete(message),
+            None => {
+                self.matched.insert(id, message);
+            }
+        }
+    }
+
+    fn complete_send(&mut self, id: u64, cancelled: bool) {
+        self.pending_sends.remove(&id);
+        if !self.discarded_sends.remove(&id) {
+            self.completed_sends.insert(id, cancelled);
+        }
+    }
+}
+
+// The state of the engine, shared with the writer threads of the transport, which complete
+// rendezvous sends once they have written out the payload.
+#[derive(Default)]
+struct Shared {
+    state: Mutex<State>,
+    progress: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<State> {
+        self.state.lock().unwrap()
+    }
+
+    fn notify_progress(&self) {
+        self.lock().progress_count += 1;
+        self.progress.notify_all();
+    }
+}
+
+// The per-process message engine. Every Port owned by the process decodes incoming frames and hands
+// them to the engine, which queues them by communicator context until a rank consumes them.
+//
+// Messages up to the eager limit are sent whole. Larger messages use a rendezvous: the sender
+// sends only the envelope (RTS), the receiver replies once a receive matches it (CTS), and only
+// then does the payload cross the wire, as Data frames of at most a chunk each. The sender packs
+// each chunk straight from its buffer as it goes out, and the receiver unpacks it straight into the
+// buffer of the receive, so a rendezvous payload is never held whole in either process's memory.
+pub(crate) struct Engine {
+    transport: Transport,
+    eager_limit: u64,
+    shared: Arc<Shared>,
+}
+
+impl Engine {
+    pub fn new(job_id: u64, rank: u32, eager_limit: u64) -> Self {
+        Self {
+            transport: Transport::new(job_id, rank),
+            eager_limit: min(eager_limit, MAX_EAGER_LIMIT),
+            shared: Arc::new(Shared::default()),
+        }
+    }
+
+    pub fn transport(&self) -> &Transport {
+        &self.transport
+    }
+
+    pub fn eager_limit(&self) -> u64 {
+        self.eager_limit
+    }
+
+    fn lock(&self) -> MutexGuard<State> {
+        self.shared.lock()
+    }
+
+    pub fn deliver(&self, peer: &Handshake, frame: Frame) -> error::Result<()> {
+        match frame.kind {
+            FrameKind::Eager => self.arrive(Message {
+                envelope: frame.envelope,
+                length: frame.payload.len() as u64,
+                payload: Payload::Eager(frame.payload),
+                sync: frame.flags & protocol::FLAG_SYNC != 0,
+                reply_port: peer.reply_port.clone(),
+                arch: peer.arch,
+            }),
+            FrameKind::Rts => self.arrive(Message {
+                envelope: frame.envelope,
+                length: protocol::decode_u64(&frame.payload)?,
+                payload: Payload::Rendezvous,
+                sync: false,
+                reply_port: peer.reply_port.clone(),
+                arch: peer.arch,
+            }),
+            FrameKind::Cts => {
+                let recv_id = protocol::decode_u64(&frame.payload)?;
+                let (address, envelope, payload) = {
+                    let mut state = self.lock();
+                    match state.pending_sends.get_mut(&frame.envelope.id) {
+                        Some(send) if send.payload.is_some() && !send.streaming => {
+                            let payload = send.payload.take().unwrap();
+                            send.streaming = true;
+                            (send.address.clone(), send.envelope, payload)
+                        }
+                        _ => {
+                            return Err(error::Error::ProtocolError(
+                                "clear-to-send for an unknown send".to_owned(),
+                            ))
+                        }
+                    }
+                };
+
+                // The send completes once the writer is done with its buffer.
+                let (shared, id) = (self.shared.clone(), frame.envelope.id);
+                self.transport.stream(
+                    &address,
+                    Envelope {
+                        id: recv_id,
+                        ..envelope
+                    },
+                    payload,
+                    Done::new(move || {
+                        shared.lock().complete_send(id, false);
+                        shared.notify_progress();
+                    }),
+                );
+            }
+            FrameKind::Data => {
+                let mut state = self.lock();
+                let complete = match state.awaiting_payload.get_mut(&frame.envelope.id) {
+                    Some(ref mut incoming)
+                        if incoming.arrived + frame.payload.len() as u64
+                            <= incoming.message.length =>
+                    {
+                        incoming.unpack(&frame.payload);
+                        incoming.is_complete()
+                    }
+                    _ => {
+                        return Err(error::Error::ProtocolError(
+                            "payload for an unknown receive".to_owned(),
+                        ))
+                    }
+                };
+                if complete {
+                    let incoming = state.awaiting_payload.remove(&frame.envelope.id).unwrap();
+                    state.complete_recv(frame.envelope.id, incoming.into_message());
+                }
+            }
+            FrameKind::SyncAck => self.lock().complete_send(frame.envelope.id, false),
+            FrameKind::Partition => {
+                if frame.payload.len() < 8 {
+                    return Err(error::Error::ProtocolError(
+                        "a partition is missing its offset".to_owned(),
+                    ));
+                }
+                let offset = protocol::decode_u64(&frame.payload[..8])?;
+                self.lock()
+                    .partitions
+                    .entry(PartitionKey::of(&frame.envelope))
+                    .or_insert_with(Vec::new)
+                    .push((offset, frame.payload[8..].to_vec(), peer.arch));
+            }
+            FrameKind::CancelSend => self.withdraw(peer, frame.envelope),
+            FrameKind::CancelReply => {
+                let mut state = self.lock();
+                if frame.flags & protocol::FLAG_CANCELLED != 0 {
+                    state.complete_send(frame.envelope.id, true);
+                } else if let Some(send) = state.pending_sends.get_mut(&frame.envelope.id) {
+                    send.cancelling = false;
+                }
+            }
+            FrameKind::Announce => self.record_announcement(peer, frame)?,
+            FrameKind::Directory => {
+                let ports = protocol::decode_directory(&frame.payload)?;
+                self.lock().directory = Some(ports);
+            }
+        }
+
+        self.notify_progress();
+        Ok(())
+    }
+
+    // Matches a newly arrived message against the posted receives, or queues it as unexpected.
+    fn arrive(&self, message: Message) {
+        let mut state = self.lock();
+
+        let posted = {
+            let context = state.context(message.envelope.context_id);
+            match context
+                .posted
+                .iter()
+                .position(|posted| posted.spec.matches(&message.envelope))
+            {
+                Some(index) => context.posted.remove(index),
+                None => None,
+            }
+        };
+
+        match posted {
+            Some(posted) => self.matched(&mut state, posted.id, message, posted.buf),
+            None => state
+                .context(message.envelope.context_id)
+                .unexpected
+                .push_back(message),
+        }
+    }
+
+    // Answers a CancelSend. The message can only be withdrawn while it is unexpected; once a
+    // receive has matched it, the receive is owed its payload.
+    fn withdraw(&self, peer: &Handshake, envelope: Envelope) {
+        let withdrawn = {
+            let mut state = self.lock();
+            let unexpected = &mut state.context(envelope.context_id).unexpected;
+            match unexpected.iter().position(|message| {
+                // Only these carry a send id; the id of a plain eager message is meaningless.
+                (message.sync || message.is_rendezvous())
+                    && message.envelope.source == envelope.source
+                    && message.envelope.id == envelope.id
+            }) {
+                Some(index) => unexpected.remove(index).is_some(),
+                None => false,
+            }
+        };
+
+        let mut reply = Frame::new(FrameKind::CancelReply, envelope, Vec::new());
+        if withdrawn {
+            reply.flags |= protocol::FLAG_CANCELLED;
+        }
+        self.transport
+            .send(&peer.reply_port, envelope.context_id, &reply);
+    }
+
+    // Records that receive `recv_id` matched `message` and tells the sender, if it is waiting. A
+    // rendezvous payload will be unpacked into `buf` as it arrives.
+    fn matched(
+        &self,
+        state: &mut State,
+        recv_id: u64,
+        message: Message,
+        buf: Option<BufferMut<'static>>,
+    ) {
+        let reply = if message.is_rendezvous() {
+            Some(Frame::new(
+                FrameKind::Cts,
+                message.envelope,
+                protocol::encode_u64(recv_id),
+            ))
+        } else if message.sync {
+            Some(Frame::new(FrameKind::SyncAck, message.envelope, Vec::new()))
+        } else {
+            None
+        };
+
+        if let Some(reply) = reply {
+            self.transport
+                .send(&message.reply_port, message.envelope.context_id, &reply);
+        }
+
+        if message.is_rendezvous() {
+            state
+                .awaiting_payload
+                .insert(recv_id, Incoming::new(message, buf, true));
+        } else {
+            state.complete_recv(recv_id, message);
+        }
+    }
+
+    // Sends a message whole, packing it straight into the frame. A synchronous send returns an id
+    // that completes once the receiver has matched the message.
+    pub fn send_eager(
+        &self,
+        address: &str,
+        envelope: Envelope,
+        buf: &Buffer,
+        sync: bool,
+    ) -> Option<u64> {
+        let mut envelope = envelope;
+        let id = if sync {
+            let mut state = self.lock();
+            let id = state.next_send_id;
+            state.next_send_id += 1;
+            envelope.id = id;
+            state.pending_sends.insert(
+                id,
+                PendingSend {
+                    address: address.to_owned(),
+                    envelope,
+                    payload: None,
+                    streaming: false,
+                    cancelling: false,
+                },
+            );
+            Some(id)
+        } else {
+            None
+        };
+
+        let flags = if sync { protocol::FLAG_SYNC } else { 0 };
+        let mut frame = Vec::with_capacity(protocol::HEADER_LEN + buf.len() as usize);
+        protocol::encode_header(FrameKind::Eager, flags, &envelope, buf.len(), &mut frame);
+        buf.pack_into(&mut frame);
+        self.transport
+            .send_encoded(address, envelope.context_id, frame);
+
+        id
+    }
+
+    // Offers a message to the receiver and holds on to the payload until the receiver asks for it.
+    // The returned id completes once the payload has been written out.
+    pub fn send_rendezvous(&self, address: &str, envelope: Envelope, payload: Outgoing) -> u64 {
+        let mut envelope = envelope;
+        let length = payload.len();
+
+        {
+            let mut state = self.lock();
+            envelope.id = state.next_send_id;
+            state.next_send_id += 1;
+            state.pending_sends.insert(
+                envelope.id,
+                PendingSend {
+                    address: address.to_owned(),
+                    envelope,
+                    payload: Some(payload),
+                    streaming: false,
+                    cancelling: false,
+                },
+            );
+        }
+
+        let rts = Frame::new(FrameKind::Rts, envelope, protocol::encode_u64(length));
+        self.transport.send(address, envelope.context_id, &rts);
+
+        envelope.id
+    }
+
+    // Returns Some once, when send `id` has completed, saying whether it was cancelled.
+    pub fn take_send_completion(&self, id: u64) -> Option<bool> {
+        self.lock().completed_sends.remove(&id)
+    }
+
+    // Asks the receiver to withdraw send `id`. The send then completes either way: cancelled if
+    // the receiver hadn't matched it yet, or normally if it had.
+    pub fn cancel_send(&self, id: u64) {
+        let mut state = self.lock();
+        if let Some(send) = state.pending_sends.get_mut(&id) {
+            if !send.cancelling {
+                send.cancelling = true;
+                let frame = Frame::new(FrameKind::CancelSend, send.envelope, Vec::new());
+                self.transport
+                    .send(&send.address, send.envelope.context_id, &frame);
+            }
+        }
+    }
+
+    // Forgets send `id`, whose request is gone. The send itself carries on, from a copy of the
+    // buffer if the receiver hasn't asked for the payload yet; a payload already being written out
+    // from the buffer is waited for.
+    pub fn discard_send(&self, id: u64) {
+        let mut state = self.lock();
+        loop {
+            match state.pending_sends.get_mut(&id) {
+                Some(ref send) if send.streaming => (),
+                Some(send) => {
+                    if let Some(Outgoing::Buffer(ref buf)) = send.payload {
+                        let mut data = Vec::with_capacity(buf.len() as usize);
+                        buf.pack_into(&mut data);
+                        send.payload = Some(Outgoing::Packed(data));
+                    }
+                    break;
+                }
+                None => break,
+            }
+            state = self.shared.progress.wait(state).unwrap();
+        }
+
+        Self::forget_send(&mut state, id);
+    }
+
+    // Forgets send `id` like `discard_send`, but keeps on sending from its buffer, which the caller
+    // promises outlives the send.
+    pub fn orphan_send(&self, id: u64) {
+        Self::forget_send(&mut self.lock(), id);
+    }
+
+    fn forget_send(state: &mut State, id: u64) {
+        if state.completed_sends.remove(&id).is_none() {
+            state.discarded_sends.insert(id);
+        }
+    }
+
+    pub fn notify_progress(&self) {
+        self.shared.notify_progress();
+    }
+
+    pub fn progress_count(&self) -> u64 {
+        self.lock().progress_count
+    }
+
+    // Blocks until the progress count moves past `since`. Callers read the count, check whatever
+    // they are waiting on, and only then wait, so no progress can slip between the two.
+    pub fn wait_progress(&self, since: u64) {
+        let mut state = self.lock();
+        while state.progress_count == since {
+            state = self.shared.progress.wait(state).unwrap();
+        }
+    }
+
+    // Posts a receive on `context_id` into `buf`. If an unexpected message already matches, it is
+    // matched immediately; otherwise the receive is queued until a matching message arrives. Returns
+    // the id to pass to `take_matched`. The request of the receive must keep `buf` borrowed until the
+    // receive is cancelled, completes or is discarded.
+    pub fn post_recv(&self, context_id: u32, spec: MatchSpec, buf: &mut BufferMut) -> u64 {
+        let buf = unsafe { buf.alias() };
+        let mut state = self.lock();
+
+        let id = state.next_recv_id;
+        state.next_recv_id += 1;
+
+        match Self::take_unexpected(&mut state, context_id, &spec) {
+            Some(message) => self.matched(&mut state, id, message, Some(buf)),
+            None => state.context(context_id).posted.push_back(PostedRecv {
+                id,
+                spec,
+                buf: Some(buf),
+            }),
+        }
+
+        id
+    }
+
+    // Withdraws receive `id` if it hasn't matched a message yet. Returns whether it was withdrawn.
+    pub fn cancel_recv(&self, id: u64) -> bool {
+        let mut state = self.lock();
+        for context in state.contexts.values_mut() {
+            if let Some(index) = context.posted.iter().position(|posted| posted.id == id) {
+                context.posted.remove(index);
+                return true;
+            }
+        }
+        false
+    }
+
+    // Lets go of receive `id`, whose request is gone, without withdrawing it. The receive still
+    // matches a message in its turn, and `complete` finishes it with that message.
+    pub fn orphan_recv(&self, id: u64, complete: Completion) {
+        let mut state = self.lock();
+        match state.matched.remove(&id) {
+            Some(message) => complete(message),
+            None => {
+                state.orphaned_recvs.insert(id, complete);
+            }
+        }
+    }
+
+    // Lets go of receive `id` like `orphan_recv`, throwing away the message it matches. Whatever
+    // is left of a rendezvous payload is read and dropped.
+    pub fn discard_recv(&self, id: u64) {
+        {
+            let mut state = self.lock();
+            if let Some(incoming) = state.awaiting_payload.get_mut(&id) {
+                incoming.buf = None;
+                if !incoming.cleared {
+                    incoming.cleared = true;
+                    let message = &incoming.message;
+                    let cts =
+                        Frame::new(FrameKind::Cts, message.envelope, protocol::encode_u64(id));
+                    self.transport
+                        .send(&message.reply_port, message.envelope.context_id, &cts);
+                }
+            }
+            for context in state.contexts.values_mut() {
+                for posted in context.posted.iter_mut().filter(|posted| posted.id == id) {
+                    posted.buf = None;
+                }
+            }
+        }
+        self.orphan_recv(id, Box::new(drop));
+    }
+
+    fn take_unexpected(state: &mut State, context_id: u32, spec: &MatchSpec) -> Option<Message> {
+        let context = state.context(context_id);
+        match context
+            .unexpected
+            .iter()
+            .position(|message| spec.matches(&message.envelope))
+        {
+            Some(index) => context.unexpected.remove(index),
+            None => None,
+        }
+    }
+
+    // Returns the envelope, length and sender's layout of the oldest unexpected message matching
+    // `spec`, leaving it queued for a later receive.
+    pub fn probe(&self, context_id: u32, spec: MatchSpec) -> Option<(Envelope, u64, Arch)> {
+        self.lock()
+            .context(context_id)
+            .unexpected
+            .iter()
+            .find(|message| spec.matches(&message.envelope))
+            .map(|message| (message.envelope, message.length, message.arch))
+    }
+
+    // Like `probe`, but dequeues the message and matches it to a new receive so no other receive
+    // can claim it. Returns the id to pass to `recv_probed` along with the envelope, length and
+    // the sender's layout. The sender of a rendezvous message is only asked for the payload once
+    // `recv_probed` supplies a buffer for it.
+    pub fn mprobe(
+        &self,
+        context_id: u32,
+        spec: MatchSpec,
+    ) -> Option<(u64, Envelope, u64, Arch)> {
+        let mut state = self.lock();
+
+        let message = Self::take_unexpected(&mut state, context_id, &spec)?;
+        let envelope = message.envelope;
+        let (length, arch) = (message.length, message.arch);
+
+        let id = state.next_recv_id;
+        state.next_recv_id += 1;
+        if message.is_rendezvous() {
+            state
+                .awaiting_payload
+                .insert(id, Incoming::new(message, None, false));
+        } else {
+            self.matched(&mut state, id, message, None);
+        }
+
+        Some((id, envelope, length, arch))
+    }
+
+    // Receives the message matched to `id` by `mprobe` into `buf`, under the same promise as
+    // `post_recv`.
+    pub fn recv_probed(&self, id: u64, buf: &mut BufferMut) {
+        let buf = unsafe { buf.alias() };
+        let mut state = self.lock();
+        if let Some(incoming) = state.awaiting_payload.get_mut(&id) {
+            incoming.buf = Some(buf);
+            if !incoming.cleared {
+                incoming.cleared = true;
+                let message = &incoming.message;
+                let cts = Frame::new(FrameKind::Cts, message.envelope, protocol::encode_u64(id));
+                self.transport
+                    .send(&message.reply_port, message.envelope.context_id, &cts);
+            }
+        }
+    }
+
+    // Numbers a new partitioned operation with `peer` on `context_id` and `tag`. The n-th send
+    // initialized on one side is matched with the n-th receive initialized on the other.
+    pub fn open_partitioned_channel(
+        &self,
+        context_id: u32,
+        peer: u32,
+        tag: i32,
+        send: bool,
+    ) -> u32 {
+        let mut state = self.lock();
+        let next = state
+            .partitioned_channels
+            .entry((context_id, peer, tag, send))
+            .or_insert(0);
+        let channel = *next;
+        *next += 1;
+        channel
+    }
+
+    // Sends the elements of one partition, packed in this rank's layout, a chunk of whole elements
+    // at a time, each along with its offset in bytes from the start of the whole buffer's data in
+    // the same layout. Fails without sending anything if a single element is too large for a frame.
+    pub fn send_partition(
+        &self,
+        address: &str,
+        key: PartitionKey,
+        offset: u64,
+        buf: &Buffer,
+    ) -> error::Result<()> {
+        let size = buf.datatype().size();
+        if size > protocol::MAX_FRAME_LEN - 8 {
+            return Err(error::Error::ElementTooLarge(size));
+        }
+        if size == 0 {
+            return Ok(());
+        }
+
+        let per_frame = (protocol::CHUNK_LEN as u64 / size).max(1) as usize;
+        let mut first = 0;
+        while first < buf.count() {
+            let count = min(per_frame, buf.count() - first);
+            let mut payload = protocol::encode_u64(offset + first as u64 * size);
+            buf.elements(first, count).pack_into(&mut payload);
+
+            let frame = Frame::new(FrameKind::Partition, key.envelope(), payload);
+            self.transport.send(address, key.context_id, &frame);
+            first += count;
+        }
+        Ok(())
+    }
+
+    // Returns the partitions of `key` that have arrived since the last call, each with the layout of
+    // the rank that sent it.
+    pub fn take_partitions(&self, key: &PartitionKey) -> Vec<(u64, Vec<u8>, Arch)> {
+        self.lock().partitions.remove(key).unwrap_or_default()
+    }
+
+    // Returns the message matched to receive `id`, if it has been matched.
+    pub fn take_matched(&self, id: u64) -> Option<Message> {
+        self.lock().matched.remove(&id)
+    }
+
+    // Called by a parent before it launches `size` ranks as job `job_id`. The ports of the ranks
+    // arrive on the receiver once all of them have announced themselves.
+    pub fn expect_job(&self, job_id: u64, size: usize) -> oneshot::Receiver<Vec<String>> {
+        let (complete, ports) = oneshot::channel();
+        self.lock().jobs.insert(
+            job_id,
+            JobWireup {
+                ports: vec![None; size],
+                complete,
+            },
+        );
+        ports
+    }
+
+    fn record_announcement(&self, peer: &Handshake, frame: Frame) -> error::Result<()> {
+        let port = String::from_utf8(frame.payload)
+            .map_err(|_| error::Error::ProtocolError("announced port is not UTF-8".to_owned()))?;
+
+        let complete = {
+            let mut state = self.lock();
+            let job = state.jobs.get_mut(&peer.job_id).ok_or_else(|| {
+                error::Error::ProtocolError(format!("no job {} was spawned here", peer.job_id))
+            })?;
+
+            let rank = frame.envelope.source as usize;
+            if rank >= job.ports.len() {
+                return Err(error::Error::ProtocolError(format!(
+                    "rank {} announced itself to a job of size {}",
+                    rank,
+                    job.ports.len()
+                )));
+            }
+            job.ports[rank] = Some(port);
+
+            if job.ports.iter().all(|port| port.is_some()) {
+                let job = state.jobs.remove(&peer.job_id).unwrap();
+                let ports: Vec<_> = job.ports.into_iter().map(|port| port.unwrap()).collect();
+                // Nobody is waiting any more if every rank the parent launched has since exited.
+                let _ = job.complete.send(ports.clone());
+                Some(ports)
+            } else {
+                None
+            }
+        };
+
+        // Once every rank has checked in, tell each of them where the others are.
+        if let Some(ports) = complete {
+            let directory = Frame::new(
+                FrameKind::Directory,
+                Envelope {
+                    context_id: frame.envelope.context_id,
+                    source: 0,
+                    tag: 0,
+                    id: 0,
+                },
+                protocol::encode_directory(&ports),
+            );
+
+            for port in &ports {
+                self.transport
+                    .send(port, frame.envelope.context_id, &directory);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Used by a spawned rank to learn the ports of the rest of its job.
+    pub fn wait_directory(&self) -> Vec<String> {
+        let mut state = self.lock();
+        loop {
+            if let Some(directory) = state.directory.take() {
+                return directory;
+            }
+            state = self.shared.progress.wait(state).unwrap();
+        }
+    }
+}
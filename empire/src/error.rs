@@ -1,3 +1,5 @@
+use super::pt2pt::Status;
+
 use std::{self, ffi::OsString};
 
 use tokio;
@@ -9,6 +11,23 @@ pub enum Error {
     IoError(std::io::Error),
     TokioIoError(tokio::io::Error),
     FailExitCode(i32),
+    ProtocolError(String),
+    Truncated(Status),
+    BufferAlreadyAttached,
+    NoBufferAttached,
+    InsufficientBuffer { needed: u64, available: u64 },
+    // A callback supplied through the C interface returned this error code.
+    CallbackFailed(i32),
+    // The reduction operation can't combine some primitive of the datatype.
+    UndefinedOp,
+    // The routine needs a partitioned request of the other kind, or this one isn't partitioned.
+    NotPartitioned,
+    // The request must have been started and not yet completed.
+    InactiveRequest,
+    NoSuchPartition(usize),
+    PartitionAlreadyReady(usize),
+    // A single element of this many bytes is larger than a frame can carry.
+    ElementTooLarge(u64),
 }
 
 impl std::error::Error for Error {
@@ -19,6 +38,20 @@ impl std::error::Error for Error {
             &Error::IoError(ref err) => err.description(),
             &Error::TokioIoError(ref err) => err.description(),
             &Error::FailExitCode(_) => "launched process exited early with a failure code",
+            &Error::ProtocolError(_) => "empire received a malformed message from a peer",
+            &Error::Truncated(_) => "a received message was larger than the receive buffer",
+            &Error::BufferAlreadyAttached => "a buffer is already attached for buffered sends",
+            &Error::NoBufferAttached => "no buffer is attached for buffered sends",
+            &Error::InsufficientBuffer { .. } => {
+                "the attached buffer has no room left for the buffered send"
+            }
+            &Error::CallbackFailed(_) => "a user callback reported an error",
+            &Error::UndefinedOp => "the reduction operation is not defined for the datatype",
+            &Error::NotPartitioned => "the request is not the right kind of partitioned request",
+            &Error::InactiveRequest => "the request is not active",
+            &Error::NoSuchPartition(_) => "the partition is out of range",
+            &Error::PartitionAlreadyReady(_) => "the partition has already been marked ready",
+            &Error::ElementTooLarge(_) => "an element of the datatype is too large to send",
         }
     }
 }
@@ -37,6 +70,40 @@ impl std::fmt::Display for Error {
             &Error::IoError(ref err) => err.fmt(f),
             &Error::TokioIoError(ref err) => err.fmt(f),
             &Error::FailExitCode(code) => write!(f, "child process exited with code '{}'", code),
+            &Error::ProtocolError(ref reason) => {
+                write!(f, "empire received a malformed message: {}", reason)
+            }
+            &Error::Truncated(ref status) => write!(
+                f,
+                "a message was truncated to the {} bytes of the receive buffer",
+                status.count
+            ),
+            &Error::BufferAlreadyAttached => {
+                write!(f, "a buffer is already attached for buffered sends")
+            }
+            &Error::NoBufferAttached => write!(f, "no buffer is attached for buffered sends"),
+            &Error::InsufficientBuffer { needed, available } => write!(
+                f,
+                "the buffered send needs {} bytes, but only {} are left in the attached buffer",
+                needed, available
+            ),
+            &Error::CallbackFailed(code) => write!(f, "a user callback returned error {}", code),
+            &Error::UndefinedOp => {
+                write!(f, "the reduction operation is not defined for the datatype")
+            }
+            &Error::NotPartitioned => {
+                write!(f, "the request is not the right kind of partitioned request")
+            }
+            &Error::InactiveRequest => write!(f, "the request is not active"),
+            &Error::NoSuchPartition(partition) => {
+                write!(f, "the request has no partition {}", partition)
+            }
+            &Error::PartitionAlreadyReady(partition) => {
+                write!(f, "partition {} has already been marked ready", partition)
+            }
+            &Error::ElementTooLarge(size) => {
+                write!(f, "an element of {} bytes is too large to send", size)
+            }
         }
     }
 }
@@ -0,0 +1,119 @@
+use super::{Request, Status, Universe, engine::Engine, error};
+
+use futures::{Async, Future, executor::{self, Notify, NotifyHandle, Spawn}};
+use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+
+// An operation run outside of MPI that completes through a generalized request, so it can be waited
+// on alongside communication. Whoever runs the operation reports that it is done through the
+// request's Completer, or by returning true from `poll`.
+pub trait Generalized: Send {
+    // Reports the status of the completed operation. Called once, when the request is completed by
+    // a wait or test.
+    fn query(&mut self) -> error::Result<Status>;
+
+    // Releases whatever the operation holds. Called once, after `query`, or when the request is
+    // dropped without being completed.
+    fn free(&mut self) -> error::Result<()> {
+        Ok(())
+    }
+
+    // Called when the request is cancelled. `complete` says whether the operation has already
+    // completed. Cancelling doesn't complete the request; the operation still has to, and `query`
+    // reports whether it was cancelled.
+    fn cancel(&mut self, _complete: bool) -> error::Result<()> {
+        Ok(())
+    }
+
+    // Called whenever the request is tested or waited on, for operations that nobody else is
+    // driving. Returns true once the operation has completed.
+    fn poll(&mut self) -> bool {
+        false
+    }
+}
+
+// Marks a generalized request complete. It can be cloned and sent to whichever thread finishes the
+// operation.
+#[derive(Clone)]
+pub struct Completer {
+    engine: Arc<Engine>,
+    complete: Arc<AtomicBool>,
+}
+
+impl Completer {
+    pub fn complete(&self) {
+        self.complete.store(true, Ordering::SeqCst);
+        self.engine.notify_progress();
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.complete.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn set_complete(&self) {
+        self.complete.store(true, Ordering::SeqCst);
+    }
+}
+
+// Wakes anyone waiting on requests when a future behind a generalized request can make progress.
+struct WakeWaiters(Arc<Engine>);
+
+impl Notify for WakeWaiters {
+    fn notify(&self, _id: usize) {
+        self.0.notify_progress();
+    }
+}
+
+// Drives a future from the tests and waits on its request.
+struct FutureOperation<F> {
+    future: Spawn<F>,
+    notify: NotifyHandle,
+    outcome: Option<error::Result<Status>>,
+}
+
+impl<F: Future<Item = Status, Error = error::Error> + Send> Generalized for FutureOperation<F> {
+    fn query(&mut self) -> error::Result<Status> {
+        self.outcome
+            .take()
+            .expect("EMPIRE internal error: the future has not completed")
+    }
+
+    fn poll(&mut self) -> bool {
+        if self.outcome.is_none() {
+            self.outcome = match self.future.poll_future_notify(&self.notify, 0) {
+                Ok(Async::Ready(status)) => Some(Ok(status)),
+                Ok(Async::NotReady) => None,
+                Err(err) => Some(Err(err)),
+            };
+        }
+        self.outcome.is_some()
+    }
+}
+
+impl Universe {
+    pub fn grequest_start<G: Generalized + 'static>(
+        &self,
+        operation: G,
+    ) -> (Request<'static>, Completer) {
+        let completer = Completer {
+            engine: self.engine().clone(),
+            complete: Arc::new(AtomicBool::new(false)),
+        };
+        let request =
+            Request::generalized(self.engine().clone(), Box::new(operation), completer.clone());
+        (request, completer)
+    }
+
+    // A request that completes with the outcome of `future`. The future is polled by whichever
+    // thread tests or waits on the request.
+    pub fn grequest_future<F>(&self, future: F) -> Request<'static>
+    where
+        F: Future<Item = Status, Error = error::Error> + Send + 'static,
+    {
+        let operation = FutureOperation {
+            future: executor::spawn(future),
+            notify: NotifyHandle::from(Arc::new(WakeWaiters(self.engine().clone()))),
+            outcome: None,
+        };
+        self.grequest_start(operation).0
+    }
+}
@@ -0,0 +1,317 @@
+use super::{Comm, Op, datatype::{Buffer, BufferMut}, error::{self, Error}, request::Request,
+            schedule::{Region, Schedule}};
+
+// The root of a rooted collective on an intercommunicator, as each rank names it. The root's
+// whole group passes Root or ProcNull, and the other group passes the root's rank in it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Root {
+    // The root is this rank of the remote group.
+    Rank(usize),
+    // This rank is the root.
+    Root,
+    // Another rank of this rank's group is the root, so this rank takes no part.
+    ProcNull,
+}
+
+// On an intercommunicator, data goes from one group to the other. Within a group, the ranks combine
+// and spread data with the algorithms of intracommunicators, in the context of the local group,
+// and only rank 0 of each group talks to the other group, in the context of the intercommunicator.
+// Both contexts share the collective's tag. Each group's steps only start receiving the next part
+// of the collective from a rank once the previous part is over on this rank, so messages of the
+// two parts between the same ranks can't be confused.
+impl Comm {
+    fn assert_intercomm(&self) {
+        assert!(
+            self.is_intercomm(),
+            "Only collectives on intercommunicators have a root in the remote group."
+        );
+    }
+
+    // Each group holds a barrier of its own, after which rank 0 of each knows that its whole group
+    // has arrived. The two tell each other so and pass the news on to their groups.
+    pub(crate) fn intercomm_barrier_schedule(&self) -> Schedule<'static> {
+        let mut schedule = self.schedule();
+        let group = self.local_group();
+        let empty: &mut [u8] = &mut [];
+        let nothing = Region::from(BufferMut::from(empty));
+
+        schedule.within(group);
+        let mut heard = group.barrier_steps(&mut schedule, &[]);
+        if group.rank() == 0 {
+            schedule.within(self);
+            heard = vec![
+                schedule.send(&nothing, self.address(0), &heard),
+                schedule.recv(&nothing, 0, &heard),
+            ];
+            schedule.within(group);
+        }
+        group.bcast_steps(&mut schedule, &nothing, 0, &heard);
+        schedule
+    }
+
+    pub fn intercomm_bcast<'b, B: Into<BufferMut<'b>>>(
+        &self,
+        buf: B,
+        root: Root,
+    ) -> error::Result<()> {
+        self.intercomm_ibcast(buf, root).wait().map(|_| ())
+    }
+
+    pub fn intercomm_ibcast<'a, B: Into<BufferMut<'a>>>(&self, buf: B, root: Root) -> Request<'a> {
+        Request::schedule(self.intercomm_bcast_schedule(Region::from(buf.into()), root))
+    }
+
+    pub fn intercomm_bcast_init<'a, B: Into<BufferMut<'a>>>(
+        &self,
+        buf: B,
+        root: Root,
+    ) -> Request<'a> {
+        self.persistent(self.intercomm_bcast_schedule(Region::from(buf.into()), root))
+    }
+
+    // The root sends `buf` to rank 0 of the other group, which broadcasts it within the group.
+    fn intercomm_bcast_schedule<'a>(&self, buf: Region<'a>, root: Root) -> Schedule<'a> {
+        self.assert_intercomm();
+        let mut schedule = self.schedule();
+
+        match root {
+            Root::Root => {
+                schedule.send(&buf, self.address(0), &[]);
+            }
+            Root::ProcNull => {}
+            Root::Rank(root) => {
+                assert!(root < self.remote_size());
+                let group = self.local_group();
+                let mut received = Vec::new();
+                if group.rank() == 0 {
+                    received.push(schedule.recv(&buf, root, &[]));
+                }
+                schedule.within(group);
+                group.bcast_steps(&mut schedule, &buf, 0, &received);
+            }
+        }
+        schedule
+    }
+
+    pub fn intercomm_reduce<'b, 'c, S: Into<Buffer<'b>>, R: Into<BufferMut<'c>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: Option<R>,
+        op: &Op,
+        root: Root,
+    ) -> error::Result<()> {
+        let sendbuf = sendbuf.map(Into::<Buffer>::into);
+        let recvbuf = recvbuf.map(Into::<BufferMut>::into);
+        self.intercomm_ireduce(sendbuf, recvbuf, op, root)?
+            .wait()
+            .map(|_| ())
+    }
+
+    pub fn intercomm_ireduce<'a, S: Into<Buffer<'a>>, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: Option<R>,
+        op: &Op,
+        root: Root,
+    ) -> error::Result<Request<'a>> {
+        let sendbuf = sendbuf.map(|sendbuf| Region::from(sendbuf.into()));
+        let recvbuf = recvbuf.map(|recvbuf| Region::from(recvbuf.into()));
+        Ok(Request::schedule(
+            self.intercomm_reduce_schedule(sendbuf, recvbuf, op, root)?,
+        ))
+    }
+
+    pub fn intercomm_reduce_init<'a, S: Into<Buffer<'a>>, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: Option<R>,
+        op: &Op,
+        root: Root,
+    ) -> error::Result<Request<'a>> {
+        let sendbuf = sendbuf.map(|sendbuf| Region::from(sendbuf.into()));
+        let recvbuf = recvbuf.map(|recvbuf| Region::from(recvbuf.into()));
+        Ok(self.persistent(self.intercomm_reduce_schedule(sendbuf, recvbuf, op, root)?))
+    }
+
+    // The other group reduces its contributions to its rank 0, which sends the result to the root.
+    // Only the root needs `recvbuf`, and only the other group `sendbuf`.
+    fn intercomm_reduce_schedule<'a>(
+        &self,
+        sendbuf: Option<Region<'a>>,
+        recvbuf: Option<Region<'a>>,
+        op: &Op,
+        root: Root,
+    ) -> error::Result<Schedule<'a>> {
+        self.assert_intercomm();
+        let mut schedule = self.schedule();
+
+        match root {
+            Root::Root => {
+                let recvbuf = recvbuf.expect("The root rank must supply a receive buffer.");
+                if !op.is_defined_for(recvbuf.datatype()) {
+                    return Err(Error::UndefinedOp);
+                }
+                schedule.recv(&recvbuf, 0, &[]);
+            }
+            Root::ProcNull => {}
+            Root::Rank(root) => {
+                assert!(root < self.remote_size());
+                let sendbuf = sendbuf.expect("The ranks of the other group must supply data.");
+                if !op.is_defined_for(sendbuf.datatype()) {
+                    return Err(Error::UndefinedOp);
+                }
+
+                let group = self.local_group();
+                let result = schedule.scratch(sendbuf.count(), sendbuf.datatype());
+                let output = if group.rank() == 0 { Some(&result) } else { None };
+                schedule.within(group);
+                let reduced = group.reduce_steps(&mut schedule, &sendbuf, output, op, 0);
+                if group.rank() == 0 {
+                    schedule.within(self);
+                    schedule.send(&result, self.address(root), &reduced);
+                }
+            }
+        }
+        Ok(schedule)
+    }
+
+    // Each group reduces its contributions to its rank 0, the two swap results, and each
+    // broadcasts the other group's result within its group. Every rank needs `sendbuf`.
+    pub(crate) fn intercomm_allreduce_schedule<'a>(
+        &self,
+        sendbuf: Option<Region<'a>>,
+        recvbuf: Region<'a>,
+        op: &Op,
+    ) -> error::Result<Schedule<'a>> {
+        let sendbuf = sendbuf.expect("Intercommunicators can't reduce in place.");
+        if !op.is_defined_for(sendbuf.datatype()) {
+            return Err(Error::UndefinedOp);
+        }
+
+        let mut schedule = self.schedule();
+        let group = self.local_group();
+        let result = schedule.scratch(sendbuf.count(), sendbuf.datatype());
+        let output = if group.rank() == 0 { Some(&result) } else { None };
+        schedule.within(group);
+        let mut last = group.reduce_steps(&mut schedule, &sendbuf, output, op, 0);
+        if group.rank() == 0 {
+            schedule.within(self);
+            last = vec![
+                schedule.send(&result, self.address(0), &last),
+                schedule.recv(&recvbuf, 0, &last),
+            ];
+            schedule.within(group);
+        }
+        group.bcast_steps(&mut schedule, &recvbuf, 0, &last);
+        Ok(schedule)
+    }
+
+    pub fn intercomm_gather<'b, 'c, S: Into<Buffer<'b>>, R: Into<BufferMut<'c>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: Option<R>,
+        root: Root,
+    ) -> error::Result<()> {
+        let sendbuf = sendbuf.map(Into::<Buffer>::into);
+        let recvbuf = recvbuf.map(Into::<BufferMut>::into);
+        self.intercomm_igather(sendbuf, recvbuf, root)
+            .wait()
+            .map(|_| ())
+    }
+
+    pub fn intercomm_igather<'a, S: Into<Buffer<'a>>, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: Option<R>,
+        root: Root,
+    ) -> Request<'a> {
+        let sendbuf = sendbuf.map(|sendbuf| Region::from(sendbuf.into()));
+        let recvbuf = recvbuf.map(|recvbuf| Region::from(recvbuf.into()));
+        Request::schedule(self.intercomm_gather_schedule(sendbuf, recvbuf, root))
+    }
+
+    pub fn intercomm_gather_init<'a, S: Into<Buffer<'a>>, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbuf: Option<S>,
+        recvbuf: Option<R>,
+        root: Root,
+    ) -> Request<'a> {
+        let sendbuf = sendbuf.map(|sendbuf| Region::from(sendbuf.into()));
+        let recvbuf = recvbuf.map(|recvbuf| Region::from(recvbuf.into()));
+        self.persistent(self.intercomm_gather_schedule(sendbuf, recvbuf, root))
+    }
+
+    // The other group gathers its blocks at its rank 0, which sends them all to the root as one
+    // message. Only the root needs `recvbuf`, which holds a block for each rank of the other group
+    // in order, and only the other group `sendbuf`.
+    fn intercomm_gather_schedule<'a>(
+        &self,
+        sendbuf: Option<Region<'a>>,
+        recvbuf: Option<Region<'a>>,
+        root: Root,
+    ) -> Schedule<'a> {
+        self.assert_intercomm();
+        let mut schedule = self.schedule();
+
+        match root {
+            Root::Root => {
+                let recvbuf = recvbuf.expect("The root rank must supply a receive buffer.");
+                schedule.recv(&recvbuf, 0, &[]);
+            }
+            Root::ProcNull => {}
+            Root::Rank(root) => {
+                assert!(root < self.remote_size());
+                let sendbuf = sendbuf.expect("The ranks of the other group must supply data.");
+                let group = self.local_group();
+                schedule.within(group);
+                if group.rank() == 0 {
+                    let count = group.size() * sendbuf.count();
+                    let blocks = schedule.scratch(count, sendbuf.datatype());
+                    let gathered =
+                        group.gather_steps(&mut schedule, Some(sendbuf), Some(blocks.clone()), 0);
+                    schedule.within(self);
+                    schedule.send(&blocks, self.address(root), &gathered);
+                } else {
+                    group.gather_steps(&mut schedule, Some(sendbuf), None, 0);
+                }
+            }
+        }
+        schedule
+    }
+
+    // Every rank sends `sendbuf` to each rank of the other group directly, and receives the block
+    // of each of them. Every rank needs `sendbuf`.
+    pub(crate) fn intercomm_allgatherv_schedule<'a>(
+        &self,
+        sendbuf: Option<Region<'a>>,
+        blocks: Vec<Region<'a>>,
+    ) -> Schedule<'a> {
+        let sendbuf = sendbuf.expect("Intercommunicators can't gather in place.");
+        assert!(blocks.len() == self.remote_size());
+        let mut schedule = self.schedule();
+
+        for (rank, block) in blocks.iter().enumerate() {
+            schedule.send(&sendbuf, self.address(rank), &[]);
+            schedule.recv(block, rank, &[]);
+        }
+        schedule
+    }
+
+    // Every rank exchanges a block with each rank of the other group directly. Every rank needs
+    // `sendbufs`.
+    pub(crate) fn intercomm_alltoallv_schedule<'a>(
+        &self,
+        sendbufs: Option<Vec<Region<'a>>>,
+        recvbufs: Vec<Region<'a>>,
+    ) -> Schedule<'a> {
+        let sendbufs = sendbufs.expect("Intercommunicators can't exchange in place.");
+        assert!(sendbufs.len() == self.remote_size() && recvbufs.len() == self.remote_size());
+        let mut schedule = self.schedule();
+
+        for (rank, (sendbuf, recvbuf)) in sendbufs.iter().zip(&recvbufs).enumerate() {
+            schedule.send(sendbuf, self.address(rank), &[]);
+            schedule.recv(recvbuf, rank, &[]);
+        }
+        schedule
+    }
+}
@@ -7,12 +7,34 @@ extern crate tokio;
 extern crate tokio_process;
 
 pub mod comm;
+pub mod dataloop;
+pub mod datatype;
 pub mod error;
+pub mod grequest;
+pub mod op;
 pub mod port;
+pub mod pt2pt;
+pub mod request;
 pub mod universe;
 
+pub use buffer::{BsendBuffer, BSEND_OVERHEAD};
 pub use comm::Comm;
+pub use datatype::{Buffer, BufferMut, CBool, Datatype, Equivalence};
 pub use error::{Error, Result};
+pub use grequest::{Completer, Generalized};
+pub use intercomm::Root;
+pub use op::Op;
+pub use pt2pt::{Destination, Message, Source, Status, Tag};
+pub use request::Request;
 pub use universe::Universe;
 
+mod arch;
+mod buffer;
+mod collective;
+mod engine;
+mod intercomm;
+mod partitioned;
+mod protocol;
 mod registrar;
+mod schedule;
+mod transport;
@@ -0,0 +1,495 @@
+use super::{datatype::{Buffer, BufferMut, CBool, Datatype, Primitive}, error::{self, Error}};
+
+use std::{cmp, fmt, ptr, os::raw::{c_int, c_long, c_short, c_uint, c_ulong, c_ushort},
+          sync::Arc};
+
+// The operations that reductions combine values with. The predefined ones apply to the primitives
+// of a datatype one at a time, so each works on any datatype built from primitives it is defined
+// for. MaxLoc and MinLoc apply to pairs of a value and an int index instead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Op {
+    Sum,
+    Prod,
+    Max,
+    Min,
+    LogicalAnd,
+    BitwiseAnd,
+    LogicalOr,
+    BitwiseOr,
+    LogicalXor,
+    BitwiseXor,
+    MaxLoc,
+    MinLoc,
+    // Keeps the incoming value, and keeps the value already there, respectively.
+    Replace,
+    NoOp,
+    User(UserOp),
+}
+
+// Combines the elements of the first buffer into those of the second, which hold the same number
+// of elements of the same datatype, leaving `input op inout` in the second.
+type UserFunction = dyn Fn(&Buffer, &mut BufferMut) + Send + Sync;
+
+// An operation written by the user.
+#[derive(Clone)]
+pub struct UserOp {
+    function: Arc<UserFunction>,
+    commutative: bool,
+}
+
+impl fmt::Debug for UserOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("UserOp")
+            .field("commutative", &self.commutative)
+            .finish()
+    }
+}
+
+// User operations are the same only if they were made by the same call.
+impl PartialEq for UserOp {
+    fn eq(&self, other: &UserOp) -> bool {
+        Arc::ptr_eq(&self.function, &other.function)
+    }
+}
+
+impl Eq for UserOp {}
+
+// A pair of complex numbers laid out the way C and C++ lay them out.
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct Complex<T> {
+    re: T,
+    im: T,
+}
+
+trait Arithmetic: Copy {
+    // Whether values can be compared, which complex numbers can't.
+    const ORDERED: bool;
+
+    fn sum(a: Self, b: Self) -> Self;
+    fn prod(a: Self, b: Self) -> Self;
+    fn max(a: Self, b: Self) -> Self;
+    fn min(a: Self, b: Self) -> Self;
+}
+
+// The logical operations treat zero as false and anything else as true, and give 1 for true.
+trait Logical: Copy {
+    fn truth(self) -> bool;
+    fn from_truth(truth: bool) -> Self;
+}
+
+trait Bitwise: Copy {
+    fn and(a: Self, b: Self) -> Self;
+    fn or(a: Self, b: Self) -> Self;
+    fn xor(a: Self, b: Self) -> Self;
+}
+
+// Integer arithmetic wraps, as it does in C for the unsigned types.
+macro_rules! integers {
+    ($($type:ty),*) => {
+        $(
+            impl Arithmetic for $type {
+                const ORDERED: bool = true;
+
+                fn sum(a: Self, b: Self) -> Self {
+                    a.wrapping_add(b)
+                }
+
+                fn prod(a: Self, b: Self) -> Self {
+                    a.wrapping_mul(b)
+                }
+
+                fn max(a: Self, b: Self) -> Self {
+                    cmp::max(a, b)
+                }
+
+                fn min(a: Self, b: Self) -> Self {
+                    cmp::min(a, b)
+                }
+            }
+
+            impl Logical for $type {
+                fn truth(self) -> bool {
+                    self != 0
+                }
+
+                fn from_truth(truth: bool) -> Self {
+                    truth as $type
+                }
+            }
+
+            impl Bitwise for $type {
+                fn and(a: Self, b: Self) -> Self {
+                    a & b
+                }
+
+                fn or(a: Self, b: Self) -> Self {
+                    a | b
+                }
+
+                fn xor(a: Self, b: Self) -> Self {
+                    a ^ b
+                }
+            }
+        )*
+    };
+}
+
+integers!(i8, u8, i16, u16, i32, u32, i64, u64, isize, usize);
+
+// C's bool and C++'s bool both reduce as CBool, reading any byte other than 0 as true.
+impl Logical for CBool {
+    fn truth(self) -> bool {
+        self.into()
+    }
+
+    fn from_truth(truth: bool) -> Self {
+        truth.into()
+    }
+}
+
+macro_rules! floats {
+    ($($type:ty),*) => {
+        $(
+            impl Arithmetic for $type {
+                const ORDERED: bool = true;
+
+                fn sum(a: Self, b: Self) -> Self {
+                    a + b
+                }
+
+                fn prod(a: Self, b: Self) -> Self {
+                    a * b
+                }
+
+                fn max(a: Self, b: Self) -> Self {
+                    a.max(b)
+                }
+
+                fn min(a: Self, b: Self) -> Self {
+                    a.min(b)
+                }
+            }
+
+            impl Arithmetic for Complex<$type> {
+                const ORDERED: bool = false;
+
+                fn sum(a: Self, b: Self) -> Self {
+                    Complex {
+                        re: a.re + b.re,
+                        im: a.im + b.im,
+                    }
+                }
+
+                fn prod(a: Self, b: Self) -> Self {
+                    Complex {
+                        re: a.re * b.re - a.im * b.im,
+                        im: a.re * b.im + a.im * b.re,
+                    }
+                }
+
+                fn max(_: Self, _: Self) -> Self {
+                    unreachable!()
+                }
+
+                fn min(_: Self, _: Self) -> Self {
+                    unreachable!()
+                }
+            }
+        )*
+    };
+}
+
+floats!(f32, f64);
+
+// Stores `combine(input, inout)` in `inout` for `len` values of type T. Neither buffer needs to be
+// aligned.
+unsafe fn combine_each<T: Copy>(
+    combine: fn(T, T) -> T,
+    input: *const u8,
+    inout: *mut u8,
+    len: usize,
+) {
+    let (input, inout) = (input as *const T, inout as *mut T);
+    for i in 0..len {
+        let value = combine(
+            ptr::read_unaligned(input.add(i)),
+            ptr::read_unaligned(inout.add(i)),
+        );
+        ptr::write_unaligned(inout.add(i), value);
+    }
+}
+
+// Each of these combines `len` values of type T, storing `input op inout` in `inout`, or returns
+// false if the operation isn't defined for T.
+unsafe fn arithmetic<T: Arithmetic>(op: &Op, input: *const u8, inout: *mut u8, len: usize) -> bool {
+    let combine: fn(T, T) -> T = match *op {
+        Op::Sum => T::sum,
+        Op::Prod => T::prod,
+        Op::Max | Op::Min if !T::ORDERED => return false,
+        Op::Max => T::max,
+        Op::Min => T::min,
+        _ => return false,
+    };
+    combine_each(combine, input, inout, len);
+    true
+}
+
+unsafe fn logical<T: Logical>(op: &Op, input: *const u8, inout: *mut u8, len: usize) -> bool {
+    let combine: fn(T, T) -> T = match *op {
+        Op::LogicalAnd => |a, b| T::from_truth(a.truth() && b.truth()),
+        Op::LogicalOr => |a, b| T::from_truth(a.truth() || b.truth()),
+        Op::LogicalXor => |a, b| T::from_truth(a.truth() != b.truth()),
+        _ => return false,
+    };
+    combine_each(combine, input, inout, len);
+    true
+}
+
+unsafe fn bitwise<T: Bitwise>(op: &Op, input: *const u8, inout: *mut u8, len: usize) -> bool {
+    let combine: fn(T, T) -> T = match *op {
+        Op::BitwiseAnd => T::and,
+        Op::BitwiseOr => T::or,
+        Op::BitwiseXor => T::xor,
+        _ => return false,
+    };
+    combine_each(combine, input, inout, len);
+    true
+}
+
+// Calls `$function::<T>($args)` with the Rust type T of an integer primitive, or gives false for
+// any other primitive.
+macro_rules! by_integer_type {
+    ($primitive:expr, $function:ident($($arg:expr),*)) => {
+        match $primitive {
+            Primitive::SignedChar | Primitive::Int8 => $function::<i8>($($arg),*),
+            Primitive::UnsignedChar | Primitive::Uint8 => $function::<u8>($($arg),*),
+            Primitive::Short => $function::<c_short>($($arg),*),
+            Primitive::UnsignedShort => $function::<c_ushort>($($arg),*),
+            Primitive::Int => $function::<c_int>($($arg),*),
+            Primitive::Unsigned => $function::<c_uint>($($arg),*),
+            Primitive::Long => $function::<c_long>($($arg),*),
+            Primitive::UnsignedLong => $function::<c_ulong>($($arg),*),
+            Primitive::LongLong | Primitive::Int64 | Primitive::Offset | Primitive::Count => {
+                $function::<i64>($($arg),*)
+            }
+            Primitive::UnsignedLongLong | Primitive::Uint64 => $function::<u64>($($arg),*),
+            Primitive::Int16 => $function::<i16>($($arg),*),
+            Primitive::Uint16 => $function::<u16>($($arg),*),
+            Primitive::Int32 => $function::<i32>($($arg),*),
+            Primitive::Uint32 => $function::<u32>($($arg),*),
+            Primitive::Aint => $function::<isize>($($arg),*),
+            _ => false,
+        }
+    };
+}
+
+// Combines a run of `len` primitives, or returns false if the operation isn't defined for them.
+// Characters and packed data aren't numbers, booleans only take the logical operations and bytes
+// only the bitwise ones. Rust has no long double arithmetic, so reductions over long doubles aren't
+// available either.
+unsafe fn apply_run(
+    op: &Op,
+    primitive: Primitive,
+    input: *const u8,
+    inout: *mut u8,
+    len: usize,
+) -> bool {
+    match *op {
+        Op::Sum | Op::Prod | Op::Max | Op::Min => match primitive {
+            Primitive::Float => arithmetic::<f32>(op, input, inout, len),
+            Primitive::Double => arithmetic::<f64>(op, input, inout, len),
+            Primitive::CFloatComplex | Primitive::CxxFloatComplex => {
+                arithmetic::<Complex<f32>>(op, input, inout, len)
+            }
+            Primitive::CDoubleComplex | Primitive::CxxDoubleComplex => {
+                arithmetic::<Complex<f64>>(op, input, inout, len)
+            }
+            _ => by_integer_type!(primitive, arithmetic(op, input, inout, len)),
+        },
+        Op::LogicalAnd | Op::LogicalOr | Op::LogicalXor => match primitive {
+            Primitive::CBool | Primitive::CxxBool => logical::<CBool>(op, input, inout, len),
+            _ => by_integer_type!(primitive, logical(op, input, inout, len)),
+        },
+        Op::BitwiseAnd | Op::BitwiseOr | Op::BitwiseXor => match primitive {
+            Primitive::Byte => bitwise::<u8>(op, input, inout, len),
+            _ => by_integer_type!(primitive, bitwise(op, input, inout, len)),
+        },
+        Op::Replace => {
+            ptr::copy(input, inout, len * primitive.size());
+            true
+        }
+        Op::NoOp => true,
+        Op::MaxLoc | Op::MinLoc | Op::User(_) => false,
+    }
+}
+
+// Combines a pair of a value of type T and an int index at `input` with the one at `inout`, the
+// indices lying `index` bytes after the values. The greater or lesser value wins, and of equal
+// values the lower index.
+unsafe fn locate<T: PartialOrd + Copy>(op: &Op, input: *const u8, inout: *mut u8, index: isize) {
+    let (a, b) = (input as *const T, inout as *mut T);
+    let (i, j) = (
+        input.wrapping_offset(index) as *const c_int,
+        inout.wrapping_offset(index) as *mut c_int,
+    );
+    let (a_value, b_value) = (ptr::read_unaligned(a), ptr::read_unaligned(b));
+    let (i_value, j_value) = (ptr::read_unaligned(i), ptr::read_unaligned(j));
+
+    let a_wins = match *op {
+        Op::MaxLoc => a_value > b_value,
+        _ => a_value < b_value,
+    };
+    if a_wins {
+        ptr::write_unaligned(b, a_value);
+        ptr::write_unaligned(j, i_value);
+    } else if a_value == b_value {
+        ptr::write_unaligned(j, cmp::min(i_value, j_value));
+    }
+}
+
+type Locate = unsafe fn(&Op, *const u8, *mut u8, isize);
+
+// The values of the predefined pair datatypes, other than long double.
+fn locator(value: Primitive) -> Option<Locate> {
+    match value {
+        Primitive::Float => Some(locate::<f32>),
+        Primitive::Double => Some(locate::<f64>),
+        Primitive::Long => Some(locate::<c_long>),
+        Primitive::Int => Some(locate::<c_int>),
+        Primitive::Short => Some(locate::<c_short>),
+        _ => None,
+    }
+}
+
+// The primitives of `count` elements of a datatype, in the order they are packed, paired up as
+// values and the int indices that follow them: the offset and type of each value, and the offset
+// of its index. None if the primitives don't alternate that way.
+fn pairs(datatype: &Datatype, count: usize) -> Option<Vec<(isize, Primitive, isize)>> {
+    let mut primitives = datatype
+        .dataloop()
+        .unwrap()
+        .runs(count, datatype.extent())
+        .flat_map(|(offset, primitive, len)| {
+            (0..len).map(move |i| (offset + (i * primitive.size()) as isize, primitive))
+        });
+
+    let mut pairs = Vec::new();
+    loop {
+        match (primitives.next(), primitives.next()) {
+            (None, _) => return Some(pairs),
+            (Some((value_offset, value)), Some((index_offset, Primitive::Int))) => {
+                pairs.push((value_offset, value, index_offset));
+            }
+            _ => return None,
+        }
+    }
+}
+
+impl Op {
+    // An operation that calls `function` to combine elements. Reductions with an operation that
+    // isn't commutative combine the contributions of the ranks in rank order.
+    pub fn user<F>(function: F, commutative: bool) -> Self
+    where
+        F: Fn(&Buffer, &mut BufferMut) + Send + Sync + 'static,
+    {
+        Op::User(UserOp {
+            function: Arc::new(function),
+            commutative,
+        })
+    }
+
+    // Replace and NoOp keep one side or the other, so they are the only predefined operations
+    // whose order matters.
+    pub fn is_commutative(&self) -> bool {
+        match *self {
+            Op::Replace | Op::NoOp => false,
+            Op::User(ref user) => user.commutative,
+            _ => true,
+        }
+    }
+
+    // Whether the operation can combine every primitive in the datatype. Collectives check this
+    // before sending anything, so that every rank fails alike. User operations are trusted to
+    // handle any datatype they are given.
+    pub fn is_defined_for(&self, datatype: &Datatype) -> bool {
+        match *self {
+            Op::User(_) => true,
+            Op::MaxLoc | Op::MinLoc => pairs(datatype, 1).map_or(false, |pairs| {
+                pairs.iter().all(|&(_, value, _)| locator(value).is_some())
+            }),
+            _ => datatype
+                .dataloop()
+                .unwrap()
+                .runs(1, datatype.extent())
+                .all(|(_, primitive, _)| unsafe {
+                    apply_run(self, primitive, ptr::null(), ptr::null_mut(), 0)
+                }),
+        }
+    }
+
+    // Combines the elements of `input` into those of `inout`, leaving `input op inout` in `inout`.
+    // Both must hold the same number of elements laid out alike.
+    pub fn reduce_local<'b, 'c, S: Into<Buffer<'b>>, R: Into<BufferMut<'c>>>(
+        &self,
+        input: S,
+        inout: R,
+    ) -> error::Result<()> {
+        let (input, mut inout) = (input.into(), inout.into());
+        assert!(
+            input.count() == inout.count()
+                && input.datatype().extent() == inout.datatype().extent()
+                && input.datatype().dataloop() == inout.datatype().dataloop(),
+            "The buffers of a local reduction must be laid out alike."
+        );
+        if !self.is_defined_for(inout.datatype()) {
+            return Err(Error::UndefinedOp);
+        }
+
+        let (count, datatype) = (inout.count(), inout.datatype().clone());
+        unsafe { self.apply(&datatype, count, input.as_ptr(), inout.as_mut_ptr()) };
+        Ok(())
+    }
+
+    // Combines `count` elements of `datatype` at `input` into those at `inout`, which are laid out
+    // alike, leaving `input op inout` in `inout`. The caller promises that the operation is
+    // defined for the datatype, and that every byte the datatype describes is readable at `input`
+    // and writable at `inout`.
+    pub(crate) unsafe fn apply(
+        &self,
+        datatype: &Datatype,
+        count: usize,
+        input: *const u8,
+        inout: *mut u8,
+    ) {
+        match *self {
+            Op::User(ref user) => {
+                let input = Buffer::new(input, count, datatype.clone());
+                let mut inout = BufferMut::new(inout, count, datatype.clone());
+                (user.function)(&input, &mut inout);
+            }
+            Op::MaxLoc | Op::MinLoc => {
+                for (offset, value, index) in pairs(datatype, count).unwrap() {
+                    locator(value).unwrap()(
+                        self,
+                        input.wrapping_offset(offset),
+                        inout.wrapping_offset(offset),
+                        index - offset,
+                    );
+                }
+            }
+            _ => {
+                let dataloop = datatype.dataloop().unwrap();
+                for (offset, primitive, len) in dataloop.runs(count, datatype.extent()) {
+                    apply_run(
+                        self,
+                        primitive,
+                        input.wrapping_offset(offset),
+                        inout.wrapping_offset(offset),
+                        len,
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use super::{Buffer, BufferMut, Comm, engine::PartitionKey, pt2pt::{Destination, Source, Status},
+            request::{Partitions, Request, Start}};
+
+// Partitioned operations are persistent: they are created inactive, and each start sends or
+// receives the whole buffer again. The buffer's elements are split into `partitions` equal parts,
+// which are packed and unpacked through the datatype like point-to-point messages; a sender
+// marks each ready with Request::pready, at which point it is sent, and a receiver can check each
+// with Request::parrived before the whole operation completes.
+//
+// A partitioned send is matched with the partitioned receive initialized in the same position on
+// the same communicator, peer and tag. Wildcards aren't allowed. Partitions travel in the sender's
+// layout, and a receiver whose layout differs converts them as it unpacks them.
+impl Comm {
+    pub fn psend_init<'a, B: Into<Buffer<'a>>>(
+        &self,
+        buf: B,
+        partitions: usize,
+        dest: Destination,
+        tag: i32,
+    ) -> Request<'a> {
+        let buf = buf.into();
+        assert!(partitions > 0);
+        assert!(buf.count() % partitions == 0);
+
+        let start = match dest {
+            Destination::Rank(dest) => {
+                assert!(dest < self.remote_size());
+
+                let channel = self.engine().open_partitioned_channel(
+                    self.context_id(),
+                    dest as u32,
+                    tag,
+                    true,
+                );
+                Start::Psend {
+                    buf,
+                    partitions,
+                    address: self.address(dest).to_owned(),
+                    key: PartitionKey {
+                        context_id: self.context_id(),
+                        source: self.rank() as u32,
+                        tag,
+                        channel,
+                        round: 0,
+                    },
+                }
+            }
+            Destination::ProcNull => Start::Null(Status::empty()),
+        };
+
+        Request::partitioned(self.engine().clone(), start, Partitions::Send(partitions))
+    }
+
+    pub fn precv_init<'a, B: Into<BufferMut<'a>>>(
+        &self,
+        buf: B,
+        partitions: usize,
+        source: Source,
+        tag: i32,
+    ) -> Request<'a> {
+        let buf = buf.into();
+        assert!(partitions > 0);
+        assert!(buf.count() % partitions == 0);
+
+        let start = match source {
+            Source::Rank(source) => {
+                assert!(source < self.remote_size());
+
+                let channel = self.engine().open_partitioned_channel(
+                    self.context_id(),
+                    source as u32,
+                    tag,
+                    false,
+                );
+                Start::Precv {
+                    buf: Some(buf),
+                    partitions,
+                    key: PartitionKey {
+                        context_id: self.context_id(),
+                        source: source as u32,
+                        tag,
+                        channel,
+                        round: 0,
+                    },
+                }
+            }
+            Source::ProcNull => Start::Null(Status::null()),
+            Source::Any => panic!("Partitioned receives must name their source."),
+        };
+
+        Request::partitioned(self.engine().clone(), start, Partitions::Recv(partitions))
+    }
+}
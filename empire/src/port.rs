@@ -1,8 +1,8 @@
-use super::error;
+use super::{error, engine::Engine, protocol::{self, Frame, Handshake, Header}};
 
-use futures::sync::oneshot;
-use std::thread;
-use tokio::{self, net::{TcpListener, TcpStream}, prelude::*};
+use futures::{future::Loop, sync::oneshot};
+use std::{thread, sync::Arc};
+use tokio::{self, io, net::{TcpListener, TcpStream}, prelude::*, runtime::Runtime};
 
 enum ServerEvent {
     Connection(TcpStream),
@@ -15,18 +15,99 @@ pub struct Port {
     server_thread: Option<thread::JoinHandle<()>>,
 }
 
-fn process_new_connection(connection: TcpStream) -> impl Future<Item = (), Error = ()> {
-    future::done::<(), ()>(Ok(()))
+fn protocol_error(err: error::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{}", err))
+}
+
+fn read_handshake(
+    connection: TcpStream,
+) -> impl Future<Item = (TcpStream, Handshake), Error = io::Error> {
+    io::read_exact(connection, [0u8; protocol::HANDSHAKE_LEN]).and_then(|(connection, fixed)| {
+        future::result(Handshake::decode_fixed(&fixed).map_err(protocol_error)).and_then(
+            move |(handshake, port_len)| {
+                io::read_exact(connection, vec![0u8; port_len]).and_then(
+                    move |(connection, reply_port)| {
+                        let mut handshake = handshake;
+                        handshake
+                            .set_reply_port(reply_port)
+                            .map_err(protocol_error)?;
+                        Ok((connection, handshake))
+                    },
+                )
+            },
+        )
+    })
+}
+
+// Resolves to None when the peer closes the connection between frames. Headers longer than
+// MAX_FRAME_LEN fail to decode, before their payload is allocated.
+fn read_frame(
+    connection: TcpStream,
+) -> impl Future<Item = Option<(TcpStream, Frame)>, Error = io::Error> {
+    io::read_exact(connection, [0u8; protocol::HEADER_LEN])
+        .then(|result| match result {
+            Ok((connection, header)) => Ok(Some((connection, header))),
+            Err(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(err) => Err(err),
+        })
+        .and_then(|read| match read {
+            Some((connection, header)) => future::Either::A(
+                future::result(Header::decode(&header).map_err(protocol_error)).and_then(
+                    move |header| {
+                        io::read_exact(connection, vec![0u8; header.length as usize]).map(
+                            move |(connection, payload)| {
+                                Some((
+                                    connection,
+                                    Frame {
+                                        kind: header.kind,
+                                        flags: header.flags,
+                                        envelope: header.envelope,
+                                        payload,
+                                    },
+                                ))
+                            },
+                        )
+                    },
+                ),
+            ),
+            None => future::Either::B(future::ok(None)),
+        })
+}
+
+fn process_new_connection(
+    connection: TcpStream,
+    engine: Arc<Engine>,
+) -> impl Future<Item = (), Error = ()> {
+    read_handshake(connection)
+        .and_then(move |(connection, handshake)| {
+            future::loop_fn(connection, move |connection| {
+                let engine = engine.clone();
+                let handshake = handshake.clone();
+                read_frame(connection).and_then(move |read| match read {
+                    Some((connection, frame)) => {
+                        engine
+                            .deliver(&handshake, frame)
+                            .map_err(protocol_error)?;
+                        Ok(Loop::Continue(connection))
+                    }
+                    None => Ok(Loop::Break(())),
+                })
+            })
+        })
+        .map_err(|err| {
+            eprintln!("Connection encountered error: {}", err);
+        })
 }
 
 impl Port {
-    pub fn new() -> error::Result<Port> {
+    pub(crate) fn new(engine: Arc<Engine>) -> error::Result<Port> {
         let listener = match TcpListener::bind(&("127.0.0.1:0".parse().unwrap())) {
             Ok(listener) => listener,
             Err(io) => return Err(error::Error::IoError(io)),
         };
 
         let name = format!("{}", listener.local_addr()?);
+        engine.transport().add_local_port(&name);
 
         let (terminal, receiver) = oneshot::channel();
 
@@ -52,9 +133,15 @@ impl Port {
                     ServerEvent::Connection(connection) => connection,
                     _ => panic!("EMPIRE internal error: Terminal events should be filtered out"),
                 })
-                .for_each(|connection| tokio::spawn(process_new_connection(connection)));
+                .for_each(move |connection| {
+                    tokio::spawn(process_new_connection(connection, engine.clone()))
+                });
 
-            tokio::run(server);
+            // Unlike tokio::run, don't wait for peers to hang up. A peer may hold its connection
+            // open until it finalizes, which could in turn be waiting on us.
+            let mut runtime = Runtime::new().expect("Could not start the port's runtime.");
+            let _ = runtime.block_on(server);
+            let _ = runtime.shutdown_now().wait();
         });
 
         Ok(Port {
@@ -79,3 +166,56 @@ impl Drop for Port {
             .expect("Server thread did not exit successfully.");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{datatype::BufferMut, engine::{MatchSpec, Payload},
+                       protocol::{Envelope, FrameKind}};
+
+    #[test]
+    fn frames_cross_a_connection() {
+        let receiver = Arc::new(Engine::new(1, 0, 1024));
+        let sender = Arc::new(Engine::new(1, 1, 1024));
+        let receiver_port = Port::new(receiver.clone()).unwrap();
+        let sender_port = Port::new(sender.clone()).unwrap();
+
+        let envelope = Envelope {
+            context_id: 9,
+            source: 1,
+            tag: 42,
+            id: 0,
+        };
+        let frame = Frame::new(FrameKind::Eager, envelope, b"over the wire".to_vec());
+        sender
+            .transport()
+            .send(receiver_port.name(), envelope.context_id, &frame);
+
+        let mut data = [0u8; 13];
+        let id = receiver.post_recv(
+            envelope.context_id,
+            MatchSpec {
+                source: Some(1),
+                tag: Some(42),
+            },
+            &mut BufferMut::from(&mut data[..]),
+        );
+        let message = loop {
+            let progress = receiver.progress_count();
+            if let Some(message) = receiver.take_matched(id) {
+                break message;
+            }
+            receiver.wait_progress(progress);
+        };
+
+        assert_eq!(message.envelope, envelope);
+        match message.payload {
+            Payload::Eager(ref payload) => assert_eq!(payload, b"over the wire"),
+            _ => panic!("the message didn't arrive eagerly"),
+        }
+        assert!(message.arch.is_native());
+        assert_eq!(message.reply_port, sender_port.name());
+
+        sender.transport().shutdown();
+    }
+}
@@ -0,0 +1,532 @@
+use super::{arch::{Arch, LongDouble}, error::{self, Error}};
+
+// Every connection starts with a handshake so the receiving Port knows which job the peer belongs
+// to, how it lays out data and where it can send replies. Handshakes with the wrong magic or
+// version are rejected.
+pub const MAGIC: [u8; 4] = *b"EMPR";
+pub const VERSION: u16 = 2;
+
+// magic (4), version (2), reserved (2), job id (8), context id (4), rank (4), arch (8),
+// reply port length (4)
+pub const HANDSHAKE_LEN: usize = 36;
+
+// big-endian (1), long size (1), MPI_Aint size (1), wchar_t size (1), long double format (1),
+// reserved (3)
+const ARCH_LEN: usize = 8;
+
+// kind (1), flags (1), reserved (2), context id (4), source (4), tag (4), id (8), length (8)
+pub const HEADER_LEN: usize = 32;
+
+// Set on an Eager frame when the sender wants a SyncAck once the message is matched.
+pub const FLAG_SYNC: u8 = 1;
+
+// Set on a CancelReply when the receiver withdrew the message.
+pub const FLAG_CANCELLED: u8 = 2;
+
+// Upper bound on the reply port name so a corrupt handshake can't make us allocate wildly.
+const MAX_PORT_NAME_LEN: usize = 4096;
+
+// Upper bound on a frame's payload, for the same reason. Senders keep their frames under it:
+// rendezvous payloads are split into chunks, eager messages are no larger than a chunk, and a
+// partition goes out a chunk of elements at a time, so only a single huge element comes near it.
+pub const MAX_FRAME_LEN: u64 = 1 << 34;
+
+// A rendezvous payload crosses the wire as Data frames of at most this many bytes, so neither side
+// holds more than a chunk of it in memory at once.
+pub const CHUNK_LEN: usize = 256 * 1024;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Handshake {
+    pub job_id: u64,
+    pub context_id: u32,
+    pub rank: u32,
+    pub arch: Arch,
+    pub reply_port: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameKind {
+    // A complete point-to-point message.
+    Eager = 1,
+    // A spawned rank telling its parent the name of its COMM_WORLD port.
+    Announce = 2,
+    // The parent's reply to Announce listing the port of every rank in the job.
+    Directory = 3,
+    // Request to send: the envelope of a large message and its length, without the payload.
+    Rts = 4,
+    // Clear to send: the receiver matched an Rts. Carries the id of the matching receive.
+    Cts = 5,
+    // The payload of a rendezvous message, addressed to the receive named in the Cts.
+    Data = 6,
+    // The receiver matched a message sent with FLAG_SYNC.
+    SyncAck = 7,
+    // One partition of a partitioned send. `envelope.id` holds the channel in its high 32 bits and
+    // the round in its low 32 bits. The payload is the u64 byte offset followed by the data.
+    Partition = 8,
+    // The sender asks to withdraw a message it sent with FLAG_SYNC or by rendezvous. The envelope
+    // is that of the message.
+    CancelSend = 9,
+    // The receiver's answer to CancelSend, with FLAG_CANCELLED set if the message was still
+    // unmatched and has been dropped. Otherwise the message was matched, and the SyncAck or Cts
+    // that says so has already been sent.
+    CancelReply = 10,
+}
+
+impl FrameKind {
+    fn from_u8(value: u8) -> error::Result<Self> {
+        Ok(match value {
+            1 => FrameKind::Eager,
+            2 => FrameKind::Announce,
+            3 => FrameKind::Directory,
+            4 => FrameKind::Rts,
+            5 => FrameKind::Cts,
+            6 => FrameKind::Data,
+            7 => FrameKind::SyncAck,
+            8 => FrameKind::Partition,
+            9 => FrameKind::CancelSend,
+            10 => FrameKind::CancelReply,
+            _ => return Err(Error::ProtocolError(format!("unknown frame kind {}", value))),
+        })
+    }
+}
+
+// Routing information carried by every frame. `source` is the sender's rank within the
+// communicator identified by `context_id`, and `id` is an opaque value chosen by the sender so that
+// replies can refer back to the originating request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Envelope {
+    pub context_id: u32,
+    pub source: u32,
+    pub tag: i32,
+    pub id: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Header {
+    pub kind: FrameKind,
+    pub flags: u8,
+    pub envelope: Envelope,
+    pub length: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub kind: FrameKind,
+    pub flags: u8,
+    pub envelope: Envelope,
+    pub payload: Vec<u8>,
+}
+
+fn put_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+fn put_u64(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+fn get_u16(bytes: &[u8], offset: usize) -> u16 {
+    let mut raw = [0u8; 2];
+    raw.copy_from_slice(&bytes[offset..offset + 2]);
+    u16::from_be_bytes(raw)
+}
+
+fn get_u32(bytes: &[u8], offset: usize) -> u32 {
+    let mut raw = [0u8; 4];
+    raw.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_be_bytes(raw)
+}
+
+fn get_u64(bytes: &[u8], offset: usize) -> u64 {
+    let mut raw = [0u8; 8];
+    raw.copy_from_slice(&bytes[offset..offset + 8]);
+    u64::from_be_bytes(raw)
+}
+
+impl Handshake {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HANDSHAKE_LEN + self.reply_port.len());
+        out.extend_from_slice(&MAGIC);
+        put_u16(&mut out, VERSION);
+        put_u16(&mut out, 0);
+        put_u64(&mut out, self.job_id);
+        put_u32(&mut out, self.context_id);
+        put_u32(&mut out, self.rank);
+        encode_arch(&self.arch, &mut out);
+        put_u32(&mut out, self.reply_port.len() as u32);
+        out.extend_from_slice(self.reply_port.as_bytes());
+        out
+    }
+
+    // Decodes the fixed portion of a handshake. The returned Handshake has an empty reply port;
+    // the caller must read `port_len` more bytes and pass them to `set_reply_port`.
+    pub fn decode_fixed(bytes: &[u8; HANDSHAKE_LEN]) -> error::Result<(Self, usize)> {
+        if bytes[..4] != MAGIC {
+            return Err(Error::ProtocolError(
+                "connection did not begin with an empire handshake".to_owned(),
+            ));
+        }
+
+        let version = get_u16(bytes, 4);
+        if version != VERSION {
+            return Err(Error::ProtocolError(format!(
+                "peer speaks protocol version {}, expected {}",
+                version, VERSION
+            )));
+        }
+
+        let arch = decode_arch(&bytes[24..24 + ARCH_LEN])?;
+
+        let port_len = get_u32(bytes, 32) as usize;
+        if port_len > MAX_PORT_NAME_LEN {
+            return Err(Error::ProtocolError(format!(
+                "handshake reply port is {} bytes long",
+                port_len
+            )));
+        }
+
+        Ok((
+            Handshake {
+                job_id: get_u64(bytes, 8),
+                context_id: get_u32(bytes, 16),
+                rank: get_u32(bytes, 20),
+                arch,
+                reply_port: String::new(),
+            },
+            port_len,
+        ))
+    }
+
+    pub fn set_reply_port(&mut self, bytes: Vec<u8>) -> error::Result<()> {
+        self.reply_port = String::from_utf8(bytes)
+            .map_err(|_| Error::ProtocolError("reply port is not valid UTF-8".to_owned()))?;
+        Ok(())
+    }
+}
+
+fn encode_arch(arch: &Arch, out: &mut Vec<u8>) {
+    out.push(arch.big_endian as u8);
+    out.push(arch.long_size as u8);
+    out.push(arch.aint_size as u8);
+    out.push(arch.wchar_size as u8);
+    out.push(arch.long_double as u8);
+    out.extend_from_slice(&[0; 3]);
+}
+
+// Only layouts we know how to convert are accepted.
+fn decode_arch(bytes: &[u8]) -> error::Result<Arch> {
+    let size = |byte: u8, sizes: &[u8]| {
+        if sizes.contains(&byte) {
+            Some(byte as usize)
+        } else {
+            None
+        }
+    };
+
+    match (
+        bytes[0],
+        size(bytes[1], &[4, 8]),
+        size(bytes[2], &[4, 8]),
+        size(bytes[3], &[2, 4]),
+        LongDouble::from_u8(bytes[4]),
+    ) {
+        (
+            big_endian @ 0..=1,
+            Some(long_size),
+            Some(aint_size),
+            Some(wchar_size),
+            Some(long_double),
+        ) => Ok(Arch {
+            big_endian: big_endian == 1,
+            long_size,
+            aint_size,
+            wchar_size,
+            long_double,
+        }),
+        _ => Err(Error::ProtocolError(format!(
+            "peer has an unsupported architecture {:?}",
+            &bytes[..5]
+        ))),
+    }
+}
+
+impl Header {
+    pub fn decode(bytes: &[u8; HEADER_LEN]) -> error::Result<Self> {
+        let length = get_u64(bytes, 24);
+        if length > MAX_FRAME_LEN {
+            return Err(Error::ProtocolError(format!(
+                "frame payload is {} bytes long",
+                length
+            )));
+        }
+
+        Ok(Header {
+            kind: FrameKind::from_u8(bytes[0])?,
+            flags: bytes[1],
+            envelope: Envelope {
+                context_id: get_u32(bytes, 4),
+                source: get_u32(bytes, 8),
+                tag: get_u32(bytes, 12) as i32,
+                id: get_u64(bytes, 16),
+            },
+            length,
+        })
+    }
+}
+
+impl Frame {
+    pub fn new(kind: FrameKind, envelope: Envelope, payload: Vec<u8>) -> Self {
+        Frame {
+            kind,
+            flags: 0,
+            envelope,
+            payload,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_LEN + self.payload.len());
+        encode_header(self.kind, self.flags, &self.envelope, self.payload.len() as u64, &mut out);
+        out.extend_from_slice(&self.payload);
+        out
+    }
+}
+
+// Starts an encoded frame. The caller appends the `length` bytes of payload, which lets large
+// payloads be packed straight into the frame.
+pub fn encode_header(
+    kind: FrameKind,
+    flags: u8,
+    envelope: &Envelope,
+    length: u64,
+    out: &mut Vec<u8>,
+) {
+    out.push(kind as u8);
+    out.push(flags);
+    put_u16(out, 0);
+    put_u32(out, envelope.context_id);
+    put_u32(out, envelope.source);
+    put_u32(out, envelope.tag as u32);
+    put_u64(out, envelope.id);
+    put_u64(out, length);
+}
+
+pub fn encode_u64(value: u64) -> Vec<u8> {
+    let mut out = Vec::with_capacity(8);
+    put_u64(&mut out, value);
+    out
+}
+
+pub fn decode_u64(bytes: &[u8]) -> error::Result<u64> {
+    if bytes.len() != 8 {
+        return Err(Error::ProtocolError(format!(
+            "expected an 8 byte payload, found {} bytes",
+            bytes.len()
+        )));
+    }
+    Ok(get_u64(bytes, 0))
+}
+
+// Directory payloads are a count followed by length-prefixed port names, indexed by rank.
+pub fn encode_directory(ports: &[String]) -> Vec<u8> {
+    let mut out = Vec::new();
+    put_u32(&mut out, ports.len() as u32);
+    for port in ports {
+        put_u32(&mut out, port.len() as u32);
+        out.extend_from_slice(port.as_bytes());
+    }
+    out
+}
+
+pub fn decode_directory(bytes: &[u8]) -> error::Result<Vec<String>> {
+    let malformed = || Error::ProtocolError("malformed directory frame".to_owned());
+
+    if bytes.len() < 4 {
+        return Err(malformed());
+    }
+
+    let count = get_u32(bytes, 0) as usize;
+    let mut offset = 4;
+    let mut ports = Vec::new();
+
+    for _ in 0..count {
+        if bytes.len() < offset + 4 {
+            return Err(malformed());
+        }
+        let len = get_u32(bytes, offset) as usize;
+        offset += 4;
+
+        if bytes.len() < offset + len {
+            return Err(malformed());
+        }
+        let port = String::from_utf8(bytes[offset..offset + len].to_vec());
+        ports.push(port.map_err(|_| malformed())?);
+        offset += len;
+    }
+
+    Ok(ports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handshake() -> Handshake {
+        Handshake {
+            job_id: 0x0123_4567_89ab_cdef,
+            context_id: 7,
+            rank: 3,
+            arch: Arch::native(),
+            reply_port: "127.0.0.1:4242".to_owned(),
+        }
+    }
+
+    fn fixed(bytes: &[u8]) -> [u8; HANDSHAKE_LEN] {
+        let mut fixed = [0u8; HANDSHAKE_LEN];
+        fixed.copy_from_slice(&bytes[..HANDSHAKE_LEN]);
+        fixed
+    }
+
+    fn header(bytes: &[u8]) -> [u8; HEADER_LEN] {
+        let mut header = [0u8; HEADER_LEN];
+        header.copy_from_slice(&bytes[..HEADER_LEN]);
+        header
+    }
+
+    #[test]
+    fn handshake_round_trips() {
+        let sent = handshake();
+        let bytes = sent.encode();
+
+        let (mut received, port_len) = Handshake::decode_fixed(&fixed(&bytes)).unwrap();
+        assert_eq!(port_len, sent.reply_port.len());
+        received
+            .set_reply_port(bytes[HANDSHAKE_LEN..].to_vec())
+            .unwrap();
+        assert_eq!(received, sent);
+    }
+
+    #[test]
+    fn handshake_rejects_bad_magic_version_and_port_length() {
+        let bytes = handshake().encode();
+
+        let mut bad_magic = fixed(&bytes);
+        bad_magic[0] = b'X';
+        assert!(Handshake::decode_fixed(&bad_magic).is_err());
+
+        let mut bad_version = fixed(&bytes);
+        bad_version[5] = bad_version[5].wrapping_add(1);
+        assert!(Handshake::decode_fixed(&bad_version).is_err());
+
+        let mut long_port = fixed(&bytes);
+        long_port[32..36].copy_from_slice(&(MAX_PORT_NAME_LEN as u32 + 1).to_be_bytes());
+        assert!(Handshake::decode_fixed(&long_port).is_err());
+    }
+
+    #[test]
+    fn arch_round_trips() {
+        for &arch in &[Arch::native(), Arch::external32()] {
+            let mut bytes = Vec::new();
+            encode_arch(&arch, &mut bytes);
+            assert_eq!(bytes.len(), ARCH_LEN);
+            assert_eq!(decode_arch(&bytes).unwrap(), arch);
+        }
+    }
+
+    #[test]
+    fn arch_rejects_unknown_layouts() {
+        let mut bytes = Vec::new();
+        encode_arch(&Arch::native(), &mut bytes);
+
+        for &(index, value) in &[(0, 2), (1, 2), (2, 16), (3, 1), (4, 0)] {
+            let mut bad = bytes.clone();
+            bad[index] = value;
+            assert!(decode_arch(&bad).is_err());
+        }
+    }
+
+    #[test]
+    fn frames_round_trip() {
+        let kinds = [
+            FrameKind::Eager,
+            FrameKind::Announce,
+            FrameKind::Directory,
+            FrameKind::Rts,
+            FrameKind::Cts,
+            FrameKind::Data,
+            FrameKind::SyncAck,
+            FrameKind::Partition,
+            FrameKind::CancelSend,
+            FrameKind::CancelReply,
+        ];
+
+        for (index, &kind) in kinds.iter().enumerate() {
+            let sent = Frame {
+                kind,
+                flags: FLAG_SYNC | FLAG_CANCELLED,
+                envelope: Envelope {
+                    context_id: 1 << 31 | index as u32,
+                    source: 5,
+                    tag: -(index as i32),
+                    id: !(index as u64),
+                },
+                payload: (0..index as u8).collect(),
+            };
+            let bytes = sent.encode();
+            assert_eq!(bytes.len(), HEADER_LEN + index);
+
+            let decoded = Header::decode(&header(&bytes)).unwrap();
+            assert_eq!(decoded.kind, kind);
+            assert_eq!(decoded.flags, sent.flags);
+            assert_eq!(decoded.envelope, sent.envelope);
+            assert_eq!(decoded.length, index as u64);
+            assert_eq!(&bytes[HEADER_LEN..], &sent.payload[..]);
+        }
+    }
+
+    #[test]
+    fn header_rejects_unknown_kinds_and_oversized_payloads() {
+        let frame = Frame::new(
+            FrameKind::Eager,
+            Envelope {
+                context_id: 0,
+                source: 0,
+                tag: 0,
+                id: 0,
+            },
+            Vec::new(),
+        );
+        let bytes = frame.encode();
+
+        let mut unknown = header(&bytes);
+        unknown[0] = 0;
+        assert!(Header::decode(&unknown).is_err());
+
+        let mut oversized = header(&bytes);
+        oversized[24..].copy_from_slice(&(MAX_FRAME_LEN + 1).to_be_bytes());
+        assert!(Header::decode(&oversized).is_err());
+    }
+
+    #[test]
+    fn directory_round_trips() {
+        let ports = vec![
+            "127.0.0.1:1".to_owned(),
+            String::new(),
+            "127.0.0.1:65535".to_owned(),
+        ];
+        assert_eq!(decode_directory(&encode_directory(&ports)).unwrap(), ports);
+        assert_eq!(decode_directory(&encode_directory(&[])).unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn directory_rejects_truncated_payloads() {
+        let bytes = encode_directory(&["127.0.0.1:1".to_owned()]);
+        for len in 0..bytes.len() {
+            assert!(decode_directory(&bytes[..len]).is_err());
+        }
+    }
+}
@@ -0,0 +1,628 @@
+use super::{Comm, arch::Arch, datatype::{Buffer, BufferMut, Datatype},
+            engine::{self, Engine, MatchSpec, Outgoing, Payload},
+            error::{self, Error}, protocol::Envelope, request::{Request, Start}};
+
+use std::{cmp::min, sync::Arc};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Destination {
+    Rank(usize),
+    // Sends to ProcNull complete immediately without sending anything.
+    ProcNull,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    Rank(usize),
+    Any,
+    // Receives from ProcNull complete immediately with an empty message.
+    ProcNull,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tag {
+    Value(i32),
+    Any,
+}
+
+// Describes a completed receive. `count` is the number of bytes received, which for a truncated
+// receive is only what fit in the buffer, and for a probe is the length of the message as its
+// sender laid it out. Use `len_as` for the length a receive would have.
+// `cancelled` is set on the status of an operation that was cancelled instead of completing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Status {
+    pub source: Source,
+    pub tag: Tag,
+    pub count: u64,
+    pub cancelled: bool,
+    // The layout `count` is measured in: the sender's for a probe, and this rank's otherwise.
+    pub(crate) arch: Arch,
+}
+
+impl Status {
+    // The status of a receive from ProcNull.
+    pub fn null() -> Self {
+        Status {
+            source: Source::ProcNull,
+            tag: Tag::Any,
+            count: 0,
+            cancelled: false,
+            arch: Arch::native(),
+        }
+    }
+
+    // The status of a null request, or of an operation that has no meaningful status.
+    pub fn empty() -> Self {
+        Status {
+            source: Source::Any,
+            tag: Tag::Any,
+            count: 0,
+            cancelled: false,
+            arch: Arch::native(),
+        }
+    }
+
+    pub fn cancelled() -> Self {
+        Status {
+            cancelled: true,
+            ..Status::empty()
+        }
+    }
+
+    fn of(envelope: &Envelope, length: u64) -> Self {
+        Status {
+            source: Source::Rank(envelope.source as usize),
+            tag: Tag::Value(envelope.tag),
+            count: length,
+            cancelled: false,
+            arch: Arch::native(),
+        }
+    }
+
+    // The status of a probed message, whose length is in the layout of its sender.
+    fn probed(envelope: &Envelope, length: u64, arch: Arch) -> Self {
+        Status {
+            arch,
+            ..Status::of(envelope, length)
+        }
+    }
+
+    // The number of bytes the message fills when it is received as `datatype`. Only differs from
+    // `count` for a probe of a message from a rank with another layout, which the receive converts.
+    pub fn len_as(&self, datatype: &Datatype) -> u64 {
+        if self.arch.is_native() {
+            self.count
+        } else {
+            datatype.len_from(&self.arch, self.count)
+        }
+    }
+
+    // The layout of `count` packed into a word, so that C statuses can carry it. Zero, which no
+    // layout packs into, is this rank's layout.
+    pub fn layout(&self) -> u32 {
+        if self.arch.is_native() {
+            0
+        } else {
+            self.arch.to_bits()
+        }
+    }
+
+    pub fn with_layout(self, layout: u32) -> Self {
+        Status {
+            arch: Arch::from_bits(layout).unwrap_or_else(Arch::native),
+            ..self
+        }
+    }
+}
+
+impl Source {
+    fn match_source(&self) -> Option<u32> {
+        match *self {
+            Source::Rank(rank) => Some(rank as u32),
+            Source::Any => None,
+            Source::ProcNull => panic!("EMPIRE internal error: ProcNull receives are never posted"),
+        }
+    }
+}
+
+impl Tag {
+    fn match_tag(&self) -> Option<i32> {
+        match *self {
+            Tag::Value(tag) => Some(tag),
+            Tag::Any => None,
+        }
+    }
+}
+
+// A message dequeued by a matched probe. No other receive can match it; it must be received with
+// `recv` or `imrecv`.
+pub struct Message {
+    engine: Arc<Engine>,
+    // None for the message matched by a probe of ProcNull.
+    id: Option<u64>,
+    status: Status,
+}
+
+impl Message {
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    pub fn is_no_proc(&self) -> bool {
+        self.id.is_none()
+    }
+
+    pub fn recv<'b, B: Into<BufferMut<'b>>>(self, buf: B) -> error::Result<Status> {
+        self.imrecv(buf).wait()
+    }
+
+    pub fn imrecv<'a, B: Into<BufferMut<'a>>>(self, buf: B) -> Request<'a> {
+        match self.id {
+            Some(id) => {
+                let mut buf = buf.into();
+                self.engine.recv_probed(id, &mut buf);
+                Request::recv(self.engine, id, buf)
+            }
+            None => Request::complete(self.engine, Ok(Status::null())),
+        }
+    }
+}
+
+// A synchronous send completes only once the receiver has matched the message; a rendezvous
+// already implies that. A rendezvous payload is packed from `buf` once the receiver asks for it,
+// so the request keeps `buf` borrowed.
+pub(crate) fn start_send<'a>(
+    engine: &Arc<Engine>,
+    address: &str,
+    envelope: Envelope,
+    buf: &Buffer<'a>,
+    sync: bool,
+    eager: bool,
+) -> Request<'a> {
+    if eager {
+        match engine.send_eager(address, envelope, buf, sync) {
+            Some(id) => Request::send(engine.clone(), id),
+            None => Request::complete(engine.clone(), Ok(Status::empty())),
+        }
+    } else {
+        let id = engine.send_rendezvous(address, envelope, Outgoing::Buffer(unsafe { buf.alias() }));
+        Request::send(engine.clone(), id)
+    }
+}
+
+// Like `start_send`, but copies the message before returning, so `buf` is free as soon as the send
+// has started.
+pub(crate) fn start_copied_send(
+    engine: &Arc<Engine>,
+    address: &str,
+    envelope: Envelope,
+    buf: &Buffer,
+    eager: bool,
+) -> Request<'static> {
+    if eager {
+        engine.send_eager(address, envelope, buf, false);
+        Request::complete(engine.clone(), Ok(Status::empty()))
+    } else {
+        let mut data = Vec::with_capacity(buf.len() as usize);
+        buf.pack_into(&mut data);
+        let id = engine.send_rendezvous(address, envelope, Outgoing::Packed(data));
+        Request::send(engine.clone(), id)
+    }
+}
+
+// Unpacks a matched message into `buf`. A message longer than `buf` fills it and then reports the
+// truncation, as required by the standard. A message from a peer with a different layout is
+// converted, and its status counts the bytes it takes up here.
+pub(crate) fn complete_recv(
+    message: engine::Message,
+    buf: &mut BufferMut,
+) -> error::Result<Status> {
+    let length = message.length;
+    let (capacity, received) = match message.payload {
+        Payload::Eager(ref payload) if message.arch.is_native() => {
+            buf.unpack(payload);
+            (buf.len(), min(length, buf.len()))
+        }
+        Payload::Eager(ref payload) => (
+            buf.len_in(&message.arch),
+            buf.unpack_from(&message.arch, payload),
+        ),
+        Payload::Unpacked(received) if message.arch.is_native() => (buf.len(), received),
+        Payload::Unpacked(received) => (buf.len_in(&message.arch), received),
+        Payload::Rendezvous => {
+            panic!("EMPIRE internal error: a receive completed before its payload arrived")
+        }
+    };
+
+    let status = Status::of(&message.envelope, received);
+    if length > capacity {
+        Err(Error::Truncated(status))
+    } else {
+        Ok(status)
+    }
+}
+
+impl Comm {
+    pub(crate) fn envelope(&self, tag: i32) -> Envelope {
+        Envelope {
+            context_id: self.context_id(),
+            source: self.rank() as u32,
+            tag,
+            id: 0,
+        }
+    }
+
+    fn match_spec(&self, source: Source, tag: Tag) -> MatchSpec {
+        if let Source::Rank(source) = source {
+            assert!(source < self.remote_size());
+        }
+
+        MatchSpec {
+            source: source.match_source(),
+            tag: tag.match_tag(),
+        }
+    }
+
+    pub(crate) fn post_recv(&self, source: Source, tag: Tag, buf: &mut BufferMut) -> u64 {
+        self.engine()
+            .post_recv(self.context_id(), self.match_spec(source, tag), buf)
+    }
+
+    // Blocks until `attempt` produces a result, checking again whenever the engine makes progress.
+    fn block_on<T, F: FnMut() -> Option<T>>(&self, mut attempt: F) -> T {
+        loop {
+            let since = self.engine().progress_count();
+            if let Some(result) = attempt() {
+                return result;
+            }
+            self.engine().wait_progress(since);
+        }
+    }
+
+    // Reports the status of a message that a receive with the same arguments would match, without
+    // receiving it.
+    pub fn probe(&self, source: Source, tag: Tag) -> Status {
+        self.block_on(|| self.iprobe(source, tag))
+    }
+
+    pub fn iprobe(&self, source: Source, tag: Tag) -> Option<Status> {
+        if source == Source::ProcNull {
+            return Some(Status::null());
+        }
+
+        self.engine()
+            .probe(self.context_id(), self.match_spec(source, tag))
+            .map(|(envelope, length, arch)| Status::probed(&envelope, length, arch))
+    }
+
+    // Unlike `probe`, the message is removed from matching, so another thread can't receive it
+    // between the probe and the receive.
+    pub fn mprobe(&self, source: Source, tag: Tag) -> Message {
+        self.block_on(|| self.improbe(source, tag))
+    }
+
+    pub fn improbe(&self, source: Source, tag: Tag) -> Option<Message> {
+        let engine = self.engine().clone();
+
+        if source == Source::ProcNull {
+            return Some(Message {
+                engine,
+                id: None,
+                status: Status::null(),
+            });
+        }
+
+        engine
+            .mprobe(self.context_id(), self.match_spec(source, tag))
+            .map(|(id, envelope, length, arch)| Message {
+                engine: engine.clone(),
+                id: Some(id),
+                status: Status::probed(&envelope, length, arch),
+            })
+    }
+
+    // Sends messages up to the communicator's eager limit whole, and larger ones by rendezvous.
+    fn start_send<'a>(
+        &self,
+        buf: &Buffer<'a>,
+        dest: Destination,
+        tag: i32,
+        sync: bool,
+    ) -> Request<'a> {
+        self.start_to(buf, dest, tag, |engine, address, envelope, eager| {
+            start_send(engine, address, envelope, buf, sync, eager)
+        })
+    }
+
+    // Like `start_send`, but the message is copied before this returns.
+    fn start_copied_send(&self, buf: &Buffer, dest: Destination, tag: i32) -> Request<'static> {
+        self.start_to(buf, dest, tag, |engine, address, envelope, eager| {
+            start_copied_send(engine, address, envelope, buf, eager)
+        })
+    }
+
+    fn start_to<'a, F>(&self, buf: &Buffer, dest: Destination, tag: i32, start: F) -> Request<'a>
+    where
+        F: FnOnce(&Arc<Engine>, &str, Envelope, bool) -> Request<'a>,
+    {
+        let engine = self.engine();
+
+        match dest {
+            Destination::Rank(dest) => {
+                assert!(dest < self.remote_size());
+
+                let eager = buf.len() <= self.eager_limit();
+                start(engine, self.address(dest), self.envelope(tag), eager)
+            }
+            Destination::ProcNull => Request::complete(engine.clone(), Ok(Status::empty())),
+        }
+    }
+
+    // Works out once what every start of a persistent send to `dest` needs.
+    fn send_template<'a>(
+        &self,
+        buf: Buffer<'a>,
+        dest: Destination,
+        tag: i32,
+        sync: bool,
+    ) -> Start<'a> {
+        match dest {
+            Destination::Rank(dest) => {
+                assert!(dest < self.remote_size());
+
+                Start::Send {
+                    eager: buf.len() <= self.eager_limit(),
+                    buf,
+                    address: self.address(dest).to_owned(),
+                    envelope: self.envelope(tag),
+                    sync,
+                }
+            }
+            Destination::ProcNull => Start::Null(Status::empty()),
+        }
+    }
+
+    // Standard mode send. Messages within the eager limit are copied to the receiver right away,
+    // so this returns without waiting for it. Larger messages wait for a matching receive.
+    pub fn send<'b, B: Into<Buffer<'b>>>(
+        &self,
+        buf: B,
+        dest: Destination,
+        tag: i32,
+    ) -> error::Result<()> {
+        self.isend(buf, dest, tag).wait().map(|_| ())
+    }
+
+    pub fn ssend<'b, B: Into<Buffer<'b>>>(
+        &self,
+        buf: B,
+        dest: Destination,
+        tag: i32,
+    ) -> error::Result<()> {
+        self.issend(buf, dest, tag).wait().map(|_| ())
+    }
+
+    pub fn rsend<'b, B: Into<Buffer<'b>>>(
+        &self,
+        buf: B,
+        dest: Destination,
+        tag: i32,
+    ) -> error::Result<()> {
+        self.irsend(buf, dest, tag).wait().map(|_| ())
+    }
+
+    // Fails if the attached buffer doesn't have room for the message.
+    pub fn bsend<'b, B: Into<Buffer<'b>>>(
+        &self,
+        buf: B,
+        dest: Destination,
+        tag: i32,
+    ) -> error::Result<()> {
+        self.ibsend(buf, dest, tag)?.wait().map(|_| ())
+    }
+
+    pub fn recv<'b, B: Into<BufferMut<'b>>>(
+        &self,
+        buf: B,
+        source: Source,
+        tag: Tag,
+    ) -> error::Result<Status> {
+        self.irecv(buf, source, tag).wait()
+    }
+
+    pub fn isend<'a, B: Into<Buffer<'a>>>(
+        &self,
+        buf: B,
+        dest: Destination,
+        tag: i32,
+    ) -> Request<'a> {
+        self.start_send(&buf.into(), dest, tag, false)
+    }
+
+    pub fn issend<'a, B: Into<Buffer<'a>>>(
+        &self,
+        buf: B,
+        dest: Destination,
+        tag: i32,
+    ) -> Request<'a> {
+        self.start_send(&buf.into(), dest, tag, true)
+    }
+
+    // A ready send may assume the receive is already posted. Nothing is gained from that here, so
+    // it behaves like a standard send.
+    pub fn irsend<'a, B: Into<Buffer<'a>>>(
+        &self,
+        buf: B,
+        dest: Destination,
+        tag: i32,
+    ) -> Request<'a> {
+        self.start_send(&buf.into(), dest, tag, false)
+    }
+
+    // The message is packed into the buffer attached to the universe and sent from there, so the
+    // returned request is already complete.
+    pub fn ibsend<'a, 'b, B: Into<Buffer<'b>>>(
+        &self,
+        buf: B,
+        dest: Destination,
+        tag: i32,
+    ) -> error::Result<Request<'a>> {
+        let buf = buf.into();
+        if dest != Destination::ProcNull {
+            self.universe()
+                .write()
+                .unwrap()
+                .bsend_buffer()?
+                .send(&buf, |packed| self.start_send(packed, dest, tag, false))?;
+        }
+
+        Ok(Request::complete(self.engine().clone(), Ok(Status::empty())))
+    }
+
+    pub fn irecv<'a, B: Into<BufferMut<'a>>>(
+        &self,
+        buf: B,
+        source: Source,
+        tag: Tag,
+    ) -> Request<'a> {
+        match source {
+            Source::ProcNull => Request::complete(self.engine().clone(), Ok(Status::null())),
+            source => {
+                let mut buf = buf.into();
+                let id = self.post_recv(source, tag, &mut buf);
+                Request::recv(self.engine().clone(), id, buf)
+            }
+        }
+    }
+
+    // The `_init` routines create inactive persistent requests; see Request::start.
+
+    pub fn send_init<'a, B: Into<Buffer<'a>>>(
+        &self,
+        buf: B,
+        dest: Destination,
+        tag: i32,
+    ) -> Request<'a> {
+        let start = self.send_template(buf.into(), dest, tag, false);
+        Request::persistent(self.engine().clone(), start)
+    }
+
+    pub fn ssend_init<'a, B: Into<Buffer<'a>>>(
+        &self,
+        buf: B,
+        dest: Destination,
+        tag: i32,
+    ) -> Request<'a> {
+        let start = self.send_template(buf.into(), dest, tag, true);
+        Request::persistent(self.engine().clone(), start)
+    }
+
+    pub fn rsend_init<'a, B: Into<Buffer<'a>>>(
+        &self,
+        buf: B,
+        dest: Destination,
+        tag: i32,
+    ) -> Request<'a> {
+        let start = self.send_template(buf.into(), dest, tag, false);
+        Request::persistent(self.engine().clone(), start)
+    }
+
+    // Each start is charged against the buffer attached at the time.
+    pub fn bsend_init<'a, B: Into<Buffer<'a>>>(
+        &self,
+        buf: B,
+        dest: Destination,
+        tag: i32,
+    ) -> Request<'a> {
+        let start = match self.send_template(buf.into(), dest, tag, false) {
+            Start::Send {
+                buf,
+                address,
+                envelope,
+                eager,
+                ..
+            } => Start::Bsend {
+                buf,
+                address,
+                envelope,
+                eager,
+                universe: self.weak_universe(),
+            },
+            start => start,
+        };
+        Request::persistent(self.engine().clone(), start)
+    }
+
+    pub fn recv_init<'a, B: Into<BufferMut<'a>>>(
+        &self,
+        buf: B,
+        source: Source,
+        tag: Tag,
+    ) -> Request<'a> {
+        let start = match source {
+            Source::ProcNull => Start::Null(Status::null()),
+            source => Start::Recv {
+                buf: Some(buf.into()),
+                context_id: self.context_id(),
+                spec: self.match_spec(source, tag),
+            },
+        };
+        Request::persistent(self.engine().clone(), start)
+    }
+
+    // Both halves start before either is waited on, so two ranks exchanging with each other can't
+    // deadlock whichever protocol the send uses.
+    pub fn sendrecv<'b, 'c, S: Into<Buffer<'b>>, R: Into<BufferMut<'c>>>(
+        &self,
+        sendbuf: S,
+        dest: Destination,
+        sendtag: i32,
+        recvbuf: R,
+        source: Source,
+        recvtag: Tag,
+    ) -> error::Result<Status> {
+        self.isendrecv(sendbuf.into(), dest, sendtag, recvbuf.into(), source, recvtag)
+            .wait()
+    }
+
+    pub fn sendrecv_replace<'b, B: Into<BufferMut<'b>>>(
+        &self,
+        buf: B,
+        dest: Destination,
+        sendtag: i32,
+        source: Source,
+        recvtag: Tag,
+    ) -> error::Result<Status> {
+        self.isendrecv_replace(buf, dest, sendtag, source, recvtag)
+            .wait()
+    }
+
+    pub fn isendrecv<'a, S: Into<Buffer<'a>>, R: Into<BufferMut<'a>>>(
+        &self,
+        sendbuf: S,
+        dest: Destination,
+        sendtag: i32,
+        recvbuf: R,
+        source: Source,
+        recvtag: Tag,
+    ) -> Request<'a> {
+        let send = self.isend(sendbuf, dest, sendtag);
+        let recv = self.irecv(recvbuf, source, recvtag);
+        Request::send_recv(send, recv)
+    }
+
+    // The send copies its message when it starts, so `buf` is free to receive into right away.
+    pub fn isendrecv_replace<'a, B: Into<BufferMut<'a>>>(
+        &self,
+        buf: B,
+        dest: Destination,
+        sendtag: i32,
+        source: Source,
+        recvtag: Tag,
+    ) -> Request<'a> {
+        let buf = buf.into();
+        let send = self.start_copied_send(&buf.as_buffer(), dest, sendtag);
+        let recv = self.irecv(buf, source, recvtag);
+        Request::send_recv(send, recv)
+    }
+}
@@ -0,0 +1,907 @@
+use super::{Universe, datatype::{Buffer, BufferMut}, engine::{Engine, MatchSpec, PartitionKey},
+            error, grequest::{Completer, Generalized}, protocol::Envelope,
+            pt2pt::{self, Source, Status, Tag}, schedule::Schedule};
+
+use std::{mem, sync::{Arc, RwLock, Weak}};
+
+enum Operation<'a> {
+    // The operation has finished; the result is held until the user asks for it.
+    Complete(error::Result<Status>),
+    Recv { id: u64, buf: BufferMut<'a> },
+    // A send that completes when the engine reports it has finished with the receiver.
+    Send { id: u64 },
+    // Completes once both halves have; the result is that of the receive.
+    SendRecv {
+        send: Box<Request<'a>>,
+        recv: Box<Request<'a>>,
+    },
+    // A partitioned send completes once every partition has been marked ready and sent.
+    Psend { key: PartitionKey, ready: Vec<bool> },
+    // A partitioned receive completes once every byte has arrived. `arrived` counts the bytes of
+    // each partition received so far.
+    Precv {
+        buf: BufferMut<'a>,
+        key: PartitionKey,
+        arrived: Vec<u64>,
+    },
+    // An operation run outside of MPI, complete once its completer says so or it polls complete.
+    Generalized {
+        operation: Box<dyn Generalized>,
+        completer: Completer,
+    },
+    // A nonblocking collective, complete once every step of its schedule is.
+    Schedule(Box<Schedule<'a>>),
+}
+
+// How a persistent request begins its operation each time it is started. Everything that stays the
+// same between starts, like the peer's address, the envelope and the protocol, is worked out once
+// when the request is created.
+pub(crate) enum Start<'a> {
+    // Communication with ProcNull completes at once with this status.
+    Null(Status),
+    Send {
+        buf: Buffer<'a>,
+        address: String,
+        envelope: Envelope,
+        sync: bool,
+        eager: bool,
+    },
+    Bsend {
+        buf: Buffer<'a>,
+        address: String,
+        envelope: Envelope,
+        eager: bool,
+        universe: Weak<RwLock<Universe>>,
+    },
+    // The buffer is lent to the receive while the request is active.
+    Recv {
+        buf: Option<BufferMut<'a>>,
+        context_id: u32,
+        spec: MatchSpec,
+    },
+    // `key` names the round the next start will use.
+    Psend {
+        buf: Buffer<'a>,
+        partitions: usize,
+        address: String,
+        key: PartitionKey,
+    },
+    Precv {
+        buf: Option<BufferMut<'a>>,
+        partitions: usize,
+        key: PartitionKey,
+    },
+    // A collective's schedule is laid out once and lent to the operation while the request is
+    // active, like a receive's buffer.
+    Schedule(Option<Box<Schedule<'a>>>),
+}
+
+// Which side of a partitioned operation a request is, and how many partitions its buffer has. A
+// partitioned request with ProcNull has partitions too, even though it never sends or receives.
+#[derive(Clone, Copy)]
+pub(crate) enum Partitions {
+    Send(usize),
+    Recv(usize),
+}
+
+struct Persistent<'a> {
+    start: Start<'a>,
+    active: bool,
+    partitions: Option<Partitions>,
+}
+
+// A pending operation started by one of the nonblocking routines on Comm. A request borrows the
+// buffer it receives into for as long as the operation may still write to it, and the buffer it
+// sends from for as long as the operation may still read from it.
+//
+// Persistent requests are created inactive by the `_init` routines on Comm. Completing one makes it
+// inactive again rather than consuming it, and `start` begins the same operation anew.
+pub struct Request<'a> {
+    engine: Arc<Engine>,
+    operation: Operation<'a>,
+    persistent: Option<Persistent<'a>>,
+}
+
+impl<'a> Request<'a> {
+    pub(crate) fn complete(engine: Arc<Engine>, result: error::Result<Status>) -> Self {
+        Request {
+            engine,
+            operation: Operation::Complete(result),
+            persistent: None,
+        }
+    }
+
+    pub(crate) fn recv(engine: Arc<Engine>, id: u64, buf: BufferMut<'a>) -> Self {
+        Request {
+            engine,
+            operation: Operation::Recv { id, buf },
+            persistent: None,
+        }
+    }
+
+    pub(crate) fn send(engine: Arc<Engine>, id: u64) -> Self {
+        Request {
+            engine,
+            operation: Operation::Send { id },
+            persistent: None,
+        }
+    }
+
+    pub(crate) fn send_recv(send: Request<'a>, recv: Request<'a>) -> Self {
+        Request {
+            engine: recv.engine.clone(),
+            operation: Operation::SendRecv {
+                send: Box::new(send),
+                recv: Box::new(recv),
+            },
+            persistent: None,
+        }
+    }
+
+    pub(crate) fn generalized(
+        engine: Arc<Engine>,
+        operation: Box<dyn Generalized>,
+        completer: Completer,
+    ) -> Self {
+        Request {
+            engine,
+            operation: Operation::Generalized {
+                operation,
+                completer,
+            },
+            persistent: None,
+        }
+    }
+
+    // Starts whatever steps of the schedule can start right away.
+    pub(crate) fn schedule(mut schedule: Schedule<'a>) -> Self {
+        schedule.progress();
+        Request {
+            engine: schedule.engine().clone(),
+            operation: Operation::Schedule(Box::new(schedule)),
+            persistent: None,
+        }
+    }
+
+    pub(crate) fn persistent(engine: Arc<Engine>, start: Start<'a>) -> Self {
+        Request {
+            engine,
+            operation: Operation::Complete(Ok(Status::empty())),
+            persistent: Some(Persistent {
+                start,
+                active: false,
+                partitions: None,
+            }),
+        }
+    }
+
+    pub(crate) fn partitioned(
+        engine: Arc<Engine>,
+        start: Start<'a>,
+        partitions: Partitions,
+    ) -> Self {
+        let mut request = Self::persistent(engine, start);
+        request.persistent.as_mut().unwrap().partitions = Some(partitions);
+        request
+    }
+
+    fn partitions(&self) -> Option<Partitions> {
+        self.persistent
+            .as_ref()
+            .and_then(|persistent| persistent.partitions)
+    }
+
+    pub fn is_persistent(&self) -> bool {
+        self.persistent.is_some()
+    }
+
+    // Inactive persistent requests are treated like null requests by the multiple-completion
+    // routines. Other requests are active until they are completed.
+    pub fn is_active(&self) -> bool {
+        self.persistent
+            .as_ref()
+            .map_or(true, |persistent| persistent.active)
+    }
+
+    // Whether the request is a collective that hasn't finished its schedule. Its remaining steps
+    // only run while the request is being completed, so it can't be freed.
+    pub fn is_running_collective(&self) -> bool {
+        matches!(self.operation, Operation::Schedule(_))
+    }
+
+    // Starts the operation of an inactive persistent request. Fails only if a buffered send doesn't
+    // fit in the attached buffer.
+    pub fn start(&mut self) -> error::Result<()> {
+        let engine = self.engine.clone();
+        let persistent = self.persistent
+            .as_mut()
+            .expect("Only persistent requests can be started.");
+        assert!(!persistent.active, "The request has already been started.");
+
+        let operation = match persistent.start {
+            Start::Null(status) => Operation::Complete(Ok(status)),
+            Start::Send {
+                ref buf,
+                ref address,
+                envelope,
+                sync,
+                eager,
+            } => pt2pt::start_send(&engine, address, envelope, buf, sync, eager)
+                .into_operation(),
+            Start::Bsend {
+                ref buf,
+                ref address,
+                envelope,
+                eager,
+                ref universe,
+            } => {
+                universe
+                    .upgrade()
+                    .expect("The MPI Universe has been destroyed - this Request was leaked")
+                    .write()
+                    .unwrap()
+                    .bsend_buffer()?
+                    .send(buf, |packed| {
+                        pt2pt::start_send(&engine, address, envelope, packed, false, eager)
+                    })?;
+                Operation::Complete(Ok(Status::empty()))
+            }
+            Start::Recv {
+                ref mut buf,
+                context_id,
+                spec,
+            } => {
+                let mut buf = buf.take().unwrap();
+                Operation::Recv {
+                    id: engine.post_recv(context_id, spec, &mut buf),
+                    buf,
+                }
+            }
+            Start::Psend {
+                partitions,
+                ref mut key,
+                ..
+            } => {
+                let round = *key;
+                key.round = key.round.wrapping_add(1);
+                Operation::Psend {
+                    key: round,
+                    ready: vec![false; partitions],
+                }
+            }
+            Start::Precv {
+                ref mut buf,
+                partitions,
+                ref mut key,
+            } => {
+                let round = *key;
+                key.round = key.round.wrapping_add(1);
+                Operation::Precv {
+                    buf: buf.take().unwrap(),
+                    key: round,
+                    arrived: vec![0; partitions],
+                }
+            }
+            Start::Schedule(ref mut schedule) => {
+                let mut schedule = schedule.take().unwrap();
+                schedule.reset();
+                schedule.progress();
+                Operation::Schedule(schedule)
+            }
+        };
+
+        persistent.active = true;
+        self.operation = operation;
+        Ok(())
+    }
+
+    fn into_operation(mut self) -> Operation<'a> {
+        mem::replace(&mut self.operation, Operation::Complete(Ok(Status::empty())))
+    }
+
+    // Asks for the operation to be cancelled. The request must still be completed as usual; the
+    // status it completes with says whether the operation was cancelled or had already gone too far
+    // and completed normally. A send can be cancelled until the receiver matches it, which takes a
+    // round trip to the receiver to find out. Sends that complete locally, like small standard mode
+    // sends, partitioned operations and collectives run to completion. Fails only if the cancel
+    // callback of a generalized request does.
+    pub fn cancel(&mut self) -> error::Result<()> {
+        match self.operation {
+            Operation::Recv { id, .. } => if self.engine.cancel_recv(id) {
+                self.finish(Ok(Status::cancelled()));
+            },
+            Operation::Send { id } => self.engine.cancel_send(id),
+            Operation::SendRecv {
+                ref mut send,
+                ref mut recv,
+            } => {
+                send.cancel()?;
+                recv.cancel()?;
+            }
+            Operation::Generalized {
+                ref mut operation,
+                ref completer,
+            } => operation.cancel(completer.is_complete())?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    // Marks a partition of a started partitioned send ready, which sends it right away. Fails if
+    // the request isn't an active partitioned send, or the partition doesn't exist or has already
+    // been marked ready.
+    pub fn pready(&mut self, partition: usize) -> error::Result<()> {
+        match self.partitions() {
+            Some(Partitions::Send(partitions)) if partition < partitions => {}
+            Some(Partitions::Send(_)) => return Err(error::Error::NoSuchPartition(partition)),
+            _ => return Err(error::Error::NotPartitioned),
+        }
+        if !self.is_active() {
+            return Err(error::Error::InactiveRequest);
+        }
+
+        let start = &self.persistent.as_ref().unwrap().start;
+        match (&mut self.operation, start) {
+            (
+                &mut Operation::Psend {
+                    key,
+                    ref mut ready,
+                },
+                &Start::Psend {
+                    ref buf,
+                    ref address,
+                    ..
+                },
+            ) => {
+                if ready[partition] {
+                    return Err(error::Error::PartitionAlreadyReady(partition));
+                }
+
+                let count = buf.count() / ready.len();
+                let offset = (partition * count) as u64 * buf.datatype().size();
+                self.engine.send_partition(
+                    address,
+                    key,
+                    offset,
+                    &buf.elements(partition * count, count),
+                )?;
+                ready[partition] = true;
+            }
+            // Partitioned sends to ProcNull complete when they start.
+            (_, &Start::Null(_)) => {}
+            // The send completed once its last partition was marked ready.
+            _ => return Err(error::Error::PartitionAlreadyReady(partition)),
+        }
+        Ok(())
+    }
+
+    // Reports whether a partition of a partitioned receive has arrived. Every partition of an
+    // inactive request has arrived, as the standard says. Fails if the request isn't a partitioned
+    // receive or the partition doesn't exist.
+    pub fn parrived(&mut self, partition: usize) -> error::Result<bool> {
+        match self.partitions() {
+            Some(Partitions::Recv(partitions)) if partition < partitions => {}
+            Some(Partitions::Recv(_)) => return Err(error::Error::NoSuchPartition(partition)),
+            _ => return Err(error::Error::NotPartitioned),
+        }
+
+        self.progress();
+
+        Ok(match self.operation {
+            Operation::Precv {
+                ref buf,
+                ref arrived,
+                ..
+            } => arrived[partition] == buf.len() / arrived.len() as u64,
+            _ => true,
+        })
+    }
+
+    // Moves the request forward without blocking. Returns true once the operation is complete.
+    fn progress(&mut self) -> bool {
+        let result = match self.operation {
+            Operation::Complete(_) => return true,
+            Operation::Recv { id, ref mut buf } => match self.engine.take_matched(id) {
+                Some(message) => pt2pt::complete_recv(message, buf),
+                None => return false,
+            },
+            Operation::Send { id } => match self.engine.take_send_completion(id) {
+                Some(true) => Ok(Status::cancelled()),
+                Some(false) => Ok(Status::empty()),
+                None => return false,
+            },
+            Operation::SendRecv {
+                ref mut send,
+                ref mut recv,
+            } => {
+                let sent = send.progress();
+                let received = recv.progress();
+                if !(sent && received) {
+                    return false;
+                }
+
+                match (send.take_result(), recv.take_result()) {
+                    (Err(err), _) => Err(err),
+                    (Ok(sent), Ok(received)) => Ok(Status {
+                        cancelled: sent.cancelled || received.cancelled,
+                        ..received
+                    }),
+                    (Ok(_), received) => received,
+                }
+            }
+            Operation::Psend { ref ready, .. } => if ready.iter().all(|&ready| ready) {
+                Ok(Status::empty())
+            } else {
+                return false;
+            },
+            Operation::Precv {
+                ref mut buf,
+                key,
+                ref mut arrived,
+            } => {
+                let partition_len = buf.len() / arrived.len() as u64;
+                let mut overflow = false;
+                for (offset, data, arch) in self.engine.take_partitions(&key) {
+                    if data.is_empty() {
+                        continue;
+                    }
+                    // Partitions hold whole elements, starting at an element of the buffer. Their
+                    // offset and data are in the sender's layout, which is converted from.
+                    let size = buf.datatype().size_in(&arch);
+                    let len = data.len() as u64;
+                    if size == 0 || offset % size != 0 || len % size != 0 {
+                        overflow = true;
+                        continue;
+                    }
+                    let (first, count) = (offset / size, len / size);
+                    if first + count > buf.count() as u64 {
+                        overflow = true;
+                        continue;
+                    }
+                    let mut elements = buf.elements_mut(first as usize, count as usize);
+                    if arch.is_native() {
+                        elements.unpack(&data);
+                    } else {
+                        elements.unpack_from(&arch, &data);
+                    }
+
+                    let size = buf.datatype().size();
+                    let (offset, end) = (first * size, (first + count) * size);
+
+                    // A sender may partition the buffer differently than the receiver.
+                    for (partition, arrived) in arrived.iter_mut().enumerate() {
+                        let start = partition as u64 * partition_len;
+                        let overlap_start = start.max(offset);
+                        let overlap_end = (start + partition_len).min(end);
+                        if overlap_start < overlap_end {
+                            *arrived += overlap_end - overlap_start;
+                        }
+                    }
+                }
+
+                let status = Status {
+                    source: Source::Rank(key.source as usize),
+                    tag: Tag::Value(key.tag),
+                    count: buf.len(),
+                    ..Status::empty()
+                };
+
+                if overflow {
+                    Err(error::Error::Truncated(status))
+                } else if arrived.iter().sum::<u64>() < buf.len() {
+                    return false;
+                } else {
+                    Ok(status)
+                }
+            }
+            Operation::Generalized {
+                ref mut operation,
+                ref completer,
+            } => {
+                if !completer.is_complete() {
+                    if !operation.poll() {
+                        return false;
+                    }
+                    completer.set_complete();
+                }
+
+                match (operation.query(), operation.free()) {
+                    (Ok(status), Ok(())) => Ok(status),
+                    (Err(err), _) | (Ok(_), Err(err)) => Err(err),
+                }
+            }
+            Operation::Schedule(ref mut schedule) => if schedule.progress() {
+                schedule.result()
+            } else {
+                return false;
+            },
+        };
+
+        self.finish(result);
+        true
+    }
+
+    fn finish(&mut self, result: error::Result<Status>) {
+        let finished = mem::replace(&mut self.operation, Operation::Complete(result));
+
+        // A persistent receive gets its buffer back for the next start, and a persistent collective
+        // its schedule.
+        match (finished, self.persistent.as_mut().map(|persistent| &mut persistent.start)) {
+            (Operation::Recv { buf, .. }, Some(&mut Start::Recv { buf: ref mut lent, .. })) => {
+                *lent = Some(buf)
+            }
+            (Operation::Precv { buf, .. }, Some(&mut Start::Precv { buf: ref mut lent, .. })) => {
+                *lent = Some(buf)
+            }
+            (Operation::Schedule(schedule), Some(&mut Start::Schedule(ref mut lent))) => {
+                *lent = Some(schedule)
+            }
+            _ => {}
+        }
+    }
+
+    // Takes the result of a completed operation, leaving the request inactive.
+    fn take_result(&mut self) -> error::Result<Status> {
+        if let Some(persistent) = self.persistent.as_mut() {
+            persistent.active = false;
+        }
+
+        match mem::replace(&mut self.operation, Operation::Complete(Ok(Status::empty()))) {
+            Operation::Complete(result) => result,
+            _ => panic!("EMPIRE internal error: the request has not completed"),
+        }
+    }
+
+    // Returns the outcome of the operation if it has completed, without consuming the request.
+    pub fn get_status(&mut self) -> Option<&error::Result<Status>> {
+        if self.progress() {
+            match self.operation {
+                Operation::Complete(ref result) => Some(result),
+                _ => unreachable!(),
+            }
+        } else {
+            None
+        }
+    }
+
+    // Consumes the request if it has completed, or gives it back if it hasn't.
+    pub fn test(mut self) -> Result<error::Result<Status>, Self> {
+        match self.test_mut() {
+            Some(result) => Ok(result),
+            None => Err(self),
+        }
+    }
+
+    pub fn wait(mut self) -> error::Result<Status> {
+        self.wait_mut()
+    }
+
+    // Like `test`, but leaves a completed request in place. This is how persistent requests are
+    // completed so they can be started again.
+    pub fn test_mut(&mut self) -> Option<error::Result<Status>> {
+        if self.progress() {
+            Some(self.take_result())
+        } else {
+            None
+        }
+    }
+
+    pub fn wait_mut(&mut self) -> error::Result<Status> {
+        loop {
+            let since = self.engine.progress_count();
+            if self.progress() {
+                return self.take_result();
+            }
+            self.engine.wait_progress(since);
+        }
+    }
+}
+
+// Dropping a request that hasn't completed gives up on it. A send carries on without it, from a
+// copy of its buffer if the receiver hasn't asked for the message yet. A receive can no longer
+// write to its buffer, but it stays posted and still takes the message it matches, which is thrown
+// away. A generalized request is freed without being queried, and a collective gives up on the
+// steps it has left.
+impl<'a> Drop for Request<'a> {
+    fn drop(&mut self) {
+        match self.operation {
+            Operation::Recv { id, .. } => self.engine.discard_recv(id),
+            Operation::Send { id } => self.engine.discard_send(id),
+            Operation::Generalized {
+                ref mut operation,
+                ..
+            } => {
+                let _ = operation.free();
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Request<'static> {
+    // Lets go of the request while its operation carries on, as MPI_Request_free does. Unlike
+    // dropping the request, a receive still writes the message it matches into its buffer, which
+    // only a buffer that outlives everything allows. Freeing a running collective is erroneous, as
+    // the standard says, because nothing would run the rest of its schedule.
+    pub fn free(mut self) {
+        assert!(
+            !self.is_running_collective(),
+            "A nonblocking collective can't be freed before it completes."
+        );
+
+        match mem::replace(&mut self.operation, Operation::Complete(Ok(Status::empty()))) {
+            Operation::Recv { id, mut buf } => self.engine.orphan_recv(
+                id,
+                Box::new(move |message| {
+                    let _ = pt2pt::complete_recv(message, &mut buf);
+                }),
+            ),
+            // The buffer of the send outlives it, so the send needn't copy it.
+            Operation::Send { id } => self.engine.orphan_send(id),
+            Operation::SendRecv { send, recv } => {
+                send.free();
+                recv.free();
+            }
+            operation => self.operation = operation,
+        }
+    }
+}
+
+// The multiple-completion routines operate on slices of optional requests. None plays the role of
+// MPI_REQUEST_NULL: it is skipped, and completed requests are replaced with None. Persistent
+// requests are left in place, inactive, and inactive requests are skipped like null ones.
+
+fn is_pending(request: &Option<Request>) -> bool {
+    request.as_ref().map_or(false, |request| request.is_active())
+}
+
+fn progress_pending(request: &mut Option<Request>) -> bool {
+    is_pending(request) && request.as_mut().unwrap().progress()
+}
+
+fn engine_of(requests: &[Option<Request>]) -> Option<Arc<Engine>> {
+    requests
+        .iter()
+        .filter_map(|request| request.as_ref())
+        .map(|request| request.engine.clone())
+        .next()
+}
+
+// Blocks until `done` reports that it has seen enough completions. `done` is called with every
+// request that has not been retired yet and reports whether waiting can stop.
+fn wait_until<'a, F: FnMut(&mut [Option<Request<'a>>]) -> bool>(
+    requests: &mut [Option<Request<'a>>],
+    mut done: F,
+) {
+    let engine = match engine_of(requests) {
+        Some(engine) => engine,
+        None => {
+            done(requests);
+            return;
+        }
+    };
+
+    loop {
+        let since = engine.progress_count();
+        if done(requests) {
+            return;
+        }
+        engine.wait_progress(since);
+    }
+}
+
+fn retire(request: &mut Option<Request>) -> error::Result<Status> {
+    let result = request.as_mut().unwrap().take_result();
+    if !request.as_ref().unwrap().is_persistent() {
+        *request = None;
+    }
+    result
+}
+
+// The results of the requests that completed, paired with their indices.
+pub type Completions = Vec<(usize, error::Result<Status>)>;
+
+// Returns the outcome of each request in order. Null and inactive requests produce an empty
+// status.
+pub fn wait_all(requests: &mut [Option<Request>]) -> Vec<error::Result<Status>> {
+    wait_until(requests, |requests| {
+        requests
+            .iter_mut()
+            .all(|request| request.as_mut().map_or(true, |request| request.progress()))
+    });
+
+    requests
+        .iter_mut()
+        .map(|request| match *request {
+            Some(_) => retire(request),
+            None => Ok(Status::empty()),
+        })
+        .collect()
+}
+
+// Returns None if every request is null or inactive.
+pub fn wait_any(requests: &mut [Option<Request>]) -> Option<(usize, error::Result<Status>)> {
+    let mut completed = None;
+
+    wait_until(requests, |requests| {
+        if !requests.iter().any(is_pending) {
+            return true;
+        }
+        completed = requests.iter_mut().position(progress_pending);
+        completed.is_some()
+    });
+
+    completed.map(|index| (index, retire(&mut requests[index])))
+}
+
+// Returns None if every request is null or inactive.
+pub fn wait_some(requests: &mut [Option<Request>]) -> Option<Completions> {
+    if !requests.iter().any(is_pending) {
+        return None;
+    }
+
+    let mut completed = Vec::new();
+    wait_until(requests, |requests| {
+        completed = completed_indices(requests);
+        !completed.is_empty()
+    });
+
+    Some(
+        completed
+            .into_iter()
+            .map(|index| (index, retire(&mut requests[index])))
+            .collect(),
+    )
+}
+
+fn completed_indices(requests: &mut [Option<Request>]) -> Vec<usize> {
+    requests
+        .iter_mut()
+        .enumerate()
+        .filter_map(|(index, request)| {
+            if progress_pending(request) {
+                Some(index)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+// Completes every request only if all of them have completed; otherwise returns None and leaves
+// them all pending.
+pub fn test_all(requests: &mut [Option<Request>]) -> Option<Vec<error::Result<Status>>> {
+    let all = requests
+        .iter_mut()
+        .fold(true, |all, request| {
+            request.as_mut().map_or(true, |request| request.progress()) && all
+        });
+
+    if all {
+        Some(wait_all(requests))
+    } else {
+        None
+    }
+}
+
+// Returns Some(None) if every request is null or inactive, and None if no request has completed.
+pub fn test_any(
+    requests: &mut [Option<Request>],
+) -> Option<Option<(usize, error::Result<Status>)>> {
+    if !requests.iter().any(is_pending) {
+        return Some(None);
+    }
+
+    requests
+        .iter_mut()
+        .position(progress_pending)
+        .map(|index| Some((index, retire(&mut requests[index]))))
+}
+
+// Returns None if every request is null or inactive. Otherwise returns the requests that have
+// completed, which may be none of them.
+pub fn test_some(requests: &mut [Option<Request>]) -> Option<Completions> {
+    if !requests.iter().any(is_pending) {
+        return None;
+    }
+
+    Some(
+        completed_indices(requests)
+            .into_iter()
+            .map(|index| (index, retire(&mut requests[index])))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{arch::Arch, datatype::{Datatype, Primitive},
+                       protocol::{self, Frame, FrameKind, Handshake}};
+    use std::os::raw::c_long;
+
+    // A peer laid out as external32, with a 4 byte big-endian long.
+    fn foreign_peer() -> Handshake {
+        Handshake {
+            job_id: 1,
+            context_id: 0,
+            rank: 1,
+            arch: Arch::external32(),
+            reply_port: "127.0.0.1:4242".to_owned(),
+        }
+    }
+
+    fn partition(key: &PartitionKey, offset: u64, values: &[i32]) -> Frame {
+        let mut payload = protocol::encode_u64(offset);
+        for value in values {
+            payload.extend_from_slice(&value.to_be_bytes());
+        }
+        Frame::new(FrameKind::Partition, key.envelope(), payload)
+    }
+
+    #[test]
+    fn partitions_from_another_layout_are_converted() {
+        let engine = Arc::new(Engine::new(1, 0, 1024));
+        let key = PartitionKey {
+            context_id: 5,
+            source: 1,
+            tag: 3,
+            channel: 0,
+            round: 0,
+        };
+
+        let mut longs: [c_long; 4] = [0; 4];
+        let mut long = Datatype::primitive(Primitive::Long);
+        long.commit();
+        let buf = unsafe { BufferMut::new(longs.as_mut_ptr() as *mut u8, 4, long) };
+        let start = Start::Precv {
+            buf: Some(buf),
+            partitions: 2,
+            key,
+        };
+        let mut request = Request::partitioned(engine.clone(), start, Partitions::Recv(2));
+        request.start().unwrap();
+
+        // Offsets are in the sender's layout, so the second partition starts 8 bytes in.
+        let peer = foreign_peer();
+        engine.deliver(&peer, partition(&key, 8, &[-7, 1 << 30])).unwrap();
+        assert!(!request.parrived(0).unwrap());
+        assert!(request.parrived(1).unwrap());
+        engine.deliver(&peer, partition(&key, 0, &[1, -1])).unwrap();
+
+        let status = request.wait_mut().unwrap();
+        assert_eq!(status.count, 4 * Primitive::Long.size() as u64);
+        drop(request);
+        assert_eq!(longs, [1, -1, -7, 1 << 30]);
+    }
+
+    #[test]
+    fn partitions_that_split_an_element_fail() {
+        let engine = Arc::new(Engine::new(1, 0, 1024));
+        let key = PartitionKey {
+            context_id: 5,
+            source: 1,
+            tag: 3,
+            channel: 0,
+            round: 0,
+        };
+
+        let mut longs: [c_long; 2] = [0; 2];
+        let mut long = Datatype::primitive(Primitive::Long);
+        long.commit();
+        let buf = unsafe { BufferMut::new(longs.as_mut_ptr() as *mut u8, 2, long) };
+        let start = Start::Precv {
+            buf: Some(buf),
+            partitions: 1,
+            key,
+        };
+        let mut request = Request::partitioned(engine.clone(), start, Partitions::Recv(1));
+        request.start().unwrap();
+
+        engine.deliver(&foreign_peer(), partition(&key, 2, &[1, 2])).unwrap();
+        match request.wait_mut() {
+            Err(error::Error::Truncated(_)) => {}
+            result => panic!("expected a truncation, got {:?}", result),
+        }
+    }
+}
@@ -0,0 +1,383 @@
+use super::{Comm, Op, datatype::{Buffer, BufferMut, Datatype}, engine::{Engine, MatchSpec},
+            error, protocol::Envelope, pt2pt::{self, Status}, request::Request};
+
+use std::{cmp::{max, min}, marker::PhantomData, sync::Arc};
+
+// Collectives are made of point-to-point messages in a context of their own, so they never match
+// the user's receives. The context of a communicator's collectives is its own with the top bit set.
+const COLLECTIVE_CONTEXT_BIT: u32 = 1 << 31;
+
+// Memory laid out like `count` elements of a datatype, for holding partial results. Only the
+// bytes between the lowest and highest byte of data the elements cover are allocated.
+struct Scratch {
+    data: Vec<u8>,
+    // Where the origin of the first element lies in `data`.
+    origin: isize,
+}
+
+impl Scratch {
+    fn new(count: usize, datatype: &Datatype) -> Self {
+        let (origin, len) = if count == 0 {
+            (0, 0)
+        } else {
+            let last = (count - 1) as isize * datatype.extent();
+            let low = datatype.true_lb() + min(0, last);
+            let high = datatype.true_lb() + datatype.true_extent() + max(0, last);
+            (-low, (high - low) as usize)
+        };
+
+        Scratch {
+            data: vec![0; len],
+            origin,
+        }
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.data.as_mut_ptr().wrapping_offset(self.origin)
+    }
+}
+
+// Copies the data of `from` into `to`, which may lay it out differently.
+fn copy(from: &Buffer, to: &mut BufferMut) {
+    let mut packed = Vec::with_capacity(from.len() as usize);
+    from.pack_into(&mut packed);
+    to.unpack(&packed);
+}
+
+// Elements that the steps of a schedule read and write: part of the user's buffers, or of scratch
+// memory the schedule owns. Steps that touch the same elements are ordered by what they come after,
+// so unlike buffers, a region can be handed to any number of them. A region made from a Buffer is
+// only ever read.
+#[derive(Clone)]
+pub(crate) struct Region<'a> {
+    base: *mut u8,
+    count: usize,
+    datatype: Datatype,
+    marker: PhantomData<&'a mut [u8]>,
+}
+
+unsafe impl<'a> Send for Region<'a> {}
+unsafe impl<'a> Sync for Region<'a> {}
+
+impl<'a> Region<'a> {
+    pub(crate) fn count(&self) -> usize {
+        self.count
+    }
+
+    pub(crate) fn datatype(&self) -> &Datatype {
+        &self.datatype
+    }
+
+    // `count` of the elements, starting at element `start`.
+    pub(crate) fn block(&self, start: usize, count: usize) -> Region<'a> {
+        assert!(start + count <= self.count);
+        Region {
+            base: self
+                .base
+                .wrapping_offset(start as isize * self.datatype.extent()),
+            count,
+            datatype: self.datatype.clone(),
+            marker: PhantomData,
+        }
+    }
+
+    fn buffer(&self) -> Buffer<'a> {
+        unsafe { Buffer::new(self.base, self.count, self.datatype.clone()) }
+    }
+
+    fn buffer_mut(&self) -> BufferMut<'a> {
+        unsafe { BufferMut::new(self.base, self.count, self.datatype.clone()) }
+    }
+}
+
+impl<'a> From<Buffer<'a>> for Region<'a> {
+    fn from(buf: Buffer<'a>) -> Self {
+        Region {
+            base: buf.as_ptr() as *mut u8,
+            count: buf.count(),
+            datatype: buf.datatype().clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a> From<BufferMut<'a>> for Region<'a> {
+    fn from(mut buf: BufferMut<'a>) -> Self {
+        Region {
+            base: buf.as_mut_ptr(),
+            count: buf.count(),
+            datatype: buf.datatype().clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+// Names a step of a schedule, for later steps to come after.
+#[derive(Clone, Copy)]
+pub(crate) struct Step(usize);
+
+enum Action<'a> {
+    Send {
+        from: Region<'a>,
+        address: String,
+        envelope: Envelope,
+        eager: bool,
+    },
+    Recv {
+        into: Region<'a>,
+        context_id: u32,
+        spec: MatchSpec,
+    },
+    // Leaves `input op inout` in `inout`.
+    Reduce {
+        input: Region<'a>,
+        inout: Region<'a>,
+        op: Op,
+    },
+    Copy {
+        from: Region<'a>,
+        to: Region<'a>,
+    },
+}
+
+enum State<'a> {
+    Waiting,
+    Running(Request<'a>),
+    Done,
+}
+
+struct Node<'a> {
+    action: Action<'a>,
+    after: Vec<Step>,
+    state: State<'a>,
+}
+
+// A collective operation laid out as a graph of steps: messages to send and receive, and local
+// reductions and copies, each of which starts once the steps it comes after have finished. A
+// schedule moves forward whenever the request running it is tested or waited on, starting every
+// step it can and checking on the messages in flight, so a rank may take part in any number of
+// collectives at once. Every rank of the communicator must build the schedules of its collectives
+// in the same order, since that order tags their messages.
+pub(crate) struct Schedule<'a> {
+    engine: Arc<Engine>,
+    // The envelope of the messages of the steps being added, whose context is also the one their
+    // receives are posted in.
+    envelope: Envelope,
+    eager_limit: u64,
+    // Dropped before the scratch memory, so that receives into it let go of it first.
+    nodes: Vec<Node<'a>>,
+    scratch: Vec<Scratch>,
+    // The first failure of a step, which the whole schedule fails with once every step is done.
+    failure: Option<error::Error>,
+}
+
+impl<'a> Schedule<'a> {
+    pub(crate) fn new(comm: &Comm, tag: i32) -> Self {
+        let mut envelope = comm.envelope(tag);
+        envelope.context_id |= COLLECTIVE_CONTEXT_BIT;
+
+        Schedule {
+            engine: comm.engine().clone(),
+            envelope,
+            eager_limit: comm.eager_limit(),
+            nodes: Vec::new(),
+            scratch: Vec::new(),
+            failure: None,
+        }
+    }
+
+    pub(crate) fn engine(&self) -> &Arc<Engine> {
+        &self.engine
+    }
+
+    // Makes the sends and receives added from now on go between the ranks of `comm`, with the same
+    // tag. An intercommunicator's collectives switch between it and its local group.
+    pub(crate) fn within(&mut self, comm: &Comm) {
+        self.envelope.context_id = comm.context_id() | COLLECTIVE_CONTEXT_BIT;
+        self.envelope.source = comm.rank() as u32;
+    }
+
+    // Memory for `count` elements of `datatype` that lives as long as the schedule. Moving the
+    // scratch into the schedule doesn't move the memory its regions point into.
+    pub(crate) fn scratch(&mut self, count: usize, datatype: &Datatype) -> Region<'a> {
+        let mut scratch = Scratch::new(count, datatype);
+        let region = Region {
+            base: scratch.as_mut_ptr(),
+            count,
+            datatype: datatype.clone(),
+            marker: PhantomData,
+        };
+        self.scratch.push(scratch);
+        region
+    }
+
+    fn add(&mut self, action: Action<'a>, after: &[Step]) -> Step {
+        self.nodes.push(Node {
+            action,
+            after: after.to_vec(),
+            state: State::Waiting,
+        });
+        Step(self.nodes.len() - 1)
+    }
+
+    // Sends the elements of `from` to the rank at `address`.
+    pub(crate) fn send(&mut self, from: &Region<'a>, address: &str, after: &[Step]) -> Step {
+        let eager = from.count as u64 * from.datatype.size() <= self.eager_limit;
+        let action = Action::Send {
+            from: from.clone(),
+            address: address.to_owned(),
+            envelope: self.envelope,
+            eager,
+        };
+        self.add(action, after)
+    }
+
+    pub(crate) fn recv(&mut self, into: &Region<'a>, source: usize, after: &[Step]) -> Step {
+        let spec = MatchSpec {
+            source: Some(source as u32),
+            tag: Some(self.envelope.tag),
+        };
+        let action = Action::Recv {
+            into: into.clone(),
+            context_id: self.envelope.context_id,
+            spec,
+        };
+        self.add(action, after)
+    }
+
+    pub(crate) fn reduce(
+        &mut self,
+        input: &Region<'a>,
+        inout: &Region<'a>,
+        op: &Op,
+        after: &[Step],
+    ) -> Step {
+        let action = Action::Reduce {
+            input: input.clone(),
+            inout: inout.clone(),
+            op: op.clone(),
+        };
+        self.add(action, after)
+    }
+
+    pub(crate) fn copy(&mut self, from: &Region<'a>, to: &Region<'a>, after: &[Step]) -> Step {
+        self.add(Action::Copy { from: from.clone(), to: to.clone() }, after)
+    }
+
+    // Begins a step. Messages run until they complete; local steps are done at once.
+    fn start(&self, action: &Action<'a>) -> State<'a> {
+        match *action {
+            Action::Send {
+                ref from,
+                ref address,
+                envelope,
+                eager,
+            } => State::Running(pt2pt::start_send(
+                &self.engine,
+                address,
+                envelope,
+                &from.buffer(),
+                false,
+                eager,
+            )),
+            Action::Recv {
+                ref into,
+                context_id,
+                spec,
+            } => {
+                let mut buf = into.buffer_mut();
+                let id = self.engine.post_recv(context_id, spec, &mut buf);
+                State::Running(Request::recv(self.engine.clone(), id, buf))
+            }
+            Action::Reduce {
+                ref input,
+                ref inout,
+                ref op,
+            } => {
+                unsafe { op.apply(&inout.datatype, inout.count, input.base, inout.base) };
+                State::Done
+            }
+            Action::Copy { ref from, ref to } => {
+                copy(&from.buffer(), &mut to.buffer_mut());
+                State::Done
+            }
+        }
+    }
+
+    // Starts the steps whose predecessors have all finished and collects the messages that have
+    // completed, over and over until nothing changes. Returns true once every step is done.
+    pub(crate) fn progress(&mut self) -> bool {
+        loop {
+            let mut changed = false;
+
+            for index in 0..self.nodes.len() {
+                let ready = {
+                    let nodes = &self.nodes;
+                    nodes[index]
+                        .after
+                        .iter()
+                        .all(|&Step(before)| match nodes[before].state {
+                            State::Done => true,
+                            _ => false,
+                        })
+                };
+
+                let state = match self.nodes[index].state {
+                    State::Waiting if ready => self.start(&self.nodes[index].action),
+                    State::Running(ref mut request) => match request.test_mut() {
+                        Some(Ok(_)) => State::Done,
+                        Some(Err(err)) => {
+                            self.failure = self.failure.take().or(Some(err));
+                            State::Done
+                        }
+                        None => continue,
+                    },
+                    _ => continue,
+                };
+                self.nodes[index].state = state;
+                changed = true;
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        self.nodes.iter().all(|node| match node.state {
+            State::Done => true,
+            _ => false,
+        })
+    }
+
+    // Puts every step of a schedule that has finished back to waiting, to run it again.
+    pub(crate) fn reset(&mut self) {
+        for node in &mut self.nodes {
+            node.state = State::Waiting;
+        }
+        self.failure = None;
+    }
+
+    // Opens the connections to every rank the schedule sends to, ahead of the first run.
+    pub(crate) fn connect(&self) {
+        for node in &self.nodes {
+            if let Action::Send {
+                ref address,
+                envelope,
+                ..
+            } = node.action
+            {
+                self.engine
+                    .transport()
+                    .connect(address, envelope.context_id);
+            }
+        }
+    }
+
+    // The outcome of a schedule that has finished.
+    pub(crate) fn result(&mut self) -> error::Result<Status> {
+        match self.failure.take() {
+            Some(err) => Err(err),
+            None => Ok(Status::empty()),
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use super::{arch::Arch, engine::Outgoing,
+            protocol::{self, Envelope, Frame, FrameKind, Handshake}};
+
+use std::{cmp::min, io::{self, Write}, net::TcpStream, collections::HashMap,
+          sync::{Mutex, mpsc::{self, Sender}}, thread::{self, JoinHandle}};
+
+// Runs a callback once the writer is done with a streamed payload, or has given up on it.
+pub(crate) struct Done(Option<Box<dyn FnOnce() + Send>>);
+
+impl Done {
+    pub fn new<F: FnOnce() + Send + 'static>(callback: F) -> Self {
+        Done(Some(Box::new(callback)))
+    }
+}
+
+impl Drop for Done {
+    fn drop(&mut self) {
+        if let Some(callback) = self.0.take() {
+            callback();
+        }
+    }
+}
+
+// What the writer thread writes out: an encoded frame, or a payload it packs into Data frames
+// itself.
+enum Queued {
+    Frame(Vec<u8>),
+    Stream {
+        envelope: Envelope,
+        payload: Outgoing,
+        done: Done,
+    },
+}
+
+// Writes `payload` as Data frames of at most a chunk each, packing each chunk only as it goes out.
+fn write_stream(stream: &mut TcpStream, envelope: &Envelope, payload: &Outgoing) -> io::Result<()> {
+    let length = payload.len();
+    let mut frame = Vec::with_capacity(protocol::HEADER_LEN + protocol::CHUNK_LEN);
+    let mut position = 0;
+    loop {
+        let len = min(protocol::CHUNK_LEN as u64, length - position) as usize;
+        frame.clear();
+        protocol::encode_header(FrameKind::Data, 0, envelope, len as u64, &mut frame);
+        payload.pack_segment_into(position, len, &mut frame);
+        stream.write_all(&frame)?;
+
+        position += len as u64;
+        if position == length {
+            return Ok(());
+        }
+    }
+}
+
+// An outgoing connection to another process's Port. Frames are handed to a writer thread so that
+// queueing a frame never blocks on the network; this keeps the Port server threads, which also
+// send replies, from deadlocking against each other when both sides have large frames in flight.
+struct Connection {
+    queue: Option<Sender<Queued>>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl Connection {
+    fn open(address: String, handshake: Handshake) -> Self {
+        let (queue, frames) = mpsc::channel::<Queued>();
+
+        let writer = thread::spawn(move || {
+            let mut stream = match TcpStream::connect(address.as_str()) {
+                Ok(stream) => stream,
+                Err(err) => {
+                    eprintln!("empire could not connect to '{}': {}", address, err);
+                    return;
+                }
+            };
+
+            // Frames are small and latency sensitive; don't let Nagle hold them back.
+            let _ = stream.set_nodelay(true);
+
+            if let Err(err) = stream.write_all(&handshake.encode()) {
+                eprintln!("empire could not send a handshake to '{}': {}", address, err);
+                return;
+            }
+
+            for queued in frames {
+                let written = match queued {
+                    Queued::Frame(frame) => stream.write_all(&frame),
+                    Queued::Stream {
+                        envelope,
+                        payload,
+                        done,
+                    } => {
+                        let written = write_stream(&mut stream, &envelope, &payload);
+                        drop(done);
+                        written
+                    }
+                };
+                if let Err(err) = written {
+                    eprintln!("empire lost its connection to '{}': {}", address, err);
+                    return;
+                }
+            }
+        });
+
+        Connection {
+            queue: Some(queue),
+            writer: Some(writer),
+        }
+    }
+
+    fn send(&self, queued: Queued) {
+        // If the writer has already failed it reported why; there's nobody left to deliver to.
+        let _ = self.queue.as_ref().unwrap().send(queued);
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        // Closing the queue lets the writer drain whatever is left and exit.
+        self.queue.take();
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+// The process-wide set of outgoing connections, keyed by the name of the remote Port. There is a
+// single connection per remote Port, so frames sent to the same peer are delivered in order.
+pub(crate) struct Transport {
+    job_id: u64,
+    rank: u32,
+    reply_port: Mutex<Option<String>>,
+    connections: Mutex<HashMap<String, Connection>>,
+}
+
+impl Transport {
+    pub fn new(job_id: u64, rank: u32) -> Self {
+        Self {
+            job_id,
+            rank,
+            reply_port: Mutex::new(None),
+            connections: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Any Port owned by this process can receive replies, since all of them feed the same engine.
+    // The first one registered is advertised in handshakes.
+    pub fn add_local_port(&self, name: &str) {
+        let mut reply_port = self.reply_port.lock().unwrap();
+        if reply_port.is_none() {
+            *reply_port = Some(name.to_owned());
+        }
+    }
+
+    pub fn send(&self, address: &str, context_id: u32, frame: &Frame) {
+        self.send_encoded(address, context_id, frame.encode());
+    }
+
+    // Sends a frame that has already been encoded, header and all. The engine splits anything that
+    // a peer would refuse as too long.
+    pub fn send_encoded(&self, address: &str, context_id: u32, frame: Vec<u8>) {
+        assert!(
+            (frame.len() - protocol::HEADER_LEN) as u64 <= protocol::MAX_FRAME_LEN,
+            "EMPIRE internal error: a frame is longer than a peer accepts"
+        );
+        let mut connections = self.connections.lock().unwrap();
+        self.connection(&mut connections, address, context_id)
+            .send(Queued::Frame(frame));
+    }
+
+    // Sends `payload` as the Data of the receive `envelope` names, packing it only as it is written
+    // out. `done` runs once the payload's buffer is no longer needed.
+    pub fn stream(&self, address: &str, envelope: Envelope, payload: Outgoing, done: Done) {
+        let mut connections = self.connections.lock().unwrap();
+        self.connection(&mut connections, address, envelope.context_id)
+            .send(Queued::Stream {
+                envelope,
+                payload,
+                done,
+            });
+    }
+
+    // Opens the connection to `address` now rather than when the first frame is sent to it, so that
+    // the first send doesn't pay for connecting.
+    pub fn connect(&self, address: &str, context_id: u32) {
+        let mut connections = self.connections.lock().unwrap();
+        self.connection(&mut connections, address, context_id);
+    }
+
+    fn connection<'c>(
+        &self,
+        connections: &'c mut HashMap<String, Connection>,
+        address: &str,
+        context_id: u32,
+    ) -> &'c Connection {
+        if !connections.contains_key(address) {
+            let handshake = Handshake {
+                job_id: self.job_id,
+                context_id,
+                rank: self.rank,
+                arch: Arch::native(),
+                reply_port: self.reply_port
+                    .lock()
+                    .unwrap()
+                    .clone()
+                    .expect("EMPIRE internal error: no local port is available for replies"),
+            };
+            connections.insert(address.to_owned(), Connection::open(address.to_owned(), handshake));
+        }
+
+        &connections[address]
+    }
+
+    // Flushes and closes every outgoing connection. Peers see the connections close, which lets
+    // their Port servers finish.
+    pub fn shutdown(&self) {
+        let connections: Vec<_> = self.connections
+            .lock()
+            .unwrap()
+            .drain()
+            .map(|(_, connection)| connection)
+            .collect();
+        drop(connections);
+    }
+}
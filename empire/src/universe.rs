@@ -1,6 +1,10 @@
-use super::{error, registrar, Comm, port::Port};
+use super::{error, registrar, Comm, buffer::BsendBuffer,
+            comm::{PARENT_CONTEXT_ID, SELF_CONTEXT_ID, WORLD_CONTEXT_ID}, engine::{self, Engine},
+            port::Port, protocol::{Envelope, Frame, FrameKind}};
 
-use std::{env, collections::HashMap, num::ParseIntError, str::FromStr, sync::{Arc, RwLock}};
+use std::{env, process, collections::HashMap, num::ParseIntError, str::FromStr,
+          sync::{Arc, RwLock, atomic::{AtomicUsize, Ordering}},
+          time::{SystemTime, UNIX_EPOCH}};
 
 fn read_integer_variable<F: FromStr<Err = ParseIntError>>(var_name: &str, default: F) -> F {
     match env::var(var_name) {
@@ -17,6 +21,30 @@ fn read_integer_variable<F: FromStr<Err = ParseIntError>>(var_name: &str, defaul
     }
 }
 
+// The eager limit may be tuned for a whole job through EMPIRE_EAGER_LIMIT, up to a chunk.
+fn read_eager_limit() -> u64 {
+    match env::var("EMPIRE_EAGER_LIMIT") {
+        Ok(limit) => limit
+            .parse()
+            .expect("EMPIRE_EAGER_LIMIT was not an integer."),
+        Err(_) => engine::DEFAULT_EAGER_LIMIT,
+    }
+}
+
+// Job ids only need to be unique among the jobs a process talks to. Mixing the process id, the
+// time and a counter is good enough for that.
+pub(crate) fn new_job_id() -> u64 {
+    static SPAWN_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.subsec_nanos() as u64 ^ elapsed.as_secs())
+        .unwrap_or(0);
+
+    ((process::id() as u64) << 32) ^ nanos
+        ^ ((SPAWN_COUNT.fetch_add(1, Ordering::SeqCst) as u64) << 48)
+}
+
 pub struct CommRegistration(registrar::Registration<Comm>);
 
 impl CommRegistration {
@@ -29,9 +57,15 @@ impl CommRegistration {
 }
 
 pub struct Universe {
+    job_id: u64,
+    engine: Arc<Engine>,
+
     // ports
     ports: HashMap<String, Box<Port>>,
 
+    // buffered-mode sends
+    bsend_buffer: Option<BsendBuffer>,
+
     // standard communicators
     comm_self: Option<CommRegistration>,
     comm_world: Option<CommRegistration>,
@@ -44,9 +78,12 @@ pub struct Universe {
 }
 
 impl Universe {
-    fn new() -> error::Result<Self> {
+    fn new(job_id: u64, rank: usize) -> error::Result<Self> {
         Ok(Self {
+            job_id,
+            engine: Arc::new(Engine::new(job_id, rank as u32, read_eager_limit())),
             ports: HashMap::new(),
+            bsend_buffer: None,
             comm_self: None,
             comm_world: None,
             comm_parent: None,
@@ -58,48 +95,135 @@ impl Universe {
         let comm_self_universe = Arc::downgrade(&universe);
 
         let mut locked = universe.write().unwrap();
-        let registration = locked.register_comm(Comm::intracomm(comm_self_universe, 0, 1)?);
+        let engine = locked.engine.clone();
+        let port = Port::new(engine.clone())?;
+        let address = port.name().to_owned();
+        let registration = locked.register_comm(Comm::intracomm(
+            comm_self_universe,
+            engine,
+            SELF_CONTEXT_ID,
+            0,
+            port,
+            vec![address],
+        ));
         locked.comm_self = Some(registration);
 
         Ok(())
     }
 
+    // Spawned ranks announce their COMM_WORLD port to the parent that launched them and receive
+    // back the ports of every rank in the job. Unless they are the initial job, their parent
+    // intercomm reaches the parent at the port they announced themselves to, and the parent
+    // reaches them at their COMM_WORLD ports.
     fn initialize_comm_world(
         universe: &Arc<RwLock<Self>>,
         rank: usize,
         size: usize,
+        parent_port: Option<String>,
+        spawned: bool,
     ) -> error::Result<()> {
         let comm_world_universe = Arc::downgrade(&universe);
 
+        let engine = universe.read().unwrap().engine.clone();
+        let port = Port::new(engine.clone())?;
+
+        let addresses = match parent_port {
+            Some(ref parent_port) => {
+                let announcement = Frame::new(
+                    FrameKind::Announce,
+                    Envelope {
+                        context_id: WORLD_CONTEXT_ID,
+                        source: rank as u32,
+                        tag: 0,
+                        id: 0,
+                    },
+                    port.name().as_bytes().to_vec(),
+                );
+                engine
+                    .transport()
+                    .send(parent_port, WORLD_CONTEXT_ID, &announcement);
+
+                let addresses = engine.wait_directory();
+                assert!(
+                    addresses.len() == size,
+                    "The parent reported {} ranks, but EMPIRE_COMM_WORLD_SIZE is {}.",
+                    addresses.len(),
+                    size
+                );
+                addresses
+            }
+            None => {
+                assert!(
+                    size == 1,
+                    "EMPIRE_COMM_WORLD_PARENT_PORT must be set when there is more than one rank."
+                );
+                vec![port.name().to_owned()]
+            }
+        };
+
         let mut locked = universe.write().unwrap();
-        let registration = locked.register_comm(Comm::intracomm(comm_world_universe, rank, size)?);
+        if spawned {
+            let parent_port = parent_port.expect("A spawned job must know the port of its parent.");
+            let registration = locked.register_comm(Comm::intercomm(
+                comm_world_universe.clone(),
+                engine.clone(),
+                PARENT_CONTEXT_ID,
+                rank,
+                None,
+                addresses.clone(),
+                vec![parent_port],
+            ));
+            locked.comm_parent = Some(registration);
+        }
+
+        let registration = locked.register_comm(Comm::intracomm(
+            comm_world_universe,
+            engine,
+            WORLD_CONTEXT_ID,
+            rank,
+            port,
+            addresses,
+        ));
         locked.comm_world = Some(registration);
 
         Ok(())
     }
 
     pub fn root() -> error::Result<Arc<RwLock<Self>>> {
-        let universe = Arc::new(RwLock::new(Universe::new()?));
+        let universe = Arc::new(RwLock::new(Universe::new(new_job_id(), 0)?));
 
         Self::initialize_comm_self(&universe)?;
-        Self::initialize_comm_world(&universe, 0, 1)?;
+        Self::initialize_comm_world(&universe, 0, 1, None, false)?;
 
         Ok(universe)
     }
 
     pub fn from_env() -> error::Result<Arc<RwLock<Self>>> {
-        let universe = Arc::new(RwLock::new(Universe::new()?));
-
-        Self::initialize_comm_self(&universe)?;
-
         let rank = read_integer_variable("EMPIRE_COMM_WORLD_RANK", 0usize);
         let size = read_integer_variable("EMPIRE_COMM_WORLD_SIZE", 1usize);
+        let job_id = match env::var("EMPIRE_JOB_ID") {
+            Ok(_) => read_integer_variable("EMPIRE_JOB_ID", 0u64),
+            Err(_) => new_job_id(),
+        };
+        let parent_port = env::var("EMPIRE_COMM_WORLD_PARENT_PORT").ok();
+        let spawned = env::var("EMPIRE_SPAWNED").map_or(false, |spawned| spawned == "1");
 
-        Self::initialize_comm_world(&universe, rank, size)?;
+        let universe = Arc::new(RwLock::new(Universe::new(job_id, rank)?));
+
+        Self::initialize_comm_self(&universe)?;
+        Self::initialize_comm_world(&universe, rank, size, parent_port, spawned)?;
 
         Ok(universe)
     }
 
+    pub fn job_id(&self) -> u64 {
+        self.job_id
+    }
+
+    pub(crate) fn engine(&self) -> &Arc<Engine> {
+        &self.engine
+    }
+
     pub fn comm_self(&self) -> Arc<Comm> {
         self.comm_self.as_ref().unwrap().unwrap()
     }
@@ -108,6 +232,13 @@ impl Universe {
         self.comm_world.as_ref().unwrap().unwrap()
     }
 
+    // The intercomm to the parent that spawned this job, if it was spawned.
+    pub fn comm_parent(&self) -> Option<Arc<Comm>> {
+        self.comm_parent
+            .as_ref()
+            .map(|registration| registration.unwrap())
+    }
+
     pub fn register_comm(&mut self, comm: Comm) -> CommRegistration {
         CommRegistration(self.registrar.track_object(comm))
     }
@@ -117,12 +248,42 @@ impl Universe {
     }
 
     pub fn open_port(&mut self) -> error::Result<&Port> {
-        let port = Box::new(Port::new()?);
+        let port = Box::new(Port::new(self.engine.clone())?);
         let port_name = port.name().to_owned();
         self.ports.insert(port_name.clone(), port);
         Ok(self.ports.get(&port_name).unwrap())
     }
 
+    // Lends `memory` to buffered sends, which pack their messages into it, until it is detached.
+    pub fn attach_buffer(&mut self, memory: &'static mut [u8]) -> error::Result<()> {
+        if self.bsend_buffer.is_some() {
+            return Err(error::Error::BufferAlreadyAttached);
+        }
+
+        self.bsend_buffer = Some(BsendBuffer::new(memory));
+        Ok(())
+    }
+
+    // The size of the attached buffer.
+    pub fn attached_buffer_size(&self) -> error::Result<u64> {
+        self.bsend_buffer
+            .as_ref()
+            .map(BsendBuffer::capacity)
+            .ok_or(error::Error::NoBufferAttached)
+    }
+
+    // Takes the attached buffer away from later buffered sends. Sends from it may still be in
+    // flight; `BsendBuffer::detach` waits for them once the universe has been unlocked.
+    pub fn detach_buffer(&mut self) -> error::Result<BsendBuffer> {
+        self.bsend_buffer.take().ok_or(error::Error::NoBufferAttached)
+    }
+
+    pub(crate) fn bsend_buffer(&mut self) -> error::Result<&mut BsendBuffer> {
+        self.bsend_buffer
+            .as_mut()
+            .ok_or(error::Error::NoBufferAttached)
+    }
+
     pub fn close_port(&mut self, port_name: &str) -> error::Result<()> {
         match self.ports.remove(port_name) {
             Some(_) => Ok(()),
@@ -130,3 +291,15 @@ impl Universe {
         }
     }
 }
+
+impl Drop for Universe {
+    fn drop(&mut self) {
+        if let Some(buffer) = self.bsend_buffer.take() {
+            buffer.detach();
+        }
+
+        // Flush outgoing connections before the ports go away. Peers' Port servers only finish
+        // once our connections to them close.
+        self.engine.transport().shutdown();
+    }
+}
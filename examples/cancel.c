@@ -0,0 +1,138 @@
+#include <mpi.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+// Large enough to go by rendezvous.
+#define LARGE_COUNT (1 << 17)
+
+static void check(int condition, int rank, const char *what) {
+    if (!condition) {
+        printf("Rank %d: %s\n", rank, what);
+        exit(EXIT_FAILURE);
+    }
+}
+
+static int cancelled(MPI_Request *request) {
+    MPI_Status status;
+    MPI_Wait(request, &status);
+
+    int flag;
+    MPI_Test_cancelled(&status, &flag);
+    return flag;
+}
+
+static void send_side(int partner, int rank) {
+    int value = 1;
+
+    // The receiver isn't receiving yet, so both sends are still unexpected there and can be
+    // withdrawn.
+    MPI_Request request;
+    MPI_Issend(&value, 1, MPI_INT, partner, 1, MPI_COMM_WORLD, &request);
+    MPI_Cancel(&request);
+    check(cancelled(&request), rank, "an unmatched synchronous send was not cancelled");
+
+    int *large = malloc(LARGE_COUNT * sizeof(int));
+    MPI_Isend(large, LARGE_COUNT, MPI_INT, partner, 2, MPI_COMM_WORLD, &request);
+    MPI_Cancel(&request);
+    check(cancelled(&request), rank, "an unmatched rendezvous send was not cancelled");
+    free(large);
+
+    MPI_Send(NULL, 0, MPI_INT, partner, 0, MPI_COMM_WORLD);
+
+    value = 2;
+    MPI_Send(&value, 1, MPI_INT, partner, 1, MPI_COMM_WORLD);
+    value = 3;
+    MPI_Send(&value, 1, MPI_INT, partner, 2, MPI_COMM_WORLD);
+
+    // Once the receiver has matched a send, cancelling it is too late.
+    value = 4;
+    MPI_Issend(&value, 1, MPI_INT, partner, 3, MPI_COMM_WORLD, &request);
+    MPI_Recv(NULL, 0, MPI_INT, partner, 4, MPI_COMM_WORLD, MPI_STATUS_IGNORE);
+    MPI_Cancel(&request);
+    check(!cancelled(&request), rank, "a matched send was cancelled");
+
+    value = 5;
+    MPI_Send(&value, 1, MPI_INT, partner, 100, MPI_COMM_WORLD);
+    value = 6;
+    MPI_Send(&value, 1, MPI_INT, partner, 100, MPI_COMM_WORLD);
+}
+
+static void recv_side(int partner, int rank) {
+    int value = -1;
+
+    MPI_Request request;
+    MPI_Irecv(&value, 1, MPI_INT, partner, 100, MPI_COMM_WORLD, &request);
+    MPI_Cancel(&request);
+    check(cancelled(&request), rank, "a posted receive was not cancelled");
+    check(request == MPI_REQUEST_NULL, rank, "completing a cancelled receive did not free it");
+
+    // Cancelling a persistent receive leaves it inactive and ready to start again.
+    MPI_Request persistent;
+    MPI_Recv_init(&value, 1, MPI_INT, partner, 100, MPI_COMM_WORLD, &persistent);
+    MPI_Start(&persistent);
+    MPI_Cancel(&persistent);
+    check(cancelled(&persistent), rank, "a persistent receive was not cancelled");
+    MPI_Start(&persistent);
+    MPI_Cancel(&persistent);
+    check(cancelled(&persistent), rank, "a restarted persistent receive was not cancelled");
+    MPI_Request_free(&persistent);
+    check(persistent == MPI_REQUEST_NULL, rank, "MPI_Request_free did not reset the handle");
+
+    // A freed receive still completes, so it takes the first message on its tag.
+    int freed = -1;
+    MPI_Irecv(&freed, 1, MPI_INT, partner, 100, MPI_COMM_WORLD, &request);
+    MPI_Request_free(&request);
+
+    MPI_Recv(NULL, 0, MPI_INT, partner, 0, MPI_COMM_WORLD, MPI_STATUS_IGNORE);
+
+    // Only the messages sent after the cancelled ones arrive.
+    MPI_Recv(&value, 1, MPI_INT, partner, 1, MPI_COMM_WORLD, MPI_STATUS_IGNORE);
+    check(value == 2, rank, "a cancelled synchronous send was delivered");
+
+    int *large = malloc(LARGE_COUNT * sizeof(int));
+    MPI_Recv(large, LARGE_COUNT, MPI_INT, partner, 2, MPI_COMM_WORLD, MPI_STATUS_IGNORE);
+    check(large[0] == 3, rank, "a cancelled rendezvous send was delivered");
+    free(large);
+
+    MPI_Message message;
+    MPI_Mprobe(partner, 3, MPI_COMM_WORLD, &message, MPI_STATUS_IGNORE);
+    MPI_Mrecv(&value, 1, MPI_INT, &message, MPI_STATUS_IGNORE);
+    check(value == 4, rank, "received the wrong matched send");
+    MPI_Send(NULL, 0, MPI_INT, partner, 4, MPI_COMM_WORLD);
+
+    MPI_Recv(&value, 1, MPI_INT, partner, 100, MPI_COMM_WORLD, MPI_STATUS_IGNORE);
+    check(value == 6, rank, "a freed receive was withdrawn");
+    check(freed == 5, rank, "a freed receive did not complete");
+}
+
+int main(int argc, char **argv) {
+    MPI_Init(&argc, &argv);
+
+    int rank, size;
+    MPI_Comm_rank(MPI_COMM_WORLD, &rank);
+    MPI_Comm_size(MPI_COMM_WORLD, &size);
+
+    MPI_Status status;
+    int flag;
+    MPI_Status_set_cancelled(&status, 1);
+    MPI_Test_cancelled(&status, &flag);
+    check(flag, rank, "MPI_Status_set_cancelled did not set the flag");
+    MPI_Status_set_cancelled(&status, 0);
+    MPI_Test_cancelled(&status, &flag);
+    check(!flag, rank, "MPI_Status_set_cancelled did not clear the flag");
+
+    // Ranks work in pairs; an odd rank out has nothing to do.
+    int partner = rank ^ 1;
+    if (partner < size) {
+        if (rank % 2 == 0) {
+            send_side(partner, rank);
+        } else {
+            recv_side(partner, rank);
+        }
+    }
+
+    printf("Rank %d: cancellation passed\n", rank);
+
+    MPI_Finalize();
+    return 0;
+}
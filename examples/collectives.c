@@ -0,0 +1,135 @@
+#include <complex.h>
+#include <mpi.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+// Large enough that the messages of the collectives go by rendezvous.
+#define LARGE_COUNT (1 << 17)
+
+static int rank;
+
+static void check(int condition, const char *what) {
+    if (!condition) {
+        printf("Rank %d: %s\n", rank, what);
+        exit(EXIT_FAILURE);
+    }
+}
+
+int main(int argc, char **argv) {
+    MPI_Init(&argc, &argv);
+
+    int size;
+    MPI_Comm_rank(MPI_COMM_WORLD, &rank);
+    MPI_Comm_size(MPI_COMM_WORLD, &size);
+
+    // A receive for any message is left posted throughout; collectives must not match it.
+    int stray = -1;
+    MPI_Request stray_request;
+    MPI_Irecv(&stray, 1, MPI_INT, MPI_ANY_SOURCE, MPI_ANY_TAG, MPI_COMM_WORLD, &stray_request);
+
+    MPI_Barrier(MPI_COMM_WORLD);
+
+    // Broadcast from every root in turn.
+    for (int root = 0; root < size; root++) {
+        int values[4] = {-1, -1, -1, -1};
+        if (rank == root) {
+            for (int i = 0; i < 4; i++) {
+                values[i] = root * 10 + i;
+            }
+        }
+        MPI_Bcast(values, 4, MPI_INT, root, MPI_COMM_WORLD);
+        check(values[0] == root * 10 && values[3] == root * 10 + 3,
+              "MPI_Bcast did not deliver the root's values");
+    }
+
+    // Broadcast of a derived datatype, which only touches the elements it describes.
+    MPI_Datatype every_other;
+    MPI_Type_vector(3, 1, 2, MPI_INT, &every_other);
+    MPI_Type_commit(&every_other);
+    int strided[5] = {-1, -1, -1, -1, -1};
+    if (rank == 0) {
+        for (int i = 0; i < 5; i++) {
+            strided[i] = i;
+        }
+    }
+    MPI_Bcast_c(strided, 1, every_other, 0, MPI_COMM_WORLD);
+    check(strided[0] == 0 && strided[2] == 2 && strided[4] == 4,
+          "MPI_Bcast_c did not deliver the vector");
+    check(rank == 0 || (strided[1] == -1 && strided[3] == -1),
+          "MPI_Bcast_c wrote outside the vector");
+
+    // A large broadcast.
+    int *large = malloc(LARGE_COUNT * sizeof(int));
+    for (int i = 0; i < LARGE_COUNT; i++) {
+        large[i] = rank == size - 1 ? i : -1;
+    }
+    MPI_Bcast(large, LARGE_COUNT, MPI_INT, size - 1, MPI_COMM_WORLD);
+    check(large[0] == 0 && large[LARGE_COUNT - 1] == LARGE_COUNT - 1,
+          "the large broadcast was wrong");
+
+    // Reductions to every root.
+    for (int root = 0; root < size; root++) {
+        int contribution[2] = {rank + 1, -rank}, result[2] = {0, 0};
+        MPI_Reduce(contribution, result, 2, MPI_INT, MPI_SUM, root, MPI_COMM_WORLD);
+        if (rank == root) {
+            check(result[0] == size * (size + 1) / 2 && result[1] == -size * (size - 1) / 2,
+                  "MPI_Reduce with MPI_SUM was wrong");
+        }
+
+        double value = rank + 0.5, max = 0, min = 0;
+        MPI_Reduce(&value, &max, 1, MPI_DOUBLE, MPI_MAX, root, MPI_COMM_WORLD);
+        MPI_Reduce(&value, &min, 1, MPI_DOUBLE, MPI_MIN, root, MPI_COMM_WORLD);
+        if (rank == root) {
+            check(max == size - 0.5 && min == 0.5, "MPI_Reduce with MPI_MAX or MPI_MIN was wrong");
+        }
+    }
+
+    // Allreduce on every rank, including the folding of sizes that aren't powers of two.
+    long factor = rank + 1, product = 0;
+    MPI_Allreduce(&factor, &product, 1, MPI_LONG, MPI_PROD, MPI_COMM_WORLD);
+    long factorial = 1;
+    for (int i = 2; i <= size; i++) {
+        factorial *= i;
+    }
+    check(product == factorial, "MPI_Allreduce with MPI_PROD was wrong");
+
+    double complex z = rank + 1.0 * I, z_sum = 0;
+    MPI_Allreduce_c(&z, &z_sum, 1, MPI_C_DOUBLE_COMPLEX, MPI_SUM, MPI_COMM_WORLD);
+    check(creal(z_sum) == size * (size - 1) / 2 && cimag(z_sum) == size,
+          "MPI_Allreduce_c of complex numbers was wrong");
+
+    int *large_sum = malloc(LARGE_COUNT * sizeof(int));
+    for (int i = 0; i < LARGE_COUNT; i++) {
+        large[i] = i + rank;
+    }
+    MPI_Allreduce(large, large_sum, LARGE_COUNT, MPI_INT, MPI_SUM, MPI_COMM_WORLD);
+    check(large_sum[0] == size * (size - 1) / 2 &&
+              large_sum[LARGE_COUNT - 1] == size * (LARGE_COUNT - 1) + size * (size - 1) / 2,
+          "the large allreduce was wrong");
+    free(large_sum);
+    free(large);
+
+    // Operations that aren't defined for a datatype, and bad arguments, are errors.
+    char letter = 'a', letters = 0;
+    check(MPI_Allreduce(&letter, &letters, 1, MPI_CHAR, MPI_SUM, MPI_COMM_WORLD) == MPI_ERR_OP,
+          "MPI_SUM of characters was allowed");
+    check(MPI_Bcast(&letter, 1, MPI_CHAR, size, MPI_COMM_WORLD) == MPI_ERR_ROOT,
+          "a broadcast from a rank outside the communicator was allowed");
+    check(MPI_Reduce(&factor, &product, 1, MPI_LONG, MPI_OP_NULL, 0, MPI_COMM_WORLD) ==
+              MPI_ERR_OP,
+          "a reduction with MPI_OP_NULL was allowed");
+
+    MPI_Barrier(MPI_COMM_WORLD);
+
+    int flag;
+    MPI_Test(&stray_request, &flag, MPI_STATUS_IGNORE);
+    check(!flag, "a collective message matched a point-to-point receive");
+    MPI_Cancel(&stray_request);
+    MPI_Wait(&stray_request, MPI_STATUS_IGNORE);
+
+    MPI_Type_free(&every_other);
+
+    printf("Rank %d: collectives passed\n", rank);
+    MPI_Finalize();
+    return 0;
+}
@@ -0,0 +1,103 @@
+#include <complex.h>
+#include <mpi.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <wchar.h>
+
+#define COUNT 4
+
+static void check(int condition, int rank, const char *what) {
+    if (!condition) {
+        printf("Rank %d: %s\n", rank, what);
+        exit(EXIT_FAILURE);
+    }
+}
+
+// Sends `sent` to the partner and receives the partner's values into `received`; both ranks of a
+// pair fill their buffers the same way, so the values must match.
+static void exchange(const void *sent, void *received, size_t size, MPI_Datatype datatype,
+                     int partner, int rank, const char *name) {
+    memset(received, 0, size * COUNT);
+    MPI_Sendrecv(sent, COUNT, datatype, partner, 0, received, COUNT, datatype, partner, 0,
+                 MPI_COMM_WORLD, MPI_STATUS_IGNORE);
+    if (memcmp(sent, received, size * COUNT) != 0) {
+        printf("Rank %d: %s values were corrupted\n", rank, name);
+        exit(EXIT_FAILURE);
+    }
+}
+
+#define EXCHANGE(type, datatype, value)                                                  \
+    do {                                                                                 \
+        type sent[COUNT], received[COUNT];                                               \
+        for (int i = 0; i < COUNT; i++) {                                                \
+            sent[i] = (value);                                                           \
+        }                                                                                \
+        exchange(sent, received, sizeof(type), datatype, partner, rank, #datatype);      \
+    } while (0)
+
+int main(int argc, char **argv) {
+    MPI_Init(&argc, &argv);
+
+    int rank, size;
+    MPI_Comm_rank(MPI_COMM_WORLD, &rank);
+    MPI_Comm_size(MPI_COMM_WORLD, &size);
+
+    // Ranks work in pairs; an odd rank out has nothing to do.
+    int partner = rank ^ 1;
+    if (partner < size) {
+        EXCHANGE(char, MPI_CHAR, 'a' + i);
+        EXCHANGE(signed char, MPI_SIGNED_CHAR, -i);
+        EXCHANGE(unsigned char, MPI_UNSIGNED_CHAR, 200 + i);
+        EXCHANGE(short, MPI_SHORT, -300 * i);
+        EXCHANGE(unsigned short, MPI_UNSIGNED_SHORT, 60000 + i);
+        EXCHANGE(int, MPI_INT, -100000 * i);
+        EXCHANGE(unsigned, MPI_UNSIGNED, 4000000000u + i);
+        EXCHANGE(long, MPI_LONG, -1000000L * i);
+        EXCHANGE(unsigned long, MPI_UNSIGNED_LONG, 3000000000ul + i);
+        EXCHANGE(long long, MPI_LONG_LONG, -(1ll << 40) * i);
+        EXCHANGE(long long, MPI_LONG_LONG_INT, (1ll << 41) * i);
+        EXCHANGE(unsigned long long, MPI_UNSIGNED_LONG_LONG, (1ull << 63) + i);
+        EXCHANGE(float, MPI_FLOAT, 1.5f * i);
+        EXCHANGE(double, MPI_DOUBLE, 0.1 * i);
+        EXCHANGE(wchar_t, MPI_WCHAR, L'x' + i);
+        EXCHANGE(bool, MPI_C_BOOL, i % 2 == 0);
+        EXCHANGE(int8_t, MPI_INT8_T, -i);
+        EXCHANGE(int16_t, MPI_INT16_T, -1000 * i);
+        EXCHANGE(int32_t, MPI_INT32_T, -100000 * i);
+        EXCHANGE(int64_t, MPI_INT64_T, -(INT64_C(1) << 50) * i);
+        EXCHANGE(uint8_t, MPI_UINT8_T, 250 + i);
+        EXCHANGE(uint16_t, MPI_UINT16_T, 65000 + i);
+        EXCHANGE(uint32_t, MPI_UINT32_T, UINT32_C(4000000000) + i);
+        EXCHANGE(uint64_t, MPI_UINT64_T, (UINT64_C(1) << 63) + i);
+        EXCHANGE(MPI_Aint, MPI_AINT, -(MPI_Aint)i);
+        EXCHANGE(MPI_Count, MPI_COUNT, (MPI_Count)1 << (40 + i));
+        EXCHANGE(float complex, MPI_C_FLOAT_COMPLEX, i + 2.0f * I);
+        EXCHANGE(float complex, MPI_C_COMPLEX, -i + 0.5f * I);
+        EXCHANGE(double complex, MPI_C_DOUBLE_COMPLEX, i * 0.25 - 3.0 * I);
+        EXCHANGE(unsigned char, MPI_BYTE, 0xf0 | i);
+
+        // long double has padding on some platforms, so only its value is compared.
+        long double sent[COUNT], received[COUNT];
+        for (int i = 0; i < COUNT; i++) {
+            sent[i] = 1.0L / (i + 3);
+        }
+        MPI_Sendrecv(sent, COUNT, MPI_LONG_DOUBLE, partner, 0, received, COUNT, MPI_LONG_DOUBLE,
+                     partner, 0, MPI_COMM_WORLD, MPI_STATUS_IGNORE);
+        for (int i = 0; i < COUNT; i++) {
+            check(sent[i] == received[i], rank, "MPI_LONG_DOUBLE values were corrupted");
+        }
+    }
+
+    // MPI_DATATYPE_NULL describes no buffer.
+    int value = 0;
+    check(MPI_Send(&value, 1, MPI_DATATYPE_NULL, MPI_PROC_NULL, 0, MPI_COMM_WORLD) == MPI_ERR_TYPE,
+          rank, "sending MPI_DATATYPE_NULL did not fail");
+
+    printf("Rank %d: datatypes passed\n", rank);
+
+    MPI_Finalize();
+    return 0;
+}
@@ -0,0 +1,310 @@
+#include <mpi.h>
+#include <stddef.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+static void check(int condition, int rank, const char *what) {
+    if (!condition) {
+        printf("Rank %d: %s\n", rank, what);
+        exit(EXIT_FAILURE);
+    }
+}
+
+typedef struct {
+    char tag;
+    double value;
+    int indices[3];
+} particle;
+
+static int rank, partner;
+
+// Both ranks of a pair send the same data, so whatever arrives must match what was sent.
+static void exchange(const void *sendbuf, int sendcount, MPI_Datatype sendtype, void *recvbuf,
+                     int recvcount, MPI_Datatype recvtype) {
+    MPI_Status status;
+    int error = MPI_Sendrecv(sendbuf, sendcount, sendtype, partner, 0, recvbuf, recvcount,
+                             recvtype, partner, 0, MPI_COMM_WORLD, &status);
+    check(error == MPI_SUCCESS, rank, "an exchange failed");
+}
+
+int main(int argc, char **argv) {
+    MPI_Init(&argc, &argv);
+
+    int size;
+    MPI_Comm_rank(MPI_COMM_WORLD, &rank);
+    MPI_Comm_size(MPI_COMM_WORLD, &size);
+
+    // Ranks work in pairs; an odd rank out exchanges with itself.
+    partner = (rank ^ 1) < size ? rank ^ 1 : rank;
+
+    // A column of a row-major matrix, sent into contiguous memory and back into a column.
+    {
+        double matrix[6][8], column[6], result[6][8];
+        for (int i = 0; i < 6; i++) {
+            for (int j = 0; j < 8; j++) {
+                matrix[i][j] = i * 10 + j;
+                result[i][j] = -1;
+            }
+        }
+
+        MPI_Datatype column_type;
+        MPI_Type_vector(6, 1, 8, MPI_DOUBLE, &column_type);
+        MPI_Type_commit(&column_type);
+
+        exchange(&matrix[0][3], 1, column_type, column, 6, MPI_DOUBLE);
+        for (int i = 0; i < 6; i++) {
+            check(column[i] == i * 10 + 3, rank, "a vector was packed wrongly");
+        }
+
+        exchange(column, 6, MPI_DOUBLE, &result[0][5], 1, column_type);
+        for (int i = 0; i < 6; i++) {
+            for (int j = 0; j < 8; j++) {
+                double expected = j == 5 ? i * 10 + 3 : -1;
+                check(result[i][j] == expected, rank, "a vector was unpacked wrongly");
+            }
+        }
+
+        // Consecutive elements of the column type start a whole matrix apart.
+        double two[2][6][8];
+        for (int k = 0; k < 2; k++) {
+            for (int i = 0; i < 6; i++) {
+                for (int j = 0; j < 8; j++) {
+                    two[k][i][j] = k * 100 + i * 10 + j;
+                }
+            }
+        }
+        double columns[12];
+        MPI_Datatype strided;
+        MPI_Type_create_resized(column_type, 0, sizeof(matrix), &strided);
+        MPI_Type_commit(&strided);
+        exchange(&two[0][0][1], 2, strided, columns, 12, MPI_DOUBLE);
+        for (int k = 0; k < 2; k++) {
+            for (int i = 0; i < 6; i++) {
+                check(columns[k * 6 + i] == k * 100 + i * 10 + 1, rank,
+                      "a resized vector was packed wrongly");
+            }
+        }
+
+        MPI_Type_free(&strided);
+        MPI_Type_free(&column_type);
+        check(column_type == MPI_DATATYPE_NULL, rank, "a freed datatype was not reset");
+    }
+
+    // Blocks out of order, and a negative stride that walks an array backwards.
+    {
+        int values[16], packed[16];
+        for (int i = 0; i < 16; i++) {
+            values[i] = i;
+        }
+
+        int blocklengths[3] = {2, 1, 3};
+        int displacements[3] = {10, 0, 4};
+        MPI_Datatype indexed;
+        MPI_Type_indexed(3, blocklengths, displacements, MPI_INT, &indexed);
+        MPI_Type_commit(&indexed);
+        exchange(values, 1, indexed, packed, 6, MPI_INT);
+        int expected[6] = {10, 11, 0, 4, 5, 6};
+        check(memcmp(packed, expected, sizeof(expected)) == 0, rank,
+              "an indexed type was packed wrongly");
+
+        MPI_Datatype backwards;
+        MPI_Type_vector(4, 1, -2, MPI_INT, &backwards);
+        MPI_Type_commit(&backwards);
+        exchange(&values[15], 1, backwards, packed, 4, MPI_INT);
+        check(packed[0] == 15 && packed[1] == 13 && packed[2] == 11 && packed[3] == 9, rank,
+              "a negative stride was packed wrongly");
+
+        int block_displacements[3] = {12, 1, 6};
+        MPI_Datatype indexed_block;
+        MPI_Type_create_indexed_block(3, 2, block_displacements, MPI_INT, &indexed_block);
+        MPI_Type_commit(&indexed_block);
+        exchange(values, 1, indexed_block, packed, 6, MPI_INT);
+        int expected_blocks[6] = {12, 13, 1, 2, 6, 7};
+        check(memcmp(packed, expected_blocks, sizeof(expected_blocks)) == 0, rank,
+              "an indexed block type was packed wrongly");
+
+        MPI_Aint byte_displacements[2] = {8 * sizeof(int), 2 * sizeof(int)};
+        int byte_blocklengths[2] = {1, 2};
+        MPI_Datatype hindexed, hvector;
+        MPI_Type_create_hindexed(2, byte_blocklengths, byte_displacements, MPI_INT, &hindexed);
+        MPI_Type_create_hvector(2, 1, 5 * sizeof(int), hindexed, &hvector);
+        MPI_Type_commit(&hvector);
+        exchange(values, 1, hvector, packed, 6, MPI_INT);
+        int expected_nested[6] = {8, 2, 3, 13, 7, 8};
+        check(memcmp(packed, expected_nested, sizeof(expected_nested)) == 0, rank,
+              "nested byte displacements were packed wrongly");
+
+        MPI_Type_free(&indexed);
+        MPI_Type_free(&backwards);
+        MPI_Type_free(&indexed_block);
+        MPI_Type_free(&hindexed);
+        MPI_Type_free(&hvector);
+    }
+
+    // An array of structs leaves the padding between members alone.
+    {
+        int blocklengths[3] = {1, 1, 3};
+        MPI_Aint displacements[3] = {offsetof(particle, tag), offsetof(particle, value),
+                                     offsetof(particle, indices)};
+        MPI_Datatype types[3] = {MPI_CHAR, MPI_DOUBLE, MPI_INT};
+        MPI_Datatype particle_type;
+        MPI_Type_create_struct(3, blocklengths, displacements, types, &particle_type);
+        MPI_Type_commit(&particle_type);
+
+        particle sent[5], received[5];
+        memset(received, 0xab, sizeof(received));
+        for (int i = 0; i < 5; i++) {
+            memset(&sent[i], 0, sizeof(particle));
+            sent[i].tag = 'a' + i;
+            sent[i].value = i * 1.5;
+            for (int j = 0; j < 3; j++) {
+                sent[i].indices[j] = i * 3 + j;
+            }
+        }
+
+        exchange(sent, 5, particle_type, received, 5, particle_type);
+        for (int i = 0; i < 5; i++) {
+            check(received[i].tag == 'a' + i && received[i].value == i * 1.5 &&
+                      received[i].indices[2] == i * 3 + 2,
+                  rank, "a struct was received wrongly");
+            unsigned char *padding = (unsigned char *)&received[i] + 1;
+            check(*padding == 0xab, rank, "padding was overwritten");
+        }
+
+        MPI_Type_free(&particle_type);
+    }
+
+    // A block of a 3D array, in both orders.
+    {
+        int array[4][5][6], block[8];
+        for (int i = 0; i < 4; i++) {
+            for (int j = 0; j < 5; j++) {
+                for (int k = 0; k < 6; k++) {
+                    array[i][j][k] = i * 100 + j * 10 + k;
+                }
+            }
+        }
+
+        int sizes[3] = {4, 5, 6}, subsizes[3] = {2, 2, 2}, starts[3] = {1, 2, 3};
+        MPI_Datatype subarray;
+        MPI_Type_create_subarray(3, sizes, subsizes, starts, MPI_ORDER_C, MPI_INT, &subarray);
+        MPI_Type_commit(&subarray);
+        exchange(array, 1, subarray, block, 8, MPI_INT);
+        int index = 0;
+        for (int i = 1; i < 3; i++) {
+            for (int j = 2; j < 4; j++) {
+                for (int k = 3; k < 5; k++) {
+                    check(block[index++] == i * 100 + j * 10 + k, rank,
+                          "a C order subarray was packed wrongly");
+                }
+            }
+        }
+
+        // The same memory seen as a Fortran array of 6 by 5 by 4.
+        int fortran_sizes[3] = {6, 5, 4}, fortran_starts[3] = {3, 2, 1};
+        MPI_Datatype fortran;
+        MPI_Type_create_subarray(3, fortran_sizes, subsizes, fortran_starts, MPI_ORDER_FORTRAN,
+                                 MPI_INT, &fortran);
+        MPI_Type_commit(&fortran);
+        int fortran_block[8];
+        exchange(array, 1, fortran, fortran_block, 8, MPI_INT);
+        check(memcmp(block, fortran_block, sizeof(block)) == 0, rank,
+              "a Fortran order subarray was packed wrongly");
+
+        int bad_starts[3] = {3, 0, 0};
+        MPI_Datatype bad;
+        check(MPI_Type_create_subarray(3, sizes, subsizes, bad_starts, MPI_ORDER_C, MPI_INT,
+                                       &bad) == MPI_ERR_ARG,
+              rank, "a subarray outside of the array was accepted");
+
+        MPI_Type_free(&subarray);
+        MPI_Type_free(&fortran);
+    }
+
+    // Every process of a 2 by 2 grid gets its share of an 8 by 6 array: rows in blocks and
+    // columns cyclically in pairs.
+    {
+        int global[8][6];
+        for (int i = 0; i < 8; i++) {
+            for (int j = 0; j < 6; j++) {
+                global[i][j] = i * 10 + j;
+            }
+        }
+
+        int gsizes[2] = {8, 6}, psizes[2] = {2, 2};
+        int distribs[2] = {MPI_DISTRIBUTE_BLOCK, MPI_DISTRIBUTE_CYCLIC};
+        int dargs[2] = {MPI_DISTRIBUTE_DFLT_DARG, 2};
+        int seen[8][6] = {{0}};
+        for (int owner = 0; owner < 4; owner++) {
+            MPI_Datatype darray;
+            MPI_Type_create_darray(4, owner, 2, gsizes, distribs, dargs, psizes, MPI_ORDER_C,
+                                   MPI_INT, &darray);
+            MPI_Type_commit(&darray);
+
+            int local[48];
+            int count = 0;
+            exchange(global, 1, darray, local, 48, MPI_INT);
+
+            int row = owner / 2, col = owner % 2;
+            for (int i = row * 4; i < row * 4 + 4; i++) {
+                for (int j = 0; j < 6; j++) {
+                    if ((j / 2) % 2 == col) {
+                        check(local[count++] == global[i][j], rank,
+                              "a distributed array was packed wrongly");
+                        seen[i][j]++;
+                    }
+                }
+            }
+            MPI_Type_free(&darray);
+        }
+        for (int i = 0; i < 8; i++) {
+            for (int j = 0; j < 6; j++) {
+                check(seen[i][j] == 1, rank, "a distributed array was not split exactly");
+            }
+        }
+    }
+
+    // A strided message too large to send eagerly, received through a different layout.
+    {
+        int n = 100000;
+        double *wide = malloc(2 * n * sizeof(double));
+        double *narrow = malloc(n * sizeof(double));
+        for (int i = 0; i < 2 * n; i++) {
+            wide[i] = i;
+        }
+
+        MPI_Datatype every_other, pairs;
+        MPI_Type_vector(n, 1, 2, MPI_DOUBLE, &every_other);
+        MPI_Type_commit(&every_other);
+        MPI_Type_contiguous(2, MPI_DOUBLE, &pairs);
+        MPI_Type_commit(&pairs);
+        exchange(wide, 1, every_other, narrow, n / 2, pairs);
+        for (int i = 0; i < n; i++) {
+            check(narrow[i] == 2 * i, rank, "a large strided message was corrupted");
+        }
+
+        free(wide);
+        free(narrow);
+        MPI_Type_free(&every_other);
+        MPI_Type_free(&pairs);
+    }
+
+    // Datatypes must be committed before use, and predefined ones can't be freed.
+    {
+        int value = 0;
+        MPI_Datatype uncommitted;
+        MPI_Type_contiguous(1, MPI_INT, &uncommitted);
+        check(MPI_Send(&value, 1, uncommitted, MPI_PROC_NULL, 0, MPI_COMM_WORLD) == MPI_ERR_TYPE,
+              rank, "an uncommitted datatype was used");
+        MPI_Type_free(&uncommitted);
+
+        MPI_Datatype predefined = MPI_INT;
+        check(MPI_Type_free(&predefined) == MPI_ERR_TYPE, rank, "a predefined type was freed");
+    }
+
+    printf("Rank %d: derived datatypes passed\n", rank);
+
+    MPI_Finalize();
+    return 0;
+}
@@ -0,0 +1,118 @@
+#include <mpi.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+static int rank;
+
+static void check(int condition, const char *what) {
+    if (!condition) {
+        printf("Rank %d: %s\n", rank, what);
+        exit(EXIT_FAILURE);
+    }
+}
+
+int main(int argc, char **argv) {
+    MPI_Init(&argc, &argv);
+
+    int size;
+    MPI_Comm_rank(MPI_COMM_WORLD, &rank);
+    MPI_Comm_size(MPI_COMM_WORLD, &size);
+
+    // An int followed by a double, without padding: 12 bytes and two basic elements.
+    MPI_Datatype int_double;
+    int blocklengths[2] = {1, 1};
+    MPI_Aint displacements[2] = {0, sizeof(int)};
+    MPI_Datatype types[2] = {MPI_INT, MPI_DOUBLE};
+    MPI_Type_create_struct(2, blocklengths, displacements, types, &int_double);
+    MPI_Type_commit(&int_double);
+
+    MPI_Datatype two_ints;
+    MPI_Type_contiguous(2, MPI_INT, &two_ints);
+    MPI_Type_commit(&two_ints);
+
+    int partner = rank ^ 1;
+    if (partner < size) {
+        int count;
+        MPI_Count count_x;
+        MPI_Status status;
+        char sent[64] = {0}, received[64];
+
+        // A receive buffer larger than the message is only partly filled.
+        MPI_Sendrecv(sent, 5, MPI_INT, partner, 0, received, 10, MPI_INT, partner, 0,
+                     MPI_COMM_WORLD, &status);
+        MPI_Get_count(&status, MPI_INT, &count);
+        check(count == 5, "five ints were not counted");
+        MPI_Get_count(&status, MPI_DOUBLE, &count);
+        check(count == MPI_UNDEFINED, "twenty bytes were counted as whole doubles");
+        MPI_Get_count(&status, two_ints, &count);
+        check(count == MPI_UNDEFINED, "five ints were counted as whole pairs of ints");
+        MPI_Get_elements(&status, two_ints, &count);
+        check(count == 5, "five ints were not counted as basic elements of pairs of ints");
+
+        // One whole int and double, then an int of the next one.
+        MPI_Sendrecv(sent, 16, MPI_BYTE, partner, 1, received, 3, int_double, partner, 1,
+                     MPI_COMM_WORLD, &status);
+        MPI_Get_count(&status, int_double, &count);
+        check(count == MPI_UNDEFINED, "a partial struct was counted as a whole one");
+        MPI_Get_elements(&status, int_double, &count);
+        check(count == 3, "a partial struct has the wrong number of basic elements");
+        MPI_Get_elements_x(&status, int_double, &count_x);
+        check(count_x == 3, "MPI_Get_elements_x disagrees with MPI_Get_elements");
+
+        // Data that stops partway through the double has no number of basic elements.
+        MPI_Sendrecv(sent, 18, MPI_BYTE, partner, 2, received, 3, int_double, partner, 2,
+                     MPI_COMM_WORLD, &status);
+        MPI_Get_elements(&status, int_double, &count);
+        check(count == MPI_UNDEFINED, "a partial double was counted");
+
+        // A truncated receive counts what fit in the buffer.
+        int error = MPI_Sendrecv(sent, 8, MPI_INT, partner, 3, received, 6, MPI_INT, partner, 3,
+                                 MPI_COMM_WORLD, &status);
+        check(error == MPI_ERR_TRUNCATE, "a truncated receive succeeded");
+        MPI_Get_count(&status, MPI_INT, &count);
+        check(count == 6, "a truncated receive did not count what was received");
+
+        // A probe counts the whole message.
+        MPI_Send(sent, 7, MPI_SHORT, partner, 4, MPI_COMM_WORLD);
+        MPI_Probe(partner, 4, MPI_COMM_WORLD, &status);
+        MPI_Get_count(&status, MPI_SHORT, &count);
+        check(count == 7, "a probed message was counted wrongly");
+        MPI_Recv(received, 7, MPI_SHORT, partner, 4, MPI_COMM_WORLD, MPI_STATUS_IGNORE);
+    }
+
+    // Receives from MPI_PROC_NULL are empty.
+    int count;
+    MPI_Status status;
+    MPI_Recv(NULL, 0, MPI_INT, MPI_PROC_NULL, 0, MPI_COMM_WORLD, &status);
+    MPI_Get_count(&status, MPI_INT, &count);
+    check(count == 0, "a receive from MPI_PROC_NULL was not empty");
+
+    // Statuses filled in by hand report the number of basic elements they were given.
+    MPI_Status_set_elements(&status, int_double, 3);
+    MPI_Get_elements(&status, int_double, &count);
+    check(count == 3, "a status set to three basic elements has a different number");
+    MPI_Get_count(&status, int_double, &count);
+    check(count == MPI_UNDEFINED, "a status set to one and a half structs has a count");
+    MPI_Status_set_elements_x(&status, int_double, 4);
+    MPI_Get_count(&status, int_double, &count);
+    check(count == 2, "a status set to four basic elements does not hold two structs");
+    MPI_Get_count(&status, MPI_BYTE, &count);
+    check(count == 24, "a status set to two structs does not hold their bytes");
+
+    // A datatype without data counts no elements in an empty message.
+    MPI_Datatype empty;
+    MPI_Type_contiguous(0, MPI_INT, &empty);
+    MPI_Type_commit(&empty);
+    MPI_Status_set_elements(&status, MPI_INT, 0);
+    MPI_Get_count(&status, empty, &count);
+    check(count == 0, "an empty datatype was counted in an empty message");
+
+    MPI_Type_free(&empty);
+    MPI_Type_free(&two_ints);
+    MPI_Type_free(&int_double);
+
+    printf("Rank %d: elements passed\n", rank);
+
+    MPI_Finalize();
+    return 0;
+}
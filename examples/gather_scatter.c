@@ -0,0 +1,237 @@
+#include <mpi.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+// Large enough that the blocks of the all-to-all go by rendezvous.
+#define LARGE_BLOCK (1 << 15)
+
+static int rank;
+
+static void check(int condition, const char *what) {
+    if (!condition) {
+        printf("Rank %d: %s\n", rank, what);
+        exit(EXIT_FAILURE);
+    }
+}
+
+int main(int argc, char **argv) {
+    MPI_Init(&argc, &argv);
+
+    int size;
+    MPI_Comm_rank(MPI_COMM_WORLD, &rank);
+    MPI_Comm_size(MPI_COMM_WORLD, &size);
+
+    int *all = malloc(2 * size * sizeof(int));
+    int *counts = malloc(size * sizeof(int));
+    int *displs = malloc(size * sizeof(int));
+    int pair[2] = {rank, rank * rank};
+
+    // Gather and scatter from every root, through the binomial trees.
+    for (int root = 0; root < size; root++) {
+        for (int i = 0; i < 2 * size; i++) {
+            all[i] = -1;
+        }
+        MPI_Gather(pair, 2, MPI_INT, all, 2, MPI_INT, root, MPI_COMM_WORLD);
+        if (rank == root) {
+            for (int i = 0; i < size; i++) {
+                check(all[2 * i] == i && all[2 * i + 1] == i * i, "MPI_Gather was wrong");
+            }
+        }
+
+        int received[2] = {-1, -1};
+        if (rank == root) {
+            for (int i = 0; i < 2 * size; i++) {
+                all[i] = root * 1000 + i;
+            }
+        }
+        MPI_Scatter(all, 2, MPI_INT, received, 2, MPI_INT, root, MPI_COMM_WORLD);
+        check(received[0] == root * 1000 + 2 * rank && received[1] == root * 1000 + 2 * rank + 1,
+              "MPI_Scatter was wrong");
+    }
+
+    // The receive side may describe the blocks with a different datatype of the same signature.
+    MPI_Datatype two_ints;
+    MPI_Type_contiguous(2, MPI_INT, &two_ints);
+    MPI_Type_commit(&two_ints);
+    MPI_Allgather(pair, 2, MPI_INT, all, 1, two_ints, MPI_COMM_WORLD);
+    for (int i = 0; i < size; i++) {
+        check(all[2 * i] == i && all[2 * i + 1] == i * i, "MPI_Allgather was wrong");
+    }
+
+    // In place, each rank's block is already in its receive buffer.
+    for (int i = 0; i < 2 * size; i++) {
+        all[i] = i / 2 == rank ? rank + 7 : -1;
+    }
+    MPI_Allgather(MPI_IN_PLACE, 0, MPI_DATATYPE_NULL, all, 2, MPI_INT, MPI_COMM_WORLD);
+    for (int i = 0; i < 2 * size; i++) {
+        check(all[i] == i / 2 + 7, "MPI_Allgather in place was wrong");
+    }
+
+    // Rank i contributes i + 1 values, gathered back to front.
+    int total = size * (size + 1) / 2;
+    int *varied = malloc(total * sizeof(int));
+    int *mine = malloc((rank + 1) * sizeof(int));
+    for (int i = 0; i <= rank; i++) {
+        mine[i] = rank * 100 + i;
+    }
+    for (int i = 0, next = total; i < size; i++) {
+        counts[i] = i + 1;
+        next -= counts[i];
+        displs[i] = next;
+    }
+
+    MPI_Gatherv(mine, rank + 1, MPI_INT, varied, counts, displs, MPI_INT, 0, MPI_COMM_WORLD);
+    if (rank == 0) {
+        for (int i = 0; i < size; i++) {
+            for (int j = 0; j <= i; j++) {
+                check(varied[displs[i] + j] == i * 100 + j, "MPI_Gatherv was wrong");
+            }
+        }
+    }
+
+    for (int i = 0; i < total; i++) {
+        varied[i] = -1;
+    }
+    MPI_Allgatherv(mine, rank + 1, MPI_INT, varied, counts, displs, MPI_INT, MPI_COMM_WORLD);
+    for (int i = 0; i < size; i++) {
+        check(varied[displs[i]] == i * 100 && varied[displs[i] + i] == i * 100 + i,
+              "MPI_Allgatherv was wrong");
+    }
+
+    for (int i = 0; i <= rank; i++) {
+        mine[i] = -1;
+    }
+    int root = size - 1;
+    if (rank == root) {
+        MPI_Scatterv(varied, counts, displs, MPI_INT, MPI_IN_PLACE, 0, MPI_INT, root,
+                     MPI_COMM_WORLD);
+    } else {
+        MPI_Scatterv(NULL, NULL, NULL, MPI_INT, mine, rank + 1, MPI_INT, root, MPI_COMM_WORLD);
+        check(mine[0] == rank * 100 && mine[rank] == rank * 100 + rank, "MPI_Scatterv was wrong");
+    }
+
+    // In-place gather at the root, and in-place reductions.
+    for (int i = 0; i < 2 * size; i++) {
+        all[i] = rank == 0 && i < 2 ? 0 : -1;
+    }
+    if (rank == 0) {
+        MPI_Gather_c(MPI_IN_PLACE, 2, MPI_INT, all, 2, MPI_INT, 0, MPI_COMM_WORLD);
+        for (int i = 0; i < size; i++) {
+            check(all[2 * i] == i && all[2 * i + 1] == i * i, "MPI_Gather_c in place was wrong");
+        }
+    } else {
+        MPI_Gather_c(pair, 2, MPI_INT, NULL, 0, MPI_INT, 0, MPI_COMM_WORLD);
+    }
+
+    int sum = rank;
+    MPI_Allreduce(MPI_IN_PLACE, &sum, 1, MPI_INT, MPI_SUM, MPI_COMM_WORLD);
+    check(sum == size * (size - 1) / 2, "MPI_Allreduce in place was wrong");
+    int max = rank;
+    MPI_Reduce(rank == 0 ? MPI_IN_PLACE : &max, &max, 1, MPI_INT, MPI_MAX, 0, MPI_COMM_WORLD);
+    check(rank != 0 || max == size - 1, "MPI_Reduce in place was wrong");
+
+    // All-to-all with large blocks, then the same exchange in place.
+    int *sendbuf = malloc(size * LARGE_BLOCK * sizeof(int));
+    int *recvbuf = malloc(size * LARGE_BLOCK * sizeof(int));
+    for (int i = 0; i < size * LARGE_BLOCK; i++) {
+        sendbuf[i] = rank * size + i / LARGE_BLOCK;
+    }
+    MPI_Alltoall(sendbuf, LARGE_BLOCK, MPI_INT, recvbuf, LARGE_BLOCK, MPI_INT, MPI_COMM_WORLD);
+    for (int i = 0; i < size; i++) {
+        check(recvbuf[i * LARGE_BLOCK] == i * size + rank &&
+                  recvbuf[(i + 1) * LARGE_BLOCK - 1] == i * size + rank,
+              "MPI_Alltoall was wrong");
+    }
+    MPI_Alltoall(MPI_IN_PLACE, 0, MPI_DATATYPE_NULL, sendbuf, LARGE_BLOCK, MPI_INT,
+                 MPI_COMM_WORLD);
+    for (int i = 0; i < size; i++) {
+        check(sendbuf[i * LARGE_BLOCK] == i * size + rank, "MPI_Alltoall in place was wrong");
+    }
+
+    // Rank i sends i + 1 values to every rank.
+    int *sendcounts = malloc(size * sizeof(int)), *sdispls = malloc(size * sizeof(int));
+    for (int i = 0; i < size; i++) {
+        sendcounts[i] = rank + 1;
+        sdispls[i] = i * (rank + 1);
+        counts[i] = i + 1;
+        displs[i] = i * (i + 1) / 2;
+    }
+    for (int i = 0; i < size * (rank + 1); i++) {
+        sendbuf[i] = rank * 1000 + i;
+    }
+    MPI_Alltoallv(sendbuf, sendcounts, sdispls, MPI_INT, varied, counts, displs, MPI_INT,
+                  MPI_COMM_WORLD);
+    for (int i = 0; i < size; i++) {
+        for (int j = 0; j <= i; j++) {
+            check(varied[displs[i] + j] == i * 1000 + rank * (i + 1) + j,
+                  "MPI_Alltoallv was wrong");
+        }
+    }
+
+    // Rank i sends rank j an int and a double, received into a struct with another layout.
+    struct {
+        int i;
+        double d;
+    } *sent = malloc(size * sizeof(*sent));
+    struct {
+        double d;
+        int i;
+    } *records = malloc(size * sizeof(*records));
+    for (int i = 0; i < size; i++) {
+        sent[i].i = rank * 10 + i;
+        sent[i].d = rank + 0.25 * i;
+    }
+
+    MPI_Datatype int_double, record;
+    int blocklengths[2] = {1, 1};
+    MPI_Datatype types[2] = {MPI_INT, MPI_DOUBLE};
+    MPI_Aint sent_offsets[2] = {0, (char *)&sent[0].d - (char *)sent};
+    MPI_Type_create_struct(2, blocklengths, sent_offsets, types, &int_double);
+    MPI_Type_commit(&int_double);
+    MPI_Aint record_offsets[2] = {(char *)&records[0].i - (char *)records, 0};
+    MPI_Type_create_struct(2, blocklengths, record_offsets, types, &record);
+    MPI_Type_commit(&record);
+
+    int *ones = malloc(size * sizeof(int));
+    MPI_Aint *send_offsets = malloc(size * sizeof(MPI_Aint));
+    MPI_Aint *recv_offsets = malloc(size * sizeof(MPI_Aint));
+    MPI_Datatype *sendtypes = malloc(size * sizeof(MPI_Datatype));
+    MPI_Datatype *recvtypes = malloc(size * sizeof(MPI_Datatype));
+    for (int i = 0; i < size; i++) {
+        ones[i] = 1;
+        send_offsets[i] = i * sizeof(*sent);
+        recv_offsets[i] = i * sizeof(*records);
+        sendtypes[i] = int_double;
+        recvtypes[i] = record;
+    }
+    MPI_Alltoallw(sent, ones, send_offsets, sendtypes, records, ones, recv_offsets, recvtypes,
+                  MPI_COMM_WORLD);
+    for (int i = 0; i < size; i++) {
+        check(records[i].i == i * 10 + rank && records[i].d == i + 0.25 * rank,
+              "MPI_Alltoallw was wrong");
+    }
+
+    MPI_Type_free(&record);
+    MPI_Type_free(&int_double);
+    MPI_Type_free(&two_ints);
+    free(recvtypes);
+    free(sendtypes);
+    free(recv_offsets);
+    free(send_offsets);
+    free(ones);
+    free(records);
+    free(sent);
+    free(sdispls);
+    free(sendcounts);
+    free(recvbuf);
+    free(sendbuf);
+    free(mine);
+    free(varied);
+    free(displs);
+    free(counts);
+    free(all);
+
+    printf("Rank %d: gather, scatter and all-to-all passed\n", rank);
+    MPI_Finalize();
+    return 0;
+}
@@ -0,0 +1,135 @@
+#include <mpi.h>
+#include <pthread.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <unistd.h>
+
+static void check(int condition, int rank, const char *what) {
+    if (!condition) {
+        printf("Rank %d: %s\n", rank, what);
+        exit(EXIT_FAILURE);
+    }
+}
+
+// A pretend file-staging operation, completed by a thread of its own.
+typedef struct {
+    int id;
+    int fail;
+    int cancelled;
+    int freed;
+    MPI_Request request;
+} staging;
+
+static int query(void *extra_state, MPI_Status *status) {
+    staging *stage = extra_state;
+    status->MPI_SOURCE = stage->id;
+    status->MPI_TAG = 42;
+    MPI_Status_set_cancelled(status, stage->cancelled);
+    return stage->fail ? MPI_ERR_OTHER : MPI_SUCCESS;
+}
+
+static int free_stage(void *extra_state) {
+    staging *stage = extra_state;
+    stage->freed++;
+    return MPI_SUCCESS;
+}
+
+static int cancel(void *extra_state, int complete) {
+    staging *stage = extra_state;
+    if (!complete) {
+        stage->cancelled = 1;
+    }
+    return MPI_SUCCESS;
+}
+
+static void start(staging *stage, int id) {
+    stage->id = id;
+    stage->fail = 0;
+    stage->cancelled = 0;
+    stage->freed = 0;
+    MPI_Grequest_start(query, free_stage, cancel, stage, &stage->request);
+}
+
+static void *stage_in_background(void *extra_state) {
+    staging *stage = extra_state;
+    usleep(10000);
+    MPI_Grequest_complete(stage->request);
+    return NULL;
+}
+
+int main(int argc, char **argv) {
+    MPI_Init(&argc, &argv);
+
+    int rank, size;
+    MPI_Comm_rank(MPI_COMM_WORLD, &rank);
+    MPI_Comm_size(MPI_COMM_WORLD, &size);
+
+    int next = (rank + 1) % size;
+    int prev = (rank + size - 1) % size;
+
+    // Staging operations finish on other threads while the ring exchange is waited on with them.
+    staging stages[2];
+    pthread_t threads[2];
+    MPI_Request requests[4];
+    for (int i = 0; i < 2; i++) {
+        start(&stages[i], i);
+        requests[i] = stages[i].request;
+        pthread_create(&threads[i], NULL, stage_in_background, &stages[i]);
+    }
+
+    int received = -1;
+    MPI_Irecv(&received, 1, MPI_INT, prev, 0, MPI_COMM_WORLD, &requests[2]);
+    MPI_Isend(&rank, 1, MPI_INT, next, 0, MPI_COMM_WORLD, &requests[3]);
+
+    MPI_Status statuses[4];
+    MPI_Waitall(4, requests, statuses);
+    for (int i = 0; i < 2; i++) {
+        pthread_join(threads[i], NULL);
+        check(statuses[i].MPI_SOURCE == i && statuses[i].MPI_TAG == 42, rank,
+              "the query callback's status was lost");
+        check(stages[i].freed == 1, rank, "a completed request was not freed exactly once");
+    }
+    check(received == prev, rank, "the ring exchange received the wrong value");
+
+    // Nothing completes a generalized request but MPI_Grequest_complete.
+    staging stage;
+    MPI_Status status;
+    int flag;
+    start(&stage, 2);
+    MPI_Test(&stage.request, &flag, &status);
+    check(!flag, rank, "a generalized request completed on its own");
+    MPI_Grequest_complete(stage.request);
+    MPI_Test(&stage.request, &flag, &status);
+    check(flag && status.MPI_SOURCE == 2, rank, "a completed generalized request did not test");
+    check(stage.request == MPI_REQUEST_NULL, rank, "a completed generalized request was not freed");
+
+    // Cancelling goes through the callback; the query reports the outcome.
+    start(&stage, 3);
+    MPI_Request request = stage.request;
+    MPI_Cancel(&request);
+    MPI_Grequest_complete(request);
+    MPI_Wait(&request, &status);
+    MPI_Test_cancelled(&status, &flag);
+    check(flag, rank, "the cancel callback was not called");
+
+    // A request freed early is only freed once the operation completes.
+    start(&stage, 4);
+    request = stage.request;
+    MPI_Request_free(&request);
+    check(request == MPI_REQUEST_NULL && stage.freed == 0, rank,
+          "freeing a pending generalized request freed the operation");
+    MPI_Grequest_complete(stage.request);
+    check(stage.freed == 1, rank, "completing a freed generalized request did not free it");
+
+    // Errors from the callbacks are returned by the completion routine.
+    start(&stage, 5);
+    stage.fail = 1;
+    MPI_Grequest_complete(stage.request);
+    check(MPI_Wait(&stage.request, &status) == MPI_ERR_OTHER, rank,
+          "the query callback's error was lost");
+
+    printf("Rank %d: generalized requests passed\n", rank);
+
+    MPI_Finalize();
+    return 0;
+}
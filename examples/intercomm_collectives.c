@@ -0,0 +1,149 @@
+#include <mpi.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#define CHILDREN 3
+
+static int rank;
+static int is_child;
+
+static void check(int condition, const char *what) {
+    if (!condition) {
+        printf("%s %d: %s\n", is_child ? "Child" : "Parent", rank, what);
+        exit(EXIT_FAILURE);
+    }
+}
+
+// The parent's side of each collective. The parent is the only rank of its group, so it passes
+// MPI_ROOT whenever its group holds the root.
+static void parent(MPI_Comm children) {
+    int size;
+    MPI_Comm_remote_size(children, &size);
+    check(size == CHILDREN, "the intercomm to the children has the wrong remote size");
+
+    int config[3] = {42, 7, -1};
+    MPI_Bcast(config, 3, MPI_INT, MPI_ROOT, children);
+
+    double scale = 0.5;
+    MPI_Request request;
+    MPI_Ibcast(&scale, 1, MPI_DOUBLE, MPI_ROOT, children, &request);
+    MPI_Wait(&request, MPI_STATUS_IGNORE);
+
+    // Results flow back from the children's group, and data from the parent to theirs.
+    int sum = 0;
+    MPI_Reduce(NULL, &sum, 1, MPI_INT, MPI_SUM, MPI_ROOT, children);
+    check(sum == CHILDREN * (CHILDREN + 1) / 2, "the reduce from the children was wrong");
+    int parent_value = 10;
+    MPI_Reduce(&parent_value, NULL, 1, MPI_INT, MPI_SUM, 0, children);
+
+    int total = 0;
+    MPI_Allreduce(&parent_value, &total, 1, MPI_INT, MPI_SUM, children);
+    check(total == CHILDREN * (CHILDREN - 1) / 2, "the allreduce received the wrong sum");
+
+    int gathered[CHILDREN];
+    MPI_Gather(NULL, 0, MPI_INT, gathered, 1, MPI_INT, MPI_ROOT, children);
+    for (int i = 0; i < CHILDREN; i++) {
+        check(gathered[i] == 100 + i, "the gather was wrong");
+    }
+
+    int everyone[CHILDREN];
+    MPI_Allgather(&parent_value, 1, MPI_INT, everyone, 1, MPI_INT, children);
+    for (int i = 0; i < CHILDREN; i++) {
+        check(everyone[i] == 200 + i, "the allgather was wrong");
+    }
+
+    int out[CHILDREN], in[CHILDREN];
+    for (int i = 0; i < CHILDREN; i++) {
+        out[i] = 300 + i;
+    }
+    MPI_Alltoall(out, 1, MPI_INT, in, 1, MPI_INT, children);
+    for (int i = 0; i < CHILDREN; i++) {
+        check(in[i] == 400 + i, "the alltoall was wrong");
+    }
+
+    // A scan is erroneous on an intercomm.
+    int prefix;
+    check(MPI_Scan(&parent_value, &prefix, 1, MPI_INT, MPI_SUM, children) == MPI_ERR_COMM,
+          "the scan on an intercomm didn't fail");
+
+    MPI_Barrier(children);
+}
+
+// The children's side. Child 0 is the root of the reduce to their group, so the other children
+// pass MPI_PROC_NULL there.
+static void child(MPI_Comm parent) {
+    int size;
+    MPI_Comm_remote_size(parent, &size);
+    check(size == 1, "the intercomm to the parent has the wrong remote size");
+
+    int config[3] = {0, 0, 0};
+    MPI_Bcast(config, 3, MPI_INT, 0, parent);
+    check(config[0] == 42 && config[1] == 7 && config[2] == -1, "the broadcast was wrong");
+
+    double scale = 0.0;
+    MPI_Request request;
+    MPI_Ibcast(&scale, 1, MPI_DOUBLE, 0, parent, &request);
+    MPI_Wait(&request, MPI_STATUS_IGNORE);
+    check(scale == 0.5, "the nonblocking broadcast was wrong");
+
+    int value = rank + 1;
+    MPI_Reduce(&value, NULL, 1, MPI_INT, MPI_SUM, 0, parent);
+    int from_parent = 0;
+    MPI_Reduce(NULL, &from_parent, 1, MPI_INT, MPI_SUM, rank == 0 ? MPI_ROOT : MPI_PROC_NULL,
+               parent);
+    check(rank != 0 || from_parent == 10, "the reduce from the parent was wrong");
+
+    int total = 0;
+    MPI_Allreduce(&rank, &total, 1, MPI_INT, MPI_SUM, parent);
+    check(total == 10, "the allreduce received the wrong sum");
+
+    int mine = 100 + rank;
+    MPI_Gather(&mine, 1, MPI_INT, NULL, 0, MPI_INT, 0, parent);
+
+    int parents = 0;
+    mine = 200 + rank;
+    MPI_Allgather(&mine, 1, MPI_INT, &parents, 1, MPI_INT, parent);
+    check(parents == 10, "the allgather was wrong");
+
+    int out = 400 + rank, in = 0;
+    MPI_Alltoall(&out, 1, MPI_INT, &in, 1, MPI_INT, parent);
+    check(in == 300 + rank, "the alltoall was wrong");
+
+    int prefix;
+    check(MPI_Scan(&rank, &prefix, 1, MPI_INT, MPI_SUM, parent) == MPI_ERR_COMM,
+          "the scan on an intercomm didn't fail");
+
+    MPI_Barrier(parent);
+}
+
+int main(int argc, char **argv) {
+    MPI_Init(&argc, &argv);
+    MPI_Comm_rank(MPI_COMM_WORLD, &rank);
+
+    MPI_Comm parent_comm;
+    MPI_Comm_get_parent(&parent_comm);
+    is_child = parent_comm != MPI_COMM_NULL;
+
+    if (is_child) {
+        child(parent_comm);
+        printf("Child %d received its configuration\n", rank);
+    } else {
+        // Each rank launches a job of children of its own.
+        MPI_Comm children;
+        char *no_args[] = {NULL};
+        int errors[CHILDREN];
+        MPI_Comm_spawn(argv[0], no_args, CHILDREN, MPI_INFO_NULL, 0, MPI_COMM_SELF, &children,
+                       errors);
+
+        int flag;
+        MPI_Comm_test_inter(children, &flag);
+        check(flag, "MPI_Comm_spawn didn't return an intercomm");
+
+        parent(children);
+        MPI_Comm_free(&children);
+        printf("Parent %d configured %d children\n", rank, CHILDREN);
+    }
+
+    MPI_Finalize();
+    return 0;
+}
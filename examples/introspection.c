@@ -0,0 +1,243 @@
+#include <mpi.h>
+#include <stddef.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+static int rank;
+
+static void check(int condition, const char *what) {
+    if (!condition) {
+        printf("Rank %d: %s\n", rank, what);
+        exit(EXIT_FAILURE);
+    }
+}
+
+// Works out the size of a datatype from how it was made, the way a profiling tool would.
+static MPI_Aint decoded_size(MPI_Datatype datatype) {
+    int num_integers, num_addresses, num_datatypes, combiner;
+    MPI_Type_get_envelope(datatype, &num_integers, &num_addresses, &num_datatypes, &combiner);
+    if (combiner == MPI_COMBINER_NAMED) {
+        int size;
+        MPI_Type_size(datatype, &size);
+        return size;
+    }
+
+    int *integers = malloc((num_integers + 1) * sizeof(int));
+    MPI_Aint *addresses = malloc((num_addresses + 1) * sizeof(MPI_Aint));
+    MPI_Datatype *datatypes = malloc((num_datatypes + 1) * sizeof(MPI_Datatype));
+    int error = MPI_Type_get_contents(datatype, num_integers, num_addresses, num_datatypes,
+                                      integers, addresses, datatypes);
+    check(error == MPI_SUCCESS, "MPI_Type_get_contents failed");
+
+    MPI_Aint size = 0, old = decoded_size(datatypes[0]);
+    switch (combiner) {
+    case MPI_COMBINER_DUP:
+    case MPI_COMBINER_RESIZED:
+        size = old;
+        break;
+    case MPI_COMBINER_CONTIGUOUS:
+        size = integers[0] * old;
+        break;
+    case MPI_COMBINER_VECTOR:
+    case MPI_COMBINER_HVECTOR:
+        size = integers[0] * integers[1] * old;
+        break;
+    case MPI_COMBINER_INDEXED:
+    case MPI_COMBINER_HINDEXED:
+        for (int i = 0; i < integers[0]; i++) {
+            size += integers[1 + i] * old;
+        }
+        break;
+    case MPI_COMBINER_INDEXED_BLOCK:
+        size = integers[0] * integers[1] * old;
+        break;
+    case MPI_COMBINER_STRUCT:
+        for (int i = 0; i < integers[0]; i++) {
+            size += integers[1 + i] * (i == 0 ? old : decoded_size(datatypes[i]));
+        }
+        break;
+    case MPI_COMBINER_SUBARRAY:
+        size = old;
+        for (int i = 0; i < integers[0]; i++) {
+            size *= integers[1 + integers[0] + i];
+        }
+        break;
+    default:
+        check(0, "an unexpected combiner was returned");
+    }
+
+    // Derived datatypes come back as new handles, and predefined ones as themselves.
+    for (int i = 0; i < num_datatypes; i++) {
+        int inner_integers, inner_addresses, inner_datatypes, inner_combiner;
+        MPI_Type_get_envelope(datatypes[i], &inner_integers, &inner_addresses, &inner_datatypes,
+                              &inner_combiner);
+        if (inner_combiner != MPI_COMBINER_NAMED) {
+            MPI_Type_free(&datatypes[i]);
+        }
+    }
+    free(integers);
+    free(addresses);
+    free(datatypes);
+    return size;
+}
+
+static void check_decodes(MPI_Datatype datatype, const char *what) {
+    int size;
+    MPI_Type_size(datatype, &size);
+    if (decoded_size(datatype) != size) {
+        printf("Rank %d: %s did not decode to its size\n", rank, what);
+        exit(EXIT_FAILURE);
+    }
+}
+
+struct record {
+    int id;
+    double values[3];
+    char name[5];
+};
+
+int main(int argc, char **argv) {
+    MPI_Init(&argc, &argv);
+    MPI_Comm_rank(MPI_COMM_WORLD, &rank);
+
+    // A struct type built from addresses.
+    struct record records[2];
+    MPI_Aint base, addresses[3];
+    MPI_Get_address(&records[0], &base);
+    MPI_Get_address(&records[0].id, &addresses[0]);
+    MPI_Get_address(records[0].values, &addresses[1]);
+    MPI_Get_address(records[0].name, &addresses[2]);
+    for (int i = 0; i < 3; i++) {
+        addresses[i] = MPI_Aint_diff(addresses[i], base);
+    }
+    check(addresses[1] == offsetof(struct record, values), "MPI_Aint_diff was wrong");
+    check(MPI_Aint_add(base, addresses[2]) == (MPI_Aint)records[0].name, "MPI_Aint_add was wrong");
+
+    int blocklengths[3] = {1, 3, 5};
+    MPI_Datatype types[3] = {MPI_INT, MPI_DOUBLE, MPI_CHAR}, record_type;
+    MPI_Type_create_struct(3, blocklengths, addresses, types, &record_type);
+
+    int size;
+    MPI_Aint lb, extent, true_lb, true_extent;
+    MPI_Type_size(record_type, &size);
+    MPI_Type_get_extent(record_type, &lb, &extent);
+    MPI_Type_get_true_extent(record_type, &true_lb, &true_extent);
+    check(size == sizeof(int) + 3 * sizeof(double) + 5, "the struct has the wrong size");
+    check(lb == 0 && extent == sizeof(struct record), "the struct has the wrong extent");
+    check(true_lb == 0 && true_extent == offsetof(struct record, name) + 5,
+          "the struct has the wrong true extent");
+
+    int num_integers, num_addresses, num_datatypes, combiner;
+    MPI_Type_get_envelope(record_type, &num_integers, &num_addresses, &num_datatypes, &combiner);
+    check(combiner == MPI_COMBINER_STRUCT && num_integers == 4 && num_addresses == 3 &&
+              num_datatypes == 3,
+          "the struct has the wrong envelope");
+
+    int integers[4];
+    MPI_Aint displacements[3];
+    MPI_Datatype datatypes[3];
+    MPI_Type_get_contents(record_type, 4, 3, 3, integers, displacements, datatypes);
+    check(integers[0] == 3 && integers[1] == 1 && integers[2] == 3 && integers[3] == 5,
+          "the struct has the wrong integers");
+    check(memcmp(displacements, addresses, sizeof(addresses)) == 0,
+          "the struct has the wrong displacements");
+    check(datatypes[0] == MPI_INT && datatypes[1] == MPI_DOUBLE && datatypes[2] == MPI_CHAR,
+          "predefined datatypes did not come back as themselves");
+    check(MPI_Type_get_contents(record_type, 3, 3, 3, integers, displacements, datatypes) ==
+              MPI_ERR_ARG,
+          "too small an array of integers was accepted");
+
+    // Predefined datatypes are named and have no contents.
+    MPI_Type_get_envelope(MPI_DOUBLE_INT, &num_integers, &num_addresses, &num_datatypes,
+                          &combiner);
+    check(combiner == MPI_COMBINER_NAMED && num_integers == 0 && num_datatypes == 0,
+          "a predefined datatype is not named");
+    check(MPI_Type_get_contents(MPI_INT, 0, 0, 0, NULL, NULL, NULL) != MPI_SUCCESS,
+          "a predefined datatype has contents");
+
+    // Nested derived datatypes decode all the way down.
+    MPI_Datatype vector, hvector, indexed, hindexed, block, subarray, resized, dup, nested;
+    int indices[3] = {4, 0, 9}, lengths[3] = {1, 2, 3};
+    MPI_Aint byte_indices[2] = {0, 64};
+    int sizes[2] = {6, 7}, subsizes[2] = {2, 3}, starts[2] = {1, 1};
+    MPI_Type_vector(3, 2, 4, MPI_INT, &vector);
+    MPI_Type_create_hvector(2, 1, 100, vector, &hvector);
+    MPI_Type_indexed(3, lengths, indices, hvector, &indexed);
+    MPI_Type_create_hindexed(2, lengths, byte_indices, MPI_SHORT, &hindexed);
+    MPI_Type_create_indexed_block(3, 2, indices, record_type, &block);
+    MPI_Type_create_subarray(2, sizes, subsizes, starts, MPI_ORDER_FORTRAN, hindexed, &subarray);
+    MPI_Type_create_resized(subarray, -8, 1000, &resized);
+    MPI_Type_dup(resized, &dup);
+    MPI_Datatype parts[2] = {dup, block};
+    int part_lengths[2] = {2, 1};
+    MPI_Aint part_displacements[2] = {0, 4096};
+    MPI_Type_create_struct(2, part_lengths, part_displacements, parts, &nested);
+
+    check_decodes(vector, "a vector");
+    check_decodes(indexed, "an indexed type of hvectors");
+    check_decodes(block, "an indexed block of structs");
+    check_decodes(subarray, "a subarray");
+    check_decodes(nested, "a struct of everything");
+
+    MPI_Type_get_envelope(dup, &num_integers, &num_addresses, &num_datatypes, &combiner);
+    check(combiner == MPI_COMBINER_DUP && num_datatypes == 1, "a duplicate has the wrong envelope");
+    MPI_Type_get_extent(dup, &lb, &extent);
+    check(lb == -8 && extent == 1000, "a duplicate has the wrong extent");
+
+    MPI_Type_get_envelope(subarray, &num_integers, &num_addresses, &num_datatypes, &combiner);
+    check(combiner == MPI_COMBINER_SUBARRAY && num_integers == 8,
+          "a subarray has the wrong envelope");
+    int subarray_integers[8];
+    MPI_Type_get_contents(subarray, 8, 0, 1, subarray_integers, NULL, datatypes);
+    int expected[8] = {2, 6, 7, 2, 3, 1, 1, MPI_ORDER_FORTRAN};
+    check(memcmp(subarray_integers, expected, sizeof(expected)) == 0,
+          "a subarray has the wrong integers");
+    MPI_Type_free(&datatypes[0]);
+
+    int gsizes[2] = {8, 6}, distribs[2] = {MPI_DISTRIBUTE_BLOCK, MPI_DISTRIBUTE_CYCLIC};
+    int dargs[2] = {MPI_DISTRIBUTE_DFLT_DARG, 2}, psizes[2] = {2, 2};
+    MPI_Datatype darray;
+    MPI_Type_create_darray(4, 3, 2, gsizes, distribs, dargs, psizes, MPI_ORDER_C, MPI_FLOAT,
+                           &darray);
+    int darray_integers[12];
+    MPI_Type_get_envelope(darray, &num_integers, &num_addresses, &num_datatypes, &combiner);
+    check(combiner == MPI_COMBINER_DARRAY && num_integers == 12, "a darray has the wrong envelope");
+    MPI_Type_get_contents(darray, 12, 0, 1, darray_integers, NULL, datatypes);
+    int expected_darray[12] = {4, 3, 2, 8, 6, MPI_DISTRIBUTE_BLOCK, MPI_DISTRIBUTE_CYCLIC,
+                               MPI_DISTRIBUTE_DFLT_DARG, 2, 2, 2, MPI_ORDER_C};
+    check(memcmp(darray_integers, expected_darray, sizeof(expected_darray)) == 0,
+          "a darray has the wrong integers");
+    check(datatypes[0] == MPI_FLOAT, "a darray has the wrong old type");
+
+    // A duplicate of a committed datatype is committed.
+    MPI_Datatype committed, committed_dup;
+    MPI_Type_contiguous(2, MPI_INT, &committed);
+    MPI_Type_commit(&committed);
+    MPI_Type_dup(committed, &committed_dup);
+    int pair[2] = {1, 2};
+    check(MPI_Send(pair, 1, committed_dup, MPI_PROC_NULL, 0, MPI_COMM_WORLD) == MPI_SUCCESS,
+          "a duplicate of a committed datatype is not committed");
+
+    // Matching sizes finds the C types.
+    MPI_Datatype matched;
+    MPI_Type_match_size(MPI_TYPECLASS_INTEGER, sizeof(int), &matched);
+    check(matched == MPI_INT, "no int matched");
+    MPI_Type_match_size(MPI_TYPECLASS_REAL, sizeof(double), &matched);
+    check(matched == MPI_DOUBLE, "no double matched");
+    MPI_Type_match_size(MPI_TYPECLASS_COMPLEX, 2 * sizeof(float), &matched);
+    check(matched == MPI_C_FLOAT_COMPLEX, "no float complex matched");
+    check(MPI_Type_match_size(MPI_TYPECLASS_REAL, 3, &matched) == MPI_ERR_ARG,
+          "a three byte real matched");
+
+    MPI_Datatype all[] = {record_type, vector, hvector, indexed, hindexed, block, subarray,
+                          resized, dup, nested, darray, committed, committed_dup};
+    for (int i = 0; i < sizeof(all) / sizeof(all[0]); i++) {
+        MPI_Type_free(&all[i]);
+    }
+
+    printf("Rank %d: introspection passed\n", rank);
+
+    MPI_Finalize();
+    return 0;
+}
@@ -0,0 +1,164 @@
+#include <mpi.h>
+#include <limits.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+static int rank;
+
+static void check(int condition, const char *what) {
+    if (!condition) {
+        printf("Rank %d: %s\n", rank, what);
+        exit(EXIT_FAILURE);
+    }
+}
+
+int main(int argc, char **argv) {
+    MPI_Init(&argc, &argv);
+
+    int size;
+    MPI_Comm_rank(MPI_COMM_WORLD, &rank);
+    MPI_Comm_size(MPI_COMM_WORLD, &size);
+
+    // Datatypes describing more than 2^31 bytes only cost their description, so they can be made
+    // and measured without the memory to back them.
+    const MPI_Count huge = 3000000000LL;
+    MPI_Datatype bytes;
+    MPI_Type_contiguous_c(huge, MPI_BYTE, &bytes);
+
+    int small_size;
+    MPI_Count count, lb, extent;
+    MPI_Type_size(bytes, &small_size);
+    check(small_size == MPI_UNDEFINED, "a size too large for an int was returned");
+    MPI_Type_size_c(bytes, &count);
+    check(count == huge, "MPI_Type_size_c did not return the size");
+    MPI_Type_get_extent_c(bytes, &lb, &extent);
+    check(lb == 0 && extent == huge, "MPI_Type_get_extent_c did not return the extent");
+    MPI_Type_get_true_extent_c(bytes, &lb, &extent);
+    check(lb == 0 && extent == huge, "MPI_Type_get_true_extent_c did not return the extent");
+    MPI_Pack_size_c(2, bytes, MPI_COMM_WORLD, &count);
+    check(count == 2 * huge, "MPI_Pack_size_c did not return the size");
+    MPI_Pack_external_size_c("external32", 2, bytes, &count);
+    check(count == 2 * huge, "MPI_Pack_external_size_c did not return the size");
+
+    // A count that doesn't fit in an int can only be described as a large count.
+    MPI_Count integers, addresses, large_counts, datatypes;
+    int combiner, small_integers, small_addresses, small_datatypes;
+    check(MPI_Type_get_envelope(bytes, &small_integers, &small_addresses, &small_datatypes,
+                                &combiner) != MPI_SUCCESS,
+          "MPI_Type_get_envelope described a large count");
+    MPI_Type_get_envelope_c(bytes, &integers, &addresses, &large_counts, &datatypes, &combiner);
+    check(combiner == MPI_COMBINER_CONTIGUOUS && integers == 0 && addresses == 0 &&
+              large_counts == 1 && datatypes == 1,
+          "the envelope of a large contiguous datatype is wrong");
+
+    MPI_Count contents[1];
+    MPI_Datatype old;
+    MPI_Type_get_contents_c(bytes, 0, 0, 1, 1, NULL, NULL, contents, &old);
+    check(contents[0] == huge && old == MPI_BYTE, "the large count was not returned");
+
+    // Subarrays keep ndims and the order as integers.
+    MPI_Datatype subarray;
+    MPI_Count sizes[2] = {huge, 4}, subsizes[2] = {2, 3}, starts[2] = {huge - 2, 1};
+    MPI_Type_create_subarray_c(2, sizes, subsizes, starts, MPI_ORDER_C, MPI_INT, &subarray);
+    MPI_Type_get_envelope_c(subarray, &integers, &addresses, &large_counts, &datatypes,
+                            &combiner);
+    check(combiner == MPI_COMBINER_SUBARRAY && integers == 2 && addresses == 0 &&
+              large_counts == 6 && datatypes == 1,
+          "the envelope of a large subarray is wrong");
+    int subarray_integers[2];
+    MPI_Count subarray_counts[6];
+    MPI_Type_get_contents_c(subarray, 2, 0, 6, 1, subarray_integers, NULL, subarray_counts,
+                            &old);
+    check(subarray_integers[0] == 2 && subarray_integers[1] == MPI_ORDER_C &&
+              subarray_counts[0] == huge && subarray_counts[3] == 3 &&
+              subarray_counts[4] == huge - 2 && old == MPI_INT,
+          "the contents of a large subarray are wrong");
+    MPI_Type_free(&subarray);
+    MPI_Type_free(&bytes);
+
+    // Datatypes that fit keep the classic layout.
+    MPI_Datatype vector;
+    MPI_Type_vector_c(3, 2, 4, MPI_INT, &vector);
+    MPI_Type_commit(&vector);
+    MPI_Type_get_envelope_c(vector, &integers, &addresses, &large_counts, &datatypes, &combiner);
+    check(combiner == MPI_COMBINER_VECTOR && integers == 3 && large_counts == 0,
+          "a small vector did not have the classic layout");
+
+    MPI_Datatype hvector;
+    MPI_Type_create_hvector_c(2, 1, 3 * sizeof(double), MPI_DOUBLE, &hvector);
+    MPI_Type_commit(&hvector);
+    MPI_Type_get_extent_c(hvector, &lb, &extent);
+    check(lb == 0 && extent == 4 * sizeof(double), "the extent of an hvector is wrong");
+
+    int partner = rank ^ 1;
+    if (partner < size) {
+        int sent[12], received[12];
+        for (int i = 0; i < 12; i++) {
+            sent[i] = rank * 100 + i;
+            received[i] = -1;
+        }
+
+        MPI_Status status;
+        MPI_Sendrecv_c(sent, 1, vector, partner, 0, received, 6, MPI_INT, partner, 0,
+                       MPI_COMM_WORLD, &status);
+        MPI_Get_count_c(&status, MPI_INT, &count);
+        check(count == 6, "MPI_Get_count_c did not count the ints");
+        MPI_Get_elements_c(&status, vector, &count);
+        check(count == 6, "MPI_Get_elements_c did not count the ints");
+        check(received[0] == partner * 100 && received[2] == partner * 100 + 4 &&
+                  received[5] == partner * 100 + 9,
+              "the vector was not received");
+
+        MPI_Request requests[2];
+        MPI_Isend_c(sent, 12, MPI_INT, partner, 1, MPI_COMM_WORLD, &requests[0]);
+        MPI_Irecv_c(received, 12, MPI_INT, partner, 1, MPI_COMM_WORLD, &requests[1]);
+        MPI_Waitall(2, requests, MPI_STATUSES_IGNORE);
+        check(received[11] == partner * 100 + 11, "the nonblocking receive was wrong");
+
+        // Buffered sends through a buffer attached with a large count.
+        MPI_Count buffer_size = 1024;
+        void *buffer = malloc(buffer_size);
+        MPI_Buffer_attach_c(buffer, buffer_size);
+        MPI_Bsend_c(sent, 3, MPI_INT, partner, 2, MPI_COMM_WORLD);
+        MPI_Recv_c(received, 3, MPI_INT, partner, 2, MPI_COMM_WORLD, MPI_STATUS_IGNORE);
+        void *detached;
+        MPI_Buffer_detach_c(&detached, &buffer_size);
+        check(detached == buffer && buffer_size == 1024, "the buffer was not detached");
+        free(buffer);
+        check(received[2] == partner * 100 + 2, "the buffered send was wrong");
+
+        // A buffer too large for an int can only be detached through the large count variant.
+        buffer_size = (MPI_Count)INT_MAX + 1;
+        buffer = malloc(buffer_size);
+        MPI_Buffer_attach_c(buffer, buffer_size);
+        int small_size;
+        check(MPI_Buffer_detach(&detached, &small_size) == MPI_ERR_COUNT,
+              "MPI_Buffer_detach reported a size larger than an int");
+        MPI_Buffer_detach_c(&detached, &buffer_size);
+        check(detached == buffer && buffer_size == (MPI_Count)INT_MAX + 1,
+              "the large buffer was not detached");
+        free(buffer);
+    }
+
+    // Positions in packed buffers are large counts too.
+    char packed[64];
+    double values[4] = {1.5, 2.5, 3.5, 4.5}, unpacked[4] = {0};
+    MPI_Count position = 0;
+    MPI_Pack_c(values, 1, hvector, packed, sizeof(packed), &position, MPI_COMM_WORLD);
+    check(position == 2 * sizeof(double), "MPI_Pack_c did not advance the position");
+    position = 0;
+    MPI_Unpack_c(packed, sizeof(packed), &position, unpacked, 2, MPI_DOUBLE, MPI_COMM_WORLD);
+    check(unpacked[0] == 1.5 && unpacked[1] == 4.5, "MPI_Unpack_c did not unpack the values");
+
+    position = 0;
+    MPI_Pack_external_c("external32", values, 4, MPI_DOUBLE, packed, sizeof(packed),
+                        &position);
+    check(position == 4 * 8, "MPI_Pack_external_c did not advance the position");
+
+    MPI_Type_free(&hvector);
+    MPI_Type_free(&vector);
+
+    printf("Rank %d: large counts passed\n", rank);
+    MPI_Finalize();
+    return 0;
+}
@@ -0,0 +1,87 @@
+#include <mpi.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+int main(int argc, char **argv) {
+    MPI_Init(&argc, &argv);
+
+    int rank, size;
+    MPI_Comm_rank(MPI_COMM_WORLD, &rank);
+    MPI_Comm_size(MPI_COMM_WORLD, &size);
+
+    int next = (rank + 1) % size;
+    int prev = (rank + size - 1) % size;
+
+    // Halo exchange with both neighbours.
+    int left = -1, right = -1;
+    MPI_Request requests[4];
+    MPI_Irecv(&left, 1, MPI_INT, prev, 0, MPI_COMM_WORLD, &requests[0]);
+    MPI_Irecv(&right, 1, MPI_INT, next, 1, MPI_COMM_WORLD, &requests[1]);
+    MPI_Isend(&rank, 1, MPI_INT, next, 0, MPI_COMM_WORLD, &requests[2]);
+    MPI_Isend(&rank, 1, MPI_INT, prev, 1, MPI_COMM_WORLD, &requests[3]);
+
+    MPI_Status statuses[4];
+    MPI_Waitall(4, requests, statuses);
+
+    for (int i = 0; i < 4; i++) {
+        if (requests[i] != MPI_REQUEST_NULL) {
+            printf("Rank %d: request %d was not freed by MPI_Waitall\n", rank, i);
+            exit(EXIT_FAILURE);
+        }
+    }
+
+    if (left != prev || right != next) {
+        printf("Rank %d: received left = %d, right = %d\n", rank, left, right);
+        exit(EXIT_FAILURE);
+    }
+
+    // Poll a receive until it completes.
+    int value = -1;
+    MPI_Request request;
+    MPI_Irecv(&value, 1, MPI_INT, prev, 2, MPI_COMM_WORLD, &request);
+    MPI_Send(&rank, 1, MPI_INT, next, 2, MPI_COMM_WORLD);
+
+    int flag = 0;
+    MPI_Status status;
+    while (!flag) {
+        MPI_Test(&request, &flag, &status);
+    }
+
+    if (value != prev || status.MPI_SOURCE != prev || status.MPI_TAG != 2) {
+        printf("Rank %d: MPI_Test completed with value = %d, source = %d\n", rank, value, status.MPI_SOURCE);
+        exit(EXIT_FAILURE);
+    }
+
+    // Null requests are ignored, and a list of only null requests completes with MPI_UNDEFINED.
+    MPI_Request nulls[2] = {MPI_REQUEST_NULL, MPI_REQUEST_NULL};
+    int index;
+    MPI_Waitany(2, nulls, &index, MPI_STATUS_IGNORE);
+    if (index != MPI_UNDEFINED) {
+        printf("Rank %d: MPI_Waitany on null requests returned index %d\n", rank, index);
+        exit(EXIT_FAILURE);
+    }
+
+    MPI_Request mixed[2] = {MPI_REQUEST_NULL, MPI_REQUEST_NULL};
+    MPI_Irecv(&value, 1, MPI_INT, prev, 3, MPI_COMM_WORLD, &mixed[1]);
+    MPI_Send(&rank, 1, MPI_INT, next, 3, MPI_COMM_WORLD);
+
+    MPI_Waitany(2, mixed, &index, &status);
+    if (index != 1 || mixed[1] != MPI_REQUEST_NULL) {
+        printf("Rank %d: MPI_Waitany completed index %d\n", rank, index);
+        exit(EXIT_FAILURE);
+    }
+
+    int outcount;
+    int indices[2];
+    MPI_Waitsome(2, mixed, &outcount, indices, MPI_STATUSES_IGNORE);
+    if (outcount != MPI_UNDEFINED) {
+        printf("Rank %d: MPI_Waitsome on null requests returned outcount %d\n", rank, outcount);
+        exit(EXIT_FAILURE);
+    }
+
+    if (rank == 0) {
+        printf("Nonblocking exchange across %d ranks succeeded\n", size);
+    }
+
+    MPI_Finalize();
+}
@@ -0,0 +1,132 @@
+#include <mpi.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+// Large enough that the messages of the collectives go by rendezvous.
+#define LARGE_COUNT (1 << 16)
+
+static int rank;
+
+static void check(int condition, const char *what) {
+    if (!condition) {
+        printf("Rank %d: %s\n", rank, what);
+        exit(EXIT_FAILURE);
+    }
+}
+
+int main(int argc, char **argv) {
+    MPI_Init(&argc, &argv);
+
+    int size;
+    MPI_Comm_rank(MPI_COMM_WORLD, &rank);
+    MPI_Comm_size(MPI_COMM_WORLD, &size);
+
+    // Poll a barrier until every rank has reached it.
+    MPI_Request barrier;
+    MPI_Ibarrier(MPI_COMM_WORLD, &barrier);
+    int done = 0, polls = 0;
+    while (!done) {
+        MPI_Test(&barrier, &done, MPI_STATUS_IGNORE);
+        polls++;
+    }
+    check(barrier == MPI_REQUEST_NULL, "MPI_Test did not free the barrier");
+
+    // Several collectives in flight at once, completed in the opposite order to how they began.
+    int config[3] = {-1, -1, -1};
+    if (rank == 0) {
+        config[0] = 7, config[1] = 8, config[2] = 9;
+    }
+    double *field = malloc(LARGE_COUNT * sizeof(double));
+    double *sum = malloc(LARGE_COUNT * sizeof(double));
+    for (int i = 0; i < LARGE_COUNT; i++) {
+        field[i] = rank + i;
+    }
+    int largest = -1;
+
+    MPI_Request requests[3];
+    MPI_Ibcast(config, 3, MPI_INT, 0, MPI_COMM_WORLD, &requests[0]);
+    MPI_Iallreduce(field, sum, LARGE_COUNT, MPI_DOUBLE, MPI_SUM, MPI_COMM_WORLD, &requests[1]);
+    MPI_Ireduce(&rank, &largest, 1, MPI_INT, MPI_MAX, size - 1, MPI_COMM_WORLD, &requests[2]);
+    for (int i = 2; i >= 0; i--) {
+        MPI_Wait(&requests[i], MPI_STATUS_IGNORE);
+    }
+
+    int rank_sum = size * (size - 1) / 2;
+    check(config[0] == 7 && config[2] == 9, "MPI_Ibcast did not deliver the root's values");
+    check(sum[0] == rank_sum && sum[LARGE_COUNT - 1] == rank_sum + (double)size * (LARGE_COUNT - 1),
+          "MPI_Iallreduce was wrong");
+    check(rank != size - 1 || largest == size - 1, "MPI_Ireduce was wrong");
+
+    // Gather and scatter, with the root in place.
+    int *all = malloc(size * sizeof(int));
+    int mine = 100 + rank;
+    if (rank == 0) {
+        all[0] = mine;
+    }
+    MPI_Request gather;
+    MPI_Igather(rank == 0 ? MPI_IN_PLACE : &mine, 1, MPI_INT, all, 1, MPI_INT, 0, MPI_COMM_WORLD,
+                &gather);
+    // A collective can't be freed before it completes, and the request stays usable.
+    check(MPI_Request_free(&gather) == MPI_ERR_REQUEST, "MPI_Request_free freed a collective");
+    MPI_Wait(&gather, MPI_STATUS_IGNORE);
+    if (rank == 0) {
+        for (int i = 0; i < size; i++) {
+            check(all[i] == 100 + i, "MPI_Igather was wrong");
+            all[i] *= 2;
+        }
+    }
+
+    MPI_Request scatter;
+    mine = -1;
+    MPI_Iscatter_c(all, 1, MPI_INT, rank == 0 ? MPI_IN_PLACE : &mine, 1, MPI_INT, 0,
+                   MPI_COMM_WORLD, &scatter);
+    MPI_Wait(&scatter, MPI_STATUS_IGNORE);
+    check(rank == 0 ? all[0] == 200 : mine == 2 * (100 + rank), "MPI_Iscatter_c was wrong");
+
+    // Rank i sends i + j to rank j, alongside an allgather of the ranks.
+    int *out = malloc(size * sizeof(int));
+    int *in = malloc(size * sizeof(int));
+    int *ranks = malloc(size * sizeof(int));
+    for (int j = 0; j < size; j++) {
+        out[j] = rank + j;
+    }
+    MPI_Ialltoall(out, 1, MPI_INT, in, 1, MPI_INT, MPI_COMM_WORLD, &requests[0]);
+    MPI_Iallgather(&rank, 1, MPI_INT, ranks, 1, MPI_INT, MPI_COMM_WORLD, &requests[1]);
+    MPI_Waitall(2, requests, MPI_STATUSES_IGNORE);
+    for (int j = 0; j < size; j++) {
+        check(in[j] == j + rank, "MPI_Ialltoall was wrong");
+        check(ranks[j] == j, "MPI_Iallgather was wrong");
+    }
+
+    // Prefix sums that overlap with a reduce-scatter.
+    int prefix = -1, offset = -1, block = -1;
+    for (int j = 0; j < size; j++) {
+        out[j] = rank * j;
+    }
+    MPI_Request prefixes[3];
+    MPI_Iscan(&rank, &prefix, 1, MPI_INT, MPI_SUM, MPI_COMM_WORLD, &prefixes[0]);
+    MPI_Iexscan(&rank, &offset, 1, MPI_INT, MPI_SUM, MPI_COMM_WORLD, &prefixes[1]);
+    MPI_Ireduce_scatter_block(out, &block, 1, MPI_INT, MPI_SUM, MPI_COMM_WORLD, &prefixes[2]);
+    MPI_Waitall(3, prefixes, MPI_STATUSES_IGNORE);
+    check(prefix == rank * (rank + 1) / 2, "MPI_Iscan was wrong");
+    check(rank == 0 || offset == rank * (rank - 1) / 2, "MPI_Iexscan was wrong");
+    check(block == rank_sum * rank, "MPI_Ireduce_scatter_block was wrong");
+
+    MPI_Request undefined;
+    char letter = 'a';
+    check(MPI_Iallreduce(&letter, &letter, 1, MPI_CHAR, MPI_SUM, MPI_COMM_WORLD, &undefined) ==
+              MPI_ERR_OP,
+          "MPI_SUM of characters was allowed");
+
+    free(ranks);
+    free(in);
+    free(out);
+    free(all);
+    free(sum);
+    free(field);
+
+    printf("Rank %d: nonblocking collectives passed after polling the barrier %d times\n", rank,
+           polls);
+    MPI_Finalize();
+    return 0;
+}
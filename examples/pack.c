@@ -0,0 +1,151 @@
+#include <mpi.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+static void check(int condition, int rank, const char *what) {
+    if (!condition) {
+        printf("Rank %d: %s\n", rank, what);
+        exit(EXIT_FAILURE);
+    }
+}
+
+int main(int argc, char **argv) {
+    MPI_Init(&argc, &argv);
+
+    int rank, size;
+    MPI_Comm_rank(MPI_COMM_WORLD, &rank);
+    MPI_Comm_size(MPI_COMM_WORLD, &size);
+
+    // A header followed by a column of a matrix, packed into one message and sent as MPI_PACKED.
+    double matrix[4][5];
+    for (int i = 0; i < 4; i++) {
+        for (int j = 0; j < 5; j++) {
+            matrix[i][j] = i * 10 + j;
+        }
+    }
+    MPI_Datatype column;
+    MPI_Type_vector(4, 1, 5, MPI_DOUBLE, &column);
+    MPI_Type_commit(&column);
+
+    int header[2] = {rank, 4}, header_size, column_size;
+    MPI_Pack_size(2, MPI_INT, MPI_COMM_WORLD, &header_size);
+    MPI_Pack_size(1, column, MPI_COMM_WORLD, &column_size);
+    check(header_size == 2 * sizeof(int) && column_size == 4 * sizeof(double), rank,
+          "MPI_Pack_size was wrong");
+
+    char packed[64];
+    int position = 0;
+    MPI_Pack(header, 2, MPI_INT, packed, sizeof(packed), &position, MPI_COMM_WORLD);
+    MPI_Pack(&matrix[0][2], 1, column, packed, sizeof(packed), &position, MPI_COMM_WORLD);
+    check(position == header_size + column_size, rank, "MPI_Pack moved to the wrong position");
+
+    int partner = rank ^ 1;
+    if (partner < size) {
+        // Both ranks pack the same amount of data.
+        char received[64];
+        int count = position;
+        MPI_Sendrecv(packed, position, MPI_PACKED, partner, 0, received, count, MPI_PACKED,
+                     partner, 0, MPI_COMM_WORLD, MPI_STATUS_IGNORE);
+
+        int received_header[2], unpacked = 0;
+        double result[4][5] = {{0}};
+        MPI_Unpack(received, count, &unpacked, received_header, 2, MPI_INT, MPI_COMM_WORLD);
+        check(received_header[0] == partner && received_header[1] == 4, rank,
+              "the header was unpacked wrongly");
+        MPI_Unpack(received, count, &unpacked, &result[0][4], 1, column, MPI_COMM_WORLD);
+        check(unpacked == count, rank, "MPI_Unpack moved to the wrong position");
+        for (int i = 0; i < 4; i++) {
+            check(result[i][4] == i * 10 + 2 && result[i][3] == 0, rank,
+                  "the column was unpacked wrongly");
+        }
+    }
+
+    // Packing beyond the end of the buffer fails without moving, and so does unpacking.
+    int full = position;
+    check(MPI_Pack(header, 2, MPI_INT, packed, full + 4, &position, MPI_COMM_WORLD) ==
+              MPI_ERR_TRUNCATE && position == full,
+          rank, "packing past the end of the buffer did not fail");
+    position = full - 4;
+    check(MPI_Unpack(packed, full, &position, header, 2, MPI_INT, MPI_COMM_WORLD) ==
+              MPI_ERR_TRUNCATE,
+          rank, "unpacking past the end of the buffer did not fail");
+
+    // external32 is big-endian with fixed sizes: a long is 4 bytes and a long double 16.
+    {
+        int value = 0x01020304;
+        long along = -2;
+        double adouble = 1.0;
+        long double along_double = -1.5L;
+        short shorts[3] = {1, -1, 256};
+
+        MPI_Aint external_size;
+        MPI_Pack_external_size("external32", 1, MPI_LONG, &external_size);
+        check(external_size == 4, rank, "external32 longs are not 4 bytes");
+        MPI_Pack_external_size("external32", 2, MPI_LONG_DOUBLE, &external_size);
+        check(external_size == 32, rank, "external32 long doubles are not 16 bytes");
+        MPI_Pack_external_size("external32", 1, column, &external_size);
+        check(external_size == 32, rank, "MPI_Pack_external_size was wrong for a vector");
+
+        unsigned char buffer[64];
+        MPI_Aint at = 0;
+        MPI_Pack_external("external32", &value, 1, MPI_INT, buffer, sizeof(buffer), &at);
+        MPI_Pack_external("external32", &along, 1, MPI_LONG, buffer, sizeof(buffer), &at);
+        MPI_Pack_external("external32", &adouble, 1, MPI_DOUBLE, buffer, sizeof(buffer), &at);
+        MPI_Pack_external("external32", &along_double, 1, MPI_LONG_DOUBLE, buffer,
+                          sizeof(buffer), &at);
+        MPI_Pack_external("external32", shorts, 3, MPI_SHORT, buffer, sizeof(buffer), &at);
+        check(at == 4 + 4 + 8 + 16 + 6, rank, "MPI_Pack_external moved to the wrong position");
+
+        unsigned char expected[] = {
+            0x01, 0x02, 0x03, 0x04,
+            0xff, 0xff, 0xff, 0xfe,
+            0x3f, 0xf0, 0, 0, 0, 0, 0, 0,
+            0xbf, 0xff, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0x00, 0x01, 0xff, 0xff, 0x01, 0x00,
+        };
+        check(memcmp(buffer, expected, sizeof(expected)) == 0, rank,
+              "external32 data is not canonical");
+
+        int value_back;
+        long long_back;
+        double double_back;
+        long double long_double_back;
+        short shorts_back[3];
+        at = 0;
+        MPI_Unpack_external("external32", buffer, sizeof(expected), &at, &value_back, 1, MPI_INT);
+        MPI_Unpack_external("external32", buffer, sizeof(expected), &at, &long_back, 1, MPI_LONG);
+        MPI_Unpack_external("external32", buffer, sizeof(expected), &at, &double_back, 1,
+                            MPI_DOUBLE);
+        MPI_Unpack_external("external32", buffer, sizeof(expected), &at, &long_double_back, 1,
+                            MPI_LONG_DOUBLE);
+        MPI_Unpack_external("external32", buffer, sizeof(expected), &at, shorts_back, 3,
+                            MPI_SHORT);
+        check(value_back == value && long_back == along && double_back == adouble &&
+                  long_double_back == along_double &&
+                  memcmp(shorts, shorts_back, sizeof(shorts)) == 0,
+              rank, "external32 data did not unpack to the original values");
+
+        // A column round trips through external32 as well.
+        double column_back[4][5] = {{0}};
+        at = 0;
+        MPI_Pack_external("external32", &matrix[0][1], 1, column, buffer, sizeof(buffer), &at);
+        at = 0;
+        MPI_Unpack_external("external32", buffer, 32, &at, &column_back[0][1], 1, column);
+        for (int i = 0; i < 4; i++) {
+            check(column_back[i][1] == matrix[i][1], rank,
+                  "an external32 column round trip failed");
+        }
+
+        check(MPI_Pack_external_size("native", 1, MPI_INT, &external_size) ==
+                  MPI_ERR_UNSUPPORTED_DATAREP,
+              rank, "an unknown data representation was accepted");
+    }
+
+    MPI_Type_free(&column);
+
+    printf("Rank %d: pack passed\n", rank);
+
+    MPI_Finalize();
+    return 0;
+}
@@ -0,0 +1,165 @@
+#include <mpi.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#define PARTITIONS 8
+#define COUNT 1000
+#define ROUNDS 3
+
+static void check(int condition, int rank, const char *what) {
+    if (!condition) {
+        printf("Rank %d: %s\n", rank, what);
+        exit(EXIT_FAILURE);
+    }
+}
+
+int main(int argc, char **argv) {
+    MPI_Init(&argc, &argv);
+
+    int rank, size;
+    MPI_Comm_rank(MPI_COMM_WORLD, &rank);
+    MPI_Comm_size(MPI_COMM_WORLD, &size);
+
+    // Ranks work in pairs; an odd rank out has nothing to do.
+    int partner = rank ^ 1;
+    if (partner >= size) {
+        MPI_Finalize();
+        return 0;
+    }
+    int sender = rank % 2 == 0;
+
+    int *buf = malloc(PARTITIONS * COUNT * sizeof(int));
+    MPI_Request request;
+    if (sender) {
+        MPI_Psend_init(buf, PARTITIONS, COUNT, MPI_INT, partner, 0, MPI_COMM_WORLD, MPI_INFO_NULL,
+                       &request);
+    } else {
+        MPI_Precv_init(buf, PARTITIONS, COUNT, MPI_INT, partner, 0, MPI_COMM_WORLD, MPI_INFO_NULL,
+                       &request);
+    }
+
+    for (int round = 0; round < ROUNDS; round++) {
+        MPI_Start(&request);
+
+        int go = round;
+        if (sender) {
+            for (int i = 0; i < PARTITIONS * COUNT; i++) {
+                buf[i] = round * PARTITIONS * COUNT + i;
+            }
+
+            // The last partition goes first, then the receiver is told to look for it.
+            MPI_Pready(PARTITIONS - 1, request);
+            check(MPI_Pready(PARTITIONS - 1, request) == MPI_ERR_ARG, rank,
+                  "a partition was marked ready twice");
+            check(MPI_Pready(PARTITIONS, request) == MPI_ERR_ARG, rank,
+                  "a partition past the end was marked ready");
+            MPI_Send(&go, 1, MPI_INT, partner, 1, MPI_COMM_WORLD);
+            MPI_Recv(&go, 1, MPI_INT, partner, 2, MPI_COMM_WORLD, MPI_STATUS_IGNORE);
+
+            MPI_Pready_range(0, 3, request);
+            int rest[] = {6, 4, 5};
+            MPI_Pready_list(3, rest, request);
+            MPI_Wait(&request, MPI_STATUS_IGNORE);
+        } else {
+            MPI_Recv(&go, 1, MPI_INT, partner, 1, MPI_COMM_WORLD, MPI_STATUS_IGNORE);
+
+            // Partitions travel on the same connection as the message, so the last one has
+            // arrived and the first hasn't been sent yet.
+            int flag = 0;
+            MPI_Parrived(request, PARTITIONS - 1, &flag);
+            check(flag, rank, "the first partition sent has not arrived");
+            MPI_Parrived(request, 0, &flag);
+            check(!flag, rank, "a partition arrived before it was ready");
+            check(MPI_Parrived(request, PARTITIONS, &flag) == MPI_ERR_ARG, rank,
+                  "a partition past the end was checked for");
+            check(MPI_Pready(0, request) == MPI_ERR_REQUEST, rank,
+                  "a partition of a receive was marked ready");
+
+            for (int i = (PARTITIONS - 1) * COUNT; i < PARTITIONS * COUNT; i++) {
+                check(buf[i] == round * PARTITIONS * COUNT + i, rank, "wrong early partition");
+            }
+            MPI_Send(&go, 1, MPI_INT, partner, 2, MPI_COMM_WORLD);
+
+            MPI_Status status;
+            MPI_Wait(&request, &status);
+            check(status.MPI_SOURCE == partner && status.MPI_TAG == 0, rank, "wrong status");
+            for (int i = 0; i < PARTITIONS * COUNT; i++) {
+                check(buf[i] == round * PARTITIONS * COUNT + i, rank, "wrong partitioned data");
+            }
+        }
+    }
+
+    // Partitions can only be marked ready while the send is active.
+    if (sender) {
+        check(MPI_Pready(0, request) == MPI_ERR_REQUEST, rank,
+              "a partition of an inactive send was marked ready");
+    }
+
+    // The two sides may partition the buffer differently.
+    int *coarse = malloc(PARTITIONS * COUNT * sizeof(int));
+    MPI_Request mismatched;
+    if (sender) {
+        MPI_Psend_init(coarse, 2, PARTITIONS / 2 * COUNT, MPI_INT, partner, 0, MPI_COMM_WORLD,
+                       MPI_INFO_NULL, &mismatched);
+        MPI_Start(&mismatched);
+        for (int i = 0; i < PARTITIONS * COUNT; i++) {
+            coarse[i] = -i;
+        }
+        MPI_Pready(1, mismatched);
+        MPI_Pready(0, mismatched);
+    } else {
+        MPI_Precv_init(coarse, PARTITIONS, COUNT, MPI_INT, partner, 0, MPI_COMM_WORLD,
+                       MPI_INFO_NULL, &mismatched);
+        MPI_Start(&mismatched);
+    }
+    MPI_Wait(&mismatched, MPI_STATUS_IGNORE);
+    if (!sender) {
+        for (int i = 0; i < PARTITIONS * COUNT; i++) {
+            check(coarse[i] == -i, rank, "wrong data with mismatched partitions");
+        }
+    }
+
+    // Partitions of a datatype with gaps are packed and unpacked like any other message. The
+    // sender sends every other int; the receiver receives them contiguously.
+    MPI_Datatype every_other;
+    MPI_Type_create_resized(MPI_INT, 0, 2 * sizeof(int), &every_other);
+    MPI_Type_commit(&every_other);
+    int *strided = calloc(2 * PARTITIONS * COUNT, sizeof(int));
+    MPI_Request gaps;
+    if (sender) {
+        for (int i = 0; i < 2 * PARTITIONS * COUNT; i++) {
+            strided[i] = i % 2 == 0 ? i / 2 : -1;
+        }
+        MPI_Psend_init(strided, PARTITIONS, COUNT, every_other, partner, 0, MPI_COMM_WORLD,
+                       MPI_INFO_NULL, &gaps);
+        MPI_Start(&gaps);
+        MPI_Pready_range(0, PARTITIONS - 1, gaps);
+    } else {
+        MPI_Precv_init(strided, PARTITIONS, COUNT, MPI_INT, partner, 0, MPI_COMM_WORLD,
+                       MPI_INFO_NULL, &gaps);
+        MPI_Start(&gaps);
+    }
+    MPI_Wait(&gaps, MPI_STATUS_IGNORE);
+    if (!sender) {
+        for (int i = 0; i < PARTITIONS * COUNT; i++) {
+            check(strided[i] == i, rank, "wrong data from a strided partitioned send");
+        }
+    }
+    MPI_Request_free(&gaps);
+    MPI_Type_free(&every_other);
+
+    // A buffer too large to address is rejected before anything is sent.
+    MPI_Request huge;
+    check(MPI_Psend_init(buf, PARTITIONS, (MPI_Count)1 << 61, MPI_INT, partner, 1, MPI_COMM_WORLD,
+                         MPI_INFO_NULL, &huge) == MPI_ERR_COUNT,
+          rank, "a partitioned buffer too large to address was accepted");
+
+    free(buf);
+    free(coarse);
+    free(strided);
+
+    printf("Rank %d: partitioned communication passed\n", rank);
+
+    MPI_Finalize();
+    return 0;
+}
@@ -0,0 +1,113 @@
+#include <mpi.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#define ITERATIONS 100
+// Large enough to go by rendezvous.
+#define LARGE_COUNT (1 << 17)
+
+static void check(int condition, int rank, const char *what) {
+    if (!condition) {
+        printf("Rank %d: %s\n", rank, what);
+        exit(EXIT_FAILURE);
+    }
+}
+
+int main(int argc, char **argv) {
+    MPI_Init(&argc, &argv);
+
+    int rank, size;
+    MPI_Comm_rank(MPI_COMM_WORLD, &rank);
+    MPI_Comm_size(MPI_COMM_WORLD, &size);
+
+    int next = (rank + 1) % size;
+    int prev = (rank + size - 1) % size;
+
+    // The same ring exchange, set up once and restarted every iteration. Each start sends whatever
+    // the buffer holds at the time.
+    int outgoing, incoming;
+    MPI_Request requests[2];
+    MPI_Recv_init(&incoming, 1, MPI_INT, prev, 0, MPI_COMM_WORLD, &requests[0]);
+    MPI_Send_init(&outgoing, 1, MPI_INT, next, 0, MPI_COMM_WORLD, &requests[1]);
+
+    for (int i = 0; i < ITERATIONS; i++) {
+        outgoing = rank * ITERATIONS + i;
+        incoming = -1;
+
+        MPI_Startall(2, requests);
+        MPI_Waitall(2, requests, MPI_STATUSES_IGNORE);
+
+        check(incoming == prev * ITERATIONS + i, rank, "received the wrong value");
+        check(requests[0] != MPI_REQUEST_NULL && requests[1] != MPI_REQUEST_NULL, rank,
+              "completing a persistent request freed it");
+    }
+
+    // Inactive requests count as null.
+    int index;
+    MPI_Status status;
+    MPI_Waitany(2, requests, &index, &status);
+    check(index == MPI_UNDEFINED, rank, "MPI_Waitany completed an inactive request");
+    MPI_Wait(&requests[0], &status);
+    check(status.MPI_SOURCE == MPI_ANY_SOURCE, rank, "an inactive request had a status");
+    MPI_Request_free(&requests[0]);
+    MPI_Request_free(&requests[1]);
+
+    // Large synchronous sends, one start at a time.
+    int *large_out = malloc(LARGE_COUNT * sizeof(int));
+    int *large_in = malloc(LARGE_COUNT * sizeof(int));
+    MPI_Request large[2];
+    MPI_Recv_init(large_in, LARGE_COUNT, MPI_INT, prev, 1, MPI_COMM_WORLD, &large[0]);
+    MPI_Ssend_init(large_out, LARGE_COUNT, MPI_INT, next, 1, MPI_COMM_WORLD, &large[1]);
+
+    for (int i = 0; i < 5; i++) {
+        for (int j = 0; j < LARGE_COUNT; j++) {
+            large_out[j] = rank + i + j;
+        }
+
+        MPI_Start(&large[0]);
+        MPI_Start(&large[1]);
+
+        int flag = 0;
+        while (!flag) {
+            MPI_Test(&large[0], &flag, &status);
+        }
+        MPI_Wait(&large[1], MPI_STATUS_IGNORE);
+
+        check(status.MPI_SOURCE == prev && status.MPI_TAG == 1, rank, "wrong large status");
+        for (int j = 0; j < LARGE_COUNT; j++) {
+            check(large_in[j] == prev + i + j, rank, "the large message was corrupted");
+        }
+    }
+    MPI_Request_free(&large[0]);
+    MPI_Request_free(&large[1]);
+    free(large_out);
+    free(large_in);
+
+    // Buffered and ready persistent sends.
+    int buffer_size = sizeof(int) + MPI_BSEND_OVERHEAD;
+    void *buffer = malloc(buffer_size);
+    MPI_Buffer_attach(buffer, buffer_size);
+
+    MPI_Request modes[3];
+    MPI_Recv_init(&incoming, 1, MPI_INT, prev, 2, MPI_COMM_WORLD, &modes[0]);
+    MPI_Bsend_init(&outgoing, 1, MPI_INT, next, 2, MPI_COMM_WORLD, &modes[1]);
+    MPI_Rsend_init(&outgoing, 1, MPI_INT, MPI_PROC_NULL, 2, MPI_COMM_WORLD, &modes[2]);
+
+    for (int i = 0; i < 3; i++) {
+        outgoing = rank + i;
+        MPI_Startall(3, modes);
+        MPI_Waitall(3, modes, MPI_STATUSES_IGNORE);
+        check(incoming == prev + i, rank, "received the wrong buffered value");
+    }
+
+    for (int i = 0; i < 3; i++) {
+        MPI_Request_free(&modes[i]);
+    }
+    MPI_Buffer_detach(&buffer, &buffer_size);
+    free(buffer);
+
+    printf("Rank %d: persistent requests passed\n", rank);
+
+    MPI_Finalize();
+    return 0;
+}
@@ -0,0 +1,93 @@
+#include <mpi.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#define STEPS 20
+
+// Large enough that the messages of the all-to-all go by rendezvous.
+#define LARGE_BLOCK (1 << 14)
+
+static int rank;
+
+static void check(int condition, const char *what) {
+    if (!condition) {
+        printf("Rank %d: %s\n", rank, what);
+        exit(EXIT_FAILURE);
+    }
+}
+
+int main(int argc, char **argv) {
+    MPI_Init(&argc, &argv);
+
+    int size;
+    MPI_Comm_rank(MPI_COMM_WORLD, &rank);
+    MPI_Comm_size(MPI_COMM_WORLD, &size);
+
+    // The collectives of a timestep loop, set up once. The buffers are read and written anew on
+    // every start.
+    double residual = 0.0, total = 0.0;
+    int step = -1;
+    int *out = malloc(size * LARGE_BLOCK * sizeof(int));
+    int *in = malloc(size * LARGE_BLOCK * sizeof(int));
+    int *steps = malloc(size * sizeof(int));
+
+    MPI_Request requests[5];
+    MPI_Allreduce_init(&residual, &total, 1, MPI_DOUBLE, MPI_SUM, MPI_COMM_WORLD, MPI_INFO_NULL,
+                       &requests[0]);
+    MPI_Bcast_init(&step, 1, MPI_INT, 0, MPI_COMM_WORLD, MPI_INFO_NULL, &requests[1]);
+    MPI_Alltoall_init(out, LARGE_BLOCK, MPI_INT, in, LARGE_BLOCK, MPI_INT, MPI_COMM_WORLD,
+                      MPI_INFO_NULL, &requests[2]);
+    MPI_Gather_init(&step, 1, MPI_INT, steps, 1, MPI_INT, size - 1, MPI_COMM_WORLD, MPI_INFO_NULL,
+                    &requests[3]);
+    MPI_Barrier_init(MPI_COMM_WORLD, MPI_INFO_NULL, &requests[4]);
+
+    int rank_sum = size * (size - 1) / 2;
+    for (int t = 0; t < STEPS; t++) {
+        residual = rank + t;
+        if (rank == 0) {
+            step = t;
+        }
+        for (int j = 0; j < size; j++) {
+            out[j * LARGE_BLOCK] = out[(j + 1) * LARGE_BLOCK - 1] = t * rank + j;
+        }
+
+        MPI_Start(&requests[0]);
+        MPI_Start(&requests[1]);
+        MPI_Start(&requests[2]);
+        MPI_Waitall(3, requests, MPI_STATUSES_IGNORE);
+        check(requests[0] != MPI_REQUEST_NULL, "MPI_Waitall freed a persistent collective");
+        check(total == rank_sum + (double)size * t, "the persistent allreduce was wrong");
+        check(step == t, "the persistent broadcast was wrong");
+        for (int j = 0; j < size; j++) {
+            int expected = t * j + rank;
+            check(in[j * LARGE_BLOCK] == expected && in[(j + 1) * LARGE_BLOCK - 1] == expected,
+                  "the persistent all-to-all was wrong");
+        }
+
+        // Blocking collectives between the starts take tags of their own.
+        int agreed = t;
+        MPI_Allreduce(MPI_IN_PLACE, &agreed, 1, MPI_INT, MPI_MAX, MPI_COMM_WORLD);
+        check(agreed == t, "the blocking allreduce between starts was wrong");
+
+        MPI_Startall(2, &requests[3]);
+        MPI_Waitall(2, &requests[3], MPI_STATUSES_IGNORE);
+        if (rank == size - 1) {
+            for (int i = 0; i < size; i++) {
+                check(steps[i] == t, "the persistent gather was wrong");
+            }
+        }
+    }
+
+    for (int i = 0; i < 5; i++) {
+        MPI_Request_free(&requests[i]);
+        check(requests[i] == MPI_REQUEST_NULL, "MPI_Request_free did not free the collective");
+    }
+
+    free(steps);
+    free(in);
+    free(out);
+
+    printf("Rank %d: %d steps of persistent collectives passed\n", rank, STEPS);
+    MPI_Finalize();
+    return 0;
+}
@@ -0,0 +1,114 @@
+#include <mpi.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+// Large enough that the last message goes by rendezvous.
+static const int lengths[] = {0, 1, 17, 100000};
+#define MESSAGES (int)(sizeof(lengths) / sizeof(lengths[0]))
+
+static void check(int condition, int rank, const char *what) {
+    if (!condition) {
+        printf("Rank %d: %s\n", rank, what);
+        exit(EXIT_FAILURE);
+    }
+}
+
+static void send_messages(int partner) {
+    for (int i = 0; i < MESSAGES; i++) {
+        int *values = malloc((lengths[i] + 1) * sizeof(int));
+        for (int j = 0; j < lengths[i]; j++) {
+            values[j] = j;
+        }
+        // The tag tells the receiver how long the message is.
+        MPI_Send(values, lengths[i], MPI_INT, partner, lengths[i], MPI_COMM_WORLD);
+        free(values);
+    }
+}
+
+static void check_values(const int *values, int length, int rank) {
+    for (int j = 0; j < length; j++) {
+        check(values[j] == j, rank, "received the wrong values");
+    }
+}
+
+int main(int argc, char **argv) {
+    MPI_Init(&argc, &argv);
+
+    int rank, size;
+    MPI_Comm_rank(MPI_COMM_WORLD, &rank);
+    MPI_Comm_size(MPI_COMM_WORLD, &size);
+
+    // Ranks work in pairs; an odd rank out has nothing to do.
+    int partner = rank ^ 1;
+    if (partner >= size) {
+        MPI_Finalize();
+        return 0;
+    }
+    int sender = rank % 2 == 0;
+
+    MPI_Status status;
+
+    if (sender) {
+        send_messages(partner);
+        send_messages(partner);
+        send_messages(partner);
+    } else {
+        int flag = 1;
+        MPI_Iprobe(partner, 1000, MPI_COMM_WORLD, &flag, &status);
+        check(!flag, rank, "MPI_Iprobe found a message that was never sent");
+
+        // Probe, then receive a buffer of the right size.
+        for (int i = 0; i < MESSAGES; i++) {
+            MPI_Probe(MPI_ANY_SOURCE, MPI_ANY_TAG, MPI_COMM_WORLD, &status);
+            check(status.MPI_SOURCE == partner, rank, "MPI_Probe reported the wrong source");
+            check(status.MPI_TAG == lengths[i], rank, "MPI_Probe matched out of order");
+
+            int *values = malloc((status.MPI_TAG + 1) * sizeof(int));
+            MPI_Recv(values, status.MPI_TAG, MPI_INT, status.MPI_SOURCE, status.MPI_TAG,
+                     MPI_COMM_WORLD, MPI_STATUS_IGNORE);
+            check_values(values, status.MPI_TAG, rank);
+            free(values);
+        }
+
+        // Matched probes take the message out of matching.
+        for (int i = 0; i < MESSAGES; i++) {
+            MPI_Message message;
+            MPI_Mprobe(partner, MPI_ANY_TAG, MPI_COMM_WORLD, &message, &status);
+            check(status.MPI_TAG == lengths[i], rank, "MPI_Mprobe matched out of order");
+
+            int *values = malloc((status.MPI_TAG + 1) * sizeof(int));
+            MPI_Mrecv(values, status.MPI_TAG, MPI_INT, &message, MPI_STATUS_IGNORE);
+            check(message == MPI_MESSAGE_NULL, rank, "MPI_Mrecv did not free the message");
+            check_values(values, status.MPI_TAG, rank);
+            free(values);
+        }
+
+        for (int i = 0; i < MESSAGES; i++) {
+            MPI_Message message;
+            do {
+                MPI_Improbe(partner, lengths[i], MPI_COMM_WORLD, &flag, &message, &status);
+            } while (!flag);
+
+            int *values = malloc((status.MPI_TAG + 1) * sizeof(int));
+            MPI_Request request;
+            MPI_Imrecv(values, status.MPI_TAG, MPI_INT, &message, &request);
+            MPI_Wait(&request, &status);
+            check(status.MPI_TAG == lengths[i], rank, "MPI_Imrecv received the wrong message");
+            check_values(values, lengths[i], rank);
+            free(values);
+        }
+    }
+
+    // Probing MPI_PROC_NULL matches an empty message at once.
+    MPI_Message message;
+    MPI_Mprobe(MPI_PROC_NULL, MPI_ANY_TAG, MPI_COMM_WORLD, &message, &status);
+    check(message == MPI_MESSAGE_NO_PROC, rank, "MPI_Mprobe of MPI_PROC_NULL was not NO_PROC");
+    MPI_Mrecv(NULL, 0, MPI_INT, &message, &status);
+    check(status.MPI_SOURCE == MPI_PROC_NULL, rank, "MPI_Mrecv of NO_PROC had a source");
+    check(message == MPI_MESSAGE_NULL, rank, "MPI_Mrecv did not free MPI_MESSAGE_NO_PROC");
+
+    printf("Rank %d: probes passed\n", rank);
+
+    MPI_Finalize();
+    return 0;
+}
@@ -0,0 +1,186 @@
+#include <mpi.h>
+#include <stdbool.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+static int rank;
+
+static void check(int condition, const char *what) {
+    if (!condition) {
+        printf("Rank %d: %s\n", rank, what);
+        exit(EXIT_FAILURE);
+    }
+}
+
+// A string of decimal digits, as its value and 10 to the power of its length. Concatenating them
+// is associative but not commutative, so the order the ranks' digits are combined in shows.
+typedef struct {
+    long value;
+    long scale;
+} digits;
+
+static MPI_Datatype digits_type;
+
+static void concatenate(void *invec, void *inoutvec, int *len, MPI_Datatype *datatype) {
+    int size;
+    MPI_Type_size(*datatype, &size);
+    check(size == sizeof(digits), "the user function was given the wrong datatype");
+
+    digits *in = invec, *inout = inoutvec;
+    for (int i = 0; i < *len; i++) {
+        inout[i].value += in[i].value * inout[i].scale;
+        inout[i].scale *= in[i].scale;
+    }
+}
+
+static void concatenate_c(void *invec, void *inoutvec, MPI_Count *len, MPI_Datatype *datatype) {
+    int count = (int)*len;
+    concatenate(invec, inoutvec, &count, datatype);
+}
+
+// The digits of ranks first to last, in order.
+static long expected_digits(int first, int last) {
+    long value = 0;
+    for (int i = first; i <= last; i++) {
+        value = value * 10 + i % 10;
+    }
+    return value;
+}
+
+int main(int argc, char **argv) {
+    MPI_Init(&argc, &argv);
+
+    int size;
+    MPI_Comm_rank(MPI_COMM_WORLD, &rank);
+    MPI_Comm_size(MPI_COMM_WORLD, &size);
+
+    // Logical operations on ints and bools. Only rank 0 contributes true to the first value, and
+    // every rank to the second. Any value other than zero is true.
+    int truths[2] = {rank == 0, -1}, results[2];
+    MPI_Allreduce(truths, results, 2, MPI_INT, MPI_LAND, MPI_COMM_WORLD);
+    check(results[0] == (size == 1) && results[1] != 0, "MPI_LAND was wrong");
+    MPI_Allreduce(truths, results, 2, MPI_INT, MPI_LOR, MPI_COMM_WORLD);
+    check(results[0] == 1 && results[1] != 0, "MPI_LOR was wrong");
+    MPI_Allreduce(truths, results, 2, MPI_INT, MPI_LXOR, MPI_COMM_WORLD);
+    check(results[0] == 1 && (results[1] != 0) == size % 2, "MPI_LXOR was wrong");
+
+    bool odd = rank % 2, any_odd, all_odd;
+    MPI_Allreduce(&odd, &any_odd, 1, MPI_C_BOOL, MPI_LOR, MPI_COMM_WORLD);
+    MPI_Allreduce(&odd, &all_odd, 1, MPI_C_BOOL, MPI_LAND, MPI_COMM_WORLD);
+    check(any_odd == (size > 1) && !all_odd, "logical operations on MPI_C_BOOL were wrong");
+
+    // Bitwise operations on unsigned ints and bytes: rank i sets bit i.
+    unsigned bit = 1u << rank, bits;
+    MPI_Allreduce(&bit, &bits, 1, MPI_UNSIGNED, MPI_BOR, MPI_COMM_WORLD);
+    check(bits == (1u << size) - 1, "MPI_BOR was wrong");
+    MPI_Allreduce(&bit, &bits, 1, MPI_UNSIGNED, MPI_BAND, MPI_COMM_WORLD);
+    check(bits == (size == 1 ? 1u : 0u), "MPI_BAND was wrong");
+    unsigned char byte = 0xf0 | rank, xor;
+    MPI_Allreduce(&byte, &xor, 1, MPI_BYTE, MPI_BXOR, MPI_COMM_WORLD);
+    unsigned char expected_xor = 0;
+    for (int i = 0; i < size; i++) {
+        expected_xor ^= 0xf0 | i;
+    }
+    check(xor == expected_xor, "MPI_BXOR of bytes was wrong");
+
+    // MAXLOC and MINLOC, where ties go to the lowest index.
+    struct {
+        double value;
+        int index;
+    } located = {rank % 2 ? 1.5 : -rank, rank}, maxloc, minloc;
+    MPI_Allreduce(&located, &maxloc, 1, MPI_DOUBLE_INT, MPI_MAXLOC, MPI_COMM_WORLD);
+    MPI_Allreduce(&located, &minloc, 1, MPI_DOUBLE_INT, MPI_MINLOC, MPI_COMM_WORLD);
+    check(size == 1 || (maxloc.value == 1.5 && maxloc.index == 1), "MPI_MAXLOC was wrong");
+    int lowest = (size - 1) / 2 * 2;
+    check(minloc.value == -lowest && minloc.index == lowest, "MPI_MINLOC was wrong");
+
+    // Pairs in a derived datatype, reduced to every root.
+    MPI_Datatype two_pairs;
+    MPI_Type_contiguous(2, MPI_2INT, &two_pairs);
+    MPI_Type_commit(&two_pairs);
+    int pairs[4] = {rank / 2, rank, -rank, rank}, extremes[4];
+    for (int root = 0; root < size; root++) {
+        MPI_Reduce(pairs, extremes, 1, two_pairs, MPI_MAXLOC, root, MPI_COMM_WORLD);
+        if (rank == root) {
+            check(extremes[0] == (size - 1) / 2 && extremes[1] == (size - 1) / 2 * 2 &&
+                      extremes[2] == 0 && extremes[3] == 0,
+                  "MPI_MAXLOC of a derived datatype was wrong");
+        }
+    }
+
+    check(MPI_Allreduce(truths, results, 1, MPI_INT, MPI_MAXLOC, MPI_COMM_WORLD) == MPI_ERR_OP,
+          "MPI_MAXLOC of a datatype that isn't pairs was allowed");
+    double real = 1.0, real_result;
+    check(MPI_Allreduce(&real, &real_result, 1, MPI_DOUBLE, MPI_LAND, MPI_COMM_WORLD) ==
+              MPI_ERR_OP,
+          "MPI_LAND of doubles was allowed");
+    check(MPI_Allreduce(&bit, &bits, 1, MPI_FLOAT, MPI_BOR, MPI_COMM_WORLD) == MPI_ERR_OP,
+          "MPI_BOR of floats was allowed");
+
+    // A user operation that isn't commutative, which has to be applied in rank order.
+    MPI_Type_contiguous(2, MPI_LONG, &digits_type);
+    MPI_Type_commit(&digits_type);
+    MPI_Op concat, concat_c;
+    MPI_Op_create(concatenate, 0, &concat);
+    MPI_Op_create_c(concatenate_c, 0, &concat_c);
+    int commute;
+    MPI_Op_commutative(concat, &commute);
+    check(!commute, "MPI_Op_commutative said the user operation commutes");
+    MPI_Op_commutative(MPI_SUM, &commute);
+    check(commute, "MPI_Op_commutative said MPI_SUM doesn't commute");
+
+    digits mine = {rank % 10, 10}, all;
+    for (int root = 0; root < size; root++) {
+        all.value = -1;
+        MPI_Reduce(&mine, &all, 1, digits_type, concat, root, MPI_COMM_WORLD);
+        check(rank != root || all.value == expected_digits(0, size - 1),
+              "MPI_Reduce with a user operation was out of order");
+    }
+
+    MPI_Allreduce(&mine, &all, 1, digits_type, concat_c, MPI_COMM_WORLD);
+    check(all.value == expected_digits(0, size - 1),
+          "MPI_Allreduce with a user operation was out of order");
+
+    MPI_Scan(&mine, &all, 1, digits_type, concat, MPI_COMM_WORLD);
+    check(all.value == expected_digits(0, rank), "MPI_Scan with a user operation was wrong");
+    all.value = -1;
+    MPI_Exscan(&mine, &all, 1, digits_type, concat, MPI_COMM_WORLD);
+    check(rank == 0 ? all.value == -1 : all.value == expected_digits(0, rank - 1),
+          "MPI_Exscan with a user operation was wrong");
+
+    digits *blocks = malloc(size * sizeof(digits));
+    for (int i = 0; i < size; i++) {
+        blocks[i] = mine;
+    }
+    MPI_Reduce_scatter_block(blocks, &all, 1, digits_type, concat, MPI_COMM_WORLD);
+    check(all.value == expected_digits(0, size - 1),
+          "MPI_Reduce_scatter_block with a user operation was out of order");
+    free(blocks);
+
+    // Local reductions, which leave `in op inout` in inout.
+    digits head = {12, 100}, tail = {345, 1000};
+    MPI_Reduce_local(&head, &tail, 1, digits_type, concat);
+    check(tail.value == 12345 && tail.scale == 100000, "MPI_Reduce_local was out of order");
+    long values[3] = {1, 2, 3}, sums[3] = {10, 20, 30};
+    MPI_Reduce_local_c(values, sums, 3, MPI_LONG, MPI_SUM);
+    check(sums[0] == 11 && sums[2] == 33, "MPI_Reduce_local_c was wrong");
+    MPI_Reduce_local(values, sums, 3, MPI_LONG, MPI_NO_OP);
+    check(sums[1] == 22, "MPI_NO_OP changed the values");
+    MPI_Reduce_local(values, sums, 3, MPI_LONG, MPI_REPLACE);
+    check(sums[0] == 1 && sums[2] == 3, "MPI_REPLACE didn't replace the values");
+    check(MPI_Reduce_local(&odd, &any_odd, 1, MPI_C_BOOL, MPI_SUM) == MPI_ERR_OP,
+          "MPI_SUM of bools was allowed");
+
+    MPI_Op sum = MPI_SUM;
+    check(MPI_Op_free(&sum) == MPI_ERR_OP, "a predefined operation was freed");
+    MPI_Op_free(&concat_c);
+    MPI_Op_free(&concat);
+    check(concat == MPI_OP_NULL, "MPI_Op_free didn't set the handle to MPI_OP_NULL");
+
+    MPI_Type_free(&digits_type);
+    MPI_Type_free(&two_pairs);
+
+    printf("Rank %d: reduction operations passed\n", rank);
+    MPI_Finalize();
+    return 0;
+}
@@ -0,0 +1,100 @@
+#include <mpi.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+// Large enough that the messages of the reduce-scatter go by rendezvous.
+#define LARGE_BLOCK (1 << 15)
+
+static int rank;
+
+static void check(int condition, const char *what) {
+    if (!condition) {
+        printf("Rank %d: %s\n", rank, what);
+        exit(EXIT_FAILURE);
+    }
+}
+
+int main(int argc, char **argv) {
+    MPI_Init(&argc, &argv);
+
+    int size;
+    MPI_Comm_rank(MPI_COMM_WORLD, &rank);
+    MPI_Comm_size(MPI_COMM_WORLD, &size);
+
+    // Rank i contributes i + 1, so the inclusive prefix sum at rank i is (i + 1)(i + 2) / 2.
+    int value[2] = {rank + 1, -rank}, prefix[2] = {0, 0};
+    MPI_Scan(value, prefix, 2, MPI_INT, MPI_SUM, MPI_COMM_WORLD);
+    check(prefix[0] == (rank + 1) * (rank + 2) / 2 && prefix[1] == -rank * (rank + 1) / 2,
+          "MPI_Scan was wrong");
+
+    double largest = rank % 2 ? rank : -rank;
+    MPI_Scan_c(MPI_IN_PLACE, &largest, 1, MPI_DOUBLE, MPI_MAX, MPI_COMM_WORLD);
+    check(largest == (rank == 0 ? 0 : rank % 2 ? rank : rank - 1), "MPI_Scan_c in place was wrong");
+
+    // The offsets a load balancer computes: the sum over the ranks before this one.
+    long items = 10 * (rank + 1), offset = -1;
+    MPI_Exscan(&items, &offset, 1, MPI_LONG, MPI_SUM, MPI_COMM_WORLD);
+    check(rank == 0 ? offset == -1 : offset == 10 * rank * (rank + 1) / 2,
+          "MPI_Exscan was wrong");
+
+    int product = rank + 1;
+    MPI_Exscan_c(MPI_IN_PLACE, &product, 1, MPI_INT, MPI_PROD, MPI_COMM_WORLD);
+    int factorial = 1;
+    for (int i = 2; i <= rank; i++) {
+        factorial *= i;
+    }
+    check(rank == 0 ? product == 1 : product == factorial, "MPI_Exscan_c in place was wrong");
+
+    // Rank i receives i + 1 elements of the sum. Element j of rank r's contribution is r + j.
+    int total = size * (size + 1) / 2;
+    int *counts = malloc(size * sizeof(int));
+    int *contribution = malloc(total * sizeof(int));
+    int *block = malloc((rank + 1) * sizeof(int));
+    for (int i = 0; i < size; i++) {
+        counts[i] = i + 1;
+    }
+    for (int j = 0; j < total; j++) {
+        contribution[j] = rank + j;
+    }
+    int first = rank * (rank + 1) / 2, rank_sum = size * (size - 1) / 2;
+    MPI_Reduce_scatter(contribution, block, counts, MPI_INT, MPI_SUM, MPI_COMM_WORLD);
+    for (int j = 0; j <= rank; j++) {
+        check(block[j] == rank_sum + size * (first + j), "MPI_Reduce_scatter was wrong");
+    }
+
+    MPI_Reduce_scatter(MPI_IN_PLACE, contribution, counts, MPI_INT, MPI_SUM, MPI_COMM_WORLD);
+    for (int j = 0; j <= rank; j++) {
+        check(contribution[j] == rank_sum + size * (first + j),
+              "MPI_Reduce_scatter in place was wrong");
+    }
+
+    // Equal blocks large enough to go by rendezvous, which sparse matrix assembly sums.
+    double *rows = malloc(size * LARGE_BLOCK * sizeof(double));
+    double *mine = malloc(LARGE_BLOCK * sizeof(double));
+    for (int i = 0; i < size * LARGE_BLOCK; i++) {
+        rows[i] = rank + 0.5 * (i / LARGE_BLOCK);
+    }
+    MPI_Reduce_scatter_block(rows, mine, LARGE_BLOCK, MPI_DOUBLE, MPI_SUM, MPI_COMM_WORLD);
+    check(mine[0] == rank_sum + 0.5 * size * rank &&
+              mine[LARGE_BLOCK - 1] == rank_sum + 0.5 * size * rank,
+          "MPI_Reduce_scatter_block was wrong");
+
+    MPI_Reduce_scatter_block_c(MPI_IN_PLACE, rows, LARGE_BLOCK, MPI_DOUBLE, MPI_MIN,
+                               MPI_COMM_WORLD);
+    check(rows[0] == 0.5 * rank && rows[LARGE_BLOCK - 1] == 0.5 * rank,
+          "MPI_Reduce_scatter_block_c in place was wrong");
+
+    char letter = 'a';
+    check(MPI_Scan(&letter, &letter, 1, MPI_CHAR, MPI_SUM, MPI_COMM_WORLD) == MPI_ERR_OP,
+          "MPI_SUM of characters was allowed");
+
+    free(mine);
+    free(rows);
+    free(block);
+    free(contribution);
+    free(counts);
+
+    printf("Rank %d: scans and reduce-scatters passed\n", rank);
+    MPI_Finalize();
+    return 0;
+}
@@ -0,0 +1,148 @@
+#include <mpi.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#define LARGE_COUNT (1 << 20)
+
+static void check(int condition, int rank, const char *what) {
+    if (!condition) {
+        printf("Rank %d: %s\n", rank, what);
+        exit(EXIT_FAILURE);
+    }
+}
+
+static int *make_large(int seed) {
+    int *values = malloc(LARGE_COUNT * sizeof(int));
+    for (int i = 0; i < LARGE_COUNT; i++) {
+        values[i] = seed + i;
+    }
+    return values;
+}
+
+static int is_large(const int *values, int seed) {
+    for (int i = 0; i < LARGE_COUNT; i++) {
+        if (values[i] != seed + i) {
+            return 0;
+        }
+    }
+    return 1;
+}
+
+int main(int argc, char **argv) {
+    MPI_Init(&argc, &argv);
+
+    int rank, size;
+    MPI_Comm_rank(MPI_COMM_WORLD, &rank);
+    MPI_Comm_size(MPI_COMM_WORLD, &size);
+
+    // Ranks work in pairs; an odd rank out has nothing to do.
+    int partner = rank ^ 1;
+    if (partner >= size) {
+        MPI_Finalize();
+        return 0;
+    }
+    int sender = rank % 2 == 0;
+
+    // A message well above the eager limit goes by rendezvous.
+    int *large = sender ? make_large(rank) : malloc(LARGE_COUNT * sizeof(int));
+    if (sender) {
+        MPI_Send(large, LARGE_COUNT, MPI_INT, partner, 0, MPI_COMM_WORLD);
+    } else {
+        MPI_Status status;
+        MPI_Recv(large, LARGE_COUNT, MPI_INT, partner, 0, MPI_COMM_WORLD, &status);
+        check(is_large(large, partner), rank, "the rendezvous message was corrupted");
+    }
+    free(large);
+
+    // A synchronous send can't complete until the receiver has matched it, even when the message
+    // is small enough to be sent eagerly.
+    int value = rank;
+    if (sender) {
+        MPI_Request request;
+        MPI_Issend(&value, 1, MPI_INT, partner, 1, MPI_COMM_WORLD, &request);
+
+        int flag = 1;
+        MPI_Test(&request, &flag, MPI_STATUS_IGNORE);
+        check(!flag, rank, "MPI_Issend completed before the receive was posted");
+
+        MPI_Send(&value, 1, MPI_INT, partner, 2, MPI_COMM_WORLD);
+        MPI_Wait(&request, MPI_STATUS_IGNORE);
+    } else {
+        int go, received = -1;
+        MPI_Recv(&go, 1, MPI_INT, partner, 2, MPI_COMM_WORLD, MPI_STATUS_IGNORE);
+        MPI_Recv(&received, 1, MPI_INT, partner, 1, MPI_COMM_WORLD, MPI_STATUS_IGNORE);
+        check(received == partner, rank, "received the wrong value from MPI_Issend");
+    }
+
+    // A ready send requires the receive to be posted first.
+    if (sender) {
+        int ready;
+        MPI_Recv(&ready, 1, MPI_INT, partner, 3, MPI_COMM_WORLD, MPI_STATUS_IGNORE);
+        MPI_Rsend(&value, 1, MPI_INT, partner, 4, MPI_COMM_WORLD);
+    } else {
+        int received = -1;
+        MPI_Request request;
+        MPI_Irecv(&received, 1, MPI_INT, partner, 4, MPI_COMM_WORLD, &request);
+        MPI_Send(&value, 1, MPI_INT, partner, 3, MPI_COMM_WORLD);
+        MPI_Wait(&request, MPI_STATUS_IGNORE);
+        check(received == partner, rank, "received the wrong value from MPI_Rsend");
+    }
+
+    // Buffered sends are limited by the attached buffer. The receiver holds off until told to, so
+    // the large message stays in the buffer.
+    if (sender) {
+        int buffer_size = LARGE_COUNT * sizeof(int) + MPI_BSEND_OVERHEAD;
+        void *buffer = malloc(buffer_size);
+
+        check(MPI_Bsend(&value, 1, MPI_INT, partner, 5, MPI_COMM_WORLD) == MPI_ERR_BUFFER, rank,
+              "MPI_Bsend succeeded without an attached buffer");
+
+        MPI_Buffer_attach(buffer, buffer_size);
+
+        large = make_large(rank);
+        MPI_Request request;
+        MPI_Ibsend(large, LARGE_COUNT, MPI_INT, partner, 5, MPI_COMM_WORLD, &request);
+        MPI_Wait(&request, MPI_STATUS_IGNORE);
+        free(large);
+
+        check(MPI_Bsend(&value, 1, MPI_INT, partner, 6, MPI_COMM_WORLD) == MPI_ERR_BUFFER, rank,
+              "MPI_Bsend succeeded without room in the buffer");
+
+        MPI_Send(&value, 1, MPI_INT, partner, 2, MPI_COMM_WORLD);
+
+        void *detached;
+        int detached_size;
+        MPI_Buffer_detach(&detached, &detached_size);
+        check(detached == buffer && detached_size == buffer_size, rank,
+              "MPI_Buffer_detach returned the wrong buffer");
+        free(buffer);
+    } else {
+        int go;
+        MPI_Recv(&go, 1, MPI_INT, partner, 2, MPI_COMM_WORLD, MPI_STATUS_IGNORE);
+
+        large = malloc(LARGE_COUNT * sizeof(int));
+        MPI_Recv(large, LARGE_COUNT, MPI_INT, partner, 5, MPI_COMM_WORLD, MPI_STATUS_IGNORE);
+        check(is_large(large, partner), rank, "the buffered message was corrupted");
+        free(large);
+    }
+
+    // With an eager limit of zero, even small messages go by rendezvous.
+    MPI_Info info;
+    MPI_Info_create(&info);
+    MPI_Info_set(info, "empire_eager_limit", "0");
+    MPI_Comm_set_info(MPI_COMM_WORLD, info);
+    MPI_Info_free(&info);
+
+    if (sender) {
+        MPI_Ssend(&value, 1, MPI_INT, partner, 7, MPI_COMM_WORLD);
+    } else {
+        int received = -1;
+        MPI_Recv(&received, 1, MPI_INT, partner, 7, MPI_COMM_WORLD, MPI_STATUS_IGNORE);
+        check(received == partner, rank, "received the wrong value with no eager limit");
+    }
+
+    printf("Rank %d: all send modes passed\n", rank);
+
+    MPI_Finalize();
+    return 0;
+}
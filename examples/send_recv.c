@@ -0,0 +1,80 @@
+#include <mpi.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+int main(int argc, char **argv) {
+    MPI_Init(&argc, &argv);
+
+    int rank, size;
+    MPI_Comm_rank(MPI_COMM_WORLD, &rank);
+    MPI_Comm_size(MPI_COMM_WORLD, &size);
+
+    // Pass a token around the ring, each rank adding its rank to it.
+    int token = 0;
+    int next = (rank + 1) % size;
+    int prev = (rank + size - 1) % size;
+
+    if (rank == 0) {
+        MPI_Send(&token, 1, MPI_INT, next, 0, MPI_COMM_WORLD);
+    }
+
+    MPI_Status status;
+    MPI_Recv(&token, 1, MPI_INT, prev, 0, MPI_COMM_WORLD, &status);
+
+    if (status.MPI_SOURCE != prev || status.MPI_TAG != 0) {
+        printf("Rank %d: unexpected status { source = %d, tag = %d }\n", rank, status.MPI_SOURCE, status.MPI_TAG);
+        exit(EXIT_FAILURE);
+    }
+
+    token += rank;
+
+    if (rank != 0) {
+        MPI_Send(&token, 1, MPI_INT, next, 0, MPI_COMM_WORLD);
+    } else {
+        printf("Token after one trip around %d ranks: %d\n", size, token);
+        if (token != size * (size - 1) / 2) {
+            exit(EXIT_FAILURE);
+        }
+    }
+
+    // Messages between a pair of ranks must arrive in the order they were sent.
+    if (size > 1) {
+        if (rank == 0) {
+            for (int i = 0; i < 10; i++) {
+                MPI_Send(&i, 1, MPI_INT, 1, i % 2, MPI_COMM_WORLD);
+            }
+        } else if (rank == 1) {
+            for (int i = 0; i < 10; i++) {
+                int value;
+                MPI_Recv(&value, 1, MPI_INT, 0, MPI_ANY_TAG, MPI_COMM_WORLD, MPI_STATUS_IGNORE);
+                if (value != i) {
+                    printf("Message %d overtaken by message %d\n", i, value);
+                    exit(EXIT_FAILURE);
+                }
+            }
+        }
+    }
+
+    // A receive buffer that is too small reports truncation.
+    if (size > 1) {
+        if (rank == 0) {
+            int values[2] = {1, 2};
+            MPI_Send(values, 2, MPI_INT, 1, 7, MPI_COMM_WORLD);
+        } else if (rank == 1) {
+            int value;
+            int result = MPI_Recv(&value, 1, MPI_INT, 0, 7, MPI_COMM_WORLD, MPI_STATUS_IGNORE);
+            if (result != MPI_ERR_TRUNCATE) {
+                printf("Expected MPI_ERR_TRUNCATE, got %d\n", result);
+                exit(EXIT_FAILURE);
+            }
+        }
+    }
+
+    MPI_Recv(NULL, 0, MPI_INT, MPI_PROC_NULL, 0, MPI_COMM_WORLD, &status);
+    if (status.MPI_SOURCE != MPI_PROC_NULL) {
+        printf("Receives from MPI_PROC_NULL should report MPI_PROC_NULL as the source\n");
+        exit(EXIT_FAILURE);
+    }
+
+    MPI_Finalize();
+}
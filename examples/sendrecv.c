@@ -0,0 +1,88 @@
+#include <mpi.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+// Large enough that every rank's send goes by rendezvous at the same time.
+#define LARGE_COUNT (1 << 18)
+
+static void check(int condition, int rank, const char *what) {
+    if (!condition) {
+        printf("Rank %d: %s\n", rank, what);
+        exit(EXIT_FAILURE);
+    }
+}
+
+int main(int argc, char **argv) {
+    MPI_Init(&argc, &argv);
+
+    int rank, size;
+    MPI_Comm_rank(MPI_COMM_WORLD, &rank);
+    MPI_Comm_size(MPI_COMM_WORLD, &size);
+
+    int next = (rank + 1) % size;
+    int prev = (rank + size - 1) % size;
+
+    MPI_Status status;
+
+    // Shift around the ring.
+    int received = -1;
+    MPI_Sendrecv(&rank, 1, MPI_INT, next, 0, &received, 1, MPI_INT, prev, 0, MPI_COMM_WORLD,
+                 &status);
+    check(received == prev, rank, "MPI_Sendrecv received the wrong value");
+    check(status.MPI_SOURCE == prev && status.MPI_TAG == 0, rank,
+          "MPI_Sendrecv reported the wrong status");
+
+    // Every rank sends a large message before any of them receives; this only works if the
+    // receives are posted before waiting on the sends.
+    int *sendbuf = malloc(LARGE_COUNT * sizeof(int));
+    int *recvbuf = malloc(LARGE_COUNT * sizeof(int));
+    for (int i = 0; i < LARGE_COUNT; i++) {
+        sendbuf[i] = rank + i;
+    }
+    MPI_Sendrecv(sendbuf, LARGE_COUNT, MPI_INT, next, 1, recvbuf, LARGE_COUNT, MPI_INT, prev, 1,
+                 MPI_COMM_WORLD, MPI_STATUS_IGNORE);
+    for (int i = 0; i < LARGE_COUNT; i++) {
+        check(recvbuf[i] == prev + i, rank, "the large shift was corrupted");
+    }
+
+    // Shift the large buffer back the other way in place.
+    MPI_Sendrecv_replace(recvbuf, LARGE_COUNT, MPI_INT, prev, 2, next, 2, MPI_COMM_WORLD,
+                         MPI_STATUS_IGNORE);
+    for (int i = 0; i < LARGE_COUNT; i++) {
+        check(recvbuf[i] == rank + i, rank, "MPI_Sendrecv_replace was corrupted");
+    }
+
+    // The nonblocking forms, both in flight at once.
+    int left = -1, right = rank;
+    MPI_Request requests[2];
+    MPI_Isendrecv(&rank, 1, MPI_INT, next, 3, &left, 1, MPI_INT, prev, 3, MPI_COMM_WORLD,
+                  &requests[0]);
+    MPI_Isendrecv_replace(&right, 1, MPI_INT, prev, 4, next, 4, MPI_COMM_WORLD, &requests[1]);
+
+    MPI_Status statuses[2];
+    MPI_Waitall(2, requests, statuses);
+    check(left == prev && right == next, rank, "MPI_Isendrecv received the wrong values");
+    check(statuses[0].MPI_SOURCE == prev && statuses[1].MPI_SOURCE == next, rank,
+          "MPI_Isendrecv reported the wrong source");
+
+    // A non-periodic shift: the ends talk to MPI_PROC_NULL.
+    int up = rank + 1 < size ? rank + 1 : MPI_PROC_NULL;
+    int down = rank > 0 ? rank - 1 : MPI_PROC_NULL;
+    received = -1;
+    MPI_Sendrecv(&rank, 1, MPI_INT, up, 5, &received, 1, MPI_INT, down, 5, MPI_COMM_WORLD,
+                 &status);
+    if (down == MPI_PROC_NULL) {
+        check(status.MPI_SOURCE == MPI_PROC_NULL && received == -1, rank,
+              "receiving from MPI_PROC_NULL changed the buffer");
+    } else {
+        check(received == down, rank, "the non-periodic shift received the wrong value");
+    }
+
+    free(sendbuf);
+    free(recvbuf);
+
+    printf("Rank %d: sendrecv passed\n", rank);
+
+    MPI_Finalize();
+    return 0;
+}
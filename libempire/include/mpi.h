@@ -10,9 +10,29 @@
 #define EMPIRE_IMPORT extern
 #endif
 
+#include <stddef.h>
+
 // MPI Types
 typedef struct empire_comm_t *MPI_Comm;
 typedef struct empire_info_t *MPI_Info;
+typedef struct empire_datatype_t *MPI_Datatype;
+typedef long long MPI_Count;
+typedef long long MPI_Offset;
+typedef ptrdiff_t MPI_Aint;
+typedef struct empire_request_t *MPI_Request;
+typedef struct empire_message_t *MPI_Message;
+typedef struct empire_op_t *MPI_Op;
+
+typedef struct MPI_Status {
+    int MPI_SOURCE;
+    int MPI_TAG;
+    int MPI_ERROR;
+
+    // Private to empire
+    long long empire_count;
+    int empire_cancelled;
+    unsigned int empire_layout;
+} MPI_Status;
 
 // Error classes
 enum {
@@ -98,9 +118,47 @@ enum {
     MPI_MAX_PORT_NAME = 256,
     MPI_MAX_INFO_KEY = 255,
     MPI_MAX_INFO_VAL = 65535,
+    MPI_ANY_SOURCE = -1,
+    MPI_ANY_TAG = -1,
+    MPI_PROC_NULL = -2,
+    MPI_ROOT = -3,
+    MPI_UNDEFINED = -32766,
+    MPI_BSEND_OVERHEAD = 32,
+};
+
+// Array orders and distributions for MPI_Type_create_subarray and MPI_Type_create_darray
+enum {
+    MPI_ORDER_C = 56,
+    MPI_ORDER_FORTRAN = 57,
+    MPI_DISTRIBUTE_BLOCK = 121,
+    MPI_DISTRIBUTE_CYCLIC = 122,
+    MPI_DISTRIBUTE_NONE = 123,
+    MPI_DISTRIBUTE_DFLT_DARG = -49767,
+};
+
+// Combiners returned by MPI_Type_get_envelope, and type classes for MPI_Type_match_size
+enum {
+    MPI_COMBINER_NAMED = 1,
+    MPI_COMBINER_DUP = 2,
+    MPI_COMBINER_CONTIGUOUS = 3,
+    MPI_COMBINER_VECTOR = 4,
+    MPI_COMBINER_HVECTOR = 6,
+    MPI_COMBINER_INDEXED = 7,
+    MPI_COMBINER_HINDEXED = 9,
+    MPI_COMBINER_INDEXED_BLOCK = 10,
+    MPI_COMBINER_STRUCT = 12,
+    MPI_COMBINER_SUBARRAY = 13,
+    MPI_COMBINER_DARRAY = 14,
+    MPI_COMBINER_RESIZED = 18,
+    MPI_TYPECLASS_REAL = 1,
+    MPI_TYPECLASS_INTEGER = 2,
+    MPI_TYPECLASS_COMPLEX = 3,
 };
 
 #define MPI_ARGV_NULL 0
+#define MPI_STATUS_IGNORE ((MPI_Status *)0)
+#define MPI_STATUSES_IGNORE ((MPI_Status *)0)
+#define MPI_IN_PLACE ((void *)-1)
 
 // MPI Routines
 #ifdef __cplusplus
@@ -114,6 +172,80 @@ EMPIRE_IMPORT MPI_Comm MPI_COMM_NULL;
 
 EMPIRE_IMPORT MPI_Info MPI_INFO_NULL;
 
+EMPIRE_IMPORT MPI_Request MPI_REQUEST_NULL;
+
+EMPIRE_IMPORT MPI_Message MPI_MESSAGE_NULL;
+EMPIRE_IMPORT MPI_Message MPI_MESSAGE_NO_PROC;
+
+EMPIRE_IMPORT MPI_Datatype MPI_DATATYPE_NULL;
+
+// C types
+EMPIRE_IMPORT MPI_Datatype MPI_CHAR;
+EMPIRE_IMPORT MPI_Datatype MPI_SHORT;
+EMPIRE_IMPORT MPI_Datatype MPI_INT;
+EMPIRE_IMPORT MPI_Datatype MPI_LONG;
+EMPIRE_IMPORT MPI_Datatype MPI_LONG_LONG_INT;
+EMPIRE_IMPORT MPI_Datatype MPI_LONG_LONG;
+EMPIRE_IMPORT MPI_Datatype MPI_SIGNED_CHAR;
+EMPIRE_IMPORT MPI_Datatype MPI_UNSIGNED_CHAR;
+EMPIRE_IMPORT MPI_Datatype MPI_UNSIGNED_SHORT;
+EMPIRE_IMPORT MPI_Datatype MPI_UNSIGNED;
+EMPIRE_IMPORT MPI_Datatype MPI_UNSIGNED_LONG;
+EMPIRE_IMPORT MPI_Datatype MPI_UNSIGNED_LONG_LONG;
+EMPIRE_IMPORT MPI_Datatype MPI_FLOAT;
+EMPIRE_IMPORT MPI_Datatype MPI_DOUBLE;
+EMPIRE_IMPORT MPI_Datatype MPI_LONG_DOUBLE;
+EMPIRE_IMPORT MPI_Datatype MPI_WCHAR;
+EMPIRE_IMPORT MPI_Datatype MPI_C_BOOL;
+EMPIRE_IMPORT MPI_Datatype MPI_INT8_T;
+EMPIRE_IMPORT MPI_Datatype MPI_INT16_T;
+EMPIRE_IMPORT MPI_Datatype MPI_INT32_T;
+EMPIRE_IMPORT MPI_Datatype MPI_INT64_T;
+EMPIRE_IMPORT MPI_Datatype MPI_UINT8_T;
+EMPIRE_IMPORT MPI_Datatype MPI_UINT16_T;
+EMPIRE_IMPORT MPI_Datatype MPI_UINT32_T;
+EMPIRE_IMPORT MPI_Datatype MPI_UINT64_T;
+EMPIRE_IMPORT MPI_Datatype MPI_AINT;
+EMPIRE_IMPORT MPI_Datatype MPI_COUNT;
+EMPIRE_IMPORT MPI_Datatype MPI_OFFSET;
+EMPIRE_IMPORT MPI_Datatype MPI_C_COMPLEX;
+EMPIRE_IMPORT MPI_Datatype MPI_C_FLOAT_COMPLEX;
+EMPIRE_IMPORT MPI_Datatype MPI_C_DOUBLE_COMPLEX;
+EMPIRE_IMPORT MPI_Datatype MPI_C_LONG_DOUBLE_COMPLEX;
+EMPIRE_IMPORT MPI_Datatype MPI_BYTE;
+EMPIRE_IMPORT MPI_Datatype MPI_PACKED;
+
+// C++ types
+EMPIRE_IMPORT MPI_Datatype MPI_CXX_BOOL;
+EMPIRE_IMPORT MPI_Datatype MPI_CXX_FLOAT_COMPLEX;
+EMPIRE_IMPORT MPI_Datatype MPI_CXX_DOUBLE_COMPLEX;
+EMPIRE_IMPORT MPI_Datatype MPI_CXX_LONG_DOUBLE_COMPLEX;
+
+// Value and int pairs for MPI_MINLOC and MPI_MAXLOC
+EMPIRE_IMPORT MPI_Datatype MPI_FLOAT_INT;
+EMPIRE_IMPORT MPI_Datatype MPI_DOUBLE_INT;
+EMPIRE_IMPORT MPI_Datatype MPI_LONG_INT;
+EMPIRE_IMPORT MPI_Datatype MPI_2INT;
+EMPIRE_IMPORT MPI_Datatype MPI_SHORT_INT;
+EMPIRE_IMPORT MPI_Datatype MPI_LONG_DOUBLE_INT;
+
+// Reduction operations
+EMPIRE_IMPORT MPI_Op MPI_OP_NULL;
+EMPIRE_IMPORT MPI_Op MPI_SUM;
+EMPIRE_IMPORT MPI_Op MPI_PROD;
+EMPIRE_IMPORT MPI_Op MPI_MAX;
+EMPIRE_IMPORT MPI_Op MPI_MIN;
+EMPIRE_IMPORT MPI_Op MPI_LAND;
+EMPIRE_IMPORT MPI_Op MPI_BAND;
+EMPIRE_IMPORT MPI_Op MPI_LOR;
+EMPIRE_IMPORT MPI_Op MPI_BOR;
+EMPIRE_IMPORT MPI_Op MPI_LXOR;
+EMPIRE_IMPORT MPI_Op MPI_BXOR;
+EMPIRE_IMPORT MPI_Op MPI_MAXLOC;
+EMPIRE_IMPORT MPI_Op MPI_MINLOC;
+EMPIRE_IMPORT MPI_Op MPI_REPLACE;
+EMPIRE_IMPORT MPI_Op MPI_NO_OP;
+
 // Library initialization
 EMPIRE_IMPORT int MPI_Init(int *argc, char ***argv);
 
@@ -127,99 +259,2352 @@ EMPIRE_IMPORT int MPI_Finalize();
 EMPIRE_IMPORT int MPI_Comm_rank(MPI_Comm comm, int *rank);
 EMPIRE_IMPORT int MPI_Comm_size(MPI_Comm comm, int *size);
 EMPIRE_IMPORT int MPI_Comm_test_inter(MPI_Comm comm, int *flag);
+EMPIRE_IMPORT int MPI_Comm_remote_size(MPI_Comm comm, int *size);
+EMPIRE_IMPORT int MPI_Comm_set_info(MPI_Comm comm, MPI_Info info);
 
-// Section 6
-// Section 6.4
-int MPI_Comm_free(MPI_Comm *comm);
+// Section 3
+// Section 3.2
+EMPIRE_IMPORT
+int
+MPI_Send(
+    const void *buf,
+    int count,
+    MPI_Datatype datatype,
+    int dest,
+    int tag,
+    MPI_Comm comm);
 
-// Section 9
-EMPIRE_IMPORT int MPI_Info_create(MPI_Info *info);
-EMPIRE_IMPORT int MPI_Info_set(MPI_Info info, const char *key, const char *value);
-EMPIRE_IMPORT int MPI_Info_delete(MPI_Info info, const char *key);
+EMPIRE_IMPORT
+int
+MPI_Send_c(
+    const void *buf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    int dest,
+    int tag,
+    MPI_Comm comm);
 
 EMPIRE_IMPORT
 int
-MPI_Info_get(
-    MPI_Info info,
-    const char *key,
-    int valuelen,
-    char *value,
-    int *flag);
+MPI_Recv(
+    void *buf,
+    int count,
+    MPI_Datatype datatype,
+    int source,
+    int tag,
+    MPI_Comm comm,
+    MPI_Status *status);
 
 EMPIRE_IMPORT
 int
-MPI_Info_get_valuelen(
-    MPI_Info info,
-    const char *key,
-    int *valuelen,
-    int *flag);
+MPI_Recv_c(
+    void *buf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    int source,
+    int tag,
+    MPI_Comm comm,
+    MPI_Status *status);
 
-EMPIRE_IMPORT int MPI_Info_get_nkeys(MPI_Info info, int *nkeys);
-EMPIRE_IMPORT int MPI_Info_get_nthkey(MPI_Info info, int n, char *key);
-EMPIRE_IMPORT int MPI_Info_dup(MPI_Info info, MPI_Info *newinfo);
-EMPIRE_IMPORT int MPI_Info_free(MPI_Info *info);
+EMPIRE_IMPORT int MPI_Get_count(const MPI_Status *status, MPI_Datatype datatype, int *count);
 
-// Section 10
-// Port routines
-EMPIRE_IMPORT int MPI_Open_port(MPI_Info info, char *port_name);
-EMPIRE_IMPORT int MPI_Close_port(char *port_name);
+EMPIRE_IMPORT
+int
+MPI_Get_count_c(
+    const MPI_Status *status,
+    MPI_Datatype datatype,
+    MPI_Count *count);
 
-// Section 10.3
+// Section 3.4
 EMPIRE_IMPORT
 int
-MPI_Comm_spawn(
-    const char *command,
-    char *argv[],
-    int maxprocs,
-    MPI_Info info,
-    int root,
+MPI_Bsend(
+    const void *buf,
+    int count,
+    MPI_Datatype datatype,
+    int dest,
+    int tag,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Bsend_c(
+    const void *buf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    int dest,
+    int tag,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Ssend(
+    const void *buf,
+    int count,
+    MPI_Datatype datatype,
+    int dest,
+    int tag,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Ssend_c(
+    const void *buf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    int dest,
+    int tag,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Rsend(
+    const void *buf,
+    int count,
+    MPI_Datatype datatype,
+    int dest,
+    int tag,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Rsend_c(
+    const void *buf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    int dest,
+    int tag,
+    MPI_Comm comm);
+
+// Section 3.6
+EMPIRE_IMPORT int MPI_Buffer_attach(void *buffer, int size);
+EMPIRE_IMPORT int MPI_Buffer_attach_c(void *buffer, MPI_Count size);
+EMPIRE_IMPORT int MPI_Buffer_detach(void *buffer_addr, int *size);
+EMPIRE_IMPORT int MPI_Buffer_detach_c(void *buffer_addr, MPI_Count *size);
+
+// Section 3.7
+EMPIRE_IMPORT
+int
+MPI_Isend(
+    const void *buf,
+    int count,
+    MPI_Datatype datatype,
+    int dest,
+    int tag,
     MPI_Comm comm,
-    MPI_Comm *intercomm,
-    int array_of_errcodes[]);
+    MPI_Request *request);
 
-#ifdef _WIN32
 EMPIRE_IMPORT
 int
-MPI_Comm_spawnW(
-    const wchar_t *command,
-    wchar_t *argv[],
-    int maxprocs,
-    MPI_Info info,
-    int root,
+MPI_Isend_c(
+    const void *buf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    int dest,
+    int tag,
     MPI_Comm comm,
-    MPI_Comm *intercomm,
-    int array_of_errcodes[]);
-#endif
+    MPI_Request *request);
 
-EMPIRE_IMPORT int MPI_Comm_get_parent(MPI_Comm *parent);
+EMPIRE_IMPORT
+int
+MPI_Ibsend(
+    const void *buf,
+    int count,
+    MPI_Datatype datatype,
+    int dest,
+    int tag,
+    MPI_Comm comm,
+    MPI_Request *request);
 
 EMPIRE_IMPORT
 int
-MPI_Comm_spawn_multiple(
+MPI_Ibsend_c(
+    const void *buf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    int dest,
+    int tag,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Issend(
+    const void *buf,
     int count,
-    char *array_of_commands[],
-    char **array_of_argv[],
-    const int array_of_maxprocs[],
-    const MPI_Info array_of_info[],
-    int root,
+    MPI_Datatype datatype,
+    int dest,
+    int tag,
     MPI_Comm comm,
-    MPI_Comm *intercomm,
-    int array_of_errcodes[]);
-    
-#ifdef _WIN32
+    MPI_Request *request);
+
 EMPIRE_IMPORT
 int
-MPI_Comm_spawn_multipleW(
+MPI_Issend_c(
+    const void *buf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    int dest,
+    int tag,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Irsend(
+    const void *buf,
     int count,
-    wchar_t *array_of_commands[],
-    wchar_t **array_of_argv[],
-    const int array_of_maxprocs[],
-    const MPI_Info array_of_info[],
-    int root,
+    MPI_Datatype datatype,
+    int dest,
+    int tag,
     MPI_Comm comm,
-    MPI_Comm *intercomm,
-    int array_of_errcodes[]);
-#endif
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Irsend_c(
+    const void *buf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    int dest,
+    int tag,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Irecv(
+    void *buf,
+    int count,
+    MPI_Datatype datatype,
+    int source,
+    int tag,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Irecv_c(
+    void *buf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    int source,
+    int tag,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT int MPI_Wait(MPI_Request *request, MPI_Status *status);
+EMPIRE_IMPORT int MPI_Test(MPI_Request *request, int *flag, MPI_Status *status);
+EMPIRE_IMPORT int MPI_Request_get_status(MPI_Request request, int *flag, MPI_Status *status);
+EMPIRE_IMPORT int MPI_Request_free(MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Waitany(
+    int count,
+    MPI_Request array_of_requests[],
+    int *index,
+    MPI_Status *status);
+
+EMPIRE_IMPORT
+int
+MPI_Testany(
+    int count,
+    MPI_Request array_of_requests[],
+    int *index,
+    int *flag,
+    MPI_Status *status);
+
+EMPIRE_IMPORT
+int
+MPI_Waitall(
+    int count,
+    MPI_Request array_of_requests[],
+    MPI_Status array_of_statuses[]);
+
+EMPIRE_IMPORT
+int
+MPI_Testall(
+    int count,
+    MPI_Request array_of_requests[],
+    int *flag,
+    MPI_Status array_of_statuses[]);
+
+EMPIRE_IMPORT
+int
+MPI_Waitsome(
+    int incount,
+    MPI_Request array_of_requests[],
+    int *outcount,
+    int array_of_indices[],
+    MPI_Status array_of_statuses[]);
+
+EMPIRE_IMPORT
+int
+MPI_Testsome(
+    int incount,
+    MPI_Request array_of_requests[],
+    int *outcount,
+    int array_of_indices[],
+    MPI_Status array_of_statuses[]);
+
+// Section 3.8
+EMPIRE_IMPORT int MPI_Probe(int source, int tag, MPI_Comm comm, MPI_Status *status);
+EMPIRE_IMPORT int MPI_Iprobe(int source, int tag, MPI_Comm comm, int *flag, MPI_Status *status);
+
+EMPIRE_IMPORT
+int
+MPI_Mprobe(
+    int source,
+    int tag,
+    MPI_Comm comm,
+    MPI_Message *message,
+    MPI_Status *status);
+
+EMPIRE_IMPORT
+int
+MPI_Improbe(
+    int source,
+    int tag,
+    MPI_Comm comm,
+    int *flag,
+    MPI_Message *message,
+    MPI_Status *status);
+
+EMPIRE_IMPORT
+int
+MPI_Mrecv(
+    void *buf,
+    int count,
+    MPI_Datatype datatype,
+    MPI_Message *message,
+    MPI_Status *status);
+
+EMPIRE_IMPORT
+int
+MPI_Mrecv_c(
+    void *buf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    MPI_Message *message,
+    MPI_Status *status);
+
+EMPIRE_IMPORT
+int
+MPI_Imrecv(
+    void *buf,
+    int count,
+    MPI_Datatype datatype,
+    MPI_Message *message,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Imrecv_c(
+    void *buf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    MPI_Message *message,
+    MPI_Request *request);
+
+EMPIRE_IMPORT int MPI_Cancel(MPI_Request *request);
+EMPIRE_IMPORT int MPI_Test_cancelled(const MPI_Status *status, int *flag);
+
+// Section 3.9
+EMPIRE_IMPORT
+int
+MPI_Send_init(
+    const void *buf,
+    int count,
+    MPI_Datatype datatype,
+    int dest,
+    int tag,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Send_init_c(
+    const void *buf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    int dest,
+    int tag,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Bsend_init(
+    const void *buf,
+    int count,
+    MPI_Datatype datatype,
+    int dest,
+    int tag,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Bsend_init_c(
+    const void *buf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    int dest,
+    int tag,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Ssend_init(
+    const void *buf,
+    int count,
+    MPI_Datatype datatype,
+    int dest,
+    int tag,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Ssend_init_c(
+    const void *buf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    int dest,
+    int tag,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Rsend_init(
+    const void *buf,
+    int count,
+    MPI_Datatype datatype,
+    int dest,
+    int tag,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Rsend_init_c(
+    const void *buf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    int dest,
+    int tag,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Recv_init(
+    void *buf,
+    int count,
+    MPI_Datatype datatype,
+    int source,
+    int tag,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Recv_init_c(
+    void *buf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    int source,
+    int tag,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT int MPI_Start(MPI_Request *request);
+EMPIRE_IMPORT int MPI_Startall(int count, MPI_Request array_of_requests[]);
+
+// Section 3.10
+EMPIRE_IMPORT
+int
+MPI_Sendrecv(
+    const void *sendbuf,
+    int sendcount,
+    MPI_Datatype sendtype,
+    int dest,
+    int sendtag,
+    void *recvbuf,
+    int recvcount,
+    MPI_Datatype recvtype,
+    int source,
+    int recvtag,
+    MPI_Comm comm,
+    MPI_Status *status);
+
+EMPIRE_IMPORT
+int
+MPI_Sendrecv_c(
+    const void *sendbuf,
+    MPI_Count sendcount,
+    MPI_Datatype sendtype,
+    int dest,
+    int sendtag,
+    void *recvbuf,
+    MPI_Count recvcount,
+    MPI_Datatype recvtype,
+    int source,
+    int recvtag,
+    MPI_Comm comm,
+    MPI_Status *status);
+
+EMPIRE_IMPORT
+int
+MPI_Isendrecv(
+    const void *sendbuf,
+    int sendcount,
+    MPI_Datatype sendtype,
+    int dest,
+    int sendtag,
+    void *recvbuf,
+    int recvcount,
+    MPI_Datatype recvtype,
+    int source,
+    int recvtag,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Isendrecv_c(
+    const void *sendbuf,
+    MPI_Count sendcount,
+    MPI_Datatype sendtype,
+    int dest,
+    int sendtag,
+    void *recvbuf,
+    MPI_Count recvcount,
+    MPI_Datatype recvtype,
+    int source,
+    int recvtag,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Sendrecv_replace(
+    void *buf,
+    int count,
+    MPI_Datatype datatype,
+    int dest,
+    int sendtag,
+    int source,
+    int recvtag,
+    MPI_Comm comm,
+    MPI_Status *status);
+
+EMPIRE_IMPORT
+int
+MPI_Sendrecv_replace_c(
+    void *buf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    int dest,
+    int sendtag,
+    int source,
+    int recvtag,
+    MPI_Comm comm,
+    MPI_Status *status);
+
+EMPIRE_IMPORT
+int
+MPI_Isendrecv_replace(
+    void *buf,
+    int count,
+    MPI_Datatype datatype,
+    int dest,
+    int sendtag,
+    int source,
+    int recvtag,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Isendrecv_replace_c(
+    void *buf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    int dest,
+    int sendtag,
+    int source,
+    int recvtag,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+// Section 4
+// Section 4.2
+EMPIRE_IMPORT
+int
+MPI_Psend_init(
+    const void *buf,
+    int partitions,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    int dest,
+    int tag,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Precv_init(
+    void *buf,
+    int partitions,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    int source,
+    int tag,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT int MPI_Pready(int partition, MPI_Request request);
+EMPIRE_IMPORT int MPI_Pready_range(int partition_low, int partition_high, MPI_Request request);
+
+EMPIRE_IMPORT
+int
+MPI_Pready_list(
+    int length,
+    const int array_of_partitions[],
+    MPI_Request request);
+
+EMPIRE_IMPORT int MPI_Parrived(MPI_Request request, int partition, int *flag);
+
+// Section 5
+// Section 5.1
+EMPIRE_IMPORT int MPI_Type_contiguous(int count, MPI_Datatype oldtype, MPI_Datatype *newtype);
+
+EMPIRE_IMPORT
+int
+MPI_Type_contiguous_c(
+    MPI_Count count,
+    MPI_Datatype oldtype,
+    MPI_Datatype *newtype);
+
+EMPIRE_IMPORT
+int
+MPI_Type_vector(
+    int count,
+    int blocklength,
+    int stride,
+    MPI_Datatype oldtype,
+    MPI_Datatype *newtype);
+
+EMPIRE_IMPORT
+int
+MPI_Type_vector_c(
+    MPI_Count count,
+    MPI_Count blocklength,
+    MPI_Count stride,
+    MPI_Datatype oldtype,
+    MPI_Datatype *newtype);
+
+EMPIRE_IMPORT
+int
+MPI_Type_create_hvector(
+    int count,
+    int blocklength,
+    MPI_Aint stride,
+    MPI_Datatype oldtype,
+    MPI_Datatype *newtype);
+
+EMPIRE_IMPORT
+int
+MPI_Type_create_hvector_c(
+    MPI_Count count,
+    MPI_Count blocklength,
+    MPI_Count stride,
+    MPI_Datatype oldtype,
+    MPI_Datatype *newtype);
+
+EMPIRE_IMPORT
+int
+MPI_Type_indexed(
+    int count,
+    const int array_of_blocklengths[],
+    const int array_of_displacements[],
+    MPI_Datatype oldtype,
+    MPI_Datatype *newtype);
+
+EMPIRE_IMPORT
+int
+MPI_Type_indexed_c(
+    MPI_Count count,
+    const MPI_Count array_of_blocklengths[],
+    const MPI_Count array_of_displacements[],
+    MPI_Datatype oldtype,
+    MPI_Datatype *newtype);
+
+EMPIRE_IMPORT
+int
+MPI_Type_create_hindexed(
+    int count,
+    const int array_of_blocklengths[],
+    const MPI_Aint array_of_displacements[],
+    MPI_Datatype oldtype,
+    MPI_Datatype *newtype);
+
+EMPIRE_IMPORT
+int
+MPI_Type_create_hindexed_c(
+    MPI_Count count,
+    const MPI_Count array_of_blocklengths[],
+    const MPI_Count array_of_displacements[],
+    MPI_Datatype oldtype,
+    MPI_Datatype *newtype);
+
+EMPIRE_IMPORT
+int
+MPI_Type_create_indexed_block(
+    int count,
+    int blocklength,
+    const int array_of_displacements[],
+    MPI_Datatype oldtype,
+    MPI_Datatype *newtype);
+
+EMPIRE_IMPORT
+int
+MPI_Type_create_indexed_block_c(
+    MPI_Count count,
+    MPI_Count blocklength,
+    const MPI_Count array_of_displacements[],
+    MPI_Datatype oldtype,
+    MPI_Datatype *newtype);
+
+EMPIRE_IMPORT
+int
+MPI_Type_create_struct(
+    int count,
+    const int array_of_blocklengths[],
+    const MPI_Aint array_of_displacements[],
+    const MPI_Datatype array_of_types[],
+    MPI_Datatype *newtype);
+
+EMPIRE_IMPORT
+int
+MPI_Type_create_struct_c(
+    MPI_Count count,
+    const MPI_Count array_of_blocklengths[],
+    const MPI_Count array_of_displacements[],
+    const MPI_Datatype array_of_types[],
+    MPI_Datatype *newtype);
+
+EMPIRE_IMPORT
+int
+MPI_Type_create_subarray(
+    int ndims,
+    const int array_of_sizes[],
+    const int array_of_subsizes[],
+    const int array_of_starts[],
+    int order,
+    MPI_Datatype oldtype,
+    MPI_Datatype *newtype);
+
+EMPIRE_IMPORT
+int
+MPI_Type_create_subarray_c(
+    int ndims,
+    const MPI_Count array_of_sizes[],
+    const MPI_Count array_of_subsizes[],
+    const MPI_Count array_of_starts[],
+    int order,
+    MPI_Datatype oldtype,
+    MPI_Datatype *newtype);
+
+EMPIRE_IMPORT
+int
+MPI_Type_create_darray(
+    int size,
+    int rank,
+    int ndims,
+    const int array_of_gsizes[],
+    const int array_of_distribs[],
+    const int array_of_dargs[],
+    const int array_of_psizes[],
+    int order,
+    MPI_Datatype oldtype,
+    MPI_Datatype *newtype);
+
+EMPIRE_IMPORT
+int
+MPI_Type_create_darray_c(
+    int size,
+    int rank,
+    int ndims,
+    const MPI_Count array_of_gsizes[],
+    const int array_of_distribs[],
+    const int array_of_dargs[],
+    const int array_of_psizes[],
+    int order,
+    MPI_Datatype oldtype,
+    MPI_Datatype *newtype);
+
+EMPIRE_IMPORT
+int
+MPI_Type_create_resized(
+    MPI_Datatype oldtype,
+    MPI_Aint lb,
+    MPI_Aint extent,
+    MPI_Datatype *newtype);
+
+EMPIRE_IMPORT
+int
+MPI_Type_create_resized_c(
+    MPI_Datatype oldtype,
+    MPI_Count lb,
+    MPI_Count extent,
+    MPI_Datatype *newtype);
+
+EMPIRE_IMPORT int MPI_Type_size(MPI_Datatype datatype, int *size);
+EMPIRE_IMPORT int MPI_Type_size_c(MPI_Datatype datatype, MPI_Count *size);
+EMPIRE_IMPORT int MPI_Type_get_extent(MPI_Datatype datatype, MPI_Aint *lb, MPI_Aint *extent);
+EMPIRE_IMPORT int MPI_Type_get_extent_c(MPI_Datatype datatype, MPI_Count *lb, MPI_Count *extent);
+
+EMPIRE_IMPORT
+int
+MPI_Type_get_true_extent(MPI_Datatype datatype, MPI_Aint *true_lb, MPI_Aint *true_extent);
+
+EMPIRE_IMPORT
+int
+MPI_Type_get_true_extent_c(MPI_Datatype datatype, MPI_Count *true_lb, MPI_Count *true_extent);
+
+EMPIRE_IMPORT int MPI_Type_commit(MPI_Datatype *datatype);
+EMPIRE_IMPORT int MPI_Type_dup(MPI_Datatype oldtype, MPI_Datatype *newtype);
+EMPIRE_IMPORT int MPI_Type_free(MPI_Datatype *datatype);
+
+EMPIRE_IMPORT int MPI_Get_elements(const MPI_Status *status, MPI_Datatype datatype, int *count);
+
+EMPIRE_IMPORT
+int
+MPI_Get_elements_c(
+    const MPI_Status *status,
+    MPI_Datatype datatype,
+    MPI_Count *count);
+
+EMPIRE_IMPORT
+int
+MPI_Get_elements_x(const MPI_Status *status, MPI_Datatype datatype, MPI_Count *count);
+
+EMPIRE_IMPORT int MPI_Get_address(const void *location, MPI_Aint *address);
+EMPIRE_IMPORT MPI_Aint MPI_Aint_add(MPI_Aint base, MPI_Aint disp);
+EMPIRE_IMPORT MPI_Aint MPI_Aint_diff(MPI_Aint addr1, MPI_Aint addr2);
+
+EMPIRE_IMPORT
+int
+MPI_Type_get_envelope(
+    MPI_Datatype datatype,
+    int *num_integers,
+    int *num_addresses,
+    int *num_datatypes,
+    int *combiner);
+
+EMPIRE_IMPORT
+int
+MPI_Type_get_envelope_c(
+    MPI_Datatype datatype,
+    MPI_Count *num_integers,
+    MPI_Count *num_addresses,
+    MPI_Count *num_large_counts,
+    MPI_Count *num_datatypes,
+    int *combiner);
+
+EMPIRE_IMPORT
+int
+MPI_Type_get_contents(
+    MPI_Datatype datatype,
+    int max_integers,
+    int max_addresses,
+    int max_datatypes,
+    int array_of_integers[],
+    MPI_Aint array_of_addresses[],
+    MPI_Datatype array_of_datatypes[]);
+
+EMPIRE_IMPORT
+int
+MPI_Type_get_contents_c(
+    MPI_Datatype datatype,
+    MPI_Count max_integers,
+    MPI_Count max_addresses,
+    MPI_Count max_large_counts,
+    MPI_Count max_datatypes,
+    int array_of_integers[],
+    MPI_Aint array_of_addresses[],
+    MPI_Count array_of_large_counts[],
+    MPI_Datatype array_of_datatypes[]);
+
+// Section 5.2
+EMPIRE_IMPORT
+int
+MPI_Pack(
+    const void *inbuf,
+    int incount,
+    MPI_Datatype datatype,
+    void *outbuf,
+    int outsize,
+    int *position,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Pack_c(
+    const void *inbuf,
+    MPI_Count incount,
+    MPI_Datatype datatype,
+    void *outbuf,
+    MPI_Count outsize,
+    MPI_Count *position,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Unpack(
+    const void *inbuf,
+    int insize,
+    int *position,
+    void *outbuf,
+    int outcount,
+    MPI_Datatype datatype,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Unpack_c(
+    const void *inbuf,
+    MPI_Count insize,
+    MPI_Count *position,
+    void *outbuf,
+    MPI_Count outcount,
+    MPI_Datatype datatype,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT int MPI_Pack_size(int incount, MPI_Datatype datatype, MPI_Comm comm, int *size);
+
+EMPIRE_IMPORT
+int
+MPI_Pack_size_c(
+    MPI_Count incount,
+    MPI_Datatype datatype,
+    MPI_Comm comm,
+    MPI_Count *size);
+
+// Section 5.3
+EMPIRE_IMPORT
+int
+MPI_Pack_external(
+    const char datarep[],
+    const void *inbuf,
+    int incount,
+    MPI_Datatype datatype,
+    void *outbuf,
+    MPI_Aint outsize,
+    MPI_Aint *position);
+
+EMPIRE_IMPORT
+int
+MPI_Pack_external_c(
+    const char datarep[],
+    const void *inbuf,
+    MPI_Count incount,
+    MPI_Datatype datatype,
+    void *outbuf,
+    MPI_Count outsize,
+    MPI_Count *position);
+
+EMPIRE_IMPORT
+int
+MPI_Unpack_external(
+    const char datarep[],
+    const void *inbuf,
+    MPI_Aint insize,
+    MPI_Aint *position,
+    void *outbuf,
+    int outcount,
+    MPI_Datatype datatype);
+
+EMPIRE_IMPORT
+int
+MPI_Unpack_external_c(
+    const char datarep[],
+    const void *inbuf,
+    MPI_Count insize,
+    MPI_Count *position,
+    void *outbuf,
+    MPI_Count outcount,
+    MPI_Datatype datatype);
+
+EMPIRE_IMPORT
+int
+MPI_Pack_external_size(
+    const char datarep[],
+    int incount,
+    MPI_Datatype datatype,
+    MPI_Aint *size);
+
+EMPIRE_IMPORT
+int
+MPI_Pack_external_size_c(
+    const char datarep[],
+    MPI_Count incount,
+    MPI_Datatype datatype,
+    MPI_Count *size);
+
+// Section 6
+// Section 6.3
+EMPIRE_IMPORT int MPI_Barrier(MPI_Comm comm);
+
+// Section 6.4
+EMPIRE_IMPORT
+int
+MPI_Bcast(
+    void *buffer,
+    int count,
+    MPI_Datatype datatype,
+    int root,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Bcast_c(
+    void *buffer,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    int root,
+    MPI_Comm comm);
+
+// Section 6.5
+EMPIRE_IMPORT
+int
+MPI_Gather(
+    const void *sendbuf,
+    int sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    int recvcount,
+    MPI_Datatype recvtype,
+    int root,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Gather_c(
+    const void *sendbuf,
+    MPI_Count sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    MPI_Count recvcount,
+    MPI_Datatype recvtype,
+    int root,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Gatherv(
+    const void *sendbuf,
+    int sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    const int recvcounts[],
+    const int displs[],
+    MPI_Datatype recvtype,
+    int root,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Gatherv_c(
+    const void *sendbuf,
+    MPI_Count sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    const MPI_Count recvcounts[],
+    const MPI_Aint displs[],
+    MPI_Datatype recvtype,
+    int root,
+    MPI_Comm comm);
+
+// Section 6.6
+EMPIRE_IMPORT
+int
+MPI_Scatter(
+    const void *sendbuf,
+    int sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    int recvcount,
+    MPI_Datatype recvtype,
+    int root,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Scatter_c(
+    const void *sendbuf,
+    MPI_Count sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    MPI_Count recvcount,
+    MPI_Datatype recvtype,
+    int root,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Scatterv(
+    const void *sendbuf,
+    const int sendcounts[],
+    const int displs[],
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    int recvcount,
+    MPI_Datatype recvtype,
+    int root,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Scatterv_c(
+    const void *sendbuf,
+    const MPI_Count sendcounts[],
+    const MPI_Aint displs[],
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    MPI_Count recvcount,
+    MPI_Datatype recvtype,
+    int root,
+    MPI_Comm comm);
+
+// Section 6.7
+EMPIRE_IMPORT
+int
+MPI_Allgather(
+    const void *sendbuf,
+    int sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    int recvcount,
+    MPI_Datatype recvtype,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Allgather_c(
+    const void *sendbuf,
+    MPI_Count sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    MPI_Count recvcount,
+    MPI_Datatype recvtype,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Allgatherv(
+    const void *sendbuf,
+    int sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    const int recvcounts[],
+    const int displs[],
+    MPI_Datatype recvtype,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Allgatherv_c(
+    const void *sendbuf,
+    MPI_Count sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    const MPI_Count recvcounts[],
+    const MPI_Aint displs[],
+    MPI_Datatype recvtype,
+    MPI_Comm comm);
+
+// Section 6.8
+EMPIRE_IMPORT
+int
+MPI_Alltoall(
+    const void *sendbuf,
+    int sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    int recvcount,
+    MPI_Datatype recvtype,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Alltoall_c(
+    const void *sendbuf,
+    MPI_Count sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    MPI_Count recvcount,
+    MPI_Datatype recvtype,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Alltoallv(
+    const void *sendbuf,
+    const int sendcounts[],
+    const int sdispls[],
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    const int recvcounts[],
+    const int rdispls[],
+    MPI_Datatype recvtype,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Alltoallv_c(
+    const void *sendbuf,
+    const MPI_Count sendcounts[],
+    const MPI_Aint sdispls[],
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    const MPI_Count recvcounts[],
+    const MPI_Aint rdispls[],
+    MPI_Datatype recvtype,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Alltoallw(
+    const void *sendbuf,
+    const int sendcounts[],
+    const MPI_Aint sdispls[],
+    const MPI_Datatype sendtypes[],
+    void *recvbuf,
+    const int recvcounts[],
+    const MPI_Aint rdispls[],
+    const MPI_Datatype recvtypes[],
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Alltoallw_c(
+    const void *sendbuf,
+    const MPI_Count sendcounts[],
+    const MPI_Aint sdispls[],
+    const MPI_Datatype sendtypes[],
+    void *recvbuf,
+    const MPI_Count recvcounts[],
+    const MPI_Aint rdispls[],
+    const MPI_Datatype recvtypes[],
+    MPI_Comm comm);
+
+// Section 6.9
+EMPIRE_IMPORT
+int
+MPI_Reduce(
+    const void *sendbuf,
+    void *recvbuf,
+    int count,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    int root,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Reduce_c(
+    const void *sendbuf,
+    void *recvbuf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    int root,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Allreduce(
+    const void *sendbuf,
+    void *recvbuf,
+    int count,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Allreduce_c(
+    const void *sendbuf,
+    void *recvbuf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm);
+
+// Section 6.9.5
+typedef void MPI_User_function(void *invec, void *inoutvec, int *len, MPI_Datatype *datatype);
+typedef void
+MPI_User_function_c(void *invec, void *inoutvec, MPI_Count *len, MPI_Datatype *datatype);
+
+EMPIRE_IMPORT int MPI_Op_create(MPI_User_function *user_fn, int commute, MPI_Op *op);
+EMPIRE_IMPORT int MPI_Op_create_c(MPI_User_function_c *user_fn, int commute, MPI_Op *op);
+EMPIRE_IMPORT int MPI_Op_free(MPI_Op *op);
+
+// Section 6.9.7
+EMPIRE_IMPORT
+int
+MPI_Reduce_local(
+    const void *inbuf,
+    void *inoutbuf,
+    int count,
+    MPI_Datatype datatype,
+    MPI_Op op);
+
+EMPIRE_IMPORT
+int
+MPI_Reduce_local_c(
+    const void *inbuf,
+    void *inoutbuf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    MPI_Op op);
+
+EMPIRE_IMPORT int MPI_Op_commutative(MPI_Op op, int *commute);
+
+// Section 6.10
+EMPIRE_IMPORT
+int
+MPI_Reduce_scatter_block(
+    const void *sendbuf,
+    void *recvbuf,
+    int recvcount,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Reduce_scatter_block_c(
+    const void *sendbuf,
+    void *recvbuf,
+    MPI_Count recvcount,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Reduce_scatter(
+    const void *sendbuf,
+    void *recvbuf,
+    const int recvcounts[],
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Reduce_scatter_c(
+    const void *sendbuf,
+    void *recvbuf,
+    const MPI_Count recvcounts[],
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm);
+
+// Section 6.11
+EMPIRE_IMPORT
+int
+MPI_Scan(
+    const void *sendbuf,
+    void *recvbuf,
+    int count,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Scan_c(
+    const void *sendbuf,
+    void *recvbuf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Exscan(
+    const void *sendbuf,
+    void *recvbuf,
+    int count,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm);
+
+EMPIRE_IMPORT
+int
+MPI_Exscan_c(
+    const void *sendbuf,
+    void *recvbuf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm);
+
+// Section 6.12
+EMPIRE_IMPORT int MPI_Ibarrier(MPI_Comm comm, MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Ibcast(
+    void *buffer,
+    int count,
+    MPI_Datatype datatype,
+    int root,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Ibcast_c(
+    void *buffer,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    int root,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Igather(
+    const void *sendbuf,
+    int sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    int recvcount,
+    MPI_Datatype recvtype,
+    int root,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Igather_c(
+    const void *sendbuf,
+    MPI_Count sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    MPI_Count recvcount,
+    MPI_Datatype recvtype,
+    int root,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Igatherv(
+    const void *sendbuf,
+    int sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    const int recvcounts[],
+    const int displs[],
+    MPI_Datatype recvtype,
+    int root,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Igatherv_c(
+    const void *sendbuf,
+    MPI_Count sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    const MPI_Count recvcounts[],
+    const MPI_Aint displs[],
+    MPI_Datatype recvtype,
+    int root,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Iscatter(
+    const void *sendbuf,
+    int sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    int recvcount,
+    MPI_Datatype recvtype,
+    int root,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Iscatter_c(
+    const void *sendbuf,
+    MPI_Count sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    MPI_Count recvcount,
+    MPI_Datatype recvtype,
+    int root,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Iscatterv(
+    const void *sendbuf,
+    const int sendcounts[],
+    const int displs[],
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    int recvcount,
+    MPI_Datatype recvtype,
+    int root,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Iscatterv_c(
+    const void *sendbuf,
+    const MPI_Count sendcounts[],
+    const MPI_Aint displs[],
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    MPI_Count recvcount,
+    MPI_Datatype recvtype,
+    int root,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Iallgather(
+    const void *sendbuf,
+    int sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    int recvcount,
+    MPI_Datatype recvtype,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Iallgather_c(
+    const void *sendbuf,
+    MPI_Count sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    MPI_Count recvcount,
+    MPI_Datatype recvtype,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Iallgatherv(
+    const void *sendbuf,
+    int sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    const int recvcounts[],
+    const int displs[],
+    MPI_Datatype recvtype,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Iallgatherv_c(
+    const void *sendbuf,
+    MPI_Count sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    const MPI_Count recvcounts[],
+    const MPI_Aint displs[],
+    MPI_Datatype recvtype,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Ialltoall(
+    const void *sendbuf,
+    int sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    int recvcount,
+    MPI_Datatype recvtype,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Ialltoall_c(
+    const void *sendbuf,
+    MPI_Count sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    MPI_Count recvcount,
+    MPI_Datatype recvtype,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Ialltoallv(
+    const void *sendbuf,
+    const int sendcounts[],
+    const int sdispls[],
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    const int recvcounts[],
+    const int rdispls[],
+    MPI_Datatype recvtype,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Ialltoallv_c(
+    const void *sendbuf,
+    const MPI_Count sendcounts[],
+    const MPI_Aint sdispls[],
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    const MPI_Count recvcounts[],
+    const MPI_Aint rdispls[],
+    MPI_Datatype recvtype,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Ialltoallw(
+    const void *sendbuf,
+    const int sendcounts[],
+    const MPI_Aint sdispls[],
+    const MPI_Datatype sendtypes[],
+    void *recvbuf,
+    const int recvcounts[],
+    const MPI_Aint rdispls[],
+    const MPI_Datatype recvtypes[],
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Ialltoallw_c(
+    const void *sendbuf,
+    const MPI_Count sendcounts[],
+    const MPI_Aint sdispls[],
+    const MPI_Datatype sendtypes[],
+    void *recvbuf,
+    const MPI_Count recvcounts[],
+    const MPI_Aint rdispls[],
+    const MPI_Datatype recvtypes[],
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Ireduce(
+    const void *sendbuf,
+    void *recvbuf,
+    int count,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    int root,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Ireduce_c(
+    const void *sendbuf,
+    void *recvbuf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    int root,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Iallreduce(
+    const void *sendbuf,
+    void *recvbuf,
+    int count,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Iallreduce_c(
+    const void *sendbuf,
+    void *recvbuf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Ireduce_scatter_block(
+    const void *sendbuf,
+    void *recvbuf,
+    int recvcount,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Ireduce_scatter_block_c(
+    const void *sendbuf,
+    void *recvbuf,
+    MPI_Count recvcount,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Ireduce_scatter(
+    const void *sendbuf,
+    void *recvbuf,
+    const int recvcounts[],
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Ireduce_scatter_c(
+    const void *sendbuf,
+    void *recvbuf,
+    const MPI_Count recvcounts[],
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Iscan(
+    const void *sendbuf,
+    void *recvbuf,
+    int count,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Iscan_c(
+    const void *sendbuf,
+    void *recvbuf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Iexscan(
+    const void *sendbuf,
+    void *recvbuf,
+    int count,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Iexscan_c(
+    const void *sendbuf,
+    void *recvbuf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm,
+    MPI_Request *request);
+
+// Section 6.13
+EMPIRE_IMPORT int MPI_Barrier_init(MPI_Comm comm, MPI_Info info, MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Bcast_init(
+    void *buffer,
+    int count,
+    MPI_Datatype datatype,
+    int root,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Bcast_init_c(
+    void *buffer,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    int root,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Gather_init(
+    const void *sendbuf,
+    int sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    int recvcount,
+    MPI_Datatype recvtype,
+    int root,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Gather_init_c(
+    const void *sendbuf,
+    MPI_Count sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    MPI_Count recvcount,
+    MPI_Datatype recvtype,
+    int root,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Gatherv_init(
+    const void *sendbuf,
+    int sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    const int recvcounts[],
+    const int displs[],
+    MPI_Datatype recvtype,
+    int root,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Gatherv_init_c(
+    const void *sendbuf,
+    MPI_Count sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    const MPI_Count recvcounts[],
+    const MPI_Aint displs[],
+    MPI_Datatype recvtype,
+    int root,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Scatter_init(
+    const void *sendbuf,
+    int sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    int recvcount,
+    MPI_Datatype recvtype,
+    int root,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Scatter_init_c(
+    const void *sendbuf,
+    MPI_Count sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    MPI_Count recvcount,
+    MPI_Datatype recvtype,
+    int root,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Scatterv_init(
+    const void *sendbuf,
+    const int sendcounts[],
+    const int displs[],
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    int recvcount,
+    MPI_Datatype recvtype,
+    int root,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Scatterv_init_c(
+    const void *sendbuf,
+    const MPI_Count sendcounts[],
+    const MPI_Aint displs[],
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    MPI_Count recvcount,
+    MPI_Datatype recvtype,
+    int root,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Allgather_init(
+    const void *sendbuf,
+    int sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    int recvcount,
+    MPI_Datatype recvtype,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Allgather_init_c(
+    const void *sendbuf,
+    MPI_Count sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    MPI_Count recvcount,
+    MPI_Datatype recvtype,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Allgatherv_init(
+    const void *sendbuf,
+    int sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    const int recvcounts[],
+    const int displs[],
+    MPI_Datatype recvtype,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Allgatherv_init_c(
+    const void *sendbuf,
+    MPI_Count sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    const MPI_Count recvcounts[],
+    const MPI_Aint displs[],
+    MPI_Datatype recvtype,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Alltoall_init(
+    const void *sendbuf,
+    int sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    int recvcount,
+    MPI_Datatype recvtype,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Alltoall_init_c(
+    const void *sendbuf,
+    MPI_Count sendcount,
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    MPI_Count recvcount,
+    MPI_Datatype recvtype,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Alltoallv_init(
+    const void *sendbuf,
+    const int sendcounts[],
+    const int sdispls[],
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    const int recvcounts[],
+    const int rdispls[],
+    MPI_Datatype recvtype,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Alltoallv_init_c(
+    const void *sendbuf,
+    const MPI_Count sendcounts[],
+    const MPI_Aint sdispls[],
+    MPI_Datatype sendtype,
+    void *recvbuf,
+    const MPI_Count recvcounts[],
+    const MPI_Aint rdispls[],
+    MPI_Datatype recvtype,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Alltoallw_init(
+    const void *sendbuf,
+    const int sendcounts[],
+    const MPI_Aint sdispls[],
+    const MPI_Datatype sendtypes[],
+    void *recvbuf,
+    const int recvcounts[],
+    const MPI_Aint rdispls[],
+    const MPI_Datatype recvtypes[],
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Alltoallw_init_c(
+    const void *sendbuf,
+    const MPI_Count sendcounts[],
+    const MPI_Aint sdispls[],
+    const MPI_Datatype sendtypes[],
+    void *recvbuf,
+    const MPI_Count recvcounts[],
+    const MPI_Aint rdispls[],
+    const MPI_Datatype recvtypes[],
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Reduce_init(
+    const void *sendbuf,
+    void *recvbuf,
+    int count,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    int root,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Reduce_init_c(
+    const void *sendbuf,
+    void *recvbuf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    int root,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Allreduce_init(
+    const void *sendbuf,
+    void *recvbuf,
+    int count,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Allreduce_init_c(
+    const void *sendbuf,
+    void *recvbuf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Reduce_scatter_block_init(
+    const void *sendbuf,
+    void *recvbuf,
+    int recvcount,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Reduce_scatter_block_init_c(
+    const void *sendbuf,
+    void *recvbuf,
+    MPI_Count recvcount,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Reduce_scatter_init(
+    const void *sendbuf,
+    void *recvbuf,
+    const int recvcounts[],
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Reduce_scatter_init_c(
+    const void *sendbuf,
+    void *recvbuf,
+    const MPI_Count recvcounts[],
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Scan_init(
+    const void *sendbuf,
+    void *recvbuf,
+    int count,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Scan_init_c(
+    const void *sendbuf,
+    void *recvbuf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Exscan_init(
+    const void *sendbuf,
+    void *recvbuf,
+    int count,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+EMPIRE_IMPORT
+int
+MPI_Exscan_init_c(
+    const void *sendbuf,
+    void *recvbuf,
+    MPI_Count count,
+    MPI_Datatype datatype,
+    MPI_Op op,
+    MPI_Comm comm,
+    MPI_Info info,
+    MPI_Request *request);
+
+// Section 7
+// Section 7.4
+int MPI_Comm_free(MPI_Comm *comm);
+
+// Section 9
+EMPIRE_IMPORT int MPI_Info_create(MPI_Info *info);
+EMPIRE_IMPORT int MPI_Info_set(MPI_Info info, const char *key, const char *value);
+EMPIRE_IMPORT int MPI_Info_delete(MPI_Info info, const char *key);
+
+EMPIRE_IMPORT
+int
+MPI_Info_get(
+    MPI_Info info,
+    const char *key,
+    int valuelen,
+    char *value,
+    int *flag);
+
+EMPIRE_IMPORT
+int
+MPI_Info_get_valuelen(
+    MPI_Info info,
+    const char *key,
+    int *valuelen,
+    int *flag);
+
+EMPIRE_IMPORT int MPI_Info_get_nkeys(MPI_Info info, int *nkeys);
+EMPIRE_IMPORT int MPI_Info_get_nthkey(MPI_Info info, int n, char *key);
+EMPIRE_IMPORT int MPI_Info_dup(MPI_Info info, MPI_Info *newinfo);
+EMPIRE_IMPORT int MPI_Info_free(MPI_Info *info);
+
+// Section 10
+// Port routines
+EMPIRE_IMPORT int MPI_Open_port(MPI_Info info, char *port_name);
+EMPIRE_IMPORT int MPI_Close_port(char *port_name);
+
+// Section 10.3
+EMPIRE_IMPORT
+int
+MPI_Comm_spawn(
+    const char *command,
+    char *argv[],
+    int maxprocs,
+    MPI_Info info,
+    int root,
+    MPI_Comm comm,
+    MPI_Comm *intercomm,
+    int array_of_errcodes[]);
+
+#ifdef _WIN32
+EMPIRE_IMPORT
+int
+MPI_Comm_spawnW(
+    const wchar_t *command,
+    wchar_t *argv[],
+    int maxprocs,
+    MPI_Info info,
+    int root,
+    MPI_Comm comm,
+    MPI_Comm *intercomm,
+    int array_of_errcodes[]);
+#endif
+
+EMPIRE_IMPORT int MPI_Comm_get_parent(MPI_Comm *parent);
+
+EMPIRE_IMPORT
+int
+MPI_Comm_spawn_multiple(
+    int count,
+    char *array_of_commands[],
+    char **array_of_argv[],
+    const int array_of_maxprocs[],
+    const MPI_Info array_of_info[],
+    int root,
+    MPI_Comm comm,
+    MPI_Comm *intercomm,
+    int array_of_errcodes[]);
+    
+#ifdef _WIN32
+EMPIRE_IMPORT
+int
+MPI_Comm_spawn_multipleW(
+    int count,
+    wchar_t *array_of_commands[],
+    wchar_t **array_of_argv[],
+    const int array_of_maxprocs[],
+    const MPI_Info array_of_info[],
+    int root,
+    MPI_Comm comm,
+    MPI_Comm *intercomm,
+    int array_of_errcodes[]);
+#endif
+
+// Section 12
+// Section 12.2
+typedef int MPI_Grequest_query_function(void *extra_state, MPI_Status *status);
+typedef int MPI_Grequest_free_function(void *extra_state);
+typedef int MPI_Grequest_cancel_function(void *extra_state, int complete);
+
+EMPIRE_IMPORT
+int
+MPI_Grequest_start(
+    MPI_Grequest_query_function *query_fn,
+    MPI_Grequest_free_function *free_fn,
+    MPI_Grequest_cancel_function *cancel_fn,
+    void *extra_state,
+    MPI_Request *request);
+
+EMPIRE_IMPORT int MPI_Grequest_complete(MPI_Request request);
+
+// Section 12.3
+EMPIRE_IMPORT int MPI_Status_set_elements(MPI_Status *status, MPI_Datatype datatype, int count);
+
+EMPIRE_IMPORT
+int
+MPI_Status_set_elements_x(MPI_Status *status, MPI_Datatype datatype, MPI_Count count);
+
+EMPIRE_IMPORT int MPI_Status_set_cancelled(MPI_Status *status, int flag);
+
+// Section 19
+// Section 19.1
+EMPIRE_IMPORT int MPI_Type_match_size(int typeclass, int size, MPI_Datatype *datatype);
 
 #ifdef __cplusplus
 }
@@ -0,0 +1,1816 @@
+use super::{Error, constants::{MPI_PROC_NULL, MPI_ROOT}, count::{Count, count_of},
+            datatype::{MPI_Aint, MPI_Count, MPI_Datatype}, handles::MPI_Comm, info::MPI_Info,
+            op::MPI_Op, pt2pt::{recv_buffer, send_buffer}, request::MPI_Request};
+
+use conv::*;
+use empire::{Buffer, BufferMut, Comm, Root};
+use std::{slice, os::raw::{c_int, c_void}};
+
+pub fn root(root: c_int, comm: &Comm) -> Result<usize, Error> {
+    match root.value_as::<usize>() {
+        Ok(root) if root < comm.size() => Ok(root),
+        _ => Err(Error::MPI_ERR_ROOT),
+    }
+}
+
+// On an intercommunicator, the root of a rooted collective passes MPI_ROOT, the other ranks of its
+// group MPI_PROC_NULL, and the ranks of the other group the root's rank in it.
+pub fn intercomm_root(root: c_int, comm: &Comm) -> Result<Root, Error> {
+    match root {
+        MPI_ROOT => Ok(Root::Root),
+        MPI_PROC_NULL => Ok(Root::ProcNull),
+        root => match root.value_as::<usize>() {
+            Ok(root) if root < comm.remote_size() => Ok(Root::Rank(root)),
+            _ => Err(Error::MPI_ERR_ROOT),
+        },
+    }
+}
+
+// Scan and Exscan are erroneous on an intercommunicator. Gatherv, Scatter, Scatterv and the
+// reduce-scatters aren't supported on one.
+fn intracomm(comm: &Comm) -> Result<(), Error> {
+    if comm.is_intercomm() {
+        Err(Error::MPI_ERR_COMM)
+    } else {
+        Ok(())
+    }
+}
+
+// MPI_IN_PLACE is the address -1, which no buffer has.
+fn is_in_place(buf: *const c_void) -> bool {
+    buf as isize == -1
+}
+
+// Whether `buf` is MPI_IN_PLACE, which intercommunicators don't allow since every rank's data goes
+// to the other group.
+fn in_place(buf: *const c_void, comm: &Comm) -> Result<bool, Error> {
+    match is_in_place(buf) {
+        true if comm.is_intercomm() => Err(Error::MPI_ERR_BUFFER),
+        in_place => Ok(in_place),
+    }
+}
+
+// A send buffer, or None for MPI_IN_PLACE where the rank may take its data from its receive
+// buffer instead.
+unsafe fn send_or_in_place<'a, C: Count>(
+    buf: *const c_void,
+    count: C,
+    datatype: MPI_Datatype,
+    in_place: bool,
+) -> Result<Option<Buffer<'a>>, Error> {
+    if !is_in_place(buf) {
+        send_buffer(buf, count, datatype).map(Some)
+    } else if in_place {
+        Ok(None)
+    } else {
+        Err(Error::MPI_ERR_BUFFER)
+    }
+}
+
+unsafe fn recv_or_in_place<'a, C: Count>(
+    buf: *mut c_void,
+    count: C,
+    datatype: MPI_Datatype,
+    in_place: bool,
+) -> Result<Option<BufferMut<'a>>, Error> {
+    if !is_in_place(buf) {
+        recv_buffer(buf, count, datatype).map(Some)
+    } else if in_place {
+        Ok(None)
+    } else {
+        Err(Error::MPI_ERR_BUFFER)
+    }
+}
+
+// A buffer holding a block of `count` elements for each of the `size` ranks, one after another.
+unsafe fn send_buffer_for_all<'a, C: Count>(
+    buf: *const c_void,
+    count: C,
+    datatype: MPI_Datatype,
+    size: usize,
+) -> Result<Buffer<'a>, Error> {
+    let block = send_buffer(buf, count, datatype)?;
+    let count = block.count().checked_mul(size).ok_or(Error::MPI_ERR_COUNT)?;
+    Ok(Buffer::new(buf as *const u8, count, block.datatype().clone()))
+}
+
+unsafe fn recv_buffer_for_all<'a, C: Count>(
+    buf: *mut c_void,
+    count: C,
+    datatype: MPI_Datatype,
+    size: usize,
+) -> Result<BufferMut<'a>, Error> {
+    let block = recv_buffer(buf, count, datatype)?;
+    let count = block.count().checked_mul(size).ok_or(Error::MPI_ERR_COUNT)?;
+    Ok(BufferMut::new(buf as *mut u8, count, block.datatype().clone()))
+}
+
+// The buffers of a reduce on an intercommunicator: the ranks of the other group send and the root
+// receives.
+unsafe fn intercomm_reduce_buffers<'a, C: Count>(
+    sendbuf: *const c_void,
+    recvbuf: *mut c_void,
+    count: C,
+    datatype: MPI_Datatype,
+    root: Root,
+) -> Result<(Option<Buffer<'a>>, Option<BufferMut<'a>>), Error> {
+    match root {
+        Root::Root => Ok((None, Some(recv_buffer(recvbuf, count, datatype)?))),
+        Root::Rank(_) => Ok((Some(send_buffer(sendbuf, count, datatype)?), None)),
+        Root::ProcNull => Ok((None, None)),
+    }
+}
+
+// The buffers of a gather on an intercommunicator, where the root receives a block from each rank
+// of the other group.
+unsafe fn intercomm_gather_buffers<'a, C: Count>(
+    sendbuf: *const c_void,
+    sendcount: C,
+    sendtype: MPI_Datatype,
+    recvbuf: *mut c_void,
+    recvcount: C,
+    recvtype: MPI_Datatype,
+    root: Root,
+    comm: &Comm,
+) -> Result<(Option<Buffer<'a>>, Option<BufferMut<'a>>), Error> {
+    match root {
+        Root::Root => {
+            let recvbuf = recv_buffer_for_all(recvbuf, recvcount, recvtype, comm.remote_size())?;
+            Ok((None, Some(recvbuf)))
+        }
+        Root::Rank(_) => Ok((Some(send_buffer(sendbuf, sendcount, sendtype)?), None)),
+        Root::ProcNull => Ok((None, None)),
+    }
+}
+
+// The arrays of the v and w variants have an entry for each rank.
+unsafe fn per_rank<'a, T>(array: *const T, size: usize) -> &'a [T] {
+    slice::from_raw_parts(array, size)
+}
+
+// The count and the offset in bytes of each rank's block. The displacements of the v variants are
+// in extents of the datatype, and those of the w variants are in bytes, for which `extent` is 1.
+unsafe fn layout<C: Count, D: Count>(
+    counts: *const C,
+    displs: *const D,
+    extent: isize,
+    size: usize,
+) -> Result<Vec<(C, isize)>, Error> {
+    per_rank(counts, size)
+        .iter()
+        .zip(per_rank(displs, size))
+        .map(|(&count, &displ)| {
+            let displ: isize = displ.value_as().map_err(|_| Error::MPI_ERR_ARG)?;
+            Ok((count, displ.wrapping_mul(extent)))
+        })
+        .collect()
+}
+
+unsafe fn extent_of(datatype: MPI_Datatype) -> Result<isize, Error> {
+    datatype.get().map(|datatype| datatype.extent())
+}
+
+unsafe fn send_blocks<'a, C: Count>(
+    buf: *const c_void,
+    layout: Vec<(C, isize)>,
+    datatypes: &[MPI_Datatype],
+) -> Result<Vec<Buffer<'a>>, Error> {
+    layout
+        .into_iter()
+        .zip(datatypes)
+        .map(|((count, offset), &datatype)| {
+            let base = (buf as *const u8).wrapping_offset(offset);
+            send_buffer(base as *const c_void, count, datatype)
+        })
+        .collect()
+}
+
+unsafe fn recv_blocks<'a, C: Count>(
+    buf: *mut c_void,
+    layout: Vec<(C, isize)>,
+    datatypes: &[MPI_Datatype],
+) -> Result<Vec<BufferMut<'a>>, Error> {
+    layout
+        .into_iter()
+        .zip(datatypes)
+        .map(|((count, offset), &datatype)| {
+            let base = (buf as *mut u8).wrapping_offset(offset);
+            recv_buffer(base as *mut c_void, count, datatype)
+        })
+        .collect()
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Barrier(comm: MPI_Comm) -> Error {
+    let comm = unsafe { comm.get() };
+    mpitry!(comm.barrier());
+    Error::MPI_SUCCESS
+}
+
+large_count! {
+    fn MPI_Bcast / MPI_Bcast_c<C = c_int>(
+        buffer: *mut c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        root: c_int,
+        comm: MPI_Comm,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+
+        let buffer = mpicheck!(unsafe { recv_buffer(buffer, count, datatype) });
+
+        if comm.is_intercomm() {
+            let root = mpicheck!(intercomm_root(root, &comm));
+            mpitry!(comm.intercomm_bcast(buffer, root));
+            return Error::MPI_SUCCESS;
+        }
+
+        let root = mpicheck!(self::root(root, &comm));
+
+        mpitry!(comm.bcast(buffer, root));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+// Only the root receives, and only the root may pass MPI_IN_PLACE, in which case its contribution
+// is its receive buffer's.
+large_count! {
+    fn MPI_Reduce / MPI_Reduce_c<C = c_int>(
+        sendbuf: *const c_void,
+        recvbuf: *mut c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        op: MPI_Op,
+        root: c_int,
+        comm: MPI_Comm,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+
+        if comm.is_intercomm() {
+            let root = mpicheck!(intercomm_root(root, &comm));
+            let (sendbuf, recvbuf) = mpicheck!(unsafe {
+                intercomm_reduce_buffers(sendbuf, recvbuf, count, datatype, root)
+            });
+            let op = mpicheck!(unsafe { op.get() });
+            mpitry!(comm.intercomm_reduce(sendbuf, recvbuf, op, root));
+            return Error::MPI_SUCCESS;
+        }
+
+        let root = mpicheck!(self::root(root, &comm));
+        let is_root = comm.rank() == root;
+        let sendbuf = mpicheck!(unsafe { send_or_in_place(sendbuf, count, datatype, is_root) });
+        let recvbuf: Option<BufferMut> = if is_root {
+            Some(mpicheck!(unsafe { recv_buffer(recvbuf, count, datatype) }))
+        } else {
+            None
+        };
+        let op = mpicheck!(unsafe { op.get() });
+
+        mpitry!(comm.reduce(sendbuf, recvbuf, op, root));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Allreduce / MPI_Allreduce_c<C = c_int>(
+        sendbuf: *const c_void,
+        recvbuf: *mut c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        op: MPI_Op,
+        comm: MPI_Comm,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+
+        let sendbuf = mpicheck!(unsafe {
+            send_or_in_place(sendbuf, count, datatype, !comm.is_intercomm())
+        });
+        let recvbuf = mpicheck!(unsafe { recv_buffer(recvbuf, count, datatype) });
+        let op = mpicheck!(unsafe { op.get() });
+
+        mpitry!(comm.allreduce(sendbuf, recvbuf, op));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Reduce_local / MPI_Reduce_local_c<C = c_int>(
+        inbuf: *const c_void,
+        inoutbuf: *mut c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        op: MPI_Op,
+    ) -> Error {
+        let inbuf = mpicheck!(unsafe { send_buffer(inbuf, count, datatype) });
+        let inoutbuf = mpicheck!(unsafe { recv_buffer(inoutbuf, count, datatype) });
+        let op = mpicheck!(unsafe { op.get() });
+
+        mpitry!(op.reduce_local(inbuf, inoutbuf));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+// The receive arguments are only significant at the root, which may pass MPI_IN_PLACE as sendbuf
+// when its block is already in place.
+large_count! {
+    fn MPI_Gather / MPI_Gather_c<C = c_int>(
+        sendbuf: *const c_void,
+        sendcount: C,
+        sendtype: MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcount: C,
+        recvtype: MPI_Datatype,
+        root: c_int,
+        comm: MPI_Comm,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+
+        if comm.is_intercomm() {
+            let root = mpicheck!(intercomm_root(root, &comm));
+            let (sendbuf, recvbuf) = mpicheck!(unsafe {
+                intercomm_gather_buffers(
+                    sendbuf, sendcount, sendtype, recvbuf, recvcount, recvtype, root, &comm,
+                )
+            });
+            mpitry!(comm.intercomm_gather(sendbuf, recvbuf, root));
+            return Error::MPI_SUCCESS;
+        }
+
+        let root = mpicheck!(self::root(root, &comm));
+        let is_root = comm.rank() == root;
+        let sendbuf =
+            mpicheck!(unsafe { send_or_in_place(sendbuf, sendcount, sendtype, is_root) });
+        let recvbuf = if is_root {
+            Some(mpicheck!(unsafe {
+                recv_buffer_for_all(recvbuf, recvcount, recvtype, comm.size())
+            }))
+        } else {
+            None
+        };
+
+        mpitry!(comm.gather(sendbuf, recvbuf, root));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Gatherv / MPI_Gatherv_c<C = c_int, D = c_int => MPI_Aint>(
+        sendbuf: *const c_void,
+        sendcount: C,
+        sendtype: MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcounts: *const C,
+        displs: *const D,
+        recvtype: MPI_Datatype,
+        root: c_int,
+        comm: MPI_Comm,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        mpicheck!(intracomm(&comm));
+
+        let root = mpicheck!(self::root(root, &comm));
+        let is_root = comm.rank() == root;
+        let sendbuf =
+            mpicheck!(unsafe { send_or_in_place(sendbuf, sendcount, sendtype, is_root) });
+        let recvbufs = if is_root {
+            let extent = mpicheck!(unsafe { extent_of(recvtype) });
+            let layout = mpicheck!(unsafe { layout(recvcounts, displs, extent, comm.size()) });
+            let recvtypes = vec![recvtype; comm.size()];
+            Some(mpicheck!(unsafe { recv_blocks(recvbuf, layout, &recvtypes) }))
+        } else {
+            None
+        };
+
+        mpitry!(comm.gatherv(sendbuf, recvbufs, root));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+// The send arguments are only significant at the root, which may pass MPI_IN_PLACE as recvbuf to
+// leave its block where it is.
+large_count! {
+    fn MPI_Scatter / MPI_Scatter_c<C = c_int>(
+        sendbuf: *const c_void,
+        sendcount: C,
+        sendtype: MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcount: C,
+        recvtype: MPI_Datatype,
+        root: c_int,
+        comm: MPI_Comm,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        mpicheck!(intracomm(&comm));
+
+        let root = mpicheck!(self::root(root, &comm));
+        let is_root = comm.rank() == root;
+        let sendbuf = if is_root {
+            Some(mpicheck!(unsafe {
+                send_buffer_for_all(sendbuf, sendcount, sendtype, comm.size())
+            }))
+        } else {
+            None
+        };
+        let recvbuf =
+            mpicheck!(unsafe { recv_or_in_place(recvbuf, recvcount, recvtype, is_root) });
+
+        mpitry!(comm.scatter(sendbuf, recvbuf, root));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Scatterv / MPI_Scatterv_c<C = c_int, D = c_int => MPI_Aint>(
+        sendbuf: *const c_void,
+        sendcounts: *const C,
+        displs: *const D,
+        sendtype: MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcount: C,
+        recvtype: MPI_Datatype,
+        root: c_int,
+        comm: MPI_Comm,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        mpicheck!(intracomm(&comm));
+
+        let root = mpicheck!(self::root(root, &comm));
+        let is_root = comm.rank() == root;
+        let sendbufs = if is_root {
+            let extent = mpicheck!(unsafe { extent_of(sendtype) });
+            let layout = mpicheck!(unsafe { layout(sendcounts, displs, extent, comm.size()) });
+            let sendtypes = vec![sendtype; comm.size()];
+            Some(mpicheck!(unsafe { send_blocks(sendbuf, layout, &sendtypes) }))
+        } else {
+            None
+        };
+        let recvbuf =
+            mpicheck!(unsafe { recv_or_in_place(recvbuf, recvcount, recvtype, is_root) });
+
+        mpitry!(comm.scatterv(sendbufs, recvbuf, root));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+// MPI_IN_PLACE as sendbuf means every rank's block is already in place in its recvbuf.
+large_count! {
+    fn MPI_Allgather / MPI_Allgather_c<C = c_int>(
+        sendbuf: *const c_void,
+        sendcount: C,
+        sendtype: MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcount: C,
+        recvtype: MPI_Datatype,
+        comm: MPI_Comm,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+
+        let sendbuf = mpicheck!(unsafe {
+            send_or_in_place(sendbuf, sendcount, sendtype, !comm.is_intercomm())
+        });
+        let recvbuf = mpicheck!(unsafe {
+            recv_buffer_for_all(recvbuf, recvcount, recvtype, comm.remote_size())
+        });
+
+        mpitry!(comm.allgather(sendbuf, recvbuf));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Allgatherv / MPI_Allgatherv_c<C = c_int, D = c_int => MPI_Aint>(
+        sendbuf: *const c_void,
+        sendcount: C,
+        sendtype: MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcounts: *const C,
+        displs: *const D,
+        recvtype: MPI_Datatype,
+        comm: MPI_Comm,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+
+        let sendbuf = mpicheck!(unsafe {
+            send_or_in_place(sendbuf, sendcount, sendtype, !comm.is_intercomm())
+        });
+        let extent = mpicheck!(unsafe { extent_of(recvtype) });
+        let layout = mpicheck!(unsafe { layout(recvcounts, displs, extent, comm.remote_size()) });
+        let recvtypes = vec![recvtype; comm.remote_size()];
+        let recvbufs = mpicheck!(unsafe { recv_blocks(recvbuf, layout, &recvtypes) });
+
+        mpitry!(comm.allgatherv(sendbuf, recvbufs));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+// With MPI_IN_PLACE as sendbuf, the send arguments are ignored and each block of recvbuf is sent
+// and then replaced.
+large_count! {
+    fn MPI_Alltoall / MPI_Alltoall_c<C = c_int>(
+        sendbuf: *const c_void,
+        sendcount: C,
+        sendtype: MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcount: C,
+        recvtype: MPI_Datatype,
+        comm: MPI_Comm,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+
+        let sendbuf = if mpicheck!(in_place(sendbuf, &comm)) {
+            None
+        } else {
+            Some(mpicheck!(unsafe {
+                send_buffer_for_all(sendbuf, sendcount, sendtype, comm.remote_size())
+            }))
+        };
+        let recvbuf = mpicheck!(unsafe {
+            recv_buffer_for_all(recvbuf, recvcount, recvtype, comm.remote_size())
+        });
+
+        mpitry!(comm.alltoall(sendbuf, recvbuf));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Alltoallv / MPI_Alltoallv_c<C = c_int, D = c_int => MPI_Aint>(
+        sendbuf: *const c_void,
+        sendcounts: *const C,
+        sdispls: *const D,
+        sendtype: MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcounts: *const C,
+        rdispls: *const D,
+        recvtype: MPI_Datatype,
+        comm: MPI_Comm,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let size = comm.remote_size();
+
+        let sendbufs = if mpicheck!(in_place(sendbuf, &comm)) {
+            None
+        } else {
+            let extent = mpicheck!(unsafe { extent_of(sendtype) });
+            let layout = mpicheck!(unsafe { layout(sendcounts, sdispls, extent, size) });
+            Some(mpicheck!(unsafe { send_blocks(sendbuf, layout, &vec![sendtype; size]) }))
+        };
+        let extent = mpicheck!(unsafe { extent_of(recvtype) });
+        let layout = mpicheck!(unsafe { layout(recvcounts, rdispls, extent, size) });
+        let recvbufs = mpicheck!(unsafe { recv_blocks(recvbuf, layout, &vec![recvtype; size]) });
+
+        mpitry!(comm.alltoallv(sendbufs, recvbufs));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+// Each block has its own datatype, and displacements are in bytes.
+large_count! {
+    fn MPI_Alltoallw / MPI_Alltoallw_c<C = c_int>(
+        sendbuf: *const c_void,
+        sendcounts: *const C,
+        sdispls: *const MPI_Aint,
+        sendtypes: *const MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcounts: *const C,
+        rdispls: *const MPI_Aint,
+        recvtypes: *const MPI_Datatype,
+        comm: MPI_Comm,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let size = comm.remote_size();
+
+        let sendbufs = if mpicheck!(in_place(sendbuf, &comm)) {
+            None
+        } else {
+            let layout = mpicheck!(unsafe { layout(sendcounts, sdispls, 1, size) });
+            let sendtypes = unsafe { per_rank(sendtypes, size) };
+            Some(mpicheck!(unsafe { send_blocks(sendbuf, layout, sendtypes) }))
+        };
+        let layout = mpicheck!(unsafe { layout(recvcounts, rdispls, 1, size) });
+        let recvtypes = unsafe { per_rank(recvtypes, size) };
+        let recvbufs = mpicheck!(unsafe { recv_blocks(recvbuf, layout, recvtypes) });
+
+        mpitry!(comm.alltoallv(sendbufs, recvbufs));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+// With MPI_IN_PLACE as sendbuf, each rank's contribution is taken from recvbuf, which holds the
+// elements of every rank, and its block is left at the start.
+large_count! {
+    fn MPI_Reduce_scatter / MPI_Reduce_scatter_c<C = c_int>(
+        sendbuf: *const c_void,
+        recvbuf: *mut c_void,
+        recvcounts: *const C,
+        datatype: MPI_Datatype,
+        op: MPI_Op,
+        comm: MPI_Comm,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        mpicheck!(intracomm(&comm));
+
+        let counts: Vec<usize> = mpicheck!(
+            unsafe { per_rank(recvcounts, comm.size()) }
+                .iter()
+                .map(|&count| count_of(count))
+                .collect()
+        );
+        let total = counts.iter().sum::<usize>();
+        let total: MPI_Count = mpicheck!(total.value_as().map_err(|_| Error::MPI_ERR_COUNT));
+
+        let (sendbuf, recvbuf) = if is_in_place(sendbuf) {
+            (None, mpicheck!(unsafe { recv_buffer(recvbuf, total, datatype) }))
+        } else {
+            let count = counts[comm.rank()] as MPI_Count;
+            (
+                Some(mpicheck!(unsafe { send_buffer(sendbuf, total, datatype) })),
+                mpicheck!(unsafe { recv_buffer(recvbuf, count, datatype) }),
+            )
+        };
+        let op = mpicheck!(unsafe { op.get() });
+
+        mpitry!(comm.reduce_scatter(sendbuf, recvbuf, &counts, op));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Reduce_scatter_block / MPI_Reduce_scatter_block_c<C = c_int>(
+        sendbuf: *const c_void,
+        recvbuf: *mut c_void,
+        recvcount: C,
+        datatype: MPI_Datatype,
+        op: MPI_Op,
+        comm: MPI_Comm,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        mpicheck!(intracomm(&comm));
+        let size = comm.size();
+
+        let (sendbuf, recvbuf) = if is_in_place(sendbuf) {
+            (
+                None,
+                mpicheck!(unsafe { recv_buffer_for_all(recvbuf, recvcount, datatype, size) }),
+            )
+        } else {
+            (
+                Some(mpicheck!(unsafe { send_buffer_for_all(sendbuf, recvcount, datatype, size) })),
+                mpicheck!(unsafe { recv_buffer(recvbuf, recvcount, datatype) }),
+            )
+        };
+        let op = mpicheck!(unsafe { op.get() });
+
+        mpitry!(comm.reduce_scatter_block(sendbuf, recvbuf, op));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Scan / MPI_Scan_c<C = c_int>(
+        sendbuf: *const c_void,
+        recvbuf: *mut c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        op: MPI_Op,
+        comm: MPI_Comm,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        mpicheck!(intracomm(&comm));
+
+        let sendbuf = mpicheck!(unsafe { send_or_in_place(sendbuf, count, datatype, true) });
+        let recvbuf = mpicheck!(unsafe { recv_buffer(recvbuf, count, datatype) });
+        let op = mpicheck!(unsafe { op.get() });
+
+        mpitry!(comm.scan(sendbuf, recvbuf, op));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+// Rank 0's recvbuf is left as it is.
+large_count! {
+    fn MPI_Exscan / MPI_Exscan_c<C = c_int>(
+        sendbuf: *const c_void,
+        recvbuf: *mut c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        op: MPI_Op,
+        comm: MPI_Comm,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        mpicheck!(intracomm(&comm));
+
+        let sendbuf = mpicheck!(unsafe { send_or_in_place(sendbuf, count, datatype, true) });
+        let recvbuf = mpicheck!(unsafe { recv_buffer(recvbuf, count, datatype) });
+        let op = mpicheck!(unsafe { op.get() });
+
+        mpitry!(comm.exscan(sendbuf, recvbuf, op));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+// The nonblocking collectives take the same arguments as the blocking ones and return a request
+// that completes with an empty status.
+#[no_mangle]
+pub extern "C" fn MPI_Ibarrier(comm: MPI_Comm, request: Option<&mut MPI_Request>) -> Error {
+    let comm = unsafe { comm.get() };
+    let request = request.expect("NULL is not a valid parameter to MPI_Ibarrier.");
+
+    *request = MPI_Request::new(comm.ibarrier());
+
+    Error::MPI_SUCCESS
+}
+
+large_count! {
+    fn MPI_Ibcast / MPI_Ibcast_c<C = c_int>(
+        buffer: *mut c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        root: c_int,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Ibcast.");
+
+        let buffer = mpicheck!(unsafe { recv_buffer(buffer, count, datatype) });
+
+        if comm.is_intercomm() {
+            let root = mpicheck!(intercomm_root(root, &comm));
+            *request = MPI_Request::new(comm.intercomm_ibcast(buffer, root));
+            return Error::MPI_SUCCESS;
+        }
+
+        let root = mpicheck!(self::root(root, &comm));
+
+        *request = MPI_Request::new(comm.ibcast(buffer, root));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Igather / MPI_Igather_c<C = c_int>(
+        sendbuf: *const c_void,
+        sendcount: C,
+        sendtype: MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcount: C,
+        recvtype: MPI_Datatype,
+        root: c_int,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Igather.");
+
+        if comm.is_intercomm() {
+            let root = mpicheck!(intercomm_root(root, &comm));
+            let (sendbuf, recvbuf) = mpicheck!(unsafe {
+                intercomm_gather_buffers(
+                    sendbuf, sendcount, sendtype, recvbuf, recvcount, recvtype, root, &comm,
+                )
+            });
+            *request = MPI_Request::new(comm.intercomm_igather(sendbuf, recvbuf, root));
+            return Error::MPI_SUCCESS;
+        }
+
+        let root = mpicheck!(self::root(root, &comm));
+        let is_root = comm.rank() == root;
+        let sendbuf =
+            mpicheck!(unsafe { send_or_in_place(sendbuf, sendcount, sendtype, is_root) });
+        let recvbuf = if is_root {
+            Some(mpicheck!(unsafe {
+                recv_buffer_for_all(recvbuf, recvcount, recvtype, comm.size())
+            }))
+        } else {
+            None
+        };
+
+        *request = MPI_Request::new(comm.igather(sendbuf, recvbuf, root));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Igatherv / MPI_Igatherv_c<C = c_int, D = c_int => MPI_Aint>(
+        sendbuf: *const c_void,
+        sendcount: C,
+        sendtype: MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcounts: *const C,
+        displs: *const D,
+        recvtype: MPI_Datatype,
+        root: c_int,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        mpicheck!(intracomm(&comm));
+        let request = request.expect("NULL is not a valid parameter to MPI_Igatherv.");
+
+        let root = mpicheck!(self::root(root, &comm));
+        let is_root = comm.rank() == root;
+        let sendbuf =
+            mpicheck!(unsafe { send_or_in_place(sendbuf, sendcount, sendtype, is_root) });
+        let recvbufs = if is_root {
+            let extent = mpicheck!(unsafe { extent_of(recvtype) });
+            let layout = mpicheck!(unsafe { layout(recvcounts, displs, extent, comm.size()) });
+            let recvtypes = vec![recvtype; comm.size()];
+            Some(mpicheck!(unsafe { recv_blocks(recvbuf, layout, &recvtypes) }))
+        } else {
+            None
+        };
+
+        *request = MPI_Request::new(comm.igatherv(sendbuf, recvbufs, root));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Iscatter / MPI_Iscatter_c<C = c_int>(
+        sendbuf: *const c_void,
+        sendcount: C,
+        sendtype: MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcount: C,
+        recvtype: MPI_Datatype,
+        root: c_int,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        mpicheck!(intracomm(&comm));
+        let request = request.expect("NULL is not a valid parameter to MPI_Iscatter.");
+
+        let root = mpicheck!(self::root(root, &comm));
+        let is_root = comm.rank() == root;
+        let sendbuf = if is_root {
+            Some(mpicheck!(unsafe {
+                send_buffer_for_all(sendbuf, sendcount, sendtype, comm.size())
+            }))
+        } else {
+            None
+        };
+        let recvbuf =
+            mpicheck!(unsafe { recv_or_in_place(recvbuf, recvcount, recvtype, is_root) });
+
+        *request = MPI_Request::new(comm.iscatter(sendbuf, recvbuf, root));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Iscatterv / MPI_Iscatterv_c<C = c_int, D = c_int => MPI_Aint>(
+        sendbuf: *const c_void,
+        sendcounts: *const C,
+        displs: *const D,
+        sendtype: MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcount: C,
+        recvtype: MPI_Datatype,
+        root: c_int,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        mpicheck!(intracomm(&comm));
+        let request = request.expect("NULL is not a valid parameter to MPI_Iscatterv.");
+
+        let root = mpicheck!(self::root(root, &comm));
+        let is_root = comm.rank() == root;
+        let sendbufs = if is_root {
+            let extent = mpicheck!(unsafe { extent_of(sendtype) });
+            let layout = mpicheck!(unsafe { layout(sendcounts, displs, extent, comm.size()) });
+            let sendtypes = vec![sendtype; comm.size()];
+            Some(mpicheck!(unsafe { send_blocks(sendbuf, layout, &sendtypes) }))
+        } else {
+            None
+        };
+        let recvbuf =
+            mpicheck!(unsafe { recv_or_in_place(recvbuf, recvcount, recvtype, is_root) });
+
+        *request = MPI_Request::new(comm.iscatterv(sendbufs, recvbuf, root));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Iallgather / MPI_Iallgather_c<C = c_int>(
+        sendbuf: *const c_void,
+        sendcount: C,
+        sendtype: MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcount: C,
+        recvtype: MPI_Datatype,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Iallgather.");
+
+        let sendbuf = mpicheck!(unsafe {
+            send_or_in_place(sendbuf, sendcount, sendtype, !comm.is_intercomm())
+        });
+        let recvbuf = mpicheck!(unsafe {
+            recv_buffer_for_all(recvbuf, recvcount, recvtype, comm.remote_size())
+        });
+
+        *request = MPI_Request::new(comm.iallgather(sendbuf, recvbuf));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Iallgatherv / MPI_Iallgatherv_c<C = c_int, D = c_int => MPI_Aint>(
+        sendbuf: *const c_void,
+        sendcount: C,
+        sendtype: MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcounts: *const C,
+        displs: *const D,
+        recvtype: MPI_Datatype,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Iallgatherv.");
+
+        let sendbuf = mpicheck!(unsafe {
+            send_or_in_place(sendbuf, sendcount, sendtype, !comm.is_intercomm())
+        });
+        let extent = mpicheck!(unsafe { extent_of(recvtype) });
+        let layout = mpicheck!(unsafe { layout(recvcounts, displs, extent, comm.remote_size()) });
+        let recvtypes = vec![recvtype; comm.remote_size()];
+        let recvbufs = mpicheck!(unsafe { recv_blocks(recvbuf, layout, &recvtypes) });
+
+        *request = MPI_Request::new(comm.iallgatherv(sendbuf, recvbufs));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Ialltoall / MPI_Ialltoall_c<C = c_int>(
+        sendbuf: *const c_void,
+        sendcount: C,
+        sendtype: MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcount: C,
+        recvtype: MPI_Datatype,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Ialltoall.");
+
+        let sendbuf = if mpicheck!(in_place(sendbuf, &comm)) {
+            None
+        } else {
+            Some(mpicheck!(unsafe {
+                send_buffer_for_all(sendbuf, sendcount, sendtype, comm.remote_size())
+            }))
+        };
+        let recvbuf = mpicheck!(unsafe {
+            recv_buffer_for_all(recvbuf, recvcount, recvtype, comm.remote_size())
+        });
+
+        *request = MPI_Request::new(comm.ialltoall(sendbuf, recvbuf));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Ialltoallv / MPI_Ialltoallv_c<C = c_int, D = c_int => MPI_Aint>(
+        sendbuf: *const c_void,
+        sendcounts: *const C,
+        sdispls: *const D,
+        sendtype: MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcounts: *const C,
+        rdispls: *const D,
+        recvtype: MPI_Datatype,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Ialltoallv.");
+        let size = comm.remote_size();
+
+        let sendbufs = if mpicheck!(in_place(sendbuf, &comm)) {
+            None
+        } else {
+            let extent = mpicheck!(unsafe { extent_of(sendtype) });
+            let layout = mpicheck!(unsafe { layout(sendcounts, sdispls, extent, size) });
+            Some(mpicheck!(unsafe { send_blocks(sendbuf, layout, &vec![sendtype; size]) }))
+        };
+        let extent = mpicheck!(unsafe { extent_of(recvtype) });
+        let layout = mpicheck!(unsafe { layout(recvcounts, rdispls, extent, size) });
+        let recvbufs = mpicheck!(unsafe { recv_blocks(recvbuf, layout, &vec![recvtype; size]) });
+
+        *request = MPI_Request::new(comm.ialltoallv(sendbufs, recvbufs));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Ialltoallw / MPI_Ialltoallw_c<C = c_int>(
+        sendbuf: *const c_void,
+        sendcounts: *const C,
+        sdispls: *const MPI_Aint,
+        sendtypes: *const MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcounts: *const C,
+        rdispls: *const MPI_Aint,
+        recvtypes: *const MPI_Datatype,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Ialltoallw.");
+        let size = comm.remote_size();
+
+        let sendbufs = if mpicheck!(in_place(sendbuf, &comm)) {
+            None
+        } else {
+            let layout = mpicheck!(unsafe { layout(sendcounts, sdispls, 1, size) });
+            let sendtypes = unsafe { per_rank(sendtypes, size) };
+            Some(mpicheck!(unsafe { send_blocks(sendbuf, layout, sendtypes) }))
+        };
+        let layout = mpicheck!(unsafe { layout(recvcounts, rdispls, 1, size) });
+        let recvtypes = unsafe { per_rank(recvtypes, size) };
+        let recvbufs = mpicheck!(unsafe { recv_blocks(recvbuf, layout, recvtypes) });
+
+        *request = MPI_Request::new(comm.ialltoallv(sendbufs, recvbufs));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Ireduce / MPI_Ireduce_c<C = c_int>(
+        sendbuf: *const c_void,
+        recvbuf: *mut c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        op: MPI_Op,
+        root: c_int,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Ireduce.");
+
+        if comm.is_intercomm() {
+            let root = mpicheck!(intercomm_root(root, &comm));
+            let (sendbuf, recvbuf) = mpicheck!(unsafe {
+                intercomm_reduce_buffers(sendbuf, recvbuf, count, datatype, root)
+            });
+            let op = mpicheck!(unsafe { op.get() });
+            let request_ = mpitry!(comm.intercomm_ireduce(sendbuf, recvbuf, op, root));
+            *request = MPI_Request::new(request_);
+            return Error::MPI_SUCCESS;
+        }
+
+        let root = mpicheck!(self::root(root, &comm));
+        let is_root = comm.rank() == root;
+        let sendbuf = mpicheck!(unsafe { send_or_in_place(sendbuf, count, datatype, is_root) });
+        let recvbuf: Option<BufferMut> = if is_root {
+            Some(mpicheck!(unsafe { recv_buffer(recvbuf, count, datatype) }))
+        } else {
+            None
+        };
+        let op = mpicheck!(unsafe { op.get() });
+
+        *request = MPI_Request::new(mpitry!(comm.ireduce(sendbuf, recvbuf, op, root)));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Iallreduce / MPI_Iallreduce_c<C = c_int>(
+        sendbuf: *const c_void,
+        recvbuf: *mut c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        op: MPI_Op,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Iallreduce.");
+
+        let sendbuf = mpicheck!(unsafe {
+            send_or_in_place(sendbuf, count, datatype, !comm.is_intercomm())
+        });
+        let recvbuf = mpicheck!(unsafe { recv_buffer(recvbuf, count, datatype) });
+        let op = mpicheck!(unsafe { op.get() });
+
+        *request = MPI_Request::new(mpitry!(comm.iallreduce(sendbuf, recvbuf, op)));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Ireduce_scatter_block / MPI_Ireduce_scatter_block_c<C = c_int>(
+        sendbuf: *const c_void,
+        recvbuf: *mut c_void,
+        recvcount: C,
+        datatype: MPI_Datatype,
+        op: MPI_Op,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        mpicheck!(intracomm(&comm));
+        let request = request.expect("NULL is not a valid parameter to MPI_Ireduce_scatter_block.");
+        let size = comm.size();
+
+        let (sendbuf, recvbuf) = if is_in_place(sendbuf) {
+            (
+                None,
+                mpicheck!(unsafe { recv_buffer_for_all(recvbuf, recvcount, datatype, size) }),
+            )
+        } else {
+            (
+                Some(mpicheck!(unsafe { send_buffer_for_all(sendbuf, recvcount, datatype, size) })),
+                mpicheck!(unsafe { recv_buffer(recvbuf, recvcount, datatype) }),
+            )
+        };
+        let op = mpicheck!(unsafe { op.get() });
+
+        *request = MPI_Request::new(mpitry!(comm.ireduce_scatter_block(sendbuf, recvbuf, op)));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Ireduce_scatter / MPI_Ireduce_scatter_c<C = c_int>(
+        sendbuf: *const c_void,
+        recvbuf: *mut c_void,
+        recvcounts: *const C,
+        datatype: MPI_Datatype,
+        op: MPI_Op,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        mpicheck!(intracomm(&comm));
+        let request = request.expect("NULL is not a valid parameter to MPI_Ireduce_scatter.");
+
+        let counts: Vec<usize> = mpicheck!(
+            unsafe { per_rank(recvcounts, comm.size()) }
+                .iter()
+                .map(|&count| count_of(count))
+                .collect()
+        );
+        let total = counts.iter().sum::<usize>();
+        let total: MPI_Count = mpicheck!(total.value_as().map_err(|_| Error::MPI_ERR_COUNT));
+
+        let (sendbuf, recvbuf) = if is_in_place(sendbuf) {
+            (None, mpicheck!(unsafe { recv_buffer(recvbuf, total, datatype) }))
+        } else {
+            let count = counts[comm.rank()] as MPI_Count;
+            (
+                Some(mpicheck!(unsafe { send_buffer(sendbuf, total, datatype) })),
+                mpicheck!(unsafe { recv_buffer(recvbuf, count, datatype) }),
+            )
+        };
+        let op = mpicheck!(unsafe { op.get() });
+
+        *request = MPI_Request::new(mpitry!(comm.ireduce_scatter(sendbuf, recvbuf, &counts, op)));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Iscan / MPI_Iscan_c<C = c_int>(
+        sendbuf: *const c_void,
+        recvbuf: *mut c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        op: MPI_Op,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        mpicheck!(intracomm(&comm));
+        let request = request.expect("NULL is not a valid parameter to MPI_Iscan.");
+
+        let sendbuf = mpicheck!(unsafe { send_or_in_place(sendbuf, count, datatype, true) });
+        let recvbuf = mpicheck!(unsafe { recv_buffer(recvbuf, count, datatype) });
+        let op = mpicheck!(unsafe { op.get() });
+
+        *request = MPI_Request::new(mpitry!(comm.iscan(sendbuf, recvbuf, op)));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Iexscan / MPI_Iexscan_c<C = c_int>(
+        sendbuf: *const c_void,
+        recvbuf: *mut c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        op: MPI_Op,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        mpicheck!(intracomm(&comm));
+        let request = request.expect("NULL is not a valid parameter to MPI_Iexscan.");
+
+        let sendbuf = mpicheck!(unsafe { send_or_in_place(sendbuf, count, datatype, true) });
+        let recvbuf = mpicheck!(unsafe { recv_buffer(recvbuf, count, datatype) });
+        let op = mpicheck!(unsafe { op.get() });
+
+        *request = MPI_Request::new(mpitry!(comm.iexscan(sendbuf, recvbuf, op)));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+// The persistent collectives take the arguments of the nonblocking ones and an `info`, which has no
+// keys for them yet and is ignored. They return an inactive request that MPI_Start runs.
+#[no_mangle]
+pub extern "C" fn MPI_Barrier_init(
+    comm: MPI_Comm,
+    _info: MPI_Info,
+    request: Option<&mut MPI_Request>,
+) -> Error {
+    let comm = unsafe { comm.get() };
+    let request = request.expect("NULL is not a valid parameter to MPI_Barrier_init.");
+
+    *request = MPI_Request::new(comm.barrier_init());
+
+    Error::MPI_SUCCESS
+}
+
+large_count! {
+    fn MPI_Bcast_init / MPI_Bcast_init_c<C = c_int>(
+        buffer: *mut c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        root: c_int,
+        comm: MPI_Comm,
+        _info: MPI_Info,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Bcast_init.");
+
+        let buffer = mpicheck!(unsafe { recv_buffer(buffer, count, datatype) });
+
+        if comm.is_intercomm() {
+            let root = mpicheck!(intercomm_root(root, &comm));
+            *request = MPI_Request::new(comm.intercomm_bcast_init(buffer, root));
+            return Error::MPI_SUCCESS;
+        }
+
+        let root = mpicheck!(self::root(root, &comm));
+
+        *request = MPI_Request::new(comm.bcast_init(buffer, root));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Gather_init / MPI_Gather_init_c<C = c_int>(
+        sendbuf: *const c_void,
+        sendcount: C,
+        sendtype: MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcount: C,
+        recvtype: MPI_Datatype,
+        root: c_int,
+        comm: MPI_Comm,
+        _info: MPI_Info,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Gather_init.");
+
+        if comm.is_intercomm() {
+            let root = mpicheck!(intercomm_root(root, &comm));
+            let (sendbuf, recvbuf) = mpicheck!(unsafe {
+                intercomm_gather_buffers(
+                    sendbuf, sendcount, sendtype, recvbuf, recvcount, recvtype, root, &comm,
+                )
+            });
+            *request = MPI_Request::new(comm.intercomm_gather_init(sendbuf, recvbuf, root));
+            return Error::MPI_SUCCESS;
+        }
+
+        let root = mpicheck!(self::root(root, &comm));
+        let is_root = comm.rank() == root;
+        let sendbuf =
+            mpicheck!(unsafe { send_or_in_place(sendbuf, sendcount, sendtype, is_root) });
+        let recvbuf = if is_root {
+            Some(mpicheck!(unsafe {
+                recv_buffer_for_all(recvbuf, recvcount, recvtype, comm.size())
+            }))
+        } else {
+            None
+        };
+
+        *request = MPI_Request::new(comm.gather_init(sendbuf, recvbuf, root));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Gatherv_init / MPI_Gatherv_init_c<C = c_int, D = c_int => MPI_Aint>(
+        sendbuf: *const c_void,
+        sendcount: C,
+        sendtype: MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcounts: *const C,
+        displs: *const D,
+        recvtype: MPI_Datatype,
+        root: c_int,
+        comm: MPI_Comm,
+        _info: MPI_Info,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        mpicheck!(intracomm(&comm));
+        let request = request.expect("NULL is not a valid parameter to MPI_Gatherv_init.");
+
+        let root = mpicheck!(self::root(root, &comm));
+        let is_root = comm.rank() == root;
+        let sendbuf =
+            mpicheck!(unsafe { send_or_in_place(sendbuf, sendcount, sendtype, is_root) });
+        let recvbufs = if is_root {
+            let extent = mpicheck!(unsafe { extent_of(recvtype) });
+            let layout = mpicheck!(unsafe { layout(recvcounts, displs, extent, comm.size()) });
+            let recvtypes = vec![recvtype; comm.size()];
+            Some(mpicheck!(unsafe { recv_blocks(recvbuf, layout, &recvtypes) }))
+        } else {
+            None
+        };
+
+        *request = MPI_Request::new(comm.gatherv_init(sendbuf, recvbufs, root));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Scatter_init / MPI_Scatter_init_c<C = c_int>(
+        sendbuf: *const c_void,
+        sendcount: C,
+        sendtype: MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcount: C,
+        recvtype: MPI_Datatype,
+        root: c_int,
+        comm: MPI_Comm,
+        _info: MPI_Info,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        mpicheck!(intracomm(&comm));
+        let request = request.expect("NULL is not a valid parameter to MPI_Scatter_init.");
+
+        let root = mpicheck!(self::root(root, &comm));
+        let is_root = comm.rank() == root;
+        let sendbuf = if is_root {
+            Some(mpicheck!(unsafe {
+                send_buffer_for_all(sendbuf, sendcount, sendtype, comm.size())
+            }))
+        } else {
+            None
+        };
+        let recvbuf =
+            mpicheck!(unsafe { recv_or_in_place(recvbuf, recvcount, recvtype, is_root) });
+
+        *request = MPI_Request::new(comm.scatter_init(sendbuf, recvbuf, root));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Scatterv_init / MPI_Scatterv_init_c<C = c_int, D = c_int => MPI_Aint>(
+        sendbuf: *const c_void,
+        sendcounts: *const C,
+        displs: *const D,
+        sendtype: MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcount: C,
+        recvtype: MPI_Datatype,
+        root: c_int,
+        comm: MPI_Comm,
+        _info: MPI_Info,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        mpicheck!(intracomm(&comm));
+        let request = request.expect("NULL is not a valid parameter to MPI_Scatterv_init.");
+
+        let root = mpicheck!(self::root(root, &comm));
+        let is_root = comm.rank() == root;
+        let sendbufs = if is_root {
+            let extent = mpicheck!(unsafe { extent_of(sendtype) });
+            let layout = mpicheck!(unsafe { layout(sendcounts, displs, extent, comm.size()) });
+            let sendtypes = vec![sendtype; comm.size()];
+            Some(mpicheck!(unsafe { send_blocks(sendbuf, layout, &sendtypes) }))
+        } else {
+            None
+        };
+        let recvbuf =
+            mpicheck!(unsafe { recv_or_in_place(recvbuf, recvcount, recvtype, is_root) });
+
+        *request = MPI_Request::new(comm.scatterv_init(sendbufs, recvbuf, root));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Allgather_init / MPI_Allgather_init_c<C = c_int>(
+        sendbuf: *const c_void,
+        sendcount: C,
+        sendtype: MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcount: C,
+        recvtype: MPI_Datatype,
+        comm: MPI_Comm,
+        _info: MPI_Info,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Allgather_init.");
+
+        let sendbuf = mpicheck!(unsafe {
+            send_or_in_place(sendbuf, sendcount, sendtype, !comm.is_intercomm())
+        });
+        let recvbuf = mpicheck!(unsafe {
+            recv_buffer_for_all(recvbuf, recvcount, recvtype, comm.remote_size())
+        });
+
+        *request = MPI_Request::new(comm.allgather_init(sendbuf, recvbuf));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Allgatherv_init / MPI_Allgatherv_init_c<C = c_int, D = c_int => MPI_Aint>(
+        sendbuf: *const c_void,
+        sendcount: C,
+        sendtype: MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcounts: *const C,
+        displs: *const D,
+        recvtype: MPI_Datatype,
+        comm: MPI_Comm,
+        _info: MPI_Info,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Allgatherv_init.");
+
+        let sendbuf = mpicheck!(unsafe {
+            send_or_in_place(sendbuf, sendcount, sendtype, !comm.is_intercomm())
+        });
+        let extent = mpicheck!(unsafe { extent_of(recvtype) });
+        let layout = mpicheck!(unsafe { layout(recvcounts, displs, extent, comm.remote_size()) });
+        let recvtypes = vec![recvtype; comm.remote_size()];
+        let recvbufs = mpicheck!(unsafe { recv_blocks(recvbuf, layout, &recvtypes) });
+
+        *request = MPI_Request::new(comm.allgatherv_init(sendbuf, recvbufs));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Alltoall_init / MPI_Alltoall_init_c<C = c_int>(
+        sendbuf: *const c_void,
+        sendcount: C,
+        sendtype: MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcount: C,
+        recvtype: MPI_Datatype,
+        comm: MPI_Comm,
+        _info: MPI_Info,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Alltoall_init.");
+
+        let sendbuf = if mpicheck!(in_place(sendbuf, &comm)) {
+            None
+        } else {
+            Some(mpicheck!(unsafe {
+                send_buffer_for_all(sendbuf, sendcount, sendtype, comm.remote_size())
+            }))
+        };
+        let recvbuf = mpicheck!(unsafe {
+            recv_buffer_for_all(recvbuf, recvcount, recvtype, comm.remote_size())
+        });
+
+        *request = MPI_Request::new(comm.alltoall_init(sendbuf, recvbuf));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Alltoallv_init / MPI_Alltoallv_init_c<C = c_int, D = c_int => MPI_Aint>(
+        sendbuf: *const c_void,
+        sendcounts: *const C,
+        sdispls: *const D,
+        sendtype: MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcounts: *const C,
+        rdispls: *const D,
+        recvtype: MPI_Datatype,
+        comm: MPI_Comm,
+        _info: MPI_Info,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Alltoallv_init.");
+        let size = comm.remote_size();
+
+        let sendbufs = if mpicheck!(in_place(sendbuf, &comm)) {
+            None
+        } else {
+            let extent = mpicheck!(unsafe { extent_of(sendtype) });
+            let layout = mpicheck!(unsafe { layout(sendcounts, sdispls, extent, size) });
+            Some(mpicheck!(unsafe { send_blocks(sendbuf, layout, &vec![sendtype; size]) }))
+        };
+        let extent = mpicheck!(unsafe { extent_of(recvtype) });
+        let layout = mpicheck!(unsafe { layout(recvcounts, rdispls, extent, size) });
+        let recvbufs = mpicheck!(unsafe { recv_blocks(recvbuf, layout, &vec![recvtype; size]) });
+
+        *request = MPI_Request::new(comm.alltoallv_init(sendbufs, recvbufs));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Alltoallw_init / MPI_Alltoallw_init_c<C = c_int>(
+        sendbuf: *const c_void,
+        sendcounts: *const C,
+        sdispls: *const MPI_Aint,
+        sendtypes: *const MPI_Datatype,
+        recvbuf: *mut c_void,
+        recvcounts: *const C,
+        rdispls: *const MPI_Aint,
+        recvtypes: *const MPI_Datatype,
+        comm: MPI_Comm,
+        _info: MPI_Info,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Alltoallw_init.");
+        let size = comm.remote_size();
+
+        let sendbufs = if mpicheck!(in_place(sendbuf, &comm)) {
+            None
+        } else {
+            let layout = mpicheck!(unsafe { layout(sendcounts, sdispls, 1, size) });
+            let sendtypes = unsafe { per_rank(sendtypes, size) };
+            Some(mpicheck!(unsafe { send_blocks(sendbuf, layout, sendtypes) }))
+        };
+        let layout = mpicheck!(unsafe { layout(recvcounts, rdispls, 1, size) });
+        let recvtypes = unsafe { per_rank(recvtypes, size) };
+        let recvbufs = mpicheck!(unsafe { recv_blocks(recvbuf, layout, recvtypes) });
+
+        *request = MPI_Request::new(comm.alltoallv_init(sendbufs, recvbufs));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Reduce_init / MPI_Reduce_init_c<C = c_int>(
+        sendbuf: *const c_void,
+        recvbuf: *mut c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        op: MPI_Op,
+        root: c_int,
+        comm: MPI_Comm,
+        _info: MPI_Info,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Reduce_init.");
+
+        if comm.is_intercomm() {
+            let root = mpicheck!(intercomm_root(root, &comm));
+            let (sendbuf, recvbuf) = mpicheck!(unsafe {
+                intercomm_reduce_buffers(sendbuf, recvbuf, count, datatype, root)
+            });
+            let op = mpicheck!(unsafe { op.get() });
+            let request_ = mpitry!(comm.intercomm_reduce_init(sendbuf, recvbuf, op, root));
+            *request = MPI_Request::new(request_);
+            return Error::MPI_SUCCESS;
+        }
+
+        let root = mpicheck!(self::root(root, &comm));
+        let is_root = comm.rank() == root;
+        let sendbuf = mpicheck!(unsafe { send_or_in_place(sendbuf, count, datatype, is_root) });
+        let recvbuf: Option<BufferMut> = if is_root {
+            Some(mpicheck!(unsafe { recv_buffer(recvbuf, count, datatype) }))
+        } else {
+            None
+        };
+        let op = mpicheck!(unsafe { op.get() });
+
+        *request = MPI_Request::new(mpitry!(comm.reduce_init(sendbuf, recvbuf, op, root)));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Allreduce_init / MPI_Allreduce_init_c<C = c_int>(
+        sendbuf: *const c_void,
+        recvbuf: *mut c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        op: MPI_Op,
+        comm: MPI_Comm,
+        _info: MPI_Info,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Allreduce_init.");
+
+        let sendbuf = mpicheck!(unsafe {
+            send_or_in_place(sendbuf, count, datatype, !comm.is_intercomm())
+        });
+        let recvbuf = mpicheck!(unsafe { recv_buffer(recvbuf, count, datatype) });
+        let op = mpicheck!(unsafe { op.get() });
+
+        *request = MPI_Request::new(mpitry!(comm.allreduce_init(sendbuf, recvbuf, op)));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Reduce_scatter_block_init / MPI_Reduce_scatter_block_init_c<C = c_int>(
+        sendbuf: *const c_void,
+        recvbuf: *mut c_void,
+        recvcount: C,
+        datatype: MPI_Datatype,
+        op: MPI_Op,
+        comm: MPI_Comm,
+        _info: MPI_Info,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        mpicheck!(intracomm(&comm));
+        let request =
+            request.expect("NULL is not a valid parameter to MPI_Reduce_scatter_block_init.");
+        let size = comm.size();
+
+        let (sendbuf, recvbuf) = if is_in_place(sendbuf) {
+            (
+                None,
+                mpicheck!(unsafe { recv_buffer_for_all(recvbuf, recvcount, datatype, size) }),
+            )
+        } else {
+            (
+                Some(mpicheck!(unsafe { send_buffer_for_all(sendbuf, recvcount, datatype, size) })),
+                mpicheck!(unsafe { recv_buffer(recvbuf, recvcount, datatype) }),
+            )
+        };
+        let op = mpicheck!(unsafe { op.get() });
+
+        *request =
+            MPI_Request::new(mpitry!(comm.reduce_scatter_block_init(sendbuf, recvbuf, op)));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Reduce_scatter_init / MPI_Reduce_scatter_init_c<C = c_int>(
+        sendbuf: *const c_void,
+        recvbuf: *mut c_void,
+        recvcounts: *const C,
+        datatype: MPI_Datatype,
+        op: MPI_Op,
+        comm: MPI_Comm,
+        _info: MPI_Info,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        mpicheck!(intracomm(&comm));
+        let request = request.expect("NULL is not a valid parameter to MPI_Reduce_scatter_init.");
+
+        let counts: Vec<usize> = mpicheck!(
+            unsafe { per_rank(recvcounts, comm.size()) }
+                .iter()
+                .map(|&count| count_of(count))
+                .collect()
+        );
+        let total = counts.iter().sum::<usize>();
+        let total: MPI_Count = mpicheck!(total.value_as().map_err(|_| Error::MPI_ERR_COUNT));
+
+        let (sendbuf, recvbuf) = if is_in_place(sendbuf) {
+            (None, mpicheck!(unsafe { recv_buffer(recvbuf, total, datatype) }))
+        } else {
+            let count = counts[comm.rank()] as MPI_Count;
+            (
+                Some(mpicheck!(unsafe { send_buffer(sendbuf, total, datatype) })),
+                mpicheck!(unsafe { recv_buffer(recvbuf, count, datatype) }),
+            )
+        };
+        let op = mpicheck!(unsafe { op.get() });
+
+        *request =
+            MPI_Request::new(mpitry!(comm.reduce_scatter_init(sendbuf, recvbuf, &counts, op)));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Scan_init / MPI_Scan_init_c<C = c_int>(
+        sendbuf: *const c_void,
+        recvbuf: *mut c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        op: MPI_Op,
+        comm: MPI_Comm,
+        _info: MPI_Info,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        mpicheck!(intracomm(&comm));
+        let request = request.expect("NULL is not a valid parameter to MPI_Scan_init.");
+
+        let sendbuf = mpicheck!(unsafe { send_or_in_place(sendbuf, count, datatype, true) });
+        let recvbuf = mpicheck!(unsafe { recv_buffer(recvbuf, count, datatype) });
+        let op = mpicheck!(unsafe { op.get() });
+
+        *request = MPI_Request::new(mpitry!(comm.scan_init(sendbuf, recvbuf, op)));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Exscan_init / MPI_Exscan_init_c<C = c_int>(
+        sendbuf: *const c_void,
+        recvbuf: *mut c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        op: MPI_Op,
+        comm: MPI_Comm,
+        _info: MPI_Info,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        mpicheck!(intracomm(&comm));
+        let request = request.expect("NULL is not a valid parameter to MPI_Exscan_init.");
+
+        let sendbuf = mpicheck!(unsafe { send_or_in_place(sendbuf, count, datatype, true) });
+        let recvbuf = mpicheck!(unsafe { recv_buffer(recvbuf, count, datatype) });
+        let op = mpicheck!(unsafe { op.get() });
+
+        *request = MPI_Request::new(mpitry!(comm.exscan_init(sendbuf, recvbuf, op)));
+
+        Error::MPI_SUCCESS
+    }
+}
@@ -2,7 +2,7 @@ use conv::*;
 
 use std::{mem, os::raw::c_int};
 
-use super::{universe, handles::MPI_Comm, status::*};
+use super::{universe, handles::MPI_Comm, info::MPI_Info, status::*};
 
 #[no_mangle]
 pub extern "C" fn MPI_Comm_rank(comm: MPI_Comm, rank: *mut c_int) -> Error {
@@ -25,8 +25,29 @@ pub extern "C" fn MPI_Comm_test_inter(comm: MPI_Comm, flag: *mut c_int) -> Error
     Error::MPI_SUCCESS
 }
 
+// The size of the remote group of an intercommunicator. Intracommunicators have no remote group.
+#[no_mangle]
+pub extern "C" fn MPI_Comm_remote_size(comm: MPI_Comm, size: *mut c_int) -> Error {
+    let comm = unsafe { comm.get() };
+    if !comm.is_intercomm() {
+        return Error::MPI_ERR_COMM;
+    }
+
+    unsafe { *size = comm.remote_size().value_as().unwrap() };
+
+    Error::MPI_SUCCESS
+}
+
+// MPI_COMM_NULL unless this job was spawned by another.
 #[no_mangle]
 pub extern "C" fn MPI_Comm_get_parent(parent: *mut MPI_Comm) -> Error {
+    unsafe {
+        *parent = match super::init::COMM_PARENT {
+            comm if comm.handle.is_null() => super::init::MPI_COMM_NULL,
+            comm => comm,
+        }
+    };
+
     Error::MPI_SUCCESS
 }
 
@@ -48,3 +69,19 @@ pub extern "C" fn MPI_Comm_free(comm: Option<&mut MPI_Comm>) -> Error {
 
     Error::MPI_SUCCESS
 }
+
+// Keys this implementation doesn't recognize are ignored, as the standard allows.
+#[no_mangle]
+pub extern "C" fn MPI_Comm_set_info(comm: MPI_Comm, info: MPI_Info) -> Error {
+    let comm = unsafe { comm.get() };
+    let info = unsafe { info.unwrap() };
+
+    if let Some(eager_limit) = info.get("empire_eager_limit") {
+        match eager_limit.parse() {
+            Ok(eager_limit) => comm.set_eager_limit(eager_limit),
+            Err(_) => return Error::MPI_ERR_INFO_VALUE,
+        }
+    }
+
+    Error::MPI_SUCCESS
+}
@@ -0,0 +1,93 @@
+use super::{constants::MPI_UNDEFINED, datatype::{MPI_Aint, MPI_Count}};
+
+use conv::*;
+use std::os::raw::c_int;
+
+// The integer types routines take counts, sizes and displacements as. The classic routines take
+// int, and MPI_Aint for byte displacements; their `_c` variants from MPI 4.0 take MPI_Count for all
+// of them, so that data can go beyond 2^31 elements.
+pub trait Count:
+    Copy
+    + 'static
+    + ValueInto<usize>
+    + ValueInto<isize>
+    + ValueFrom<usize>
+    + ValueFrom<isize>
+    + ValueFrom<u64>
+{
+    fn undefined() -> Self;
+}
+
+impl Count for c_int {
+    fn undefined() -> Self {
+        MPI_UNDEFINED
+    }
+}
+
+impl Count for MPI_Aint {
+    fn undefined() -> Self {
+        MPI_UNDEFINED as MPI_Aint
+    }
+}
+
+impl Count for MPI_Count {
+    fn undefined() -> Self {
+        MPI_Count::from(MPI_UNDEFINED)
+    }
+}
+
+// Counts of elements can't be negative.
+pub fn count_of<C: Count>(count: C) -> Result<usize, ::Error> {
+    count.value_as().map_err(|_| ::Error::MPI_ERR_COUNT)
+}
+
+// Counts and sizes too large for the type they are returned as are MPI_UNDEFINED, like counts that
+// aren't a whole number of elements.
+pub fn count_or_undefined<C: Count>(count: Option<u64>) -> C {
+    count
+        .and_then(|count| count.value_into().ok())
+        .unwrap_or_else(C::undefined)
+}
+
+// Defines a routine that takes counts twice: as the classic routine, with each of the type
+// parameters set to the type it gives, and as its `_c` variant, with all of them MPI_Count unless
+// another type follows `=>`. The body is generic over the type parameters, which the parameters'
+// types are written in terms of.
+macro_rules! large_count {
+    (@large) => { ::datatype::MPI_Count };
+    (@large $large:ty) => { $large };
+    ($(
+        fn $name:ident / $name_c:ident <$($param:ident = $small:ty $(=> $large:ty)*),*> (
+            $($arg:ident: $ty:ty),* $(,)*
+        ) -> Error $body:block
+    )*) => {
+        $(
+            #[allow(non_snake_case)]
+            pub mod $name {
+                use super::*;
+
+                pub fn generic<$($param: ::count::Count),*>($($arg: $ty),*) -> Error $body
+
+                mod small {
+                    use super::*;
+                    $(type $param = $small;)*
+
+                    #[no_mangle]
+                    pub extern "C" fn $name($($arg: $ty),*) -> Error {
+                        generic::<$($param),*>($($arg),*)
+                    }
+                }
+
+                mod large {
+                    use super::*;
+                    $(type $param = large_count!(@large $($large)*);)*
+
+                    #[no_mangle]
+                    pub extern "C" fn $name_c($($arg: $ty),*) -> Error {
+                        generic::<$($param),*>($($arg),*)
+                    }
+                }
+            }
+        )*
+    };
+}
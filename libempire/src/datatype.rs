@@ -0,0 +1,1041 @@
+use super::{Error, constants::*, count::{count_of, count_or_undefined, Count},
+            status::MPI_Status};
+
+use conv::*;
+use empire::datatype::{Constructor, Datatype, Distribution, Order, Pair, Primitive};
+use std::{mem, ptr, slice, os::raw::{c_int, c_longlong, c_void}};
+
+#[allow(non_camel_case_types)]
+pub type MPI_Count = c_longlong;
+
+#[allow(non_camel_case_types)]
+pub type MPI_Aint = isize;
+
+#[no_mangle]
+pub static mut MPI_DATATYPE_NULL: MPI_Datatype = MPI_Datatype {
+    handle: ptr::null_mut(),
+};
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct MPI_Datatype {
+    pub handle: *mut Option<Datatype>,
+}
+
+impl MPI_Datatype {
+    pub fn null() -> Self {
+        Self {
+            handle: Box::into_raw(Box::new(None)),
+        }
+    }
+
+    pub fn new(datatype: Datatype) -> Self {
+        Self {
+            handle: Box::into_raw(Box::new(Some(datatype))),
+        }
+    }
+
+    fn assert_non_null(&self) {
+        assert!(
+            self.handle != ptr::null_mut(),
+            "NULL is not an allowed value for MPI_Datatype. You may not have initialized MPI yet. \
+             Use MPI_DATATYPE_NULL instead."
+        );
+    }
+
+    // MPI_DATATYPE_NULL describes no buffer, so routines that need a datatype fail on it.
+    pub unsafe fn get(&self) -> Result<&Datatype, Error> {
+        self.assert_non_null();
+        (*self.handle).as_ref().ok_or(Error::MPI_ERR_TYPE)
+    }
+
+    pub unsafe fn get_mut(&mut self) -> Result<&mut Datatype, Error> {
+        self.assert_non_null();
+        (*self.handle).as_mut().ok_or(Error::MPI_ERR_TYPE)
+    }
+
+    pub unsafe fn free(&mut self) {
+        self.assert_non_null();
+        Box::from_raw(self.handle);
+        self.handle = ptr::null_mut();
+    }
+}
+
+macro_rules! predefined_datatypes {
+    ($($name:ident: $datatype:expr),* $(,)*) => {
+        $(
+            #[no_mangle]
+            pub static mut $name: MPI_Datatype = MPI_Datatype {
+                handle: ptr::null_mut(),
+            };
+        )*
+
+        pub fn initialize_datatypes() {
+            unsafe { MPI_DATATYPE_NULL = MPI_Datatype::null() };
+            $(
+                unsafe { $name = MPI_Datatype::new($datatype) };
+            )*
+        }
+
+        // The predefined handle a named datatype came from.
+        pub fn predefined_handle(datatype: &Datatype) -> Option<MPI_Datatype> {
+            $(
+                let handle = unsafe { $name };
+                if unsafe { handle.get() }.map_or(false, |named| named.is_same(datatype)) {
+                    return Some(handle);
+                }
+            )*
+            None
+        }
+
+        pub fn finalize_datatypes() {
+            unsafe { MPI_DATATYPE_NULL.free() };
+            $(
+                unsafe { $name.free() };
+            )*
+        }
+    };
+}
+
+predefined_datatypes! {
+    MPI_CHAR: Datatype::primitive(Primitive::Char),
+    MPI_SHORT: Datatype::primitive(Primitive::Short),
+    MPI_INT: Datatype::primitive(Primitive::Int),
+    MPI_LONG: Datatype::primitive(Primitive::Long),
+    MPI_LONG_LONG_INT: Datatype::primitive(Primitive::LongLong),
+    MPI_LONG_LONG: Datatype::primitive(Primitive::LongLong),
+    MPI_SIGNED_CHAR: Datatype::primitive(Primitive::SignedChar),
+    MPI_UNSIGNED_CHAR: Datatype::primitive(Primitive::UnsignedChar),
+    MPI_UNSIGNED_SHORT: Datatype::primitive(Primitive::UnsignedShort),
+    MPI_UNSIGNED: Datatype::primitive(Primitive::Unsigned),
+    MPI_UNSIGNED_LONG: Datatype::primitive(Primitive::UnsignedLong),
+    MPI_UNSIGNED_LONG_LONG: Datatype::primitive(Primitive::UnsignedLongLong),
+    MPI_FLOAT: Datatype::primitive(Primitive::Float),
+    MPI_DOUBLE: Datatype::primitive(Primitive::Double),
+    MPI_LONG_DOUBLE: Datatype::primitive(Primitive::LongDouble),
+    MPI_WCHAR: Datatype::primitive(Primitive::WChar),
+    MPI_C_BOOL: Datatype::primitive(Primitive::CBool),
+    MPI_INT8_T: Datatype::primitive(Primitive::Int8),
+    MPI_INT16_T: Datatype::primitive(Primitive::Int16),
+    MPI_INT32_T: Datatype::primitive(Primitive::Int32),
+    MPI_INT64_T: Datatype::primitive(Primitive::Int64),
+    MPI_UINT8_T: Datatype::primitive(Primitive::Uint8),
+    MPI_UINT16_T: Datatype::primitive(Primitive::Uint16),
+    MPI_UINT32_T: Datatype::primitive(Primitive::Uint32),
+    MPI_UINT64_T: Datatype::primitive(Primitive::Uint64),
+    MPI_AINT: Datatype::primitive(Primitive::Aint),
+    MPI_COUNT: Datatype::primitive(Primitive::Count),
+    MPI_OFFSET: Datatype::primitive(Primitive::Offset),
+    MPI_C_COMPLEX: Datatype::primitive(Primitive::CFloatComplex),
+    MPI_C_FLOAT_COMPLEX: Datatype::primitive(Primitive::CFloatComplex),
+    MPI_C_DOUBLE_COMPLEX: Datatype::primitive(Primitive::CDoubleComplex),
+    MPI_C_LONG_DOUBLE_COMPLEX: Datatype::primitive(Primitive::CLongDoubleComplex),
+    MPI_CXX_BOOL: Datatype::primitive(Primitive::CxxBool),
+    MPI_CXX_FLOAT_COMPLEX: Datatype::primitive(Primitive::CxxFloatComplex),
+    MPI_CXX_DOUBLE_COMPLEX: Datatype::primitive(Primitive::CxxDoubleComplex),
+    MPI_CXX_LONG_DOUBLE_COMPLEX: Datatype::primitive(Primitive::CxxLongDoubleComplex),
+    MPI_BYTE: Datatype::primitive(Primitive::Byte),
+    MPI_PACKED: Datatype::primitive(Primitive::Packed),
+    MPI_FLOAT_INT: Datatype::pair(Pair::FloatInt),
+    MPI_DOUBLE_INT: Datatype::pair(Pair::DoubleInt),
+    MPI_LONG_INT: Datatype::pair(Pair::LongInt),
+    MPI_2INT: Datatype::pair(Pair::TwoInt),
+    MPI_SHORT_INT: Datatype::pair(Pair::ShortInt),
+    MPI_LONG_DOUBLE_INT: Datatype::pair(Pair::LongDoubleInt),
+}
+
+// Block lengths, array dimensions and the like, which can't be negative.
+fn nonnegative<C: Count>(value: C) -> Result<usize, Error> {
+    value.value_as().map_err(|_| Error::MPI_ERR_ARG)
+}
+
+// Displacements and strides, which MPI_Count can hold values of that an MPI_Aint can't.
+fn displacement<C: Count>(value: C) -> Result<isize, Error> {
+    value.value_as().map_err(|_| Error::MPI_ERR_ARG)
+}
+
+// The arrays passed to the constructors may be NULL when they are empty, which
+// slice::from_raw_parts doesn't allow.
+unsafe fn array<'a, T>(array: *const T, len: usize) -> &'a [T] {
+    if len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(array, len)
+    }
+}
+
+unsafe fn nonnegative_array<C: Count>(values: *const C, len: usize) -> Result<Vec<usize>, Error> {
+    array(values, len).iter().map(|&value| nonnegative(value)).collect()
+}
+
+unsafe fn displacements<C: Count>(values: *const C, len: usize) -> Result<Vec<isize>, Error> {
+    array(values, len).iter().map(|&value| displacement(value)).collect()
+}
+
+fn order_of(order: c_int) -> Result<Order, Error> {
+    match order {
+        MPI_ORDER_C => Ok(Order::C),
+        MPI_ORDER_FORTRAN => Ok(Order::Fortran),
+        _ => Err(Error::MPI_ERR_ARG),
+    }
+}
+
+fn set_newtype(newtype: Option<&mut MPI_Datatype>, name: &str, datatype: Datatype) -> Error {
+    let newtype =
+        newtype.unwrap_or_else(|| panic!("NULL is not a valid parameter to {}.", name));
+    *newtype = MPI_Datatype::new(datatype);
+    Error::MPI_SUCCESS
+}
+
+large_count! {
+    fn MPI_Type_contiguous / MPI_Type_contiguous_c<C = c_int>(
+        count: C,
+        oldtype: MPI_Datatype,
+        newtype: Option<&mut MPI_Datatype>,
+    ) -> Error {
+        let count = mpicheck!(count_of(count));
+        let oldtype = mpicheck!(unsafe { oldtype.get() });
+
+        set_newtype(newtype, "MPI_Type_contiguous", Datatype::contiguous(count, oldtype))
+    }
+}
+
+large_count! {
+    fn MPI_Type_vector / MPI_Type_vector_c<C = c_int>(
+        count: C,
+        blocklength: C,
+        stride: C,
+        oldtype: MPI_Datatype,
+        newtype: Option<&mut MPI_Datatype>,
+    ) -> Error {
+        let count = mpicheck!(count_of(count));
+        let blocklength = mpicheck!(nonnegative(blocklength));
+        let stride = mpicheck!(displacement(stride));
+        let oldtype = mpicheck!(unsafe { oldtype.get() });
+
+        let datatype = Datatype::vector(count, blocklength, stride, oldtype);
+        set_newtype(newtype, "MPI_Type_vector", datatype)
+    }
+}
+
+large_count! {
+    fn MPI_Type_create_hvector / MPI_Type_create_hvector_c<C = c_int, A = MPI_Aint>(
+        count: C,
+        blocklength: C,
+        stride: A,
+        oldtype: MPI_Datatype,
+        newtype: Option<&mut MPI_Datatype>,
+    ) -> Error {
+        let count = mpicheck!(count_of(count));
+        let blocklength = mpicheck!(nonnegative(blocklength));
+        let stride = mpicheck!(displacement(stride));
+        let oldtype = mpicheck!(unsafe { oldtype.get() });
+
+        let datatype = Datatype::hvector(count, blocklength, stride, oldtype);
+        set_newtype(newtype, "MPI_Type_create_hvector", datatype)
+    }
+}
+
+large_count! {
+    fn MPI_Type_indexed / MPI_Type_indexed_c<C = c_int>(
+        count: C,
+        array_of_blocklengths: *const C,
+        array_of_displacements: *const C,
+        oldtype: MPI_Datatype,
+        newtype: Option<&mut MPI_Datatype>,
+    ) -> Error {
+        let count = mpicheck!(count_of(count));
+        let blocklengths = mpicheck!(unsafe { nonnegative_array(array_of_blocklengths, count) });
+        let displacements = mpicheck!(unsafe { displacements(array_of_displacements, count) });
+        let oldtype = mpicheck!(unsafe { oldtype.get() });
+
+        let datatype = Datatype::indexed(&blocklengths, &displacements, oldtype);
+        set_newtype(newtype, "MPI_Type_indexed", datatype)
+    }
+}
+
+large_count! {
+    fn MPI_Type_create_hindexed / MPI_Type_create_hindexed_c<C = c_int, A = MPI_Aint>(
+        count: C,
+        array_of_blocklengths: *const C,
+        array_of_displacements: *const A,
+        oldtype: MPI_Datatype,
+        newtype: Option<&mut MPI_Datatype>,
+    ) -> Error {
+        let count = mpicheck!(count_of(count));
+        let blocklengths = mpicheck!(unsafe { nonnegative_array(array_of_blocklengths, count) });
+        let displacements = mpicheck!(unsafe { displacements(array_of_displacements, count) });
+        let oldtype = mpicheck!(unsafe { oldtype.get() });
+
+        let datatype = Datatype::hindexed(&blocklengths, &displacements, oldtype);
+        set_newtype(newtype, "MPI_Type_create_hindexed", datatype)
+    }
+}
+
+large_count! {
+    fn MPI_Type_create_indexed_block / MPI_Type_create_indexed_block_c<C = c_int>(
+        count: C,
+        blocklength: C,
+        array_of_displacements: *const C,
+        oldtype: MPI_Datatype,
+        newtype: Option<&mut MPI_Datatype>,
+    ) -> Error {
+        let count = mpicheck!(count_of(count));
+        let blocklength = mpicheck!(nonnegative(blocklength));
+        let displacements = mpicheck!(unsafe { displacements(array_of_displacements, count) });
+        let oldtype = mpicheck!(unsafe { oldtype.get() });
+
+        let datatype = Datatype::indexed_block(blocklength, &displacements, oldtype);
+        set_newtype(newtype, "MPI_Type_create_indexed_block", datatype)
+    }
+}
+
+large_count! {
+    fn MPI_Type_create_struct / MPI_Type_create_struct_c<C = c_int, A = MPI_Aint>(
+        count: C,
+        array_of_blocklengths: *const C,
+        array_of_displacements: *const A,
+        array_of_types: *const MPI_Datatype,
+        newtype: Option<&mut MPI_Datatype>,
+    ) -> Error {
+        let count = mpicheck!(count_of(count));
+        let blocklengths = mpicheck!(unsafe { nonnegative_array(array_of_blocklengths, count) });
+        let displacements = mpicheck!(unsafe { displacements(array_of_displacements, count) });
+        let types: Vec<_> = mpicheck!(
+            unsafe { array(array_of_types, count) }
+                .iter()
+                .map(|datatype| unsafe { datatype.get() }.map(|datatype| datatype.clone()))
+                .collect()
+        );
+
+        let datatype = Datatype::structure(&blocklengths, &displacements, &types);
+        set_newtype(newtype, "MPI_Type_create_struct", datatype)
+    }
+}
+
+large_count! {
+    fn MPI_Type_create_subarray / MPI_Type_create_subarray_c<C = c_int>(
+        ndims: c_int,
+        array_of_sizes: *const C,
+        array_of_subsizes: *const C,
+        array_of_starts: *const C,
+        order: c_int,
+        oldtype: MPI_Datatype,
+        newtype: Option<&mut MPI_Datatype>,
+    ) -> Error {
+        let ndims = match nonnegative(ndims) {
+            Ok(ndims) if ndims > 0 => ndims,
+            _ => return Error::MPI_ERR_DIMS,
+        };
+        let sizes = mpicheck!(unsafe { nonnegative_array(array_of_sizes, ndims) });
+        let subsizes = mpicheck!(unsafe { nonnegative_array(array_of_subsizes, ndims) });
+        let starts = mpicheck!(unsafe { nonnegative_array(array_of_starts, ndims) });
+        let order = mpicheck!(order_of(order));
+        let oldtype = mpicheck!(unsafe { oldtype.get() });
+
+        if izip!(&sizes, &subsizes, &starts)
+            .any(|(&size, &subsize, &start)| size == 0 || subsize == 0 || start + subsize > size)
+        {
+            return Error::MPI_ERR_ARG;
+        }
+
+        let datatype = Datatype::subarray(&sizes, &subsizes, &starts, order, oldtype);
+        set_newtype(newtype, "MPI_Type_create_subarray", datatype)
+    }
+}
+
+large_count! {
+    fn MPI_Type_create_darray / MPI_Type_create_darray_c<C = c_int>(
+        size: c_int,
+        rank: c_int,
+        ndims: c_int,
+        array_of_gsizes: *const C,
+        array_of_distribs: *const c_int,
+        array_of_dargs: *const c_int,
+        array_of_psizes: *const c_int,
+        order: c_int,
+        oldtype: MPI_Datatype,
+        newtype: Option<&mut MPI_Datatype>,
+    ) -> Error {
+        let size = mpicheck!(nonnegative(size));
+        let rank = match nonnegative(rank) {
+            Ok(rank) if rank < size => rank,
+            _ => return Error::MPI_ERR_RANK,
+        };
+        let ndims = match nonnegative(ndims) {
+            Ok(ndims) if ndims > 0 => ndims,
+            _ => return Error::MPI_ERR_DIMS,
+        };
+        let gsizes = mpicheck!(unsafe { nonnegative_array(array_of_gsizes, ndims) });
+        let psizes = mpicheck!(unsafe { nonnegative_array(array_of_psizes, ndims) });
+        let order = mpicheck!(order_of(order));
+        let oldtype = mpicheck!(unsafe { oldtype.get() });
+
+        if psizes.iter().product::<usize>() != size {
+            return Error::MPI_ERR_ARG;
+        }
+
+        let mut distributions = Vec::with_capacity(ndims);
+        for (&distrib, &darg, &gsize, &psize) in izip!(
+            unsafe { array(array_of_distribs, ndims) },
+            unsafe { array(array_of_dargs, ndims) },
+            &gsizes,
+            &psizes
+        ) {
+            let darg = match darg {
+                MPI_DISTRIBUTE_DFLT_DARG => None,
+                darg => match nonnegative(darg) {
+                    Ok(darg) if darg > 0 => Some(darg),
+                    _ => return Error::MPI_ERR_ARG,
+                },
+            };
+            distributions.push(match distrib {
+                MPI_DISTRIBUTE_BLOCK => {
+                    if darg.map_or(false, |darg| darg * psize < gsize) {
+                        return Error::MPI_ERR_ARG;
+                    }
+                    Distribution::Block(darg)
+                }
+                MPI_DISTRIBUTE_CYCLIC => Distribution::Cyclic(darg),
+                MPI_DISTRIBUTE_NONE if psize == 1 => Distribution::Undistributed,
+                _ => return Error::MPI_ERR_ARG,
+            });
+        }
+
+        let datatype = Datatype::darray(
+            size,
+            rank,
+            &gsizes,
+            &distributions,
+            &psizes,
+            order,
+            oldtype,
+        );
+        set_newtype(newtype, "MPI_Type_create_darray", datatype)
+    }
+}
+
+large_count! {
+    fn MPI_Type_create_resized / MPI_Type_create_resized_c<A = MPI_Aint>(
+        oldtype: MPI_Datatype,
+        lb: A,
+        extent: A,
+        newtype: Option<&mut MPI_Datatype>,
+    ) -> Error {
+        let lb = mpicheck!(displacement(lb));
+        let extent = mpicheck!(displacement(extent));
+        let oldtype = mpicheck!(unsafe { oldtype.get() });
+
+        set_newtype(newtype, "MPI_Type_create_resized", Datatype::resized(lb, extent, oldtype))
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Type_commit(datatype: Option<&mut MPI_Datatype>) -> Error {
+    let datatype = datatype.expect("NULL is not a valid parameter to MPI_Type_commit.");
+    mpicheck!(unsafe { datatype.get_mut() }).commit();
+    Error::MPI_SUCCESS
+}
+
+// Communication already under way holds on to the datatype, so it carries on unaffected.
+#[no_mangle]
+pub extern "C" fn MPI_Type_free(datatype: Option<&mut MPI_Datatype>) -> Error {
+    let datatype = datatype.expect("NULL is not a valid parameter to MPI_Type_free.");
+    if mpicheck!(unsafe { datatype.get() }).is_named() {
+        return Error::MPI_ERR_TYPE;
+    }
+
+    unsafe { mem::replace(datatype, MPI_DATATYPE_NULL).free() };
+    Error::MPI_SUCCESS
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Type_dup(oldtype: MPI_Datatype, newtype: Option<&mut MPI_Datatype>) -> Error {
+    let oldtype = mpicheck!(unsafe { oldtype.get() });
+
+    set_newtype(newtype, "MPI_Type_dup", Datatype::dup(oldtype))
+}
+
+// Sizes too large to be returned are MPI_UNDEFINED.
+large_count! {
+    fn MPI_Type_size / MPI_Type_size_c<C = c_int>(
+        datatype: MPI_Datatype,
+        size: Option<&mut C>,
+    ) -> Error {
+        let size = size.expect("NULL is not a valid parameter to MPI_Type_size.");
+        let datatype = mpicheck!(unsafe { datatype.get() });
+
+        *size = count_or_undefined(Some(datatype.size()));
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Type_get_extent / MPI_Type_get_extent_c<A = MPI_Aint>(
+        datatype: MPI_Datatype,
+        lb: Option<&mut A>,
+        extent: Option<&mut A>,
+    ) -> Error {
+        let lb = lb.expect("NULL is not a valid parameter to MPI_Type_get_extent.");
+        let extent = extent.expect("NULL is not a valid parameter to MPI_Type_get_extent.");
+        let datatype = mpicheck!(unsafe { datatype.get() });
+
+        *lb = datatype.lb().value_as().unwrap();
+        *extent = datatype.extent().value_as().unwrap();
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Type_get_true_extent / MPI_Type_get_true_extent_c<A = MPI_Aint>(
+        datatype: MPI_Datatype,
+        true_lb: Option<&mut A>,
+        true_extent: Option<&mut A>,
+    ) -> Error {
+        let true_lb = true_lb.expect("NULL is not a valid parameter to MPI_Type_get_true_extent.");
+        let true_extent =
+            true_extent.expect("NULL is not a valid parameter to MPI_Type_get_true_extent.");
+        let datatype = mpicheck!(unsafe { datatype.get() });
+
+        *true_lb = datatype.true_lb().value_as().unwrap();
+        *true_extent = datatype.true_extent().value_as().unwrap();
+        Error::MPI_SUCCESS
+    }
+}
+
+// Addresses are plain integers, so arithmetic on them is too.
+#[no_mangle]
+pub extern "C" fn MPI_Get_address(
+    location: *const c_void,
+    address: Option<&mut MPI_Aint>,
+) -> Error {
+    let address = address.expect("NULL is not a valid parameter to MPI_Get_address.");
+    *address = location as MPI_Aint;
+    Error::MPI_SUCCESS
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Aint_add(base: MPI_Aint, disp: MPI_Aint) -> MPI_Aint {
+    base.wrapping_add(disp)
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Aint_diff(addr1: MPI_Aint, addr2: MPI_Aint) -> MPI_Aint {
+    addr1.wrapping_sub(addr2)
+}
+
+fn counts<'a>(values: &'a [usize]) -> impl Iterator<Item = MPI_Count> + 'a {
+    values.iter().map(|&value| value as MPI_Count)
+}
+
+fn order_code(order: Order) -> c_int {
+    match order {
+        Order::C => MPI_ORDER_C,
+        Order::Fortran => MPI_ORDER_FORTRAN,
+    }
+}
+
+fn distribution_codes(distribution: &Distribution) -> (c_int, c_int) {
+    let darg = |darg: Option<usize>| darg.map_or(MPI_DISTRIBUTE_DFLT_DARG, |darg| darg as c_int);
+    match *distribution {
+        Distribution::Block(darg_of) => (MPI_DISTRIBUTE_BLOCK, darg(darg_of)),
+        Distribution::Cyclic(darg_of) => (MPI_DISTRIBUTE_CYCLIC, darg(darg_of)),
+        Distribution::Undistributed => (MPI_DISTRIBUTE_NONE, MPI_DISTRIBUTE_DFLT_DARG),
+    }
+}
+
+// The arguments a derived datatype was made from, laid out the way MPI_Type_get_contents returns
+// them: the combiner, then the integers, addresses and datatypes. Named datatypes have none. The
+// integers are kept as large counts until they are laid out for one of the routines.
+struct Contents {
+    combiner: c_int,
+    integers: Vec<MPI_Count>,
+    addresses: Vec<MPI_Aint>,
+    datatypes: Vec<Datatype>,
+}
+
+// The arguments as one of the routines returns them.
+struct Layout {
+    integers: Vec<c_int>,
+    addresses: Vec<MPI_Aint>,
+    large_counts: Vec<MPI_Count>,
+}
+
+impl Contents {
+    // The classic layout, which is only possible when every integer fits in an int.
+    fn classic(&self) -> Option<Layout> {
+        let integers: Option<_> =
+            self.integers.iter().map(|&integer| integer.value_as().ok()).collect();
+        integers.map(|integers| Layout {
+            integers,
+            addresses: self.addresses.clone(),
+            large_counts: vec![],
+        })
+    }
+
+    // The layout of the `_c` routines, which MPI 4.0 gives every datatype made by a large count
+    // constructor: counts, block lengths, sizes and displacements are large counts, and only the
+    // integers that were ints in the constructor remain. We don't record which constructor made a
+    // datatype, so datatypes get the classic layout whenever it can hold their arguments.
+    fn large(&self) -> Layout {
+        if let Some(layout) = self.classic() {
+            return layout;
+        }
+
+        let ints = |integers: &[MPI_Count]| -> Vec<c_int> {
+            integers.iter().map(|&integer| integer as c_int).collect()
+        };
+        let (integers, large_counts) = match self.combiner {
+            // ndims, sizes, subsizes, starts and order.
+            MPI_COMBINER_SUBARRAY => {
+                let (ndims, order) = (self.integers[0], self.integers[self.integers.len() - 1]);
+                let sizes = &self.integers[1..self.integers.len() - 1];
+                (ints(&[ndims, order]), sizes.to_vec())
+            }
+            // size, rank, ndims and gsizes, then distribs, dargs, psizes and order.
+            MPI_COMBINER_DARRAY => {
+                let gsizes = 3..3 + self.integers[2] as usize;
+                let mut integers = ints(&self.integers[..gsizes.start]);
+                integers.extend(ints(&self.integers[gsizes.end..]));
+                (integers, self.integers[gsizes].to_vec())
+            }
+            _ => (
+                vec![],
+                self.integers
+                    .iter()
+                    .cloned()
+                    .chain(self.addresses.iter().map(|&address| address as MPI_Count))
+                    .collect(),
+            ),
+        };
+        Layout {
+            integers,
+            addresses: vec![],
+            large_counts,
+        }
+    }
+}
+
+fn contents(datatype: &Datatype) -> Option<Contents> {
+    let of = |combiner, integers, addresses, datatypes| {
+        Some(Contents {
+            combiner,
+            integers,
+            addresses,
+            datatypes,
+        })
+    };
+
+    match *datatype.constructor() {
+        Constructor::Primitive(_) | Constructor::Pair(_) => None,
+        Constructor::Dup(ref old) => of(MPI_COMBINER_DUP, vec![], vec![], vec![old.clone()]),
+        Constructor::Contiguous { count, ref old } => of(
+            MPI_COMBINER_CONTIGUOUS,
+            vec![count as MPI_Count],
+            vec![],
+            vec![old.clone()],
+        ),
+        Constructor::Vector {
+            count,
+            blocklength,
+            stride,
+            ref old,
+        } => of(
+            MPI_COMBINER_VECTOR,
+            vec![count as MPI_Count, blocklength as MPI_Count, stride as MPI_Count],
+            vec![],
+            vec![old.clone()],
+        ),
+        Constructor::Hvector {
+            count,
+            blocklength,
+            stride,
+            ref old,
+        } => of(
+            MPI_COMBINER_HVECTOR,
+            vec![count as MPI_Count, blocklength as MPI_Count],
+            vec![stride],
+            vec![old.clone()],
+        ),
+        Constructor::Indexed {
+            ref blocklengths,
+            ref displacements,
+            ref old,
+        } => of(
+            MPI_COMBINER_INDEXED,
+            Some(blocklengths.len() as MPI_Count)
+                .into_iter()
+                .chain(counts(blocklengths))
+                .chain(displacements.iter().map(|&displacement| displacement as MPI_Count))
+                .collect(),
+            vec![],
+            vec![old.clone()],
+        ),
+        Constructor::Hindexed {
+            ref blocklengths,
+            ref displacements,
+            ref old,
+        } => of(
+            MPI_COMBINER_HINDEXED,
+            Some(blocklengths.len() as MPI_Count)
+                .into_iter()
+                .chain(counts(blocklengths))
+                .collect(),
+            displacements.clone(),
+            vec![old.clone()],
+        ),
+        Constructor::IndexedBlock {
+            blocklength,
+            ref displacements,
+            ref old,
+        } => of(
+            MPI_COMBINER_INDEXED_BLOCK,
+            vec![displacements.len() as MPI_Count, blocklength as MPI_Count]
+                .into_iter()
+                .chain(displacements.iter().map(|&displacement| displacement as MPI_Count))
+                .collect(),
+            vec![],
+            vec![old.clone()],
+        ),
+        Constructor::Struct {
+            ref blocklengths,
+            ref displacements,
+            ref types,
+        } => of(
+            MPI_COMBINER_STRUCT,
+            Some(blocklengths.len() as MPI_Count)
+                .into_iter()
+                .chain(counts(blocklengths))
+                .collect(),
+            displacements.clone(),
+            types.clone(),
+        ),
+        Constructor::Subarray {
+            ref sizes,
+            ref subsizes,
+            ref starts,
+            order,
+            ref old,
+        } => of(
+            MPI_COMBINER_SUBARRAY,
+            Some(sizes.len() as MPI_Count)
+                .into_iter()
+                .chain(counts(sizes))
+                .chain(counts(subsizes))
+                .chain(counts(starts))
+                .chain(Some(MPI_Count::from(order_code(order))))
+                .collect(),
+            vec![],
+            vec![old.clone()],
+        ),
+        Constructor::Darray {
+            size,
+            rank,
+            ref gsizes,
+            ref distributions,
+            ref psizes,
+            order,
+            ref old,
+        } => {
+            let codes: Vec<_> = distributions.iter().map(distribution_codes).collect();
+            of(
+                MPI_COMBINER_DARRAY,
+                vec![size as MPI_Count, rank as MPI_Count, gsizes.len() as MPI_Count]
+                    .into_iter()
+                    .chain(counts(gsizes))
+                    .chain(codes.iter().map(|&(distrib, _)| MPI_Count::from(distrib)))
+                    .chain(codes.iter().map(|&(_, darg)| MPI_Count::from(darg)))
+                    .chain(counts(psizes))
+                    .chain(Some(MPI_Count::from(order_code(order))))
+                    .collect(),
+                vec![],
+                vec![old.clone()],
+            )
+        }
+        Constructor::Resized { lb, extent, ref old } => {
+            of(MPI_COMBINER_RESIZED, vec![], vec![lb, extent], vec![old.clone()])
+        }
+    }
+}
+
+// The combiner and the numbers of integers, addresses, large counts and datatypes. Datatypes
+// whose arguments don't fit in the classic layout can only be described by the `_c` routines.
+fn envelope(datatype: MPI_Datatype, large: bool) -> Result<(c_int, [usize; 4]), Error> {
+    let datatype = unsafe { datatype.get() }?;
+    let contents = match contents(datatype) {
+        Some(contents) => contents,
+        None => return Ok((MPI_COMBINER_NAMED, [0; 4])),
+    };
+
+    let layout = if large {
+        contents.large()
+    } else {
+        contents.classic().ok_or(Error::MPI_ERR_TYPE)?
+    };
+    Ok((
+        contents.combiner,
+        [
+            layout.integers.len(),
+            layout.addresses.len(),
+            layout.large_counts.len(),
+            contents.datatypes.len(),
+        ],
+    ))
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Type_get_envelope(
+    datatype: MPI_Datatype,
+    num_integers: Option<&mut c_int>,
+    num_addresses: Option<&mut c_int>,
+    num_datatypes: Option<&mut c_int>,
+    combiner: Option<&mut c_int>,
+) -> Error {
+    let num_integers =
+        num_integers.expect("NULL is not a valid parameter to MPI_Type_get_envelope.");
+    let num_addresses =
+        num_addresses.expect("NULL is not a valid parameter to MPI_Type_get_envelope.");
+    let num_datatypes =
+        num_datatypes.expect("NULL is not a valid parameter to MPI_Type_get_envelope.");
+    let combiner = combiner.expect("NULL is not a valid parameter to MPI_Type_get_envelope.");
+
+    let (combiner_of, [integers, addresses, _, datatypes]) = mpicheck!(envelope(datatype, false));
+    *num_integers = integers as c_int;
+    *num_addresses = addresses as c_int;
+    *num_datatypes = datatypes as c_int;
+    *combiner = combiner_of;
+    Error::MPI_SUCCESS
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Type_get_envelope_c(
+    datatype: MPI_Datatype,
+    num_integers: Option<&mut MPI_Count>,
+    num_addresses: Option<&mut MPI_Count>,
+    num_large_counts: Option<&mut MPI_Count>,
+    num_datatypes: Option<&mut MPI_Count>,
+    combiner: Option<&mut c_int>,
+) -> Error {
+    let num_integers =
+        num_integers.expect("NULL is not a valid parameter to MPI_Type_get_envelope_c.");
+    let num_addresses =
+        num_addresses.expect("NULL is not a valid parameter to MPI_Type_get_envelope_c.");
+    let num_large_counts =
+        num_large_counts.expect("NULL is not a valid parameter to MPI_Type_get_envelope_c.");
+    let num_datatypes =
+        num_datatypes.expect("NULL is not a valid parameter to MPI_Type_get_envelope_c.");
+    let combiner = combiner.expect("NULL is not a valid parameter to MPI_Type_get_envelope_c.");
+
+    let (combiner_of, [integers, addresses, large_counts, datatypes]) =
+        mpicheck!(envelope(datatype, true));
+    *num_integers = integers as MPI_Count;
+    *num_addresses = addresses as MPI_Count;
+    *num_large_counts = large_counts as MPI_Count;
+    *num_datatypes = datatypes as MPI_Count;
+    *combiner = combiner_of;
+    Error::MPI_SUCCESS
+}
+
+// Copies the arguments of `datatype` out, if there's room for them. Named datatypes are returned
+// as their predefined handles, and derived ones as new handles the caller has to free.
+unsafe fn get_contents<C: Count>(
+    datatype: MPI_Datatype,
+    large: bool,
+    max: [C; 4],
+    array_of_integers: *mut c_int,
+    array_of_addresses: *mut MPI_Aint,
+    array_of_large_counts: *mut MPI_Count,
+    array_of_datatypes: *mut MPI_Datatype,
+) -> Result<(), Error> {
+    let datatype = datatype.get()?;
+    let contents = contents(datatype).ok_or(Error::MPI_ERR_TYPE)?;
+    let layout = if large {
+        contents.large()
+    } else {
+        contents.classic().ok_or(Error::MPI_ERR_TYPE)?
+    };
+
+    let lens = [
+        layout.integers.len(),
+        layout.addresses.len(),
+        layout.large_counts.len(),
+        contents.datatypes.len(),
+    ];
+    for (&len, &max) in lens.iter().zip(&max) {
+        if len > nonnegative(max)? {
+            return Err(Error::MPI_ERR_ARG);
+        }
+    }
+
+    ptr::copy_nonoverlapping(layout.integers.as_ptr(), array_of_integers, lens[0]);
+    ptr::copy_nonoverlapping(layout.addresses.as_ptr(), array_of_addresses, lens[1]);
+    ptr::copy_nonoverlapping(layout.large_counts.as_ptr(), array_of_large_counts, lens[2]);
+    for (i, datatype) in contents.datatypes.into_iter().enumerate() {
+        let handle = if datatype.is_named() {
+            predefined_handle(&datatype).unwrap_or_else(|| MPI_Datatype::new(datatype))
+        } else {
+            MPI_Datatype::new(datatype)
+        };
+        *array_of_datatypes.add(i) = handle;
+    }
+    Ok(())
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Type_get_contents(
+    datatype: MPI_Datatype,
+    max_integers: c_int,
+    max_addresses: c_int,
+    max_datatypes: c_int,
+    array_of_integers: *mut c_int,
+    array_of_addresses: *mut MPI_Aint,
+    array_of_datatypes: *mut MPI_Datatype,
+) -> Error {
+    mpicheck!(unsafe {
+        get_contents(
+            datatype,
+            false,
+            [max_integers, max_addresses, 0, max_datatypes],
+            array_of_integers,
+            array_of_addresses,
+            ptr::null_mut(),
+            array_of_datatypes,
+        )
+    });
+    Error::MPI_SUCCESS
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Type_get_contents_c(
+    datatype: MPI_Datatype,
+    max_integers: MPI_Count,
+    max_addresses: MPI_Count,
+    max_large_counts: MPI_Count,
+    max_datatypes: MPI_Count,
+    array_of_integers: *mut c_int,
+    array_of_addresses: *mut MPI_Aint,
+    array_of_large_counts: *mut MPI_Count,
+    array_of_datatypes: *mut MPI_Datatype,
+) -> Error {
+    mpicheck!(unsafe {
+        get_contents(
+            datatype,
+            true,
+            [max_integers, max_addresses, max_large_counts, max_datatypes],
+            array_of_integers,
+            array_of_addresses,
+            array_of_large_counts,
+            array_of_datatypes,
+        )
+    });
+    Error::MPI_SUCCESS
+}
+
+// Only the C types are available, so those are what sizes are matched against.
+#[no_mangle]
+pub extern "C" fn MPI_Type_match_size(
+    typeclass: c_int,
+    size: c_int,
+    datatype: Option<&mut MPI_Datatype>,
+) -> Error {
+    let datatype = datatype.expect("NULL is not a valid parameter to MPI_Type_match_size.");
+
+    let candidates = unsafe {
+        match typeclass {
+            MPI_TYPECLASS_INTEGER => vec![MPI_SIGNED_CHAR, MPI_SHORT, MPI_INT, MPI_LONG_LONG],
+            MPI_TYPECLASS_REAL => vec![MPI_FLOAT, MPI_DOUBLE, MPI_LONG_DOUBLE],
+            MPI_TYPECLASS_COMPLEX => vec![
+                MPI_C_FLOAT_COMPLEX,
+                MPI_C_DOUBLE_COMPLEX,
+                MPI_C_LONG_DOUBLE_COMPLEX,
+            ],
+            _ => return Error::MPI_ERR_ARG,
+        }
+    };
+
+    match candidates.into_iter().find(|candidate| {
+        unsafe { candidate.get() }.map_or(false, |candidate| candidate.size() as c_int == size)
+    }) {
+        Some(candidate) => {
+            *datatype = candidate;
+            Error::MPI_SUCCESS
+        }
+        None => Error::MPI_ERR_ARG,
+    }
+}
+
+large_count! {
+    fn MPI_Get_count / MPI_Get_count_c<C = c_int>(
+        status: Option<&MPI_Status>,
+        datatype: MPI_Datatype,
+        count: Option<&mut C>,
+    ) -> Error {
+        let status = status.expect("NULL is not a valid parameter to MPI_Get_count.");
+        let count = count.expect("NULL is not a valid parameter to MPI_Get_count.");
+        let datatype = mpicheck!(unsafe { datatype.get() });
+
+        *count = count_or_undefined(datatype.count_in(status.len_as(datatype)));
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Get_elements / MPI_Get_elements_c<C = c_int>(
+        status: Option<&MPI_Status>,
+        datatype: MPI_Datatype,
+        count: Option<&mut C>,
+    ) -> Error {
+        let status = status.expect("NULL is not a valid parameter to MPI_Get_elements.");
+        let count = count.expect("NULL is not a valid parameter to MPI_Get_elements.");
+        let datatype = mpicheck!(unsafe { datatype.get() });
+
+        let elements = datatype.basic_elements_in(status.len_as(datatype));
+        *count = count_or_undefined(elements);
+        Error::MPI_SUCCESS
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Get_elements_x(
+    status: Option<&MPI_Status>,
+    datatype: MPI_Datatype,
+    count: Option<&mut MPI_Count>,
+) -> Error {
+    let status = status.expect("NULL is not a valid parameter to MPI_Get_elements_x.");
+    let count = count.expect("NULL is not a valid parameter to MPI_Get_elements_x.");
+    let datatype = mpicheck!(unsafe { datatype.get() });
+
+    let elements = datatype.basic_elements_in(status.len_as(datatype));
+    *count = count_or_undefined::<MPI_Count>(elements);
+    Error::MPI_SUCCESS
+}
+
+// The status records bytes, so the count is kept as the length of that many basic elements.
+fn set_elements(status: &mut MPI_Status, datatype: MPI_Datatype, count: u64) -> Error {
+    let datatype = mpicheck!(unsafe { datatype.get() });
+    status.set_count(datatype.basic_elements_len(count));
+    Error::MPI_SUCCESS
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Status_set_elements(
+    status: Option<&mut MPI_Status>,
+    datatype: MPI_Datatype,
+    count: c_int,
+) -> Error {
+    let status = status.expect("NULL is not a valid parameter to MPI_Status_set_elements.");
+    let count = mpicheck!(count.value_as().map_err(|_| Error::MPI_ERR_COUNT));
+    set_elements(status, datatype, count)
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Status_set_elements_x(
+    status: Option<&mut MPI_Status>,
+    datatype: MPI_Datatype,
+    count: MPI_Count,
+) -> Error {
+    let status = status.expect("NULL is not a valid parameter to MPI_Status_set_elements_x.");
+    let count = mpicheck!(count.value_as().map_err(|_| Error::MPI_ERR_COUNT));
+    set_elements(status, datatype, count)
+}
@@ -0,0 +1,127 @@
+use super::{Error, init::universe, request::MPI_Request, status::MPI_Status};
+
+use empire::{error, Completer, Generalized, Status};
+use std::{os::raw::{c_int, c_void}, sync::Mutex};
+
+#[allow(non_camel_case_types)]
+pub type MPI_Grequest_query_function =
+    unsafe extern "C" fn(extra_state: *mut c_void, status: *mut MPI_Status) -> c_int;
+
+#[allow(non_camel_case_types)]
+pub type MPI_Grequest_free_function = unsafe extern "C" fn(extra_state: *mut c_void) -> c_int;
+
+#[allow(non_camel_case_types)]
+pub type MPI_Grequest_cancel_function =
+    unsafe extern "C" fn(extra_state: *mut c_void, complete: c_int) -> c_int;
+
+// A generalized request that MPI_Grequest_complete hasn't been called for yet.
+struct Incomplete {
+    // The address of the handle's allocation, which is what MPI_Grequest_complete is given.
+    handle: usize,
+    completer: Completer,
+    // Freed by MPI_Request_free. The handle stays allocated until MPI_Grequest_complete, which is
+    // still called with it, and is freed then.
+    freed: bool,
+}
+
+// The request itself can't be reached through its handle while a wait or test has taken it out, and
+// MPI_Grequest_complete is usually called from another thread while that happens. So completers
+// are looked up here instead.
+static INCOMPLETE: Mutex<Vec<Incomplete>> = Mutex::new(Vec::new());
+
+struct Callbacks {
+    query_fn: MPI_Grequest_query_function,
+    free_fn: MPI_Grequest_free_function,
+    cancel_fn: MPI_Grequest_cancel_function,
+    extra_state: *mut c_void,
+}
+
+// The callbacks are called from whichever thread completes the request, as MPI requires the user
+// to allow.
+unsafe impl Send for Callbacks {}
+
+fn check(code: c_int) -> error::Result<()> {
+    if code == Error::MPI_SUCCESS as c_int {
+        Ok(())
+    } else {
+        Err(error::Error::CallbackFailed(code))
+    }
+}
+
+impl Generalized for Callbacks {
+    fn query(&mut self) -> error::Result<Status> {
+        let mut status = MPI_Status::from_status(&Status::empty(), Error::MPI_SUCCESS);
+        check(unsafe { (self.query_fn)(self.extra_state, &mut status) })?;
+        Ok(status.to_status())
+    }
+
+    fn free(&mut self) -> error::Result<()> {
+        check(unsafe { (self.free_fn)(self.extra_state) })
+    }
+
+    fn cancel(&mut self, complete: bool) -> error::Result<()> {
+        check(unsafe { (self.cancel_fn)(self.extra_state, complete as c_int) })
+    }
+}
+
+// Keeps the handle of a generalized request that hasn't completed yet alive for
+// MPI_Grequest_complete. Returns false for any other request.
+pub fn free_when_complete(request: &MPI_Request) -> bool {
+    let mut incomplete = INCOMPLETE.lock().unwrap();
+    match incomplete
+        .iter_mut()
+        .find(|incomplete| incomplete.handle == request.handle as usize)
+    {
+        Some(incomplete) => {
+            incomplete.freed = true;
+            true
+        }
+        None => false,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Grequest_start(
+    query_fn: MPI_Grequest_query_function,
+    free_fn: MPI_Grequest_free_function,
+    cancel_fn: MPI_Grequest_cancel_function,
+    extra_state: *mut c_void,
+    request: Option<&mut MPI_Request>,
+) -> Error {
+    let request = request.expect("NULL is not a valid parameter to MPI_Grequest_start.");
+
+    let (pending, completer) = universe().read().unwrap().grequest_start(Callbacks {
+        query_fn,
+        free_fn,
+        cancel_fn,
+        extra_state,
+    });
+    *request = MPI_Request::new(pending);
+
+    INCOMPLETE.lock().unwrap().push(Incomplete {
+        handle: request.handle as usize,
+        completer,
+        freed: false,
+    });
+
+    Error::MPI_SUCCESS
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Grequest_complete(mut request: MPI_Request) -> Error {
+    let mut incomplete = INCOMPLETE.lock().unwrap();
+    let index = incomplete
+        .iter()
+        .position(|incomplete| incomplete.handle == request.handle as usize)
+        .expect("MPI_Grequest_complete requires a generalized request that has not completed.");
+    let completed = incomplete.remove(index);
+
+    completed.completer.complete();
+
+    // A request that was freed early goes now, which calls its free callback.
+    if completed.freed {
+        unsafe { request.free() };
+    }
+
+    Error::MPI_SUCCESS
+}
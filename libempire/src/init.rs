@@ -1,6 +1,8 @@
 use std::{ptr, os::raw::{c_char, c_int}, sync::{Arc, RwLock}};
 
-use super::{handles::*, info::{MPI_Info, MPI_INFO_NULL}, status::*};
+use super::{datatype, op, handles::*, info::{MPI_Info, MPI_INFO_NULL},
+            message::{MPI_Message, MPI_MESSAGE_NO_PROC, MPI_MESSAGE_NULL},
+            request::{MPI_Request, MPI_REQUEST_NULL}, status::*};
 
 use empire::Universe;
 
@@ -21,6 +23,11 @@ pub static mut MPI_COMM_NULL: MPI_Comm = MPI_Comm {
     handle: ptr::null_mut(),
 };
 
+// The handle MPI_Comm_get_parent returns in a spawned job. It stays null in a job with no parent.
+pub static mut COMM_PARENT: MPI_Comm = MPI_Comm {
+    handle: ptr::null_mut(),
+};
+
 pub fn universe() -> &'static Arc<RwLock<Universe>> {
     unsafe {
         UNIVERSE
@@ -41,7 +48,16 @@ fn initialize_mpi() -> Error {
         MPI_COMM_WORLD = MPI_Comm::new(CommHandle::SystemComm(Arc::downgrade(&locked.comm_world())))
     };
     unsafe { MPI_COMM_NULL = MPI_Comm::new(CommHandle::NullComm) };
+    if let Some(parent) = locked.comm_parent() {
+        unsafe { COMM_PARENT = MPI_Comm::new(CommHandle::SystemComm(Arc::downgrade(&parent))) };
+    }
     unsafe { MPI_INFO_NULL = MPI_Info::null() };
+    unsafe { MPI_REQUEST_NULL = MPI_Request::null() };
+    unsafe { MPI_MESSAGE_NULL = MPI_Message::null() };
+    unsafe { MPI_MESSAGE_NO_PROC = MPI_Message::null() };
+
+    datatype::initialize_datatypes();
+    op::initialize_ops();
 
     Error::MPI_SUCCESS
 }
@@ -63,9 +79,20 @@ pub extern "C" fn MPI_Finalize() -> Error {
         MPI_COMM_SELF.free();
         MPI_COMM_WORLD.free();
         MPI_COMM_NULL.free();
+        if !COMM_PARENT.handle.is_null() {
+            COMM_PARENT.free();
+        }
 
         MPI_INFO_NULL.free();
+        MPI_REQUEST_NULL.free();
+        MPI_MESSAGE_NULL.free();
+        MPI_MESSAGE_NO_PROC.free();
+    }
 
+    datatype::finalize_datatypes();
+    op::finalize_ops();
+
+    unsafe {
         UNIVERSE = None;
     }
 
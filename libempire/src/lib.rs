@@ -6,12 +6,24 @@ extern crate itertools;
 // Status contains the macro for mpitry, so list first.
 #[macro_use]
 mod status;
+// Count contains the macro for the `_c` variants of routines, so list next.
+#[macro_use]
+mod count;
 
 // Contain MPI function definitions
+pub mod collective;
 pub mod comm;
+pub mod datatype;
+pub mod grequest;
 pub mod info;
 pub mod init;
+pub mod message;
+pub mod op;
+pub mod pack;
+pub mod partitioned;
 pub mod port;
+pub mod pt2pt;
+pub mod request;
 pub mod spawn;
 
 // Supporting modules
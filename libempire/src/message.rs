@@ -0,0 +1,168 @@
+use super::{universe, Error, datatype::MPI_Datatype, handles::MPI_Comm, request::MPI_Request,
+            status::MPI_Status, pt2pt::{recv_buffer, recv_tag, source}};
+
+use empire::{Message, Source, Tag};
+use std::{mem, ptr, os::raw::{c_int, c_void}};
+
+#[no_mangle]
+pub static mut MPI_MESSAGE_NULL: MPI_Message = MPI_Message {
+    handle: ptr::null_mut(),
+};
+
+#[no_mangle]
+pub static mut MPI_MESSAGE_NO_PROC: MPI_Message = MPI_Message {
+    handle: ptr::null_mut(),
+};
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct MPI_Message {
+    pub handle: *mut Option<Message>,
+}
+
+impl MPI_Message {
+    pub fn null() -> Self {
+        Self {
+            handle: Box::into_raw(Box::new(None)),
+        }
+    }
+
+    // The message matched by a probe of MPI_PROC_NULL is always MPI_MESSAGE_NO_PROC.
+    pub unsafe fn new(message: Message) -> Self {
+        if message.is_no_proc() {
+            MPI_MESSAGE_NO_PROC
+        } else {
+            Self {
+                handle: Box::into_raw(Box::new(Some(message))),
+            }
+        }
+    }
+
+    fn assert_non_null(&self) {
+        assert!(
+            self.handle != ptr::null_mut(),
+            "NULL is not an allowed value for MPI_Message. You may not have initialized MPI yet. \
+             Use MPI_MESSAGE_NULL instead."
+        );
+    }
+
+    pub unsafe fn is_no_proc(&self) -> bool {
+        self.assert_non_null();
+        self.handle == MPI_MESSAGE_NO_PROC.handle
+    }
+
+    // Takes the message out of the handle and sets the handle to MPI_MESSAGE_NULL.
+    pub unsafe fn take(&mut self) -> Message {
+        if self.is_no_proc() {
+            *self = MPI_MESSAGE_NULL;
+            // Any probe of MPI_PROC_NULL produces an equivalent message.
+            return universe()
+                .read()
+                .unwrap()
+                .comm_self()
+                .improbe(Source::ProcNull, Tag::Any)
+                .unwrap();
+        }
+
+        let message = mem::replace(&mut *self.handle, None)
+            .expect("MPI_MESSAGE_NULL is not allowed in this routine.");
+        self.free();
+        *self = MPI_MESSAGE_NULL;
+        message
+    }
+
+    pub unsafe fn free(&mut self) {
+        self.assert_non_null();
+        Box::from_raw(self.handle);
+        self.handle = ptr::null_mut();
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Mprobe(
+    source: c_int,
+    tag: c_int,
+    comm: MPI_Comm,
+    message: Option<&mut MPI_Message>,
+    status: Option<&mut MPI_Status>,
+) -> Error {
+    let comm = unsafe { comm.get() };
+    let message = message.expect("NULL is not a valid parameter to MPI_Mprobe.");
+
+    let source = mpicheck!(self::source(source, &comm));
+    let tag = mpicheck!(recv_tag(tag));
+
+    let matched = comm.mprobe(source, tag);
+    let result = Ok(matched.status());
+    *message = unsafe { MPI_Message::new(matched) };
+
+    MPI_Status::set(status, &result)
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Improbe(
+    source: c_int,
+    tag: c_int,
+    comm: MPI_Comm,
+    flag: *mut c_int,
+    message: Option<&mut MPI_Message>,
+    status: Option<&mut MPI_Status>,
+) -> Error {
+    let comm = unsafe { comm.get() };
+    let message = message.expect("NULL is not a valid parameter to MPI_Improbe.");
+
+    let source = mpicheck!(self::source(source, &comm));
+    let tag = mpicheck!(recv_tag(tag));
+
+    match comm.improbe(source, tag) {
+        Some(matched) => {
+            unsafe { *flag = 1 };
+            let result = Ok(matched.status());
+            *message = unsafe { MPI_Message::new(matched) };
+            MPI_Status::set(status, &result)
+        }
+        None => {
+            unsafe { *flag = 0 };
+            Error::MPI_SUCCESS
+        }
+    }
+}
+
+large_count! {
+    fn MPI_Mrecv / MPI_Mrecv_c<C = c_int>(
+        buf: *mut c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        message: Option<&mut MPI_Message>,
+        status: Option<&mut MPI_Status>,
+    ) -> Error {
+        let message = message.expect("NULL is not a valid parameter to MPI_Mrecv.");
+
+        let buf = mpicheck!(unsafe { recv_buffer(buf, count, datatype) });
+
+        let result = unsafe { message.take() }.recv(buf);
+
+        MPI_Status::set(status, &result)
+    }
+}
+
+large_count! {
+    fn MPI_Imrecv / MPI_Imrecv_c<C = c_int>(
+        buf: *mut c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        message: Option<&mut MPI_Message>,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let message = message.expect("NULL is not a valid parameter to MPI_Imrecv.");
+        let request = request.expect("NULL is not a valid parameter to MPI_Imrecv.");
+
+        let buf = mpicheck!(unsafe { recv_buffer(buf, count, datatype) });
+
+        let matched = unsafe { message.take() };
+        *request = MPI_Request::new(matched.imrecv(buf));
+
+        Error::MPI_SUCCESS
+    }
+}
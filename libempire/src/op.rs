@@ -0,0 +1,147 @@
+use empire::{Buffer, BufferMut, Op};
+use std::{mem, ptr, os::raw::{c_int, c_void}};
+
+use super::{Error, datatype::{predefined_handle, MPI_Datatype}};
+
+#[no_mangle]
+pub static mut MPI_OP_NULL: MPI_Op = MPI_Op {
+    handle: ptr::null_mut(),
+};
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct MPI_Op {
+    pub handle: *mut Option<Op>,
+}
+
+impl MPI_Op {
+    pub fn null() -> Self {
+        Self {
+            handle: Box::into_raw(Box::new(None)),
+        }
+    }
+
+    pub fn new(op: Op) -> Self {
+        Self {
+            handle: Box::into_raw(Box::new(Some(op))),
+        }
+    }
+
+    fn assert_non_null(&self) {
+        assert!(
+            self.handle != ptr::null_mut(),
+            "NULL is not an allowed value for MPI_Op. You may not have initialized MPI yet. Use \
+             MPI_OP_NULL instead."
+        );
+    }
+
+    // MPI_OP_NULL combines nothing, so reductions fail on it.
+    pub unsafe fn get(&self) -> Result<&Op, Error> {
+        self.assert_non_null();
+        (*self.handle).as_ref().ok_or(Error::MPI_ERR_OP)
+    }
+
+    pub unsafe fn free(&mut self) {
+        self.assert_non_null();
+        Box::from_raw(self.handle);
+        self.handle = ptr::null_mut();
+    }
+}
+
+macro_rules! predefined_ops {
+    ($($name:ident: $op:expr),* $(,)*) => {
+        $(
+            #[no_mangle]
+            pub static mut $name: MPI_Op = MPI_Op {
+                handle: ptr::null_mut(),
+            };
+        )*
+
+        pub fn initialize_ops() {
+            unsafe { MPI_OP_NULL = MPI_Op::null() };
+            $(
+                unsafe { $name = MPI_Op::new($op) };
+            )*
+        }
+
+        pub fn finalize_ops() {
+            unsafe { MPI_OP_NULL.free() };
+            $(
+                unsafe { $name.free() };
+            )*
+        }
+    };
+}
+
+predefined_ops! {
+    MPI_SUM: Op::Sum,
+    MPI_PROD: Op::Prod,
+    MPI_MAX: Op::Max,
+    MPI_MIN: Op::Min,
+    MPI_LAND: Op::LogicalAnd,
+    MPI_BAND: Op::BitwiseAnd,
+    MPI_LOR: Op::LogicalOr,
+    MPI_BOR: Op::BitwiseOr,
+    MPI_LXOR: Op::LogicalXor,
+    MPI_BXOR: Op::BitwiseXor,
+    MPI_MAXLOC: Op::MaxLoc,
+    MPI_MINLOC: Op::MinLoc,
+    MPI_REPLACE: Op::Replace,
+    MPI_NO_OP: Op::NoOp,
+}
+
+// The user's function is given the datatype of the elements as a handle. Derived datatypes get a
+// handle of their own for the length of the call.
+large_count! {
+    fn MPI_Op_create / MPI_Op_create_c<C = c_int>(
+        user_fn: unsafe extern "C" fn(*mut c_void, *mut c_void, *mut C, *mut MPI_Datatype),
+        commute: c_int,
+        op: Option<&mut MPI_Op>,
+    ) -> Error {
+        let op = op.expect("NULL is not a valid parameter to MPI_Op_create.");
+
+        let function = move |input: &Buffer, inout: &mut BufferMut| {
+            let mut len = C::value_from(inout.count())
+                .expect("A user operation was applied to more elements than it can be told of.");
+            let predefined = predefined_handle(inout.datatype());
+            let mut datatype =
+                predefined.unwrap_or_else(|| MPI_Datatype::new(inout.datatype().clone()));
+
+            unsafe {
+                user_fn(
+                    input.as_ptr() as *mut c_void,
+                    inout.as_mut_ptr() as *mut c_void,
+                    &mut len,
+                    &mut datatype,
+                );
+                if predefined.is_none() {
+                    datatype.free();
+                }
+            }
+        };
+        *op = MPI_Op::new(Op::user(function, commute != 0));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+// Only user operations can be freed. Reductions already under way hold on to the operation.
+#[no_mangle]
+pub extern "C" fn MPI_Op_free(op: Option<&mut MPI_Op>) -> Error {
+    let op = op.expect("NULL is not a valid parameter to MPI_Op_free.");
+    match *mpicheck!(unsafe { op.get() }) {
+        Op::User(_) => {}
+        _ => return Error::MPI_ERR_OP,
+    }
+
+    unsafe { mem::replace(op, MPI_OP_NULL).free() };
+    Error::MPI_SUCCESS
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Op_commutative(op: MPI_Op, commute: Option<&mut c_int>) -> Error {
+    let commute = commute.expect("NULL is not a valid parameter to MPI_Op_commutative.");
+    *commute = mpicheck!(unsafe { op.get() }).is_commutative() as c_int;
+    Error::MPI_SUCCESS
+}
@@ -0,0 +1,210 @@
+use super::{Error, count::{count_of, count_or_undefined}, datatype::{MPI_Aint, MPI_Datatype},
+            handles::MPI_Comm, pt2pt::{recv_buffer, send_buffer, send_bytes}};
+
+use conv::*;
+use empire::{Buffer, BufferMut};
+use std::{ptr, ffi::CStr, os::raw::{c_char, c_int, c_void}};
+
+// Packed data is in the native representation unless it is external32. The communicator passed to
+// the native routines isn't consulted: packed data travels as MPI_PACKED bytes, which are never
+// converted, so data packed for a process with a different architecture should use external32.
+
+// Appends the data in `buf` to the packed buffer at `position`, returning the position after it.
+// Nothing is written if the data doesn't fit.
+unsafe fn pack(
+    buf: &Buffer,
+    external: bool,
+    outbuf: *mut c_void,
+    outsize: usize,
+    position: usize,
+) -> Result<usize, Error> {
+    if position > outsize {
+        return Err(Error::MPI_ERR_ARG);
+    }
+
+    let mut packed = Vec::new();
+    if external {
+        buf.pack_external(&mut packed);
+    } else {
+        buf.pack_into(&mut packed);
+    }
+    if packed.len() > outsize - position {
+        return Err(Error::MPI_ERR_TRUNCATE);
+    }
+
+    ptr::copy_nonoverlapping(
+        packed.as_ptr(),
+        (outbuf as *mut u8).add(position),
+        packed.len(),
+    );
+    Ok(position + packed.len())
+}
+
+// Fills `buf` from the packed buffer at `position`, returning the position after the data used.
+unsafe fn unpack(
+    inbuf: *const c_void,
+    insize: usize,
+    position: usize,
+    buf: &mut BufferMut,
+    external: bool,
+) -> Result<usize, Error> {
+    let len = if external {
+        buf.external_len()
+    } else {
+        buf.len()
+    };
+    if position > insize {
+        return Err(Error::MPI_ERR_ARG);
+    }
+    if len > (insize - position) as u64 {
+        return Err(Error::MPI_ERR_TRUNCATE);
+    }
+    let len = len as usize;
+
+    let data = send_bytes((inbuf as *const u8).add(position) as *const c_void, len);
+    if external {
+        buf.unpack_external(data);
+    } else {
+        buf.unpack(data);
+    }
+    Ok(position + len)
+}
+
+// external32 is the only data representation there is.
+unsafe fn check_datarep(datarep: *const c_char) -> Result<(), Error> {
+    if CStr::from_ptr(datarep).to_bytes() == b"external32" {
+        Ok(())
+    } else {
+        Err(Error::MPI_ERR_UNSUPPORTED_DATAREP)
+    }
+}
+
+fn position_of<T: ValueInto<usize>>(position: T) -> Result<usize, Error> {
+    position.value_into().map_err(|_| Error::MPI_ERR_ARG)
+}
+
+large_count! {
+    fn MPI_Pack / MPI_Pack_c<C = c_int>(
+        inbuf: *const c_void,
+        incount: C,
+        datatype: MPI_Datatype,
+        outbuf: *mut c_void,
+        outsize: C,
+        position: Option<&mut C>,
+        _comm: MPI_Comm,
+    ) -> Error {
+        let position = position.expect("NULL is not a valid parameter to MPI_Pack.");
+
+        let buf = mpicheck!(unsafe { send_buffer(inbuf, incount, datatype) });
+        let outsize = mpicheck!(position_of(outsize));
+        let start = mpicheck!(position_of(*position));
+
+        let end = mpicheck!(unsafe { pack(&buf, false, outbuf, outsize, start) });
+        *position = end.value_as().unwrap();
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Unpack / MPI_Unpack_c<C = c_int>(
+        inbuf: *const c_void,
+        insize: C,
+        position: Option<&mut C>,
+        outbuf: *mut c_void,
+        outcount: C,
+        datatype: MPI_Datatype,
+        _comm: MPI_Comm,
+    ) -> Error {
+        let position = position.expect("NULL is not a valid parameter to MPI_Unpack.");
+
+        let mut buf = mpicheck!(unsafe { recv_buffer(outbuf, outcount, datatype) });
+        let insize = mpicheck!(position_of(insize));
+        let start = mpicheck!(position_of(*position));
+
+        let end = mpicheck!(unsafe { unpack(inbuf, insize, start, &mut buf, false) });
+        *position = end.value_as().unwrap();
+        Error::MPI_SUCCESS
+    }
+}
+
+// Sizes too large to be returned are MPI_UNDEFINED.
+large_count! {
+    fn MPI_Pack_size / MPI_Pack_size_c<C = c_int>(
+        incount: C,
+        datatype: MPI_Datatype,
+        _comm: MPI_Comm,
+        size: Option<&mut C>,
+    ) -> Error {
+        let size = size.expect("NULL is not a valid parameter to MPI_Pack_size.");
+
+        let incount = mpicheck!(count_of(incount));
+        let datatype = mpicheck!(unsafe { datatype.get() });
+
+        *size = count_or_undefined((incount as u64).checked_mul(datatype.size()));
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Pack_external / MPI_Pack_external_c<C = c_int, A = MPI_Aint>(
+        datarep: *const c_char,
+        inbuf: *const c_void,
+        incount: C,
+        datatype: MPI_Datatype,
+        outbuf: *mut c_void,
+        outsize: A,
+        position: Option<&mut A>,
+    ) -> Error {
+        let position = position.expect("NULL is not a valid parameter to MPI_Pack_external.");
+
+        mpicheck!(unsafe { check_datarep(datarep) });
+        let buf = mpicheck!(unsafe { send_buffer(inbuf, incount, datatype) });
+        let outsize = mpicheck!(position_of(outsize));
+        let start = mpicheck!(position_of(*position));
+
+        let end = mpicheck!(unsafe { pack(&buf, true, outbuf, outsize, start) });
+        *position = end.value_as().unwrap();
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Unpack_external / MPI_Unpack_external_c<C = c_int, A = MPI_Aint>(
+        datarep: *const c_char,
+        inbuf: *const c_void,
+        insize: A,
+        position: Option<&mut A>,
+        outbuf: *mut c_void,
+        outcount: C,
+        datatype: MPI_Datatype,
+    ) -> Error {
+        let position = position.expect("NULL is not a valid parameter to MPI_Unpack_external.");
+
+        mpicheck!(unsafe { check_datarep(datarep) });
+        let mut buf = mpicheck!(unsafe { recv_buffer(outbuf, outcount, datatype) });
+        let insize = mpicheck!(position_of(insize));
+        let start = mpicheck!(position_of(*position));
+
+        let end = mpicheck!(unsafe { unpack(inbuf, insize, start, &mut buf, true) });
+        *position = end.value_as().unwrap();
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Pack_external_size / MPI_Pack_external_size_c<C = c_int, A = MPI_Aint>(
+        datarep: *const c_char,
+        incount: C,
+        datatype: MPI_Datatype,
+        size: Option<&mut A>,
+    ) -> Error {
+        let size = size.expect("NULL is not a valid parameter to MPI_Pack_external_size.");
+
+        mpicheck!(unsafe { check_datarep(datarep) });
+        let incount = mpicheck!(count_of(incount));
+        let datatype = mpicheck!(unsafe { datatype.get() });
+
+        *size = count_or_undefined((incount as u64).checked_mul(datatype.external_size()));
+        Error::MPI_SUCCESS
+    }
+}
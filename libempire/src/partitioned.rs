@@ -0,0 +1,168 @@
+use super::{Error, constants::*, count::count_of, datatype::{MPI_Count, MPI_Datatype},
+            handles::MPI_Comm, info::MPI_Info, request::MPI_Request,
+            pt2pt::{recv_buffer, send_buffer}};
+
+use conv::*;
+use empire::{Destination, Source};
+use std::{slice, os::raw::{c_int, c_void}};
+
+// Returns the number of partitions and the number of elements in the whole buffer, whose data
+// must fit in memory.
+fn partitioned_count(
+    partitions: c_int,
+    count: MPI_Count,
+    datatype: MPI_Datatype,
+) -> Result<(usize, MPI_Count), Error> {
+    let partitions: usize = match partitions.value_as() {
+        Ok(partitions) if partitions > 0 => partitions,
+        _ => return Err(Error::MPI_ERR_ARG),
+    };
+    let total = count
+        .checked_mul(partitions as MPI_Count)
+        .ok_or(Error::MPI_ERR_COUNT)?;
+    let datatype = unsafe { datatype.get() }?;
+    (count_of(total)? as u64)
+        .checked_mul(datatype.size())
+        .ok_or(Error::MPI_ERR_COUNT)?;
+
+    Ok((partitions, total))
+}
+
+fn partition(partition: c_int) -> Result<usize, Error> {
+    partition.value_as().map_err(|_| Error::MPI_ERR_ARG)
+}
+
+// Partitioned operations take no info keys yet, so `info` is ignored.
+#[no_mangle]
+pub extern "C" fn MPI_Psend_init(
+    buf: *const c_void,
+    partitions: c_int,
+    count: MPI_Count,
+    datatype: MPI_Datatype,
+    dest: c_int,
+    tag: c_int,
+    comm: MPI_Comm,
+    _info: MPI_Info,
+    request: Option<&mut MPI_Request>,
+) -> Error {
+    let comm = unsafe { comm.get() };
+    let request = request.expect("NULL is not a valid parameter to MPI_Psend_init.");
+
+    let (partitions, total) = mpicheck!(partitioned_count(partitions, count, datatype));
+    let dest = match dest {
+        MPI_PROC_NULL => Destination::ProcNull,
+        dest => match dest.value_as::<usize>() {
+            Ok(dest) if dest < comm.remote_size() => Destination::Rank(dest),
+            _ => return Error::MPI_ERR_RANK,
+        },
+    };
+    if tag < 0 {
+        return Error::MPI_ERR_TAG;
+    }
+
+    let buf = mpicheck!(unsafe { send_buffer(buf, total, datatype) });
+    *request = MPI_Request::new(comm.psend_init(buf, partitions, dest, tag));
+
+    Error::MPI_SUCCESS
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Precv_init(
+    buf: *mut c_void,
+    partitions: c_int,
+    count: MPI_Count,
+    datatype: MPI_Datatype,
+    source: c_int,
+    tag: c_int,
+    comm: MPI_Comm,
+    _info: MPI_Info,
+    request: Option<&mut MPI_Request>,
+) -> Error {
+    let comm = unsafe { comm.get() };
+    let request = request.expect("NULL is not a valid parameter to MPI_Precv_init.");
+
+    let (partitions, total) = mpicheck!(partitioned_count(partitions, count, datatype));
+    // MPI_ANY_SOURCE and MPI_ANY_TAG aren't allowed.
+    let source = match source {
+        MPI_PROC_NULL => Source::ProcNull,
+        source => match source.value_as::<usize>() {
+            Ok(source) if source < comm.remote_size() => Source::Rank(source),
+            _ => return Error::MPI_ERR_RANK,
+        },
+    };
+    if tag < 0 {
+        return Error::MPI_ERR_TAG;
+    }
+
+    let buf = mpicheck!(unsafe { recv_buffer(buf, total, datatype) });
+    *request = MPI_Request::new(comm.precv_init(buf, partitions, source, tag));
+
+    Error::MPI_SUCCESS
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Pready(partition: c_int, mut request: MPI_Request) -> Error {
+    let partition = mpicheck!(self::partition(partition));
+
+    match *unsafe { request.get_mut() } {
+        Some(ref mut pending) => mpitry!(pending.pready(partition)),
+        None => return Error::MPI_ERR_REQUEST,
+    }
+
+    Error::MPI_SUCCESS
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Pready_range(
+    partition_low: c_int,
+    partition_high: c_int,
+    request: MPI_Request,
+) -> Error {
+    for partition in partition_low..(partition_high + 1) {
+        let error = MPI_Pready(partition, request);
+        if error != Error::MPI_SUCCESS {
+            return error;
+        }
+    }
+
+    Error::MPI_SUCCESS
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Pready_list(
+    length: c_int,
+    array_of_partitions: *const c_int,
+    request: MPI_Request,
+) -> Error {
+    let length: usize = mpicheck!(length.value_as().map_err(|_| Error::MPI_ERR_ARG));
+    if length == 0 {
+        return Error::MPI_SUCCESS;
+    }
+
+    let partitions = unsafe { slice::from_raw_parts(array_of_partitions, length) };
+    for &partition in partitions {
+        let error = MPI_Pready(partition, request);
+        if error != Error::MPI_SUCCESS {
+            return error;
+        }
+    }
+
+    Error::MPI_SUCCESS
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Parrived(
+    mut request: MPI_Request,
+    partition: c_int,
+    flag: *mut c_int,
+) -> Error {
+    let partition = mpicheck!(self::partition(partition));
+
+    let arrived = match *unsafe { request.get_mut() } {
+        Some(ref mut pending) => mpitry!(pending.parrived(partition)),
+        None => return Error::MPI_ERR_REQUEST,
+    };
+    unsafe { *flag = arrived as c_int };
+
+    Error::MPI_SUCCESS
+}
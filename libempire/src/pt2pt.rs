@@ -0,0 +1,644 @@
+use super::{universe, Error, constants::*, count::{count_of, Count}, datatype::MPI_Datatype,
+            handles::MPI_Comm, request::MPI_Request, status::MPI_Status};
+
+use conv::*;
+use empire::{Buffer, BufferMut, Comm, Datatype, Destination, Source, Tag};
+use std::{ptr, slice, os::raw::{c_int, c_void}};
+
+// Buffers may be NULL when the message is empty, which slice::from_raw_parts doesn't allow.
+pub unsafe fn send_bytes<'a>(buf: *const c_void, len: usize) -> &'a [u8] {
+    if len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(buf as *const u8, len)
+    }
+}
+
+fn described<C: Count>(count: C, datatype: MPI_Datatype) -> Result<(usize, Datatype), Error> {
+    let count = count_of(count)?;
+    let datatype = unsafe { datatype.get() }?;
+    if !datatype.is_committed() {
+        return Err(Error::MPI_ERR_TYPE);
+    }
+    Ok((count, datatype.clone()))
+}
+
+// Messages are packed straight from the elements `datatype` describes at `buf`, and unpacked
+// straight into them.
+pub unsafe fn send_buffer<'a, C: Count>(
+    buf: *const c_void,
+    count: C,
+    datatype: MPI_Datatype,
+) -> Result<Buffer<'a>, Error> {
+    let (count, datatype) = described(count, datatype)?;
+    Ok(Buffer::new(buf as *const u8, count, datatype))
+}
+
+pub unsafe fn recv_buffer<'a, C: Count>(
+    buf: *mut c_void,
+    count: C,
+    datatype: MPI_Datatype,
+) -> Result<BufferMut<'a>, Error> {
+    let (count, datatype) = described(count, datatype)?;
+    Ok(BufferMut::new(buf as *mut u8, count, datatype))
+}
+
+pub fn destination(dest: c_int, comm: &Comm) -> Result<Destination, Error> {
+    if dest == MPI_PROC_NULL {
+        return Ok(Destination::ProcNull);
+    }
+
+    match dest.value_as::<usize>() {
+        Ok(dest) if dest < comm.remote_size() => Ok(Destination::Rank(dest)),
+        _ => Err(Error::MPI_ERR_RANK),
+    }
+}
+
+pub fn source(source: c_int, comm: &Comm) -> Result<Source, Error> {
+    match source {
+        MPI_ANY_SOURCE => Ok(Source::Any),
+        MPI_PROC_NULL => Ok(Source::ProcNull),
+        source => match source.value_as::<usize>() {
+            Ok(source) if source < comm.remote_size() => Ok(Source::Rank(source)),
+            _ => Err(Error::MPI_ERR_RANK),
+        },
+    }
+}
+
+pub fn send_tag(tag: c_int) -> Result<i32, Error> {
+    if tag < 0 {
+        Err(Error::MPI_ERR_TAG)
+    } else {
+        Ok(tag)
+    }
+}
+
+pub fn recv_tag(tag: c_int) -> Result<Tag, Error> {
+    match tag {
+        MPI_ANY_TAG => Ok(Tag::Any),
+        tag if tag < 0 => Err(Error::MPI_ERR_TAG),
+        tag => Ok(Tag::Value(tag)),
+    }
+}
+
+large_count! {
+    fn MPI_Send / MPI_Send_c<C = c_int>(
+        buf: *const c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        dest: c_int,
+        tag: c_int,
+        comm: MPI_Comm,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+
+        let buf = mpicheck!(unsafe { send_buffer(buf, count, datatype) });
+        let dest = mpicheck!(destination(dest, &comm));
+        let tag = mpicheck!(send_tag(tag));
+
+        mpitry!(comm.send(buf, dest, tag));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Bsend / MPI_Bsend_c<C = c_int>(
+        buf: *const c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        dest: c_int,
+        tag: c_int,
+        comm: MPI_Comm,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+
+        let buf = mpicheck!(unsafe { send_buffer(buf, count, datatype) });
+        let dest = mpicheck!(destination(dest, &comm));
+        let tag = mpicheck!(send_tag(tag));
+
+        mpitry!(comm.bsend(buf, dest, tag));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Ssend / MPI_Ssend_c<C = c_int>(
+        buf: *const c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        dest: c_int,
+        tag: c_int,
+        comm: MPI_Comm,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+
+        let buf = mpicheck!(unsafe { send_buffer(buf, count, datatype) });
+        let dest = mpicheck!(destination(dest, &comm));
+        let tag = mpicheck!(send_tag(tag));
+
+        mpitry!(comm.ssend(buf, dest, tag));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Rsend / MPI_Rsend_c<C = c_int>(
+        buf: *const c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        dest: c_int,
+        tag: c_int,
+        comm: MPI_Comm,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+
+        let buf = mpicheck!(unsafe { send_buffer(buf, count, datatype) });
+        let dest = mpicheck!(destination(dest, &comm));
+        let tag = mpicheck!(send_tag(tag));
+
+        mpitry!(comm.rsend(buf, dest, tag));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Recv / MPI_Recv_c<C = c_int>(
+        buf: *mut c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        source: c_int,
+        tag: c_int,
+        comm: MPI_Comm,
+        status: Option<&mut MPI_Status>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+
+        let buf = mpicheck!(unsafe { recv_buffer(buf, count, datatype) });
+        let source = mpicheck!(self::source(source, &comm));
+        let tag = mpicheck!(recv_tag(tag));
+
+        let result = comm.recv(buf, source, tag);
+        MPI_Status::set(status, &result)
+    }
+}
+
+large_count! {
+    fn MPI_Isend / MPI_Isend_c<C = c_int>(
+        buf: *const c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        dest: c_int,
+        tag: c_int,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Isend.");
+
+        let buf = mpicheck!(unsafe { send_buffer(buf, count, datatype) });
+        let dest = mpicheck!(destination(dest, &comm));
+        let tag = mpicheck!(send_tag(tag));
+
+        *request = MPI_Request::new(comm.isend(buf, dest, tag));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Ibsend / MPI_Ibsend_c<C = c_int>(
+        buf: *const c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        dest: c_int,
+        tag: c_int,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Ibsend.");
+
+        let buf = mpicheck!(unsafe { send_buffer(buf, count, datatype) });
+        let dest = mpicheck!(destination(dest, &comm));
+        let tag = mpicheck!(send_tag(tag));
+
+        let pending = mpitry!(comm.ibsend(buf, dest, tag));
+        *request = MPI_Request::new(pending);
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Issend / MPI_Issend_c<C = c_int>(
+        buf: *const c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        dest: c_int,
+        tag: c_int,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Issend.");
+
+        let buf = mpicheck!(unsafe { send_buffer(buf, count, datatype) });
+        let dest = mpicheck!(destination(dest, &comm));
+        let tag = mpicheck!(send_tag(tag));
+
+        *request = MPI_Request::new(comm.issend(buf, dest, tag));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Irsend / MPI_Irsend_c<C = c_int>(
+        buf: *const c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        dest: c_int,
+        tag: c_int,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Irsend.");
+
+        let buf = mpicheck!(unsafe { send_buffer(buf, count, datatype) });
+        let dest = mpicheck!(destination(dest, &comm));
+        let tag = mpicheck!(send_tag(tag));
+
+        *request = MPI_Request::new(comm.irsend(buf, dest, tag));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Irecv / MPI_Irecv_c<C = c_int>(
+        buf: *mut c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        source: c_int,
+        tag: c_int,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Irecv.");
+
+        let buf = mpicheck!(unsafe { recv_buffer(buf, count, datatype) });
+        let source = mpicheck!(self::source(source, &comm));
+        let tag = mpicheck!(recv_tag(tag));
+
+        *request = MPI_Request::new(comm.irecv(buf, source, tag));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Send_init / MPI_Send_init_c<C = c_int>(
+        buf: *const c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        dest: c_int,
+        tag: c_int,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Send_init.");
+
+        let buf = mpicheck!(unsafe { send_buffer(buf, count, datatype) });
+        let dest = mpicheck!(destination(dest, &comm));
+        let tag = mpicheck!(send_tag(tag));
+
+        *request = MPI_Request::new(comm.send_init(buf, dest, tag));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Bsend_init / MPI_Bsend_init_c<C = c_int>(
+        buf: *const c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        dest: c_int,
+        tag: c_int,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Bsend_init.");
+
+        let buf = mpicheck!(unsafe { send_buffer(buf, count, datatype) });
+        let dest = mpicheck!(destination(dest, &comm));
+        let tag = mpicheck!(send_tag(tag));
+
+        *request = MPI_Request::new(comm.bsend_init(buf, dest, tag));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Ssend_init / MPI_Ssend_init_c<C = c_int>(
+        buf: *const c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        dest: c_int,
+        tag: c_int,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Ssend_init.");
+
+        let buf = mpicheck!(unsafe { send_buffer(buf, count, datatype) });
+        let dest = mpicheck!(destination(dest, &comm));
+        let tag = mpicheck!(send_tag(tag));
+
+        *request = MPI_Request::new(comm.ssend_init(buf, dest, tag));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Rsend_init / MPI_Rsend_init_c<C = c_int>(
+        buf: *const c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        dest: c_int,
+        tag: c_int,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Rsend_init.");
+
+        let buf = mpicheck!(unsafe { send_buffer(buf, count, datatype) });
+        let dest = mpicheck!(destination(dest, &comm));
+        let tag = mpicheck!(send_tag(tag));
+
+        *request = MPI_Request::new(comm.rsend_init(buf, dest, tag));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Recv_init / MPI_Recv_init_c<C = c_int>(
+        buf: *mut c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        source: c_int,
+        tag: c_int,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Recv_init.");
+
+        let buf = mpicheck!(unsafe { recv_buffer(buf, count, datatype) });
+        let source = mpicheck!(self::source(source, &comm));
+        let tag = mpicheck!(recv_tag(tag));
+
+        *request = MPI_Request::new(comm.recv_init(buf, source, tag));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Sendrecv / MPI_Sendrecv_c<C = c_int>(
+        sendbuf: *const c_void,
+        sendcount: C,
+        sendtype: MPI_Datatype,
+        dest: c_int,
+        sendtag: c_int,
+        recvbuf: *mut c_void,
+        recvcount: C,
+        recvtype: MPI_Datatype,
+        source: c_int,
+        recvtag: c_int,
+        comm: MPI_Comm,
+        status: Option<&mut MPI_Status>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+
+        let sendbuf = mpicheck!(unsafe { send_buffer(sendbuf, sendcount, sendtype) });
+        let dest = mpicheck!(destination(dest, &comm));
+        let sendtag = mpicheck!(send_tag(sendtag));
+        let recvbuf = mpicheck!(unsafe { recv_buffer(recvbuf, recvcount, recvtype) });
+        let source = mpicheck!(self::source(source, &comm));
+        let recvtag = mpicheck!(recv_tag(recvtag));
+
+        let result = comm.sendrecv(
+            sendbuf,
+            dest,
+            sendtag,
+            recvbuf,
+            source,
+            recvtag,
+        );
+        MPI_Status::set(status, &result)
+    }
+}
+
+large_count! {
+    fn MPI_Sendrecv_replace / MPI_Sendrecv_replace_c<C = c_int>(
+        buf: *mut c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        dest: c_int,
+        sendtag: c_int,
+        source: c_int,
+        recvtag: c_int,
+        comm: MPI_Comm,
+        status: Option<&mut MPI_Status>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+
+        let buf = mpicheck!(unsafe { recv_buffer(buf, count, datatype) });
+        let dest = mpicheck!(destination(dest, &comm));
+        let sendtag = mpicheck!(send_tag(sendtag));
+        let source = mpicheck!(self::source(source, &comm));
+        let recvtag = mpicheck!(recv_tag(recvtag));
+
+        let result = comm.sendrecv_replace(
+            buf,
+            dest,
+            sendtag,
+            source,
+            recvtag,
+        );
+        MPI_Status::set(status, &result)
+    }
+}
+
+large_count! {
+    fn MPI_Isendrecv / MPI_Isendrecv_c<C = c_int>(
+        sendbuf: *const c_void,
+        sendcount: C,
+        sendtype: MPI_Datatype,
+        dest: c_int,
+        sendtag: c_int,
+        recvbuf: *mut c_void,
+        recvcount: C,
+        recvtype: MPI_Datatype,
+        source: c_int,
+        recvtag: c_int,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Isendrecv.");
+
+        let sendbuf = mpicheck!(unsafe { send_buffer(sendbuf, sendcount, sendtype) });
+        let dest = mpicheck!(destination(dest, &comm));
+        let sendtag = mpicheck!(send_tag(sendtag));
+        let recvbuf = mpicheck!(unsafe { recv_buffer(recvbuf, recvcount, recvtype) });
+        let source = mpicheck!(self::source(source, &comm));
+        let recvtag = mpicheck!(recv_tag(recvtag));
+
+        *request = MPI_Request::new(comm.isendrecv(
+            sendbuf,
+            dest,
+            sendtag,
+            recvbuf,
+            source,
+            recvtag,
+        ));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+large_count! {
+    fn MPI_Isendrecv_replace / MPI_Isendrecv_replace_c<C = c_int>(
+        buf: *mut c_void,
+        count: C,
+        datatype: MPI_Datatype,
+        dest: c_int,
+        sendtag: c_int,
+        source: c_int,
+        recvtag: c_int,
+        comm: MPI_Comm,
+        request: Option<&mut MPI_Request>,
+    ) -> Error {
+        let comm = unsafe { comm.get() };
+        let request = request.expect("NULL is not a valid parameter to MPI_Isendrecv_replace.");
+
+        let buf = mpicheck!(unsafe { recv_buffer(buf, count, datatype) });
+        let dest = mpicheck!(destination(dest, &comm));
+        let sendtag = mpicheck!(send_tag(sendtag));
+        let source = mpicheck!(self::source(source, &comm));
+        let recvtag = mpicheck!(recv_tag(recvtag));
+
+        *request = MPI_Request::new(comm.isendrecv_replace(
+            buf,
+            dest,
+            sendtag,
+            source,
+            recvtag,
+        ));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Probe(
+    source: c_int,
+    tag: c_int,
+    comm: MPI_Comm,
+    status: Option<&mut MPI_Status>,
+) -> Error {
+    let comm = unsafe { comm.get() };
+
+    let source = mpicheck!(self::source(source, &comm));
+    let tag = mpicheck!(recv_tag(tag));
+
+    MPI_Status::set(status, &Ok(comm.probe(source, tag)))
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Iprobe(
+    source: c_int,
+    tag: c_int,
+    comm: MPI_Comm,
+    flag: *mut c_int,
+    status: Option<&mut MPI_Status>,
+) -> Error {
+    let comm = unsafe { comm.get() };
+
+    let source = mpicheck!(self::source(source, &comm));
+    let tag = mpicheck!(recv_tag(tag));
+
+    match comm.iprobe(source, tag) {
+        Some(probed) => {
+            unsafe { *flag = 1 };
+            MPI_Status::set(status, &Ok(probed))
+        }
+        None => {
+            unsafe { *flag = 0 };
+            Error::MPI_SUCCESS
+        }
+    }
+}
+
+large_count! {
+    fn MPI_Buffer_attach / MPI_Buffer_attach_c<C = c_int>(
+        buffer: *mut c_void,
+        size: C,
+    ) -> Error {
+        let size: usize = mpicheck!(size.value_as().map_err(|_| Error::MPI_ERR_ARG));
+
+        // The memory is lent to the universe until it is detached.
+        let memory: &'static mut [u8] = if buffer.is_null() {
+            &mut []
+        } else {
+            unsafe { slice::from_raw_parts_mut(buffer as *mut u8, size) }
+        };
+        mpitry!(universe().write().unwrap().attach_buffer(memory));
+
+        Error::MPI_SUCCESS
+    }
+}
+
+// `buffer_addr` is really a void**, declared as void* so that C callers needn't cast.
+large_count! {
+    fn MPI_Buffer_detach / MPI_Buffer_detach_c<C = c_int>(
+        buffer_addr: *mut c_void,
+        size: *mut C,
+    ) -> Error {
+        // A buffer attached with MPI_Buffer_attach_c can be too large to report through the int
+        // variant, in which case it stays attached.
+        let attached = mpitry!(universe().read().unwrap().attached_buffer_size());
+        let attached: C = mpicheck!(attached.value_as().map_err(|_| Error::MPI_ERR_COUNT));
+
+        // Waiting for the buffered sends to be delivered may need the universe, so it is only done
+        // once the buffer has been taken out of it.
+        let detached = mpitry!(universe().write().unwrap().detach_buffer());
+        let memory = detached.detach();
+
+        unsafe {
+            *(buffer_addr as *mut *mut c_void) = if memory.is_empty() {
+                ptr::null_mut()
+            } else {
+                memory.as_mut_ptr() as *mut c_void
+            };
+            *size = attached;
+        }
+
+        Error::MPI_SUCCESS
+    }
+}
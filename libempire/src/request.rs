@@ -0,0 +1,461 @@
+use super::{Error, grequest, constants::*, status::MPI_Status};
+
+use conv::*;
+use empire::{Request, Status, request};
+use std::{mem, ptr, slice, os::raw::c_int};
+
+#[no_mangle]
+pub static mut MPI_REQUEST_NULL: MPI_Request = MPI_Request {
+    handle: ptr::null_mut(),
+};
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct MPI_Request {
+    pub handle: *mut Option<Request<'static>>,
+}
+
+impl MPI_Request {
+    pub fn null() -> Self {
+        Self {
+            handle: Box::into_raw(Box::new(None)),
+        }
+    }
+
+    pub fn new(request: Request<'static>) -> Self {
+        Self {
+            handle: Box::into_raw(Box::new(Some(request))),
+        }
+    }
+
+    fn assert_non_null(&self) {
+        assert!(
+            self.handle != ptr::null_mut(),
+            "NULL is not an allowed value for MPI_Request. You may not have initialized MPI yet. \
+             Use MPI_REQUEST_NULL instead."
+        );
+    }
+
+    pub unsafe fn is_null(&self) -> bool {
+        self.assert_non_null();
+        self.handle == MPI_REQUEST_NULL.handle
+    }
+
+    // Takes the pending operation out of the request, leaving the handle allocated so that the
+    // operation can be put back with `restore` if it hasn't completed.
+    pub unsafe fn take(&mut self) -> Option<Request<'static>> {
+        if self.is_null() {
+            None
+        } else {
+            mem::replace(&mut *self.handle, None)
+        }
+    }
+
+    // Puts back an operation taken with `take`. If the operation is gone, it completed, and the
+    // handle is freed and set to MPI_REQUEST_NULL. Persistent requests are always put back.
+    pub unsafe fn restore(&mut self, request: Option<Request<'static>>) {
+        if self.is_null() {
+            assert!(request.is_none());
+            return;
+        }
+
+        match request {
+            Some(request) => *self.handle = Some(request),
+            None => {
+                self.free();
+                *self = MPI_REQUEST_NULL;
+            }
+        }
+    }
+
+    // Frees the handle once its request has completed, unless the request is persistent.
+    pub unsafe fn release(&mut self) {
+        let persistent = match *self.get_mut() {
+            Some(ref request) => request.is_persistent(),
+            None => false,
+        };
+
+        if !self.is_null() && !persistent {
+            self.free();
+            *self = MPI_REQUEST_NULL;
+        }
+    }
+
+    pub unsafe fn get_mut(&mut self) -> &mut Option<Request<'static>> {
+        self.assert_non_null();
+        &mut *self.handle
+    }
+
+    pub unsafe fn free(&mut self) {
+        self.assert_non_null();
+        Box::from_raw(self.handle);
+        self.handle = ptr::null_mut();
+    }
+}
+
+unsafe fn requests_from_ptr<'a>(
+    count: c_int,
+    requests: *mut MPI_Request,
+) -> &'a mut [MPI_Request] {
+    let count: usize = count.value_as().expect("A negative count is malformed.");
+    if count == 0 {
+        &mut []
+    } else {
+        slice::from_raw_parts_mut(requests, count)
+    }
+}
+
+// MPI_STATUSES_IGNORE is NULL.
+unsafe fn statuses_from_ptr<'a>(
+    count: usize,
+    statuses: *mut MPI_Status,
+) -> Option<&'a mut [MPI_Status]> {
+    if statuses.is_null() {
+        None
+    } else {
+        Some(slice::from_raw_parts_mut(statuses, count))
+    }
+}
+
+unsafe fn take_all(requests: &mut [MPI_Request]) -> Vec<Option<Request<'static>>> {
+    requests.iter_mut().map(|request| request.take()).collect()
+}
+
+unsafe fn restore_all(requests: &mut [MPI_Request], pending: Vec<Option<Request<'static>>>) {
+    for (request, pending) in requests.iter_mut().zip(pending) {
+        request.restore(pending);
+    }
+}
+
+// Fills in the statuses of a multiple-completion routine. Any failure is reported through
+// MPI_ERR_IN_STATUS, with the individual error in each status.
+fn set_statuses<'a, I: IntoIterator<Item = &'a mut MPI_Status>>(
+    statuses: Option<I>,
+    results: &[::empire::Result<Status>],
+) -> Error {
+    let errors: Vec<_> = match statuses {
+        Some(statuses) => statuses
+            .into_iter()
+            .zip(results)
+            .map(|(status, result)| MPI_Status::set(Some(status), result))
+            .collect(),
+        None => results
+            .iter()
+            .map(|result| MPI_Status::set(None, result))
+            .collect(),
+    };
+
+    if errors.iter().all(|&error| error == Error::MPI_SUCCESS) {
+        Error::MPI_SUCCESS
+    } else {
+        Error::MPI_ERR_IN_STATUS
+    }
+}
+
+fn set_flag(flag: *mut c_int, value: bool) {
+    unsafe { *flag = value as c_int };
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Wait(
+    request: Option<&mut MPI_Request>,
+    status: Option<&mut MPI_Status>,
+) -> Error {
+    let request = request.expect("NULL is not a valid parameter to MPI_Wait.");
+
+    let result = match *unsafe { request.get_mut() } {
+        Some(ref mut pending) => pending.wait_mut(),
+        None => Ok(Status::empty()),
+    };
+    unsafe { request.release() };
+
+    MPI_Status::set(status, &result)
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Test(
+    request: Option<&mut MPI_Request>,
+    flag: *mut c_int,
+    status: Option<&mut MPI_Status>,
+) -> Error {
+    let request = request.expect("NULL is not a valid parameter to MPI_Test.");
+
+    let result = match *unsafe { request.get_mut() } {
+        Some(ref mut pending) => match pending.test_mut() {
+            Some(result) => result,
+            None => {
+                set_flag(flag, false);
+                return Error::MPI_SUCCESS;
+            }
+        },
+        None => Ok(Status::empty()),
+    };
+    unsafe { request.release() };
+
+    set_flag(flag, true);
+    MPI_Status::set(status, &result)
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Request_get_status(
+    mut request: MPI_Request,
+    flag: *mut c_int,
+    status: Option<&mut MPI_Status>,
+) -> Error {
+    let pending = match *unsafe { request.get_mut() } {
+        Some(ref mut pending) => pending,
+        None => {
+            set_flag(flag, true);
+            return MPI_Status::set(status, &Ok(Status::empty()));
+        }
+    };
+
+    match pending.get_status() {
+        Some(result) => {
+            set_flag(flag, true);
+            MPI_Status::set(status, result)
+        }
+        None => {
+            set_flag(flag, false);
+            Error::MPI_SUCCESS
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Waitall(
+    count: c_int,
+    array_of_requests: *mut MPI_Request,
+    array_of_statuses: *mut MPI_Status,
+) -> Error {
+    let requests = unsafe { requests_from_ptr(count, array_of_requests) };
+    let statuses = unsafe { statuses_from_ptr(requests.len(), array_of_statuses) };
+
+    let mut pending = unsafe { take_all(requests) };
+    let results = request::wait_all(&mut pending);
+    unsafe { restore_all(requests, pending) };
+
+    set_statuses(statuses, &results)
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Testall(
+    count: c_int,
+    array_of_requests: *mut MPI_Request,
+    flag: *mut c_int,
+    array_of_statuses: *mut MPI_Status,
+) -> Error {
+    let requests = unsafe { requests_from_ptr(count, array_of_requests) };
+    let statuses = unsafe { statuses_from_ptr(requests.len(), array_of_statuses) };
+
+    let mut pending = unsafe { take_all(requests) };
+    let results = request::test_all(&mut pending);
+    unsafe { restore_all(requests, pending) };
+
+    set_flag(flag, results.is_some());
+    match results {
+        Some(results) => set_statuses(statuses, &results),
+        None => Error::MPI_SUCCESS,
+    }
+}
+
+fn set_index(index: *mut c_int, value: Option<usize>) {
+    unsafe {
+        *index = match value {
+            Some(value) => value.value_as().unwrap(),
+            None => MPI_UNDEFINED,
+        }
+    };
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Waitany(
+    count: c_int,
+    array_of_requests: *mut MPI_Request,
+    index: *mut c_int,
+    status: Option<&mut MPI_Status>,
+) -> Error {
+    let requests = unsafe { requests_from_ptr(count, array_of_requests) };
+
+    let mut pending = unsafe { take_all(requests) };
+    let completed = request::wait_any(&mut pending);
+    unsafe { restore_all(requests, pending) };
+
+    match completed {
+        Some((completed_index, result)) => {
+            set_index(index, Some(completed_index));
+            MPI_Status::set(status, &result)
+        }
+        None => {
+            set_index(index, None);
+            MPI_Status::set(status, &Ok(Status::empty()))
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Testany(
+    count: c_int,
+    array_of_requests: *mut MPI_Request,
+    index: *mut c_int,
+    flag: *mut c_int,
+    status: Option<&mut MPI_Status>,
+) -> Error {
+    let requests = unsafe { requests_from_ptr(count, array_of_requests) };
+
+    let mut pending = unsafe { take_all(requests) };
+    let completed = request::test_any(&mut pending);
+    unsafe { restore_all(requests, pending) };
+
+    match completed {
+        Some(Some((completed_index, result))) => {
+            set_flag(flag, true);
+            set_index(index, Some(completed_index));
+            MPI_Status::set(status, &result)
+        }
+        Some(None) => {
+            set_flag(flag, true);
+            set_index(index, None);
+            MPI_Status::set(status, &Ok(Status::empty()))
+        }
+        None => {
+            set_flag(flag, false);
+            set_index(index, None);
+            Error::MPI_SUCCESS
+        }
+    }
+}
+
+fn set_completions(
+    completions: Option<request::Completions>,
+    outcount: *mut c_int,
+    array_of_indices: *mut c_int,
+    array_of_statuses: *mut MPI_Status,
+) -> Error {
+    let completions = match completions {
+        Some(completions) => completions,
+        None => {
+            unsafe { *outcount = MPI_UNDEFINED };
+            return Error::MPI_SUCCESS;
+        }
+    };
+
+    unsafe { *outcount = completions.len().value_as().unwrap() };
+
+    let (completed_indices, results): (Vec<_>, Vec<_>) = completions.into_iter().unzip();
+
+    if !completed_indices.is_empty() {
+        let indices =
+            unsafe { slice::from_raw_parts_mut(array_of_indices, completed_indices.len()) };
+        for (index, completed_index) in indices.iter_mut().zip(completed_indices) {
+            *index = completed_index.value_as().unwrap();
+        }
+    }
+
+    set_statuses(
+        unsafe { statuses_from_ptr(results.len(), array_of_statuses) },
+        &results,
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Waitsome(
+    incount: c_int,
+    array_of_requests: *mut MPI_Request,
+    outcount: *mut c_int,
+    array_of_indices: *mut c_int,
+    array_of_statuses: *mut MPI_Status,
+) -> Error {
+    let requests = unsafe { requests_from_ptr(incount, array_of_requests) };
+
+    let mut pending = unsafe { take_all(requests) };
+    let completions = request::wait_some(&mut pending);
+    unsafe { restore_all(requests, pending) };
+
+    set_completions(completions, outcount, array_of_indices, array_of_statuses)
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Testsome(
+    incount: c_int,
+    array_of_requests: *mut MPI_Request,
+    outcount: *mut c_int,
+    array_of_indices: *mut c_int,
+    array_of_statuses: *mut MPI_Status,
+) -> Error {
+    let requests = unsafe { requests_from_ptr(incount, array_of_requests) };
+
+    let mut pending = unsafe { take_all(requests) };
+    let completions = request::test_some(&mut pending);
+    unsafe { restore_all(requests, pending) };
+
+    set_completions(completions, outcount, array_of_indices, array_of_statuses)
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Start(request: Option<&mut MPI_Request>) -> Error {
+    let request = request.expect("NULL is not a valid parameter to MPI_Start.");
+
+    let pending = unsafe { request.get_mut() }
+        .as_mut()
+        .expect("MPI_REQUEST_NULL is not allowed in this routine.");
+    mpitry!(pending.start());
+
+    Error::MPI_SUCCESS
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Startall(count: c_int, array_of_requests: *mut MPI_Request) -> Error {
+    let requests = unsafe { requests_from_ptr(count, array_of_requests) };
+
+    for request in requests.iter_mut() {
+        let error = MPI_Start(Some(request));
+        if error != Error::MPI_SUCCESS {
+            return error;
+        }
+    }
+
+    Error::MPI_SUCCESS
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Cancel(request: Option<&mut MPI_Request>) -> Error {
+    let request = request.expect("NULL is not a valid parameter to MPI_Cancel.");
+
+    let pending = unsafe { request.get_mut() }
+        .as_mut()
+        .expect("MPI_REQUEST_NULL is not allowed in this routine.");
+    mpitry!(pending.cancel());
+
+    Error::MPI_SUCCESS
+}
+
+// An operation that hasn't completed carries on without the request: a send still goes out, and a
+// receive still takes the message it matches and fills its buffer with it. A generalized request
+// is kept until MPI_Grequest_complete. A nonblocking collective only makes progress while its
+// request is completed, so freeing one that is still running is an error.
+#[no_mangle]
+pub extern "C" fn MPI_Request_free(request: Option<&mut MPI_Request>) -> Error {
+    let request = request.expect("NULL is not a valid parameter to MPI_Request_free.");
+
+    unsafe {
+        assert!(!request.is_null(), "MPI_REQUEST_NULL cannot be freed.");
+        if request
+            .get_mut()
+            .as_ref()
+            .map_or(false, |pending| pending.is_running_collective())
+        {
+            return Error::MPI_ERR_REQUEST;
+        }
+        if !grequest::free_when_complete(request) {
+            if let Some(pending) = request.take() {
+                pending.free();
+            }
+            request.free();
+        }
+        *request = MPI_REQUEST_NULL;
+    }
+
+    Error::MPI_SUCCESS
+}
@@ -1,6 +1,10 @@
-use empire::error;
+use super::constants::*;
+
+use empire::{error, Datatype, Source, Status, Tag};
+use std::{mem, os::raw::{c_int, c_longlong, c_uint}};
 
 #[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(C)]
 pub enum Error {
     MPI_SUCCESS = 0,
@@ -80,6 +84,108 @@ pub enum Error {
     MPI_ERR_LASTCODE,
 }
 
+impl Error {
+    // The error class with the value `code`, if there is one.
+    pub fn from_code(code: c_int) -> Option<Self> {
+        if code >= 0 && code <= Error::MPI_ERR_LASTCODE as c_int {
+            Some(unsafe { mem::transmute(code) })
+        } else {
+            None
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+#[repr(C)]
+pub struct MPI_Status {
+    pub MPI_SOURCE: c_int,
+    pub MPI_TAG: c_int,
+    pub MPI_ERROR: c_int,
+    count: c_longlong,
+    cancelled: c_int,
+    layout: c_uint,
+}
+
+impl MPI_Status {
+    pub fn from_status(status: &Status, error: Error) -> Self {
+        MPI_Status {
+            MPI_SOURCE: match status.source {
+                Source::Rank(rank) => rank as c_int,
+                Source::Any => MPI_ANY_SOURCE,
+                Source::ProcNull => MPI_PROC_NULL,
+            },
+            MPI_TAG: match status.tag {
+                Tag::Value(tag) => tag,
+                Tag::Any => MPI_ANY_TAG,
+            },
+            MPI_ERROR: error as c_int,
+            count: status.count as c_longlong,
+            cancelled: status.cancelled as c_int,
+            layout: status.layout(),
+        }
+    }
+
+    // Reads back a status filled in by the user, as the query callback of a generalized request
+    // does.
+    pub fn to_status(&self) -> Status {
+        let mut status = Status::empty().with_layout(self.layout);
+        status.source = match self.MPI_SOURCE {
+            MPI_ANY_SOURCE => Source::Any,
+            MPI_PROC_NULL => Source::ProcNull,
+            rank => Source::Rank(rank as usize),
+        };
+        status.tag = match self.MPI_TAG {
+            MPI_ANY_TAG => Tag::Any,
+            tag => Tag::Value(tag),
+        };
+        status.count = self.count as u64;
+        status.cancelled = self.cancelled != 0;
+        status
+    }
+
+    // The number of bytes the message fills when it is received as `datatype`, which for a probe
+    // of a rank with another layout isn't the number of bytes it was sent as.
+    pub fn len_as(&self, datatype: &Datatype) -> u64 {
+        self.to_status().len_as(datatype)
+    }
+
+    // Sets the number of bytes received, which are in this rank's layout.
+    pub fn set_count(&mut self, count: u64) {
+        self.count = count as c_longlong;
+        self.layout = 0;
+    }
+
+    // Writes the outcome of a receive to a status the user may have ignored, and returns the
+    // error code for the receive. Truncated receives still describe the message they received.
+    pub fn set(status: Option<&mut MPI_Status>, result: &error::Result<Status>) -> Error {
+        let (completed, error) = match result {
+            Ok(status) => (Some(status), Error::MPI_SUCCESS),
+            Err(error::Error::Truncated(ref status)) => (Some(status), Error::MPI_ERR_TRUNCATE),
+            Err(ref err) => (None, error_to_mpi_error(err)),
+        };
+
+        if let (Some(out), Some(completed)) = (status, completed) {
+            *out = MPI_Status::from_status(completed, error);
+        }
+
+        error
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Test_cancelled(status: Option<&MPI_Status>, flag: *mut c_int) -> Error {
+    let status = status.expect("NULL is not a valid parameter to MPI_Test_cancelled.");
+    unsafe { *flag = status.cancelled };
+    Error::MPI_SUCCESS
+}
+
+#[no_mangle]
+pub extern "C" fn MPI_Status_set_cancelled(status: Option<&mut MPI_Status>, flag: c_int) -> Error {
+    let status = status.expect("NULL is not a valid parameter to MPI_Status_set_cancelled.");
+    status.cancelled = (flag != 0) as c_int;
+    Error::MPI_SUCCESS
+}
+
 pub fn result_to_mpi_error<T>(result: &error::Result<T>) -> Error {
     match result {
         Ok(_) => Error::MPI_SUCCESS,
@@ -94,6 +200,20 @@ pub fn error_to_mpi_error(err: &error::Error) -> Error {
         error::Error::IoError(_) => Error::MPI_ERR_IO,
         error::Error::TokioIoError(_) => Error::MPI_ERR_IO,
         error::Error::FailExitCode(_) => Error::MPI_ERR_SPAWN,
+        error::Error::ProtocolError(_) => Error::MPI_ERR_INTERN,
+        error::Error::Truncated(_) => Error::MPI_ERR_TRUNCATE,
+        error::Error::BufferAlreadyAttached => Error::MPI_ERR_BUFFER,
+        error::Error::NoBufferAttached => Error::MPI_ERR_BUFFER,
+        error::Error::InsufficientBuffer { .. } => Error::MPI_ERR_BUFFER,
+        error::Error::CallbackFailed(code) => {
+            Error::from_code(*code).unwrap_or(Error::MPI_ERR_OTHER)
+        }
+        error::Error::UndefinedOp => Error::MPI_ERR_OP,
+        error::Error::NotPartitioned => Error::MPI_ERR_REQUEST,
+        error::Error::InactiveRequest => Error::MPI_ERR_REQUEST,
+        error::Error::NoSuchPartition(_) => Error::MPI_ERR_ARG,
+        error::Error::PartitionAlreadyReady(_) => Error::MPI_ERR_ARG,
+        error::Error::ElementTooLarge(_) => Error::MPI_ERR_TYPE,
     }
 }
 
@@ -105,3 +225,13 @@ macro_rules! mpitry {
         }
     };
 }
+
+// Like mpitry, for helpers that already produce an MPI error code.
+macro_rules! mpicheck {
+    ($e:expr) => {
+        match $e {
+            Ok(var) => var,
+            Err(err) => return err,
+        }
+    };
+}
@@ -75,15 +75,20 @@ fn main() {
 
     let mut any_failures = false;
     {
-        let empire::comm::SpawnMultipleResult { comm, results } = locked
+        let empire::comm::SpawnMultipleResult { mut comm, results } = locked
             .comm_self()
-            .spawn_multiple_root(commands, 0)
+            .launch(commands)
             .expect("Failed to spawn MPI processes");
 
-        for x in 0..results.len() {
-            if let Err(ref err) = results[x] {
+        let exits = comm.wait_children();
+        for (x, (result, exit)) in results.into_iter().zip(exits).enumerate() {
+            // A rank that couldn't be spawned has no exit status of its own.
+            if let Err(ref err) = result {
                 eprintln!("Error: Could not spawn MPI rank {}. {}", x, err);
                 any_failures = true;
+            } else if let Err(ref err) = exit {
+                eprintln!("Error: MPI rank {} failed. {}", x, err);
+                any_failures = true;
             }
         }
     }